@@ -0,0 +1,1534 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Builtin pager.
+//!
+//! The pager shows the output of a command in a full-screen interface. Besides
+//! scrolling and incremental search, it can jump between commits and files,
+//! and fold the diff of each file. Commits and files are found by markers,
+//! which [`SectionMarkingFormatter`] emits for the labels of log entries and
+//! diff file headers. [`PagerWriter`] removes the markers from the output, and
+//! sends their offsets to the pager separately.
+
+use std::collections::HashSet;
+use std::io;
+use std::io::BufRead as _;
+use std::io::BufReader;
+use std::io::Read;
+use std::io::Write;
+use std::iter::zip;
+use std::ops::Range;
+use std::sync::mpsc;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::mpsc::Sender;
+use std::sync::mpsc::TryRecvError;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use crossterm::event;
+use crossterm::event::Event;
+use crossterm::event::KeyCode;
+use crossterm::event::KeyEvent;
+use crossterm::event::KeyEventKind;
+use crossterm::event::KeyModifiers;
+use os_pipe::PipeWriter;
+use ratatui::layout::Constraint;
+use ratatui::layout::Layout;
+use ratatui::layout::Rect;
+use ratatui::style::Color;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+use ratatui::text::Line;
+use ratatui::text::Span;
+use ratatui::widgets::Block;
+use ratatui::widgets::Clear;
+use ratatui::widgets::Paragraph;
+use ratatui::Frame;
+use unicode_width::UnicodeWidthChar as _;
+
+use crate::formatter::Formatter;
+use crate::tui_util;
+use crate::tui_util::TuiState;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Deserialize)]
+#[serde(rename_all(deserialize = "kebab-case"))]
+pub enum PagerInterface {
+    QuitIfOnePage,
+    FullScreenClearOutput,
+    QuitQuicklyOrClearOutput,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Deserialize)]
+#[serde(rename_all(deserialize = "kebab-case"))]
+pub enum WrappingMode {
+    None,
+    Word,
+    Anywhere,
+}
+
+impl WrappingMode {
+    fn next(self) -> Self {
+        match self {
+            WrappingMode::None => WrappingMode::Word,
+            WrappingMode::Word => WrappingMode::Anywhere,
+            WrappingMode::Anywhere => WrappingMode::None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Deserialize)]
+#[serde(rename_all(deserialize = "kebab-case"))]
+pub struct BuiltinPagerConfig {
+    pub interface: PagerInterface,
+    pub wrapping: WrappingMode,
+}
+
+/// How long `quit-quickly-or-clear-output` waits for the command to finish.
+const QUIT_QUICKLY_DELAY: Duration = Duration::from_secs(2);
+
+const TAB_WIDTH: usize = 8;
+
+/// Kind of the section started by a marker.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum SectionKind {
+    Commit,
+    File,
+}
+
+impl SectionKind {
+    /// Marker embedded in the output until [`PagerWriter`] removes it. This is
+    /// an Application Program Command escape sequence, which terminals ignore.
+    fn marker(self) -> &'static [u8] {
+        match self {
+            SectionKind::Commit => b"\x1b_jj:commit\x1b\\",
+            SectionKind::File => b"\x1b_jj:file\x1b\\",
+        }
+    }
+
+    /// Parses the marker at the start of `data`.
+    fn from_marker(data: &[u8]) -> Option<Self> {
+        [SectionKind::Commit, SectionKind::File]
+            .into_iter()
+            .find(|kind| data.starts_with(kind.marker()))
+    }
+}
+
+/// Formatter that marks the start of log entries and diff files for the
+/// builtin pager.
+pub struct SectionMarkingFormatter<'a> {
+    inner: Box<dyn Formatter + 'a>,
+    labels: Vec<String>,
+}
+
+impl<'a> SectionMarkingFormatter<'a> {
+    pub fn new(inner: Box<dyn Formatter + 'a>) -> Self {
+        SectionMarkingFormatter {
+            inner,
+            labels: vec![],
+        }
+    }
+
+    fn section_kind(&self, label: &str) -> Option<SectionKind> {
+        match label {
+            "log" | "op_log" => Some(SectionKind::Commit),
+            "header" | "file_header" if self.labels.iter().any(|l| l == "diff") => {
+                Some(SectionKind::File)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Write for SectionMarkingFormatter<'_> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.inner.write(data)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl Formatter for SectionMarkingFormatter<'_> {
+    fn raw(&mut self) -> io::Result<Box<dyn Write + '_>> {
+        self.inner.raw()
+    }
+
+    fn push_label(&mut self, label: &str) -> io::Result<()> {
+        if let Some(kind) = self.section_kind(label) {
+            self.inner.raw()?.write_all(kind.marker())?;
+        }
+        self.labels.push(label.to_owned());
+        self.inner.push_label(label)
+    }
+
+    fn pop_label(&mut self) -> io::Result<()> {
+        self.labels.pop();
+        self.inner.pop_label()
+    }
+
+    fn push_hyperlink(&mut self, url: &str) -> io::Result<()> {
+        self.inner.push_hyperlink(url)
+    }
+
+    fn pop_hyperlink(&mut self) -> io::Result<()> {
+        self.inner.pop_hyperlink()
+    }
+}
+
+/// Offsets in the stdout stream where sections start.
+pub struct SectionMarks(Receiver<(u64, SectionKind)>);
+
+/// Writer to the stdout of the builtin pager.
+///
+/// The markers embedded by [`SectionMarkingFormatter`] aren't written to the
+/// pipe, but sent to the pager along with their offsets in the stream.
+pub struct PagerWriter {
+    pipe: PipeWriter,
+    // Number of bytes written to the pipe, and the channel of the markers
+    sections: Mutex<(u64, Sender<(u64, SectionKind)>)>,
+}
+
+impl PagerWriter {
+    pub fn new(pipe: PipeWriter) -> (Self, SectionMarks) {
+        let (sender, receiver) = mpsc::channel();
+        let writer = PagerWriter {
+            pipe,
+            sections: Mutex::new((0, sender)),
+        };
+        (writer, SectionMarks(receiver))
+    }
+}
+
+impl Write for &PagerWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let mut sections = self.sections.lock().unwrap();
+        let (offset, sender) = &mut *sections;
+        if let Some(kind) = SectionKind::from_marker(data) {
+            // The marker is sent before the data following it is written, so
+            // the pager receives it by the time it reads the data. The pager
+            // might have quit.
+            sender.send((*offset, kind)).ok();
+            return Ok(kind.marker().len());
+        }
+        let end = (1..data.len())
+            .find(|&i| SectionKind::from_marker(&data[i..]).is_some())
+            .unwrap_or(data.len());
+        let written = (&self.pipe).write(&data[..end])?;
+        *offset += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (&self.pipe).flush()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// Line read from the output of the command.
+#[derive(Clone, Debug)]
+struct InputLine {
+    stream: Stream,
+    data: Vec<u8>,
+    section: Option<SectionKind>,
+}
+
+/// Line of the input with the escape sequences interpreted.
+#[derive(Clone, Debug, Default)]
+struct PagerLine {
+    text: String,
+    /// Styles starting at the byte offsets of the `text`.
+    styles: Vec<(usize, Style)>,
+    section: Option<SectionKind>,
+    /// Start of the file section this line belongs to.
+    file_start: Option<usize>,
+}
+
+impl PagerLine {
+    fn style_at(&self, offset: usize) -> Style {
+        let index = self.styles.partition_point(|&(start, _)| start <= offset);
+        index
+            .checked_sub(1)
+            .map_or_else(Style::default, |i| self.styles[i].1)
+    }
+}
+
+/// Parses a line of text with ANSI escape sequences. The `style` is carried
+/// over from the previous line of the same stream.
+fn parse_line(data: &[u8], style: &mut Style) -> PagerLine {
+    let mut line = PagerLine::default();
+    let mut column = 0;
+    let mut rest = data;
+    while !rest.is_empty() {
+        if rest[0] == b'\x1b' {
+            rest = parse_escape_sequence(rest, style);
+            continue;
+        }
+        let end = rest
+            .iter()
+            .position(|&b| b == b'\x1b')
+            .unwrap_or(rest.len());
+        let text = String::from_utf8_lossy(&rest[..end]);
+        if line.styles.last().is_none_or(|&(_, last)| last != *style) {
+            line.styles.push((line.text.len(), *style));
+        }
+        for c in text.chars() {
+            match c {
+                '\t' => {
+                    let n = TAB_WIDTH - column % TAB_WIDTH;
+                    line.text.extend(std::iter::repeat_n(' ', n));
+                    column += n;
+                }
+                c if c.is_control() => {}
+                c => {
+                    line.text.push(c);
+                    column += c.width().unwrap_or(0);
+                }
+            }
+        }
+        rest = &rest[end..];
+    }
+    line
+}
+
+/// Interprets the escape sequence at the start of `data`, and returns the
+/// remainder.
+fn parse_escape_sequence<'a>(data: &'a [u8], style: &mut Style) -> &'a [u8] {
+    // String terminator is either BEL or ESC '\'
+    let find_string_end = |body: &[u8]| {
+        body.iter()
+            .enumerate()
+            .find_map(|(i, &b)| match b {
+                b'\x07' => Some((i, i + 1)),
+                b'\x1b' if body.get(i + 1) == Some(&b'\\') => Some((i, i + 2)),
+                _ => None,
+            })
+            .unwrap_or((body.len(), body.len()))
+    };
+    match data.get(1) {
+        Some(b'[') => {
+            let body = &data[2..];
+            let Some(end) = body.iter().position(|b| (0x40..=0x7e).contains(b)) else {
+                return &[];
+            };
+            if body[end] == b'm' {
+                apply_sgr(&body[..end], style);
+            }
+            &body[end + 1..]
+        }
+        Some(b']' | b'_') => {
+            // Operating System Command, such as hyperlinks, or Application
+            // Program Command
+            let body = &data[2..];
+            &body[find_string_end(body).1..]
+        }
+        Some(_) => &data[2..],
+        None => &[],
+    }
+}
+
+/// Applies Select Graphic Rendition parameters to the `style`.
+fn apply_sgr(params: &[u8], style: &mut Style) {
+    let params: Vec<u8> = params
+        .split(|&b| b == b';' || b == b':')
+        .map(|param| {
+            std::str::from_utf8(param)
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(0)
+        })
+        .collect();
+    let mut iter = params.iter().copied();
+    while let Some(param) = iter.next() {
+        *style = match param {
+            0 => Style::default(),
+            1 => style.add_modifier(Modifier::BOLD),
+            2 => style.add_modifier(Modifier::DIM),
+            3 => style.add_modifier(Modifier::ITALIC),
+            4 => style.add_modifier(Modifier::UNDERLINED),
+            5 => style.add_modifier(Modifier::SLOW_BLINK),
+            7 => style.add_modifier(Modifier::REVERSED),
+            9 => style.add_modifier(Modifier::CROSSED_OUT),
+            22 => style.remove_modifier(Modifier::BOLD | Modifier::DIM),
+            23 => style.remove_modifier(Modifier::ITALIC),
+            24 => style.remove_modifier(Modifier::UNDERLINED),
+            25 => style.remove_modifier(Modifier::SLOW_BLINK),
+            27 => style.remove_modifier(Modifier::REVERSED),
+            29 => style.remove_modifier(Modifier::CROSSED_OUT),
+            30..=37 => style.fg(Color::Indexed(param - 30)),
+            38 => style.fg(parse_extended_color(&mut iter)),
+            39 => style.fg(Color::Reset),
+            40..=47 => style.bg(Color::Indexed(param - 40)),
+            48 => style.bg(parse_extended_color(&mut iter)),
+            49 => style.bg(Color::Reset),
+            90..=97 => style.fg(Color::Indexed(param - 90 + 8)),
+            100..=107 => style.bg(Color::Indexed(param - 100 + 8)),
+            _ => *style,
+        };
+    }
+}
+
+fn parse_extended_color(iter: &mut impl Iterator<Item = u8>) -> Color {
+    match iter.next() {
+        Some(5) => Color::Indexed(iter.next().unwrap_or(0)),
+        Some(2) => {
+            let mut next = || iter.next().unwrap_or(0);
+            Color::Rgb(next(), next(), next())
+        }
+        _ => Color::Reset,
+    }
+}
+
+/// Converts text with ANSI escape sequences to styled lines.
+pub fn ansi_to_lines(data: &[u8]) -> Vec<Line<'static>> {
+    if data.is_empty() {
+        return vec![];
+    }
+    let data = data.strip_suffix(b"\n").unwrap_or(data);
+    let mut style = Style::default();
+    data.split(|&b| b == b'\n')
+        .map(|data| {
+            let line = parse_line(data, &mut style);
+            let ends = line
+                .styles
+                .iter()
+                .skip(1)
+                .map(|&(start, _)| start)
+                .chain([line.text.len()]);
+            let spans: Vec<Span> = zip(&line.styles, ends)
+                .map(|(&(start, style), end)| Span::styled(line.text[start..end].to_owned(), style))
+                .collect();
+            Line::from(spans)
+        })
+        .collect()
+}
+
+/// Splits the `text` into rows of the given display `width`. Returns the byte
+/// ranges of the rows.
+fn wrap_text(text: &str, width: usize, mode: WrappingMode) -> Vec<Range<usize>> {
+    let width = match mode {
+        WrappingMode::None => usize::MAX,
+        WrappingMode::Word | WrappingMode::Anywhere => width.max(1),
+    };
+    let mut rows = vec![];
+    let mut start = 0;
+    let mut column = 0;
+    // Position after the last whitespace in the current row
+    let mut word_break = None;
+    for (i, c) in text.char_indices() {
+        let char_width = c.width().unwrap_or(0);
+        if column + char_width > width && i > start {
+            let end = match word_break {
+                Some(end) if mode == WrappingMode::Word => end,
+                _ => i,
+            };
+            rows.push(start..end);
+            column = text[end..i].chars().filter_map(|c| c.width()).sum();
+            start = end;
+            word_break = None;
+        }
+        column += char_width;
+        if c.is_whitespace() {
+            word_break = Some(i + c.len_utf8());
+        }
+    }
+    rows.push(start..text.len());
+    rows
+}
+
+/// Finds the occurrences of the `pattern`. The search is case-insensitive
+/// unless the pattern contains uppercase characters.
+fn find_matches(text: &str, pattern: &str) -> Vec<Range<usize>> {
+    if pattern.is_empty() {
+        return vec![];
+    }
+    let ignore_case = !pattern.chars().any(char::is_uppercase);
+    let fold = |c: char| {
+        if ignore_case {
+            c.to_lowercase().next().unwrap_or(c)
+        } else {
+            c
+        }
+    };
+    let mut matches = vec![];
+    let mut start = 0;
+    while let Some(first) = text[start..].chars().next() {
+        let mut chars = text[start..].char_indices();
+        let matched = pattern
+            .chars()
+            .all(|p| chars.next().is_some_and(|(_, c)| fold(c) == fold(p)));
+        if matched {
+            let len = chars.next().map_or(text.len() - start, |(i, _)| i);
+            matches.push(start..start + len);
+            start += len;
+        } else {
+            start += first.len_utf8();
+        }
+    }
+    matches
+}
+
+/// Top-left position of the view.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+struct Position {
+    line: usize,
+    row: usize,
+}
+
+#[derive(Clone, Debug)]
+struct SearchPrompt {
+    input: String,
+    forward: bool,
+    origin: Position,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Outcome {
+    Continue,
+    Quit,
+}
+
+struct PagerState {
+    lines: Vec<PagerLine>,
+    stream_styles: [Style; 2],
+    current_file: Option<usize>,
+    eof: bool,
+    wrapping: WrappingMode,
+    /// Start lines of the folded file sections.
+    folded: HashSet<usize>,
+    top: Position,
+    left: usize,
+    width: usize,
+    height: usize,
+    search: Option<String>,
+    prompt: Option<SearchPrompt>,
+    message: Option<String>,
+    show_help: bool,
+}
+
+impl PagerState {
+    fn new(wrapping: WrappingMode) -> Self {
+        PagerState {
+            lines: vec![],
+            stream_styles: [Style::default(); 2],
+            current_file: None,
+            eof: false,
+            wrapping,
+            folded: HashSet::new(),
+            top: Position { line: 0, row: 0 },
+            left: 0,
+            width: 80,
+            height: 24,
+            search: None,
+            prompt: None,
+            message: None,
+            show_help: false,
+        }
+    }
+
+    fn push_line(&mut self, input: &InputLine) {
+        let style = &mut self.stream_styles[input.stream as usize];
+        let mut line = parse_line(&input.data, style);
+        if input.stream == Stream::Stdout {
+            line.section = input.section;
+            match line.section {
+                Some(SectionKind::Commit) => self.current_file = None,
+                Some(SectionKind::File) => self.current_file = Some(self.lines.len()),
+                None => {}
+            }
+            line.file_start = self.current_file;
+        }
+        self.lines.push(line);
+    }
+
+    fn is_hidden(&self, index: usize) -> bool {
+        self.lines[index]
+            .file_start
+            .is_some_and(|start| start != index && self.folded.contains(&start))
+    }
+
+    fn next_visible(&self, index: usize) -> Option<usize> {
+        (index + 1..self.lines.len()).find(|&i| !self.is_hidden(i))
+    }
+
+    fn prev_visible(&self, index: usize) -> Option<usize> {
+        (0..index.min(self.lines.len()))
+            .rev()
+            .find(|&i| !self.is_hidden(i))
+    }
+
+    fn rows(&self, index: usize) -> Vec<Range<usize>> {
+        wrap_text(&self.lines[index].text, self.width, self.wrapping)
+    }
+
+    fn row_count(&self, index: usize) -> usize {
+        if self.wrapping == WrappingMode::None {
+            1
+        } else {
+            self.rows(index).len()
+        }
+    }
+
+    /// Number of lines in the folded section starting at `index`.
+    fn folded_line_count(&self, index: usize) -> usize {
+        self.lines[index + 1..]
+            .iter()
+            .take_while(|line| line.file_start == Some(index) && line.section.is_none())
+            .count()
+    }
+
+    /// Last position where the view is filled with the content.
+    fn bottom(&self) -> Position {
+        let Some(last) = self.prev_visible(self.lines.len()) else {
+            return Position { line: 0, row: 0 };
+        };
+        let mut pos = Position {
+            line: last,
+            row: self.row_count(last) - 1,
+        };
+        for _ in 1..self.height {
+            match self.step_up(pos) {
+                Some(prev) => pos = prev,
+                None => break,
+            }
+        }
+        pos
+    }
+
+    fn step_down(&self, pos: Position) -> Option<Position> {
+        if pos.row + 1 < self.row_count(pos.line) {
+            Some(Position {
+                line: pos.line,
+                row: pos.row + 1,
+            })
+        } else {
+            let line = self.next_visible(pos.line)?;
+            Some(Position { line, row: 0 })
+        }
+    }
+
+    fn step_up(&self, pos: Position) -> Option<Position> {
+        if pos.row > 0 {
+            Some(Position {
+                line: pos.line,
+                row: pos.row - 1,
+            })
+        } else {
+            let line = self.prev_visible(pos.line)?;
+            let row = self.row_count(line) - 1;
+            Some(Position { line, row })
+        }
+    }
+
+    fn scroll_down(&mut self, rows: usize) {
+        let bottom = self.bottom();
+        for _ in 0..rows {
+            if self.top >= bottom {
+                break;
+            }
+            match self.step_down(self.top) {
+                Some(pos) => self.top = pos,
+                None => break,
+            }
+        }
+    }
+
+    fn scroll_up(&mut self, rows: usize) {
+        for _ in 0..rows {
+            match self.step_up(self.top) {
+                Some(pos) => self.top = pos,
+                None => break,
+            }
+        }
+    }
+
+    /// Moves the view so the line at `index` is at the top.
+    fn jump_to(&mut self, index: usize) {
+        if let Some(start) = self.lines[index].file_start {
+            if start != index {
+                self.folded.remove(&start);
+            }
+        }
+        self.top = Position {
+            line: index,
+            row: 0,
+        };
+        self.clamp_top();
+    }
+
+    /// Moves the view back into the content after lines are folded or the
+    /// screen is resized.
+    fn clamp_top(&mut self) {
+        if self.lines.is_empty() {
+            self.top = Position { line: 0, row: 0 };
+            return;
+        }
+        if self.is_hidden(self.top.line) {
+            let start = self.lines[self.top.line].file_start.unwrap();
+            self.top = Position {
+                line: start,
+                row: 0,
+            };
+        }
+        self.top.row = self.top.row.min(self.row_count(self.top.line) - 1);
+        self.top = self.top.min(self.bottom());
+    }
+
+    fn jump_to_section(&mut self, kind: SectionKind, forward: bool) {
+        let is_section = |line: &PagerLine| line.section == Some(kind);
+        let found = if forward {
+            (self.top.line + 1..self.lines.len()).find(|&i| is_section(&self.lines[i]))
+        } else {
+            (0..self.top.line.min(self.lines.len()))
+                .rev()
+                .find(|&i| is_section(&self.lines[i]))
+        };
+        match found {
+            Some(index) => self.jump_to(index),
+            None => {
+                let name = match kind {
+                    SectionKind::Commit => "commit",
+                    SectionKind::File => "file",
+                };
+                let direction = if forward { "next" } else { "previous" };
+                self.message = Some(format!("No {direction} {name}"));
+            }
+        }
+    }
+
+    /// Folds or unfolds the file section at the top of the view.
+    fn toggle_fold(&mut self) {
+        let start = self
+            .lines
+            .get(self.top.line)
+            .and_then(|line| line.file_start);
+        let Some(start) = start else {
+            self.message = Some("Not in a file diff".to_owned());
+            return;
+        };
+        if !self.folded.remove(&start) {
+            self.folded.insert(start);
+            self.top = Position {
+                line: start,
+                row: 0,
+            };
+        }
+        self.clamp_top();
+    }
+
+    /// Folds all file sections, or unfolds them if all are folded.
+    fn toggle_fold_all(&mut self) {
+        let starts: Vec<usize> = (0..self.lines.len())
+            .filter(|&i| self.lines[i].section == Some(SectionKind::File))
+            .collect();
+        if starts.iter().all(|start| self.folded.contains(start)) {
+            self.folded.clear();
+        } else {
+            self.folded.extend(starts);
+        }
+        self.clamp_top();
+    }
+
+    fn find_line(&self, pattern: &str, from: usize, forward: bool) -> Option<usize> {
+        let matches = |i: &usize| !find_matches(&self.lines[*i].text, pattern).is_empty();
+        if forward {
+            (from..self.lines.len()).find(matches)
+        } else {
+            (0..(from + 1).min(self.lines.len())).rev().find(matches)
+        }
+    }
+
+    fn search_next(&mut self, forward: bool) {
+        let Some(pattern) = self.search.clone() else {
+            self.message = Some("No previous search".to_owned());
+            return;
+        };
+        let from = if forward {
+            self.top.line + 1
+        } else if let Some(from) = self.top.line.checked_sub(1) {
+            from
+        } else {
+            self.message = Some("Pattern not found".to_owned());
+            return;
+        };
+        match self.find_line(&pattern, from, forward) {
+            Some(index) => self.jump_to(index),
+            None => self.message = Some("Pattern not found".to_owned()),
+        }
+    }
+
+    /// Moves the view to the first match of the search being typed.
+    fn update_incremental_search(&mut self) {
+        let Some(prompt) = &self.prompt else {
+            return;
+        };
+        let origin = prompt.origin;
+        let found = if prompt.input.is_empty() {
+            None
+        } else {
+            self.find_line(&prompt.input, origin.line, prompt.forward)
+        };
+        self.search = Some(prompt.input.clone()).filter(|input| !input.is_empty());
+        match found {
+            Some(index) => {
+                self.message = None;
+                self.jump_to(index);
+            }
+            None => {
+                if !prompt.input.is_empty() {
+                    self.message = Some("Pattern not found".to_owned());
+                }
+                self.top = origin;
+                self.clamp_top();
+            }
+        }
+    }
+
+    fn handle_prompt_key(&mut self, key: KeyEvent) {
+        let Some(prompt) = &mut self.prompt else {
+            return;
+        };
+        match key.code {
+            KeyCode::Enter => {
+                self.prompt = None;
+                return;
+            }
+            KeyCode::Esc => {
+                let origin = prompt.origin;
+                self.prompt = None;
+                self.search = None;
+                self.message = None;
+                self.top = origin;
+                self.clamp_top();
+                return;
+            }
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.prompt = None;
+                return;
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                prompt.input.clear();
+            }
+            KeyCode::Backspace => {
+                if prompt.input.pop().is_none() {
+                    self.prompt = None;
+                    return;
+                }
+            }
+            KeyCode::Char(c) => prompt.input.push(c),
+            _ => return,
+        }
+        self.update_incremental_search();
+    }
+
+    fn render_row(&self, index: usize, range: Range<usize>) -> Line<'static> {
+        let line = &self.lines[index];
+        let mut range = range;
+        if self.wrapping == WrappingMode::None {
+            let mut column = 0;
+            let skip = line.text[range.clone()]
+                .char_indices()
+                .find(|(_, c)| {
+                    column += c.width().unwrap_or(0);
+                    column > self.left
+                })
+                .map_or(range.len(), |(i, _)| i);
+            range.start += skip;
+        }
+        let matches = self
+            .search
+            .as_deref()
+            .map(|pattern| find_matches(&line.text, pattern))
+            .unwrap_or_default();
+        // Split the row at the boundaries of styles and matches
+        let mut boundaries: Vec<usize> = itertools::chain(
+            line.styles.iter().map(|&(start, _)| start),
+            matches.iter().flat_map(|m| [m.start, m.end]),
+        )
+        .filter(|offset| range.contains(offset))
+        .chain([range.start, range.end])
+        .collect();
+        boundaries.sort_unstable();
+        boundaries.dedup();
+        let spans: Vec<Span> = boundaries
+            .windows(2)
+            .map(|w| {
+                let mut style = line.style_at(w[0]);
+                if matches.iter().any(|m| m.contains(&w[0])) {
+                    style = style.add_modifier(Modifier::REVERSED);
+                }
+                Span::styled(line.text[w[0]..w[1]].to_owned(), style)
+            })
+            .collect();
+        Line::from(spans)
+    }
+
+    fn render_help(&self, frame: &mut Frame, area: Rect) {
+        const HELP: &[(&str, &str)] = &[
+            ("q, Ctrl-c", "Quit"),
+            ("j, k, Up, Down", "Scroll one line"),
+            ("Space, b, PgDn, PgUp", "Scroll one screen"),
+            ("d, u", "Scroll half a screen"),
+            ("g, G, Home, End", "Go to the start or end"),
+            ("Left, Right", "Scroll horizontally"),
+            ("\\", "Cycle line wrapping modes"),
+            ("/, ?", "Search forward or backward"),
+            ("n, N", "Go to the next or previous match"),
+            ("], [", "Go to the next or previous file"),
+            ("}, {", "Go to the next or previous commit"),
+            ("Tab", "Fold or unfold the current file"),
+            ("Shift-Tab", "Fold or unfold all files"),
+        ];
+        let lines: Vec<Line> = HELP
+            .iter()
+            .map(|(keys, action)| Line::from(format!("{keys:<22}{action}")))
+            .collect();
+        let width = (area.width).min(60);
+        let height = (area.height).min(lines.len() as u16 + 2);
+        let popup = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+        frame.render_widget(Clear, popup);
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title(" Key bindings ")),
+            popup,
+        );
+    }
+
+    /// Reads the available input without blocking. Returns true if anything
+    /// was read.
+    fn receive_input(&mut self, receiver: &Receiver<InputLine>) -> bool {
+        // Don't block the interface while reading long output
+        const MAX_LINES: usize = 10000;
+        let mut received = false;
+        for _ in 0..MAX_LINES {
+            match receiver.try_recv() {
+                Ok(line) => {
+                    self.push_line(&line);
+                    received = true;
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    received |= !self.eof;
+                    self.eof = true;
+                    break;
+                }
+            }
+        }
+        received
+    }
+}
+
+impl TuiState for PagerState {
+    type Outcome = Outcome;
+
+    fn handle_key(&mut self, key: KeyEvent) -> Outcome {
+        if self.prompt.is_some() {
+            self.handle_prompt_key(key);
+            return Outcome::Continue;
+        }
+        if self.show_help {
+            self.show_help = false;
+            return Outcome::Continue;
+        }
+        self.message = None;
+        let page = self.height.max(1);
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Char('c') if ctrl => return Outcome::Quit,
+            KeyCode::Char('n' | 'e') if ctrl => self.scroll_down(1),
+            KeyCode::Char('p' | 'y') if ctrl => self.scroll_up(1),
+            KeyCode::Char('f') if ctrl => self.scroll_down(page),
+            KeyCode::Char('b') if ctrl => self.scroll_up(page),
+            KeyCode::Char('d') if ctrl => self.scroll_down(page / 2),
+            KeyCode::Char('u') if ctrl => self.scroll_up(page / 2),
+            KeyCode::Char('q' | 'Q') => return Outcome::Quit,
+            KeyCode::Esc => self.search = None,
+            KeyCode::Char('j') | KeyCode::Down | KeyCode::Enter => self.scroll_down(1),
+            KeyCode::Char('k') | KeyCode::Up => self.scroll_up(1),
+            KeyCode::Char(' ' | 'f') | KeyCode::PageDown => self.scroll_down(page),
+            KeyCode::Char('b') | KeyCode::PageUp => self.scroll_up(page),
+            KeyCode::Char('d') => self.scroll_down(page / 2),
+            KeyCode::Char('u') => self.scroll_up(page / 2),
+            KeyCode::Char('g' | '<') | KeyCode::Home => {
+                self.top = Position { line: 0, row: 0 };
+                self.clamp_top();
+            }
+            KeyCode::Char('G' | '>') | KeyCode::End => self.top = self.bottom(),
+            KeyCode::Left => self.left = self.left.saturating_sub(self.width / 4),
+            KeyCode::Right if self.wrapping == WrappingMode::None => {
+                self.left += self.width / 4;
+            }
+            KeyCode::Char('\\') => {
+                self.wrapping = self.wrapping.next();
+                self.left = 0;
+                self.top.row = 0;
+                self.clamp_top();
+                let name = match self.wrapping {
+                    WrappingMode::None => "none",
+                    WrappingMode::Word => "word",
+                    WrappingMode::Anywhere => "anywhere",
+                };
+                self.message = Some(format!("Wrapping: {name}"));
+            }
+            KeyCode::Char(c @ ('/' | '?')) => {
+                self.prompt = Some(SearchPrompt {
+                    input: String::new(),
+                    forward: c == '/',
+                    origin: self.top,
+                });
+            }
+            KeyCode::Char('n') => self.search_next(true),
+            KeyCode::Char('N') => self.search_next(false),
+            KeyCode::Char(']') => self.jump_to_section(SectionKind::File, true),
+            KeyCode::Char('[') => self.jump_to_section(SectionKind::File, false),
+            KeyCode::Char('}') => self.jump_to_section(SectionKind::Commit, true),
+            KeyCode::Char('{') => self.jump_to_section(SectionKind::Commit, false),
+            KeyCode::Tab => self.toggle_fold(),
+            KeyCode::BackTab => self.toggle_fold_all(),
+            KeyCode::Char('h') | KeyCode::F(1) => self.show_help = true,
+            _ => {}
+        }
+        Outcome::Continue
+    }
+
+    /// Builds the styled spans of the `range` of the line at `index`.
+    fn render(&self, frame: &mut Frame) {
+        let [content_area, status_area] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+
+        let mut rows = vec![];
+        let mut pos = Some(self.top).filter(|pos| pos.line < self.lines.len());
+        let mut last_line = None;
+        while let Some(current) = pos {
+            if rows.len() >= content_area.height as usize {
+                break;
+            }
+            let ranges = self.rows(current.line);
+            let mut row = self.render_row(current.line, ranges[current.row].clone());
+            if current.row + 1 == ranges.len() && self.folded.contains(&current.line) {
+                let count = self.folded_line_count(current.line);
+                row.push_span(Span::styled(
+                    format!(" [{count} lines folded]"),
+                    Style::new().add_modifier(Modifier::DIM),
+                ));
+            }
+            rows.push(row);
+            last_line = Some(current.line);
+            pos = self.step_down(current);
+        }
+        frame.render_widget(Paragraph::new(rows), content_area);
+
+        let status = if let Some(prompt) = &self.prompt {
+            let prefix = if prompt.forward { '/' } else { '?' };
+            let message = self
+                .message
+                .as_deref()
+                .map(|message| format!("  ({message})"))
+                .unwrap_or_default();
+            format!("{prefix}{}{message}", prompt.input)
+        } else if let Some(message) = &self.message {
+            message.clone()
+        } else {
+            let first = self.top.line.min(self.lines.len()) + 1;
+            let last = last_line.map_or(0, |line| line + 1);
+            let end = if !self.eof {
+                " (reading)"
+            } else if pos.is_none() {
+                " (END)"
+            } else {
+                ""
+            };
+            format!(
+                "lines {first}-{last} of {total}{end}  (h: help, q: quit)",
+                total = self.lines.len()
+            )
+        };
+        frame.render_widget(
+            Paragraph::new(status).style(Style::new().add_modifier(Modifier::REVERSED)),
+            status_area,
+        );
+
+        if self.show_help {
+            self.render_help(frame, content_area);
+        }
+    }
+}
+
+fn spawn_reader(
+    input: impl Read + Send + 'static,
+    stream: Stream,
+    sections: Option<SectionMarks>,
+    sender: Sender<InputLine>,
+) {
+    thread::spawn(move || read_input(input, stream, sections.as_ref(), &sender));
+}
+
+/// Reads the `input` line by line. Each line starts the section of the first
+/// marker within it.
+fn read_input(
+    input: impl Read,
+    stream: Stream,
+    sections: Option<&SectionMarks>,
+    sender: &Sender<InputLine>,
+) {
+    let mut reader = BufReader::new(input);
+    let mut offset = 0;
+    let mut next_mark = None;
+    loop {
+        let mut data = vec![];
+        match reader.read_until(b'\n', &mut data) {
+            Ok(0) | Err(_) => break,
+            Ok(len) => {
+                offset += len as u64;
+                let mut section = None;
+                // Markers within the line have been sent before the line
+                while let Some((mark_offset, kind)) = next_mark
+                    .take()
+                    .or_else(|| sections.and_then(|marks| marks.0.try_recv().ok()))
+                {
+                    if mark_offset >= offset {
+                        next_mark = Some((mark_offset, kind));
+                        break;
+                    }
+                    section.get_or_insert(kind);
+                }
+                if data.ends_with(b"\n") {
+                    data.pop();
+                }
+                let line = InputLine {
+                    stream,
+                    data,
+                    section,
+                };
+                // Stop reading once the pager quits
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Runs the pager until the user quits. The `output` and `error` streams are
+/// read until they are closed. The `sections` are received from the
+/// [`PagerWriter`] of the `output`.
+pub fn run_pager(
+    config: BuiltinPagerConfig,
+    output: impl Read + Send + 'static,
+    sections: SectionMarks,
+    error: impl Read + Send + 'static,
+) -> io::Result<()> {
+    let (sender, receiver) = mpsc::channel();
+    spawn_reader(output, Stream::Stdout, Some(sections), sender.clone());
+    spawn_reader(error, Stream::Stderr, None, sender);
+
+    let mut state = PagerState::new(config.wrapping);
+    let (width, height) = crossterm::terminal::size()?;
+    state.width = width.into();
+    state.height = height.into();
+
+    let deadline = match config.interface {
+        PagerInterface::QuitIfOnePage => None,
+        PagerInterface::FullScreenClearOutput => Some(Instant::now()),
+        PagerInterface::QuitQuicklyOrClearOutput => Some(Instant::now() + QUIT_QUICKLY_DELAY),
+    };
+    // Buffer the input until it turns out not to fit in the screen
+    let mut buffered = vec![];
+    let mut buffered_rows = 0;
+    while buffered_rows < state.height {
+        let received = match deadline {
+            None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            Some(deadline) => {
+                receiver.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            }
+        };
+        match received {
+            Ok(line) => {
+                state.push_line(&line);
+                buffered_rows += wrap_text(
+                    &state.lines.last().unwrap().text,
+                    state.width,
+                    WrappingMode::Anywhere,
+                )
+                .len();
+                buffered.push(line);
+            }
+            Err(RecvTimeoutError::Timeout) => break,
+            Err(RecvTimeoutError::Disconnected) => {
+                return print_lines(&buffered);
+            }
+        }
+    }
+    drop(buffered);
+
+    tui_util::with_terminal(|terminal| {
+        let mut dirty = true;
+        loop {
+            dirty |= state.receive_input(&receiver);
+            let size = terminal.size()?;
+            state.width = size.width.into();
+            state.height = size.height.saturating_sub(1).into();
+            if dirty {
+                terminal.draw(|frame| state.render(frame))?;
+                dirty = false;
+            }
+            if !event::poll(Duration::from_millis(50))? {
+                continue;
+            }
+            match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => {
+                    if state.handle_key(key) == Outcome::Quit {
+                        return Ok(());
+                    }
+                    dirty = true;
+                }
+                Event::Resize(width, height) => {
+                    state.width = width.into();
+                    state.height = height.saturating_sub(1).into();
+                    state.clamp_top();
+                    dirty = true;
+                }
+                _ => {}
+            }
+        }
+    })
+}
+
+/// Prints the input which fits in the screen as is.
+fn print_lines(lines: &[InputLine]) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    let mut stderr = io::stderr().lock();
+    for line in lines {
+        let output: &mut dyn Write = match line.stream {
+            Stream::Stdout => &mut stdout,
+            Stream::Stderr => &mut stderr,
+        };
+        output.write_all(&line.data)?;
+        output.write_all(b"\n")?;
+    }
+    stdout.flush()?;
+    stderr.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formatter::PlainTextFormatter;
+    use crate::tui_util::testing::key;
+    use crate::tui_util::testing::type_keys;
+
+    /// Reads the `input` as the output of a command.
+    fn read_lines(input: &[u8]) -> Vec<InputLine> {
+        let (reader, writer) = os_pipe::pipe().unwrap();
+        let (writer, sections) = PagerWriter::new(writer);
+        (&writer).write_all(input).unwrap();
+        drop(writer);
+        let (sender, receiver) = mpsc::channel();
+        read_input(reader, Stream::Stdout, Some(&sections), &sender);
+        drop(sender);
+        receiver.into_iter().collect()
+    }
+
+    fn new_state(input: &[u8], width: usize, height: usize) -> PagerState {
+        let mut state = PagerState::new(WrappingMode::Anywhere);
+        state.width = width;
+        state.height = height;
+        for line in read_lines(input) {
+            state.push_line(&line);
+        }
+        state.eof = true;
+        state
+    }
+
+    fn render(state: &PagerState) -> String {
+        tui_util::testing::render(state, state.width as u16, state.height as u16 + 1)
+    }
+
+    fn sample_input() -> Vec<u8> {
+        let mut input = vec![];
+        for (commit, files) in [("aaa", ["foo", "bar"]), ("bbb", ["baz", "qux"])] {
+            input.extend(SectionKind::Commit.marker());
+            input.extend(format!("commit {commit}\n").as_bytes());
+            for file in files {
+                input.extend(SectionKind::File.marker());
+                input.extend(format!("Modified regular file {file}:\n").as_bytes());
+                input.extend(format!("   1    1: {file} line 1\n").as_bytes());
+                input.extend(format!("   2    2: {file} line 2\n").as_bytes());
+            }
+        }
+        input.pop();
+        input
+    }
+
+    #[test]
+    fn test_section_marking_formatter() {
+        let mut output = vec![];
+        {
+            let mut formatter =
+                SectionMarkingFormatter::new(Box::new(PlainTextFormatter::new(&mut output)));
+            let formatter: &mut dyn Formatter = &mut formatter;
+            write!(formatter.labeled("log"), "commit").unwrap();
+            // Headers outside of diffs aren't marked
+            write!(formatter.labeled("header"), " header").unwrap();
+            formatter
+                .with_label("diff", |formatter| {
+                    write!(formatter.labeled("header"), " file")
+                })
+                .unwrap();
+        }
+        assert_eq!(
+            output,
+            b"\x1b_jj:commit\x1b\\commit header\x1b_jj:file\x1b\\ file"
+        );
+    }
+
+    #[test]
+    fn test_pager_writer() {
+        let lines =
+            read_lines(b"\x1b_jj:commit\x1b\\a\nb\x1b_jj:file\x1b\\c\n\nd\x1b_jj:file\x1b\\");
+        let lines = lines
+            .iter()
+            .map(|line| (std::str::from_utf8(&line.data).unwrap(), line.section))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            [
+                ("a", Some(SectionKind::Commit)),
+                ("bc", Some(SectionKind::File)),
+                ("", None),
+                ("d", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_line() {
+        let mut style = Style::default();
+        let line = parse_line(
+            b"\x1b_unknown\x1b\\\x1b[1m\x1b[38;5;2mbold\x1b[0m\tplain \x1b]8;;http://x\x1b\\link\x1b]8;;\x1b\\ \x1b[31mred",
+            &mut style,
+        );
+        assert_eq!(line.text, "bold    plain link red");
+        assert_eq!(
+            line.styles,
+            [
+                (
+                    0,
+                    Style::new()
+                        .fg(Color::Indexed(2))
+                        .add_modifier(Modifier::BOLD)
+                ),
+                (4, Style::default()),
+                (19, Style::new().fg(Color::Indexed(1))),
+            ]
+        );
+        // The style is carried over to the next line
+        assert_eq!(style, Style::new().fg(Color::Indexed(1)));
+    }
+
+    #[test]
+    fn test_wrap_text() {
+        fn rows(text: &str, mode: WrappingMode) -> Vec<&str> {
+            wrap_text(text, 10, mode)
+                .into_iter()
+                .map(|range| &text[range])
+                .collect()
+        }
+        let text = "the quick brown fox jumps";
+        assert_eq!(
+            rows(text, WrappingMode::Anywhere),
+            ["the quick ", "brown fox ", "jumps"]
+        );
+        assert_eq!(
+            rows("a quick brownfox jumps", WrappingMode::Word),
+            ["a quick ", "brownfox ", "jumps"]
+        );
+        assert_eq!(
+            rows("averyveryverylongword", WrappingMode::Word),
+            ["averyveryv", "erylongwor", "d"]
+        );
+        assert_eq!(rows(text, WrappingMode::None), [text]);
+        assert_eq!(rows("", WrappingMode::Anywhere), [""]);
+        assert_eq!(
+            rows("日本語のテキスト", WrappingMode::Anywhere),
+            ["日本語のテ", "キスト"]
+        );
+    }
+
+    #[test]
+    fn test_find_matches() {
+        assert_eq!(find_matches("Foo foo FOO", "foo"), [0..3, 4..7, 8..11]);
+        assert_eq!(find_matches("Foo foo FOO Foo", "Foo"), [0..3, 12..15]);
+        assert_eq!(find_matches("aaaa", "aa"), [0..2, 2..4]);
+        assert_eq!(find_matches("ÄÖ äö", "äö"), [0..4, 5..9]);
+        assert!(find_matches("abc", "").is_empty());
+    }
+
+    #[test]
+    fn test_jump_to_sections() {
+        let mut state = new_state(&sample_input(), 40, 4);
+        let top_line = |state: &PagerState| state.lines[state.top.line].text.clone();
+        type_keys(&mut state, "]");
+        assert_eq!(top_line(&state), "Modified regular file foo:");
+        type_keys(&mut state, "]]");
+        assert_eq!(top_line(&state), "Modified regular file baz:");
+        type_keys(&mut state, "{");
+        assert_eq!(top_line(&state), "commit bbb");
+        type_keys(&mut state, "{");
+        assert_eq!(top_line(&state), "commit aaa");
+        type_keys(&mut state, "{");
+        assert_eq!(state.message.as_deref(), Some("No previous commit"));
+        type_keys(&mut state, "}");
+        assert_eq!(top_line(&state), "commit bbb");
+        // The view doesn't scroll past the end
+        type_keys(&mut state, "]]");
+        assert_eq!(top_line(&state), "   2    2: baz line 2");
+        assert_eq!(state.top, state.bottom());
+    }
+
+    #[test]
+    fn test_fold_files() {
+        let mut state = new_state(&sample_input(), 50, 8);
+        type_keys(&mut state, "]");
+        state.handle_key(key(KeyCode::Tab));
+        insta::assert_snapshot!(render(&state), @r"
+        Modified regular file foo: [2 lines folded]
+        Modified regular file bar:
+           1    1: bar line 1
+           2    2: bar line 2
+        commit bbb
+        Modified regular file baz:
+           1    1: baz line 1
+           2    2: baz line 2
+        lines 2-11 of 14  (h: help, q: quit)
+        ");
+
+        // Folding inside a file moves the view to the file header
+        type_keys(&mut state, "jj");
+        state.handle_key(key(KeyCode::Tab));
+        state.handle_key(key(KeyCode::Up));
+        insta::assert_snapshot!(render(&state), @r"
+        Modified regular file foo: [2 lines folded]
+        Modified regular file bar: [2 lines folded]
+        commit bbb
+        Modified regular file baz:
+           1    1: baz line 1
+           2    2: baz line 2
+        Modified regular file qux:
+           1    1: qux line 1
+        lines 2-13 of 14  (h: help, q: quit)
+        ");
+
+        state.handle_key(key(KeyCode::BackTab));
+        insta::assert_snapshot!(render(&state), @r"
+        commit aaa
+        Modified regular file foo: [2 lines folded]
+        Modified regular file bar: [2 lines folded]
+        commit bbb
+        Modified regular file baz: [2 lines folded]
+        Modified regular file qux: [2 lines folded]
+
+
+        lines 1-12 of 14 (END)  (h: help, q: quit)
+        ");
+        state.handle_key(key(KeyCode::BackTab));
+        assert!(state.folded.is_empty());
+    }
+
+    #[test]
+    fn test_incremental_search() {
+        let mut state = new_state(&sample_input(), 40, 3);
+        type_keys(&mut state, "/ba");
+        insta::assert_snapshot!(render(&state), @r"
+        Modified regular file bar:
+           1    1: bar line 1
+           2    2: bar line 2
+        /ba
+        ");
+        type_keys(&mut state, "z");
+        insta::assert_snapshot!(render(&state), @r"
+        Modified regular file baz:
+           1    1: baz line 1
+           2    2: baz line 2
+        /baz
+        ");
+        type_keys(&mut state, "x");
+        assert_eq!(state.top.line, 0);
+        assert_eq!(state.message.as_deref(), Some("Pattern not found"));
+        state.handle_key(key(KeyCode::Backspace));
+        state.handle_key(key(KeyCode::Enter));
+        assert!(state.prompt.is_none());
+        assert_eq!(state.top.line, 8);
+
+        // Matches in folded files are revealed
+        state.handle_key(key(KeyCode::BackTab));
+        type_keys(&mut state, "g/bar line");
+        state.handle_key(key(KeyCode::Enter));
+        assert_eq!(state.top.line, 5);
+        assert!(!state.folded.contains(&4));
+        type_keys(&mut state, "n");
+        assert_eq!(state.top.line, 6);
+        type_keys(&mut state, "NN");
+        assert_eq!(state.top.line, 5);
+        assert_eq!(state.message.as_deref(), Some("Pattern not found"));
+
+        // Escape restores the original position
+        type_keys(&mut state, "/commit b");
+        assert_eq!(state.top.line, 7);
+        state.handle_key(key(KeyCode::Esc));
+        assert_eq!(state.top.line, 5);
+        assert_eq!(state.search, None);
+    }
+
+    #[test]
+    fn test_scroll_wrapped_lines() {
+        let mut state = new_state(b"0123456789abcdef\nline 2\nline 3\nline 4", 10, 3);
+        insta::assert_snapshot!(render(&state), @r"
+        0123456789
+        abcdef
+        line 2
+        lines 1-2
+        ");
+        type_keys(&mut state, "j");
+        assert_eq!(state.top, Position { line: 0, row: 1 });
+        type_keys(&mut state, "jjj");
+        insta::assert_snapshot!(render(&state), @r"
+        line 2
+        line 3
+        line 4
+        lines 2-4
+        ");
+        type_keys(&mut state, "\\");
+        type_keys(&mut state, "g");
+        state.handle_key(key(KeyCode::Right));
+        insta::assert_snapshot!(render(&state), @r"
+        23456789ab
+        ne 2
+        ne 3
+        lines 1-3
+        ");
+    }
+}
@@ -45,6 +45,8 @@ use clap::ArgAction;
 use clap::ArgMatches;
 use clap::Command;
 use clap::FromArgMatches as _;
+use clap_complete::env::EnvCompleter;
+use clap_complete::env::Shells;
 use clap_complete::ArgValueCandidates;
 use clap_complete::ArgValueCompleter;
 use indexmap::IndexMap;
@@ -140,6 +142,7 @@ use tracing_chrome::ChromeLayerBuilder;
 use tracing_subscriber::prelude::*;
 
 use crate::command_error::cli_error;
+use crate::command_error::config_error;
 use crate::command_error::config_error_with_message;
 use crate::command_error::handle_command_result;
 use crate::command_error::internal_error;
@@ -2416,6 +2419,12 @@ impl WorkspaceCommandTransaction<'_> {
         self.tx.repo_mut()
     }
 
+    /// Merges the `other_op` into this transaction.
+    pub fn merge_operation(&mut self, other_op: Operation) -> Result<(), CommandError> {
+        self.tx.merge_operation(other_op)?;
+        Ok(())
+    }
+
     pub fn check_out(&mut self, commit: &Commit) -> Result<Commit, CheckOutCommitError> {
         let workspace_id = self.helper.workspace_id().to_owned();
         self.id_prefix_context.take(); // invalidate
@@ -3425,11 +3434,252 @@ fn resolve_default_command(
     Ok(string_args)
 }
 
+/// Command alias defined in the `aliases` table.
+#[derive(Clone, Debug)]
+enum CommandAlias {
+    /// Arguments to which the alias arguments are appended.
+    Args(Vec<String>),
+    /// Arguments in which `$1`, `$@`, and named parameters are substituted.
+    Template {
+        params: Vec<String>,
+        command: Vec<String>,
+    },
+    /// Shell script to be run by `jj util exec`.
+    Shell {
+        /// Command to run the script, such as `["sh", "-c"]`.
+        shell: Vec<String>,
+        params: Vec<String>,
+        script: String,
+    },
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(untagged)]
+enum CommandAliasBody {
+    Script(String),
+    Args(Vec<String>),
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CommandAliasTable {
+    command: CommandAliasBody,
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    shell: bool,
+}
+
+/// Part of an argument of a command alias template.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum AliasArgPiece {
+    Text(String),
+    /// Index of the alias argument to substitute.
+    Arg(usize),
+    /// All alias arguments, which must be the whole argument.
+    AllArgs,
+}
+
+impl CommandAlias {
+    fn load(config: &StackedConfig, name: &str) -> Result<Self, CommandError> {
+        let name_path = ["aliases", name];
+        if !config.get_value(name_path)?.is_inline_table() {
+            return Ok(CommandAlias::Args(config.get(name_path)?));
+        }
+        let table: CommandAliasTable = config.get(name_path)?;
+        let invalid = |message: &str| config_error(format!("Invalid alias `{name}`: {message}"));
+        for (i, param) in table.args.iter().enumerate() {
+            let is_identifier = param.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                && param.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !is_identifier {
+                return Err(invalid(&format!("Invalid parameter name `{param}`")));
+            }
+            if table.args[..i].contains(param) {
+                return Err(invalid(&format!("Redefinition of parameter `{param}`")));
+            }
+        }
+        match (table.command, table.shell) {
+            (CommandAliasBody::Args(command), false) => {
+                // Check references to undefined parameters early
+                for arg in &command {
+                    parse_alias_arg(arg, &table.args).map_err(|message| invalid(&message))?;
+                }
+                Ok(CommandAlias::Template {
+                    params: table.args,
+                    command,
+                })
+            }
+            (CommandAliasBody::Script(script), true) => Ok(CommandAlias::Shell {
+                shell: config.get("ui.shell")?,
+                params: table.args,
+                script,
+            }),
+            (CommandAliasBody::Script(_), false) => Err(invalid(
+                "The command must be a list of arguments unless `shell = true` is set",
+            )),
+            (CommandAliasBody::Args(_), true) => {
+                Err(invalid("The command of a shell alias must be a string"))
+            }
+        }
+    }
+
+    /// Expands the alias with the given arguments. If `completing`, the last
+    /// argument is being completed, so the expansion is truncated after it,
+    /// and missing arguments are substituted with empty strings.
+    fn expand(
+        &self,
+        name: &str,
+        args: &[String],
+        completing: bool,
+    ) -> Result<Vec<String>, CommandError> {
+        let missing_arg = |params: &[String], index: usize| {
+            let param = params
+                .get(index)
+                .map_or_else(|| format!("${}", index + 1), |param| param.clone());
+            user_error(format!("Missing argument `{param}` for alias `{name}`"))
+        };
+        match self {
+            CommandAlias::Args(definition) => Ok([definition, args].concat()),
+            CommandAlias::Template { params, command } => {
+                let templates: Vec<Vec<AliasArgPiece>> = command
+                    .iter()
+                    .map(|arg| parse_alias_arg(arg, params))
+                    .try_collect()
+                    .map_err(config_error)?;
+                let pieces = templates.iter().flatten();
+                let uses_all_args = pieces.clone().any(|p| *p == AliasArgPiece::AllArgs);
+                let num_consumed = pieces
+                    .filter_map(|piece| match piece {
+                        AliasArgPiece::Arg(index) => Some(index + 1),
+                        _ => None,
+                    })
+                    .chain([params.len()])
+                    .max()
+                    .unwrap();
+                if !completing && args.len() < num_consumed {
+                    return Err(missing_arg(params, args.len()));
+                }
+                let last_index = args.len().checked_sub(1);
+                let mut expanded = vec![];
+                // End of the expansion where the last argument is substituted
+                let mut completion_end = None;
+                for pieces in &templates {
+                    if pieces[..] == [AliasArgPiece::AllArgs] {
+                        expanded.extend_from_slice(args);
+                        if last_index.is_some() {
+                            completion_end = Some(expanded.len());
+                        }
+                        continue;
+                    }
+                    let mut arg = String::new();
+                    for piece in pieces {
+                        match piece {
+                            AliasArgPiece::Text(text) => arg.push_str(text),
+                            AliasArgPiece::Arg(index) => {
+                                arg.push_str(args.get(*index).map_or("", |arg| arg));
+                                if Some(*index) == last_index {
+                                    completion_end = Some(expanded.len() + 1);
+                                }
+                            }
+                            AliasArgPiece::AllArgs => unreachable!(),
+                        }
+                    }
+                    expanded.push(arg);
+                }
+                if !uses_all_args && args.len() > num_consumed {
+                    expanded.extend_from_slice(&args[num_consumed..]);
+                    completion_end = Some(expanded.len());
+                }
+                if let Some(end) = completion_end.filter(|_| completing) {
+                    expanded.truncate(end);
+                }
+                Ok(expanded)
+            }
+            CommandAlias::Shell {
+                shell,
+                params,
+                script,
+            } => {
+                if !completing && args.len() < params.len() {
+                    return Err(missing_arg(params, args.len()));
+                }
+                // Named parameters are shell variables set to the positional
+                // parameters, which are passed separately to not be evaluated.
+                let mut script_with_params = String::new();
+                for (i, param) in params.iter().enumerate() {
+                    script_with_params.push_str(&format!("{param}=\"${{{}}}\"\n", i + 1));
+                }
+                script_with_params.push_str(script);
+                let expanded = ["util", "exec", "--"]
+                    .into_iter()
+                    .map(str::to_owned)
+                    .chain(shell.iter().cloned())
+                    .chain([script_with_params, "jj".to_owned()])
+                    .chain(args.iter().cloned())
+                    .collect();
+                Ok(expanded)
+            }
+        }
+    }
+}
+
+/// Parses `$1`, `${name}`, `$name`, `$@`, and `$$` in the argument of a
+/// command alias.
+fn parse_alias_arg(arg: &str, params: &[String]) -> Result<Vec<AliasArgPiece>, String> {
+    if arg == "$@" {
+        return Ok(vec![AliasArgPiece::AllArgs]);
+    }
+    let mut pieces = vec![];
+    let mut text = String::new();
+    let mut rest = arg;
+    while let Some(pos) = rest.find('$') {
+        text.push_str(&rest[..pos]);
+        let after = &rest[pos + 1..];
+        let (reference, len) = if let Some(after_dollar) = after.strip_prefix('$') {
+            text.push('$');
+            rest = after_dollar;
+            continue;
+        } else if after.starts_with('@') {
+            return Err(format!("`$@` must be a whole argument in `{arg}`"));
+        } else if let Some(braced) = after.strip_prefix('{') {
+            let end = braced
+                .find('}')
+                .ok_or_else(|| format!("Unclosed `${{` in `{arg}`"))?;
+            (&braced[..end], end + 2)
+        } else {
+            let end = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            (&after[..end], end)
+        };
+        let index = if let Ok(n) = reference.parse::<usize>() {
+            n.checked_sub(1)
+                .ok_or_else(|| format!("Arguments are numbered from `$1` in `{arg}`"))?
+        } else if let Some(index) = params.iter().position(|param| param == reference) {
+            index
+        } else if reference.is_empty() {
+            return Err(format!(
+                "`$` must be followed by a parameter in `{arg}`; use `$$` for a literal `$`"
+            ));
+        } else {
+            return Err(format!("Undefined parameter `{reference}` in `{arg}`"));
+        };
+        pieces.push(AliasArgPiece::Text(mem::take(&mut text)));
+        pieces.push(AliasArgPiece::Arg(index));
+        rest = &after[len..];
+    }
+    text.push_str(rest);
+    pieces.push(AliasArgPiece::Text(text));
+    pieces.retain(|piece| *piece != AliasArgPiece::Text(String::new()));
+    Ok(pieces)
+}
+
 fn resolve_aliases(
     ui: &Ui,
     config: &StackedConfig,
     app: &Command,
     mut string_args: Vec<String>,
+    completing: bool,
 ) -> Result<Vec<String>, CommandError> {
     let defined_aliases: HashSet<_> = config.table_keys("aliases").collect();
     let mut resolved_aliases = HashSet::new();
@@ -3463,12 +3713,15 @@ fn resolve_aliases(
                         "Recursive alias definition involving `{alias_name}`"
                     )));
                 }
+                if completing && alias_args.is_empty() {
+                    // The alias name itself is being completed
+                    return Ok(string_args);
+                }
                 if let Some(&alias_name) = defined_aliases.get(&*alias_name) {
-                    let alias_definition: Vec<String> = config.get(["aliases", alias_name])?;
+                    let alias = CommandAlias::load(config, alias_name)?;
                     assert!(string_args.ends_with(&alias_args));
                     string_args.truncate(string_args.len() - 1 - alias_args.len());
-                    string_args.extend(alias_definition);
-                    string_args.extend_from_slice(&alias_args);
+                    string_args.extend(alias.expand(alias_name, &alias_args, completing)?);
                     resolved_aliases.insert(alias_name);
                     continue;
                 } else {
@@ -3539,6 +3792,8 @@ fn handle_shell_completion(
     // skip the first two args ("jj" and "--") for alias resolution, then we
     // stitch the args back together, like clap_complete expects them.
     let orig_args = env::args_os().skip(2);
+    // Index of the argument to complete if it has been moved by an alias
+    let mut moved_index = None;
     if orig_args.len() > 0 {
         let arg_index: Option<usize> = env::var("_CLAP_COMPLETE_INDEX")
             .ok()
@@ -3548,21 +3803,51 @@ fn handle_shell_completion(
             // empty arg at the complete position. If the args doesn't include a
             // command name, the default command would be expanded at that
             // position. Therefore, no other command names would be suggested.
-            // TODO: Maybe we should instead expand args[..index] + [""], adjust
-            // the index accordingly, strip the last "", and append remainder?
             let pad_len = usize::saturating_sub(index + 1, orig_args.len());
-            let padded_args = orig_args.chain(std::iter::repeat_n(OsString::new(), pad_len));
-            expand_args(ui, app, padded_args, config)?
+            let padded_args = orig_args
+                .chain(std::iter::repeat_n(OsString::new(), pad_len))
+                .collect_vec();
+            let leading_args = &padded_args[..=index];
+            let resolved_aliases: Vec<OsString> =
+                expand_args_for_completion(ui, app, leading_args.iter().cloned(), config)?
+                    .into_iter()
+                    .map(OsString::from)
+                    .collect();
+            if resolved_aliases == leading_args {
+                // Nothing is expanded, so the remainder can be passed as is
+                padded_args
+            } else {
+                // The expansion is truncated after the argument to complete,
+                // which is the last one.
+                moved_index = Some(resolved_aliases.len() - 1);
+                resolved_aliases
+            }
         } else {
-            expand_args(ui, app, orig_args, config)?
+            expand_args_for_completion(ui, app, orig_args, config)?
+                .into_iter()
+                .map(OsString::from)
+                .collect()
         };
-        args.extend(resolved_aliases.into_iter().map(OsString::from));
+        args.extend(resolved_aliases);
     }
+    let shells = Shells::builtins()
+        .0
+        .iter()
+        .map(|&shell| MovedIndexCompleter {
+            shell,
+            index: moved_index,
+        })
+        .collect_vec();
+    let shell_refs = shells
+        .iter()
+        .map(|shell| shell as &dyn EnvCompleter)
+        .collect_vec();
     let ran_completion = clap_complete::CompleteEnv::with_factory(|| {
         app.clone()
             // for completing aliases
             .allow_external_subcommands(true)
     })
+    .shells(Shells(&shell_refs))
     .try_complete(args.iter(), Some(cwd))?;
     assert!(
         ran_completion,
@@ -3571,6 +3856,76 @@ fn handle_shell_completion(
     Ok(())
 }
 
+/// Shell completion adapter which completes the argument at the given index.
+///
+/// The builtin adapters of some shells read the index from the
+/// `_CLAP_COMPLETE_INDEX` environment variable, which no longer points to the
+/// argument to complete once an alias is expanded.
+struct MovedIndexCompleter {
+    shell: &'static dyn EnvCompleter,
+    index: Option<usize>,
+}
+
+impl EnvCompleter for MovedIndexCompleter {
+    fn name(&self) -> &'static str {
+        self.shell.name()
+    }
+
+    fn is(&self, name: &str) -> bool {
+        self.shell.is(name)
+    }
+
+    fn write_registration(
+        &self,
+        var: &str,
+        name: &str,
+        bin: &str,
+        completer: &str,
+        buf: &mut dyn io::Write,
+    ) -> io::Result<()> {
+        self.shell
+            .write_registration(var, name, bin, completer, buf)
+    }
+
+    fn write_complete(
+        &self,
+        cmd: &mut Command,
+        args: Vec<OsString>,
+        current_dir: Option<&Path>,
+        buf: &mut dyn io::Write,
+    ) -> io::Result<()> {
+        let shell_name = self.shell.name();
+        // Other shells complete the last argument, which is the one to complete
+        // since the expansion is truncated after it
+        let Some(index) = self
+            .index
+            .filter(|_| matches!(shell_name, "bash" | "elvish" | "zsh"))
+        else {
+            return self.shell.write_complete(cmd, args, current_dir, buf);
+        };
+        // Same output as the builtin adapters
+        let ifs = env::var("_CLAP_IFS").ok();
+        let candidates = clap_complete::engine::complete(cmd, args, index, current_dir)?;
+        for (i, candidate) in candidates.iter().enumerate() {
+            if i != 0 {
+                write!(buf, "{}", ifs.as_deref().unwrap_or("\n"))?;
+            }
+            let value = candidate.get_value().to_string_lossy();
+            if shell_name == "zsh" {
+                write!(buf, "{}", value.replace('\\', "\\\\").replace(':', "\\:"))?;
+                if let Some(help) = candidate.get_help() {
+                    let help = help.to_string();
+                    let help = help.lines().next().unwrap_or_default();
+                    write!(buf, ":{}", help.replace('\\', "\\\\"))?;
+                }
+            } else {
+                write!(buf, "{value}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
 pub fn expand_args(
     ui: &Ui,
     app: &Command,
@@ -3587,7 +3942,24 @@ pub fn expand_args(
     }
 
     let string_args = resolve_default_command(ui, config, app, string_args)?;
-    resolve_aliases(ui, config, app, string_args)
+    resolve_aliases(ui, config, app, string_args, false)
+}
+
+/// Expands args like [`expand_args()`], but the last argument is the one being
+/// completed. The expansion is truncated after the last argument.
+pub fn expand_args_for_completion(
+    ui: &Ui,
+    app: &Command,
+    args_os: impl IntoIterator<Item = OsString>,
+    config: &StackedConfig,
+) -> Result<Vec<String>, CommandError> {
+    let string_args: Vec<String> = args_os
+        .into_iter()
+        .map(|arg_os| arg_os.into_string())
+        .try_collect()
+        .map_err(|_| cli_error("Non-utf8 argument"))?;
+    let string_args = resolve_default_command(ui, config, app, string_args)?;
+    resolve_aliases(ui, config, app, string_args, true)
 }
 
 fn parse_args(app: &Command, string_args: &[String]) -> Result<(ArgMatches, Args), clap::Error> {
@@ -4050,4 +4422,93 @@ mod tests {
             vec![("foo", 1), ("bar", 2), ("foo", 3)]
         );
     }
+
+    #[test]
+    fn test_parse_alias_arg() {
+        use AliasArgPiece::*;
+        let params = ["dest".to_owned(), "rev_2".to_owned()];
+        let parse = |arg: &str| parse_alias_arg(arg, &params);
+
+        assert_eq!(parse("log"), Ok(vec![Text("log".to_owned())]));
+        assert_eq!(parse("$@"), Ok(vec![AllArgs]));
+        assert_eq!(parse("$1"), Ok(vec![Arg(0)]));
+        assert_eq!(parse("$dest"), Ok(vec![Arg(0)]));
+        assert_eq!(
+            parse("${rev_2}..$3-"),
+            Ok(vec![
+                Arg(1),
+                Text("..".to_owned()),
+                Arg(2),
+                Text("-".to_owned())
+            ])
+        );
+        assert_eq!(parse("$$1"), Ok(vec![Text("$1".to_owned())]));
+        assert!(parse("$0").is_err());
+        assert!(parse("$").is_err());
+        assert!(parse("${dest").is_err());
+        assert!(parse("$unknown").is_err());
+        assert!(parse("-r=$@").is_err());
+    }
+
+    #[test]
+    fn test_expand_command_alias() {
+        let to_strings = |args: &[&str]| args.iter().map(|&arg| arg.to_owned()).collect_vec();
+        let alias = CommandAlias::Template {
+            params: vec!["dest".to_owned()],
+            command: to_strings(&["rebase", "-s", "$2", "-d", "$dest"]),
+        };
+        let expand = |args: &[&str], completing: bool| {
+            alias
+                .expand("rb", &to_strings(args), completing)
+                .map_err(|err| err.error.to_string())
+        };
+
+        assert_eq!(
+            expand(&["main", "@", "--skip-emptied"], false),
+            Ok(to_strings(&[
+                "rebase",
+                "-s",
+                "@",
+                "-d",
+                "main",
+                "--skip-emptied"
+            ]))
+        );
+        assert_eq!(
+            expand(&["main"], false),
+            Err("Missing argument `$2` for alias `rb`".to_owned())
+        );
+        assert_eq!(
+            expand(&[], false),
+            Err("Missing argument `dest` for alias `rb`".to_owned())
+        );
+        // The expansion ends with the argument being completed
+        assert_eq!(
+            expand(&["ma"], true),
+            Ok(to_strings(&["rebase", "-s", "", "-d", "ma"]))
+        );
+        assert_eq!(
+            expand(&["main", "x"], true),
+            Ok(to_strings(&["rebase", "-s", "x"]))
+        );
+
+        let alias = CommandAlias::Shell {
+            shell: to_strings(&["sh", "-c"]),
+            params: vec!["dest".to_owned()],
+            script: "jj rebase -d \"$dest\"".to_owned(),
+        };
+        assert_eq!(
+            alias.expand("rb", &to_strings(&["main"]), false).unwrap(),
+            to_strings(&[
+                "util",
+                "exec",
+                "--",
+                "sh",
+                "-c",
+                "dest=\"${1}\"\njj rebase -d \"$dest\"",
+                "jj",
+                "main",
+            ])
+        );
+    }
 }
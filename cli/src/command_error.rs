@@ -34,6 +34,7 @@ use jj_lib::fileset::FilesetParseError;
 use jj_lib::fileset::FilesetParseErrorKind;
 use jj_lib::fix::FixError;
 use jj_lib::gitignore::GitIgnoreError;
+use jj_lib::op_bundle::OpBundleError;
 use jj_lib::op_heads_store::OpHeadResolutionError;
 use jj_lib::op_heads_store::OpHeadsStoreError;
 use jj_lib::op_store::OpStoreError;
@@ -481,6 +482,7 @@ mod git {
     use jj_lib::git::GitPushError;
     use jj_lib::git::GitRemoteManagementError;
     use jj_lib::git::UnexpectedGitBackendError;
+    use jj_lib::git_op_log::GitOpLogError;
 
     use super::*;
 
@@ -574,6 +576,26 @@ jj currently does not support partial clones. To use jj with this repository, tr
         }
     }
 
+    impl From<GitOpLogError> for CommandError {
+        fn from(err: GitOpLogError) -> Self {
+            match err {
+                GitOpLogError::UnexpectedBackend(err) => err.into(),
+                GitOpLogError::FetchPrepare(err) => err.into(),
+                GitOpLogError::Fetch(err) => err.into(),
+                GitOpLogError::Push(err) => err.into(),
+                GitOpLogError::Bundle(err) => err.into(),
+                GitOpLogError::Backend(err) => err.into(),
+                GitOpLogError::OpStore(err) => err.into(),
+                GitOpLogError::Rejected(_) => user_error_with_hint(
+                    err,
+                    "Run `jj git op-log fetch` to merge the remote operations first.",
+                ),
+                GitOpLogError::InvalidCarrier(_) => user_error(err),
+                GitOpLogError::Git(_) => internal_error(err),
+            }
+        }
+    }
+
     #[cfg(feature = "git2")]
     fn map_git2_error(err: git2::Error) -> CommandError {
         if err.class() == git2::ErrorClass::Ssh {
@@ -717,6 +739,25 @@ impl From<FixError> for CommandError {
     }
 }
 
+impl From<OpBundleError> for CommandError {
+    fn from(err: OpBundleError) -> Self {
+        match err {
+            OpBundleError::Backend(err) => err.into(),
+            OpBundleError::OpStore(err) => err.into(),
+            OpBundleError::Io(err) => err.into(),
+            OpBundleError::IndexRead(err) => {
+                internal_error_with_message("Failed to read index", err)
+            }
+            OpBundleError::RevsetEvaluation(err) => err.into(),
+            OpBundleError::UnsupportedFormat
+            | OpBundleError::Corrupt(_)
+            | OpBundleError::IncompatibleBackend
+            | OpBundleError::UnsupportedObject { .. }
+            | OpBundleError::IdMismatch { .. } => user_error(err),
+        }
+    }
+}
+
 fn find_source_parse_error_hint(err: &dyn error::Error) -> Option<String> {
     let source = err.source()?;
     if let Some(source) = source.downcast_ref() {
@@ -13,10 +13,14 @@
 // limitations under the License.
 
 use clap_complete::ArgValueCandidates;
+use itertools::Itertools as _;
 use jj_lib::absorb::absorb_hunks;
-use jj_lib::absorb::split_hunks_to_trees;
+use jj_lib::absorb::plan_absorb;
 use jj_lib::absorb::AbsorbSource;
 use jj_lib::matchers::EverythingMatcher;
+use jj_lib::repo::Repo as _;
+use jj_lib::revset::RevsetExpression;
+use jj_lib::revset::RevsetIteratorExt as _;
 use pollster::FutureExt as _;
 use tracing::instrument;
 
@@ -26,6 +30,8 @@ use crate::cli_util::RevisionArg;
 use crate::command_error::CommandError;
 use crate::complete;
 use crate::diff_util::DiffFormat;
+use crate::merge_tools::edit_absorb_plan;
+use crate::merge_tools::format_hunk_header;
 use crate::ui::Ui;
 
 /// Move changes from a revision into the stack of mutable revisions
@@ -38,6 +44,10 @@ use crate::ui::Ui;
 /// The source revision will be abandoned if all changes are absorbed into the
 /// destination revisions, and if the source revision has no description.
 ///
+/// Use `--dry-run` to see which revision each hunk would be moved to, or
+/// `--interactive` to review the hunks and change their destinations before
+/// absorbing them.
+///
 /// The modification made by `jj absorb` can be reviewed by `jj op show -p`.
 #[derive(clap::Args, Clone, Debug)]
 pub(crate) struct AbsorbArgs {
@@ -62,6 +72,18 @@ pub(crate) struct AbsorbArgs {
     /// Move only changes to these paths (instead of all paths)
     #[arg(value_name = "FILESETS", value_hint = clap::ValueHint::AnyPath)]
     paths: Vec<String>,
+    /// Interactively choose the destination of each hunk
+    ///
+    /// Each hunk is shown with the revision it would be absorbed into. Hunks
+    /// can be moved to another destination revision, or left in the source
+    /// revision. Moving a hunk to a revision other than the suggested one may
+    /// result in conflicts.
+    #[arg(long, short)]
+    interactive: bool,
+    /// Print which revision each hunk would be absorbed into, without
+    /// absorbing anything
+    #[arg(long)]
+    dry_run: bool,
 }
 
 #[instrument(skip_all)]
@@ -82,19 +104,75 @@ pub(crate) fn cmd_absorb(
         .to_matcher();
 
     let repo = workspace_command.repo().as_ref();
-    let source = AbsorbSource::from_commit(repo, source_commit)?;
-    let selected_trees = split_hunks_to_trees(repo, &source, &destinations, &matcher).block_on()?;
+    let source = AbsorbSource::from_commit(repo, source_commit.clone())?;
+    let mut plan = plan_absorb(repo, &source, &destinations, &matcher).block_on()?;
 
     let path_converter = workspace_command.path_converter();
-    for (path, reason) in selected_trees.skipped_paths {
-        let ui_path = path_converter.format_file_path(&path);
+    for (path, reason) in &plan.skipped_paths {
+        let ui_path = path_converter.format_file_path(path);
         writeln!(ui.warning_default(), "Skipping {ui_path}: {reason}")?;
     }
 
-    workspace_command.check_rewritable(selected_trees.target_commits.keys())?;
+    if args.interactive {
+        // Any ancestor in the destination set can be chosen
+        let candidate_commits: Vec<_> = destinations
+            .intersection(
+                &RevsetExpression::commit(source_commit.id().clone())
+                    .parents()
+                    .ancestors(),
+            )
+            .evaluate(repo)?
+            .iter()
+            .commits(repo.store())
+            .try_collect()?;
+        let candidates = candidate_commits
+            .iter()
+            .map(|commit| {
+                let label = workspace_command.format_commit_summary(commit);
+                (commit.id().clone(), label)
+            })
+            .collect_vec();
+        edit_absorb_plan(&mut plan, &candidates, path_converter)?;
+    }
+
+    if args.dry_run {
+        let mut formatter = ui.stdout_formatter();
+        let summary_template = workspace_command.commit_summary_template();
+        for (file, hunk) in plan.hunks() {
+            let ui_path = path_converter.format_file_path(&file.path);
+            let header = format_hunk_header(file, hunk);
+            write!(formatter, "{ui_path} {header} -> ")?;
+            if let Some(commit_id) = &hunk.destination {
+                let commit = repo.store().get_commit(commit_id)?;
+                summary_template.format(&commit, formatter.as_mut())?;
+                writeln!(formatter)?;
+            } else {
+                writeln!(formatter.labeled("hint"), "(not absorbed)")?;
+            }
+        }
+        return Ok(());
+    }
+
+    if let Some(mut formatter) = ui.status_formatter() {
+        let remaining_hunks = plan
+            .hunks()
+            .filter(|(_, hunk)| hunk.destination.is_none())
+            .collect_vec();
+        if !remaining_hunks.is_empty() {
+            writeln!(formatter, "Hunks left in the source revision:")?;
+            for (file, hunk) in remaining_hunks {
+                let ui_path = path_converter.format_file_path(&file.path);
+                let header = format_hunk_header(file, hunk);
+                writeln!(formatter, "  {ui_path} {header}")?;
+            }
+        }
+    }
+
+    let target_commits = plan.build_trees(repo, &source).block_on()?;
+    workspace_command.check_rewritable(target_commits.keys())?;
 
     let mut tx = workspace_command.start_transaction();
-    let stats = absorb_hunks(tx.repo_mut(), &source, selected_trees.target_commits)?;
+    let stats = absorb_hunks(tx.repo_mut(), &source, target_commits)?;
 
     if let Some(mut formatter) = ui.status_formatter() {
         if !stats.rewritten_destinations.is_empty() {
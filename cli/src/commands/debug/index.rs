@@ -53,6 +53,26 @@ pub fn cmd_debug_index(
         )?;
         writeln!(ui.stdout(), "Number of heads: {}", stats.num_heads)?;
         writeln!(ui.stdout(), "Number of changes: {}", stats.num_changes)?;
+        writeln!(
+            ui.stdout(),
+            "Number of changed-path filters: {}",
+            stats.num_changed_path_filters
+        )?;
+        writeln!(
+            ui.stdout(),
+            "Number of visible heads: {}",
+            stats.num_visible_heads
+        )?;
+        writeln!(
+            ui.stdout(),
+            "Number of visible commits: {}",
+            stats.num_visible_commits
+        )?;
+        writeln!(
+            ui.stdout(),
+            "Number of divergent changes: {}",
+            stats.num_divergent_changes
+        )?;
         writeln!(ui.stdout(), "Stats per level:")?;
         for (i, level) in stats.levels.iter().enumerate() {
             writeln!(ui.stdout(), "  Level {i}:")?;
@@ -12,10 +12,17 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+
 use clap_complete::ArgValueCandidates;
 use clap_complete::ArgValueCompleter;
+use itertools::Itertools as _;
 use jj_lib::annotate::get_annotation_for_file;
+use jj_lib::annotate::AnnotationOptions;
 use jj_lib::annotate::FileAnnotation;
+use jj_lib::annotate::LineComparison;
 use jj_lib::repo::Repo;
 use jj_lib::revset::RevsetExpression;
 use tracing::instrument;
@@ -23,6 +30,7 @@ use tracing::instrument;
 use crate::cli_util::CommandHelper;
 use crate::cli_util::RevisionArg;
 use crate::command_error::user_error;
+use crate::command_error::user_error_with_message;
 use crate::command_error::CommandError;
 use crate::commit_templater::AnnotationLine;
 use crate::commit_templater::CommitTemplateLanguage;
@@ -34,6 +42,9 @@ use crate::ui::Ui;
 ///
 /// Annotates a revision line by line. Each line includes the source change that
 /// introduced the associated line. A path to the desired file must be provided.
+///
+/// Copies and renames are followed to the source file, as configured by the
+/// `copy-tracking` settings.
 #[derive(clap::Args, Clone, Debug)]
 pub(crate) struct FileAnnotateArgs {
     /// the file to annotate
@@ -66,6 +77,30 @@ pub(crate) struct FileAnnotateArgs {
     ///     https://jj-vcs.github.io/jj/latest/templates/
     #[arg(long, short = 'T', add = ArgValueCandidates::new(complete::template_aliases))]
     template: Option<String>,
+    /// Ignore changes made by these revisions
+    ///
+    /// Lines changed by the ignored revisions are attributed to the revisions
+    /// that introduced the corresponding lines before. Lines added by the
+    /// ignored revisions are still attributed to them.
+    #[arg(
+        long,
+        value_name = "REVSETS",
+        add = ArgValueCandidates::new(complete::all_revisions)
+    )]
+    ignore_rev: Vec<RevisionArg>,
+    /// Ignore changes made by the revisions listed in the file
+    ///
+    /// Each line of the file is a revset, typically a commit ID. Empty lines
+    /// and comments starting with `#` are skipped. The format is compatible
+    /// with Git's `blame.ignoreRevsFile`.
+    #[arg(long, value_name = "PATH", value_hint = clap::ValueHint::FilePath)]
+    ignore_revs_file: Option<PathBuf>,
+    /// Ignore whitespace when comparing lines
+    #[arg(long, short = 'w')]
+    ignore_all_space: bool,
+    /// Ignore changes in amount of whitespace when comparing lines
+    #[arg(long, short = 'b', conflicts_with = "ignore_all_space")]
+    ignore_space_change: bool,
 }
 
 #[instrument(skip_all)]
@@ -104,12 +139,50 @@ pub(crate) fn cmd_file_annotate(
         CommitTemplateLanguage::wrap_annotation_line,
     )?;
 
+    let mut ignored_revisions = args.ignore_rev.clone();
+    if let Some(path) = &args.ignore_revs_file {
+        let path = command.cwd().join(path);
+        let content = fs::read_to_string(&path).map_err(|err| {
+            user_error_with_message(format!("Failed to read {}", path.display()), err)
+        })?;
+        ignored_revisions.extend(
+            content
+                .lines()
+                .map(|line| line.trim())
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(|line| RevisionArg::from(line.to_owned())),
+        );
+    }
+    let ignored_commits = if ignored_revisions.is_empty() {
+        HashSet::new()
+    } else {
+        workspace_command
+            .parse_union_revsets(ui, &ignored_revisions)?
+            .evaluate_to_commit_ids()?
+            .try_collect()?
+    };
+    let line_comparison = if args.ignore_all_space {
+        LineComparison::IgnoreAllSpace
+    } else if args.ignore_space_change {
+        LineComparison::IgnoreSpaceChange
+    } else {
+        LineComparison::Exact
+    };
+    let options = AnnotationOptions {
+        follow_copies: true,
+        ignored_commits,
+        line_comparison,
+    };
+
     // TODO: Should we add an option to limit the domain to e.g. recent commits?
-    // Note that this is probably different from "--skip REVS", which won't
-    // exclude the revisions, but will ignore diffs in those revisions as if
-    // ancestor revisions had new content.
     let domain = RevsetExpression::all();
-    let annotation = get_annotation_for_file(repo.as_ref(), &starting_commit, &domain, &file_path)?;
+    let annotation = get_annotation_for_file(
+        repo.as_ref(),
+        &starting_commit,
+        &domain,
+        &file_path,
+        &options,
+    )?;
 
     render_file_annotation(repo.as_ref(), ui, &template, &annotation)?;
     Ok(())
@@ -125,7 +198,8 @@ fn render_file_annotation(
     let mut formatter = ui.stdout_formatter();
     let mut last_id = None;
     let default_id = repo.store().root_commit_id();
-    for (line_number, (commit_id, content)) in annotation.lines().enumerate() {
+    let lines = annotation.lines().zip(annotation.original_paths());
+    for (line_number, ((commit_id, content), original_path)) in lines.enumerate() {
         /* At least in cases where the repository was jj-initialized shallowly,
         then unshallow'd with git, some changes will not have a commit id
         because jj does not import the unshallow'd commits. So we default
@@ -136,6 +210,7 @@ fn render_file_annotation(
         let annotation_line = AnnotationLine {
             commit,
             content: content.to_owned(),
+            original_path: original_path.to_owned(),
             line_number: line_number + 1,
             first_line_in_hunk,
         };
@@ -21,6 +21,7 @@ use jj_lib::backend::BackendResult;
 use jj_lib::conflicts::materialize_merge_result;
 use jj_lib::conflicts::materialize_tree_value;
 use jj_lib::conflicts::MaterializedTreeValue;
+use jj_lib::diff::DiffAlgorithm;
 use jj_lib::fileset::FilePattern;
 use jj_lib::fileset::FilesetExpression;
 use jj_lib::merge::MergedTreeValue;
@@ -137,6 +138,7 @@ fn write_tree_entries<P: AsRef<RepoPath>>(
                 materialize_merge_result(
                     &contents,
                     workspace_command.env().conflict_marker_style(),
+                    DiffAlgorithm::Default,
                     &mut ui.stdout_formatter(),
                 )?;
             }
@@ -17,6 +17,7 @@ mod export;
 mod fetch;
 mod import;
 mod init;
+mod op_log;
 mod push;
 mod remote;
 mod root;
@@ -42,6 +43,8 @@ use self::import::cmd_git_import;
 use self::import::GitImportArgs;
 use self::init::cmd_git_init;
 use self::init::GitInitArgs;
+use self::op_log::cmd_git_op_log;
+use self::op_log::GitOpLogCommand;
 use self::push::cmd_git_push;
 use self::push::GitPushArgs;
 use self::remote::cmd_git_remote;
@@ -70,6 +73,8 @@ pub enum GitCommand {
     Fetch(GitFetchArgs),
     Import(GitImportArgs),
     Init(GitInitArgs),
+    #[command(subcommand)]
+    OpLog(GitOpLogCommand),
     Push(GitPushArgs),
     #[command(subcommand)]
     Remote(RemoteCommand),
@@ -87,6 +92,7 @@ pub fn cmd_git(
         GitCommand::Fetch(args) => cmd_git_fetch(ui, command, args),
         GitCommand::Import(args) => cmd_git_import(ui, command, args),
         GitCommand::Init(args) => cmd_git_init(ui, command, args),
+        GitCommand::OpLog(args) => cmd_git_op_log(ui, command, args),
         GitCommand::Push(args) => cmd_git_push(ui, command, args),
         GitCommand::Remote(args) => cmd_git_remote(ui, command, args),
         GitCommand::Root(args) => cmd_git_root(ui, command, args),
@@ -0,0 +1,84 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap_complete::ArgValueCandidates;
+use jj_lib::git_op_log;
+use jj_lib::ref_name::RemoteNameBuf;
+
+use crate::cli_util::CommandHelper;
+use crate::command_error::cli_error;
+use crate::command_error::CommandError;
+use crate::commands::git::push::get_default_push_remote;
+use crate::commands::operation::import::merge_imported_operations;
+use crate::commands::operation::import::print_conflicting_commits;
+use crate::complete;
+use crate::git_util::with_remote_git_callbacks;
+use crate::ui::Ui;
+
+/// Fetch the operation log from a Git remote
+///
+/// The fetched operations are merged with the current operation as if they
+/// were run concurrently in this repo. Working-copy commits of other repos
+/// are not checked out.
+#[derive(clap::Args, Clone, Debug)]
+pub struct GitOpLogFetchArgs {
+    /// The remote to fetch from
+    ///
+    /// This defaults to the `git.push` setting. If that is not configured, and
+    /// if there are multiple remotes, the remote named "origin" will be used.
+    #[arg(long, add = ArgValueCandidates::new(complete::git_remotes))]
+    remote: Option<RemoteNameBuf>,
+}
+
+pub fn cmd_git_op_log_fetch(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &GitOpLogFetchArgs,
+) -> Result<(), CommandError> {
+    // The fetched operations will be merged with the current head.
+    if command.global_args().at_operation.is_some() {
+        return Err(cli_error("--at-op is not respected"));
+    }
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let remote = match &args.remote {
+        Some(name) => name.clone(),
+        None => get_default_push_remote(ui, &workspace_command)?,
+    };
+    let git_settings = workspace_command.settings().git_settings()?;
+    let repo_loader = workspace_command.workspace().repo_loader().clone();
+    let stats = with_remote_git_callbacks(ui, |cb| {
+        git_op_log::fetch_op_log(&repo_loader, &git_settings, &remote, cb)
+    })?;
+    if stats.head_ids.is_empty() {
+        writeln!(
+            ui.status(),
+            "Remote {} has no operation log",
+            remote.as_symbol()
+        )?;
+        return Ok(());
+    }
+    writeln!(
+        ui.status(),
+        "Fetched {} operations and {} new commits",
+        stats.num_operations,
+        stats.commit_ids.len()
+    )?;
+    print_conflicting_commits(ui, &stats.conflicting_commit_ids)?;
+    merge_imported_operations(
+        ui,
+        &mut workspace_command,
+        &stats.head_ids,
+        format!("fetch operations from remote {}", remote.as_symbol()),
+    )
+}
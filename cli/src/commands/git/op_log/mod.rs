@@ -0,0 +1,49 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod fetch;
+mod push;
+
+use clap::Subcommand;
+
+use self::fetch::cmd_git_op_log_fetch;
+use self::fetch::GitOpLogFetchArgs;
+use self::push::cmd_git_op_log_push;
+use self::push::GitOpLogPushArgs;
+use crate::cli_util::CommandHelper;
+use crate::command_error::CommandError;
+use crate::ui::Ui;
+
+/// Synchronize the operation log through a Git remote
+///
+/// Pushing the operation log shares jj-specific state, such as hidden commits,
+/// the evolution of changes, and bookmark conflicts, with other repos. The
+/// operations are stored under the `refs/jj/op-log/` namespace on the remote,
+/// along with the commits they refer to and their change ids.
+#[derive(Subcommand, Clone, Debug)]
+pub enum GitOpLogCommand {
+    Fetch(GitOpLogFetchArgs),
+    Push(GitOpLogPushArgs),
+}
+
+pub fn cmd_git_op_log(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    subcommand: &GitOpLogCommand,
+) -> Result<(), CommandError> {
+    match subcommand {
+        GitOpLogCommand::Fetch(args) => cmd_git_op_log_fetch(ui, command, args),
+        GitOpLogCommand::Push(args) => cmd_git_op_log_push(ui, command, args),
+    }
+}
@@ -0,0 +1,72 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap_complete::ArgValueCandidates;
+use jj_lib::git_op_log;
+use jj_lib::ref_name::RemoteNameBuf;
+
+use crate::cli_util::CommandHelper;
+use crate::command_error::cli_error;
+use crate::command_error::CommandError;
+use crate::commands::git::push::get_default_push_remote;
+use crate::complete;
+use crate::git_util::with_remote_git_callbacks;
+use crate::ui::Ui;
+
+/// Push the operation log to a Git remote
+///
+/// Operations that were pushed to or fetched from the remote before are not
+/// pushed again. If another repo pushed operations since the last fetch, run
+/// `jj git op-log fetch` first.
+#[derive(clap::Args, Clone, Debug)]
+pub struct GitOpLogPushArgs {
+    /// The remote to push to
+    ///
+    /// This defaults to the `git.push` setting. If that is not configured, and
+    /// if there are multiple remotes, the remote named "origin" will be used.
+    #[arg(long, add = ArgValueCandidates::new(complete::git_remotes))]
+    remote: Option<RemoteNameBuf>,
+}
+
+pub fn cmd_git_op_log_push(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &GitOpLogPushArgs,
+) -> Result<(), CommandError> {
+    if command.global_args().at_operation.is_some() {
+        return Err(cli_error("--at-op is not respected"));
+    }
+    let workspace_command = command.workspace_helper(ui)?;
+    let remote = match &args.remote {
+        Some(name) => name.clone(),
+        None => get_default_push_remote(ui, &workspace_command)?,
+    };
+    let git_settings = workspace_command.settings().git_settings()?;
+    let repo = workspace_command.repo();
+    let stats = with_remote_git_callbacks(ui, |cb| {
+        git_op_log::push_op_log(repo, &git_settings, &remote, cb)
+    })?;
+    if let Some(stats) = stats {
+        writeln!(
+            ui.status(),
+            "Pushed {} operations and {} commits to remote {}",
+            stats.num_operations,
+            stats.commit_ids.len(),
+            remote.as_symbol()
+        )?;
+    } else {
+        writeln!(ui.status(), "Nothing changed.")?;
+    }
+    Ok(())
+}
@@ -658,7 +658,7 @@ fn print_commits_ready_to_push(
     Ok(())
 }
 
-fn get_default_push_remote(
+pub(super) fn get_default_push_remote(
     ui: &Ui,
     workspace_command: &WorkspaceCommandHelper,
 ) -> Result<RemoteNameBuf, CommandError> {
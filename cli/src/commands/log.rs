@@ -18,10 +18,12 @@ use itertools::Itertools as _;
 use jj_lib::backend::CommitId;
 use jj_lib::config::ConfigGetError;
 use jj_lib::config::ConfigGetResultExt as _;
+use jj_lib::fileset::FilesetExpression;
 use jj_lib::graph::reverse_graph;
 use jj_lib::graph::GraphEdge;
 use jj_lib::graph::GraphEdgeType;
 use jj_lib::graph::TopoGroupedGraphIterator;
+use jj_lib::matchers::Matcher;
 use jj_lib::repo::Repo as _;
 use jj_lib::revset::RevsetEvaluationError;
 use jj_lib::revset::RevsetExpression;
@@ -34,12 +36,16 @@ use crate::cli_util::format_template;
 use crate::cli_util::CommandHelper;
 use crate::cli_util::LogContentFormat;
 use crate::cli_util::RevisionArg;
+use crate::cli_util::WorkspaceCommandHelper;
 use crate::command_error::CommandError;
 use crate::commit_templater::CommitTemplateLanguage;
 use crate::complete;
 use crate::diff_util::DiffFormatArgs;
 use crate::graphlog::get_graphlog;
 use crate::graphlog::GraphStyle;
+use crate::log_browser;
+use crate::log_browser::LogBrowserOptions;
+use crate::revset_util::RevsetExpressionEvaluator;
 use crate::ui::Ui;
 
 /// Show revision history
@@ -116,6 +122,15 @@ pub(crate) struct LogArgs {
     /// Show patch
     #[arg(long, short = 'p')]
     patch: bool,
+    /// Browse the log in a full-screen interface
+    ///
+    /// The diff of the selected revision is shown below the graph. The
+    /// selected revision can be described (`d`), squashed into its parent
+    /// (`s`), rebased (`r`, or `R` with descendants), abandoned (`a`), or a
+    /// new revision can be created on top of it (`n`). Each of these is
+    /// recorded as an ordinary operation.
+    #[arg(long, conflicts_with_all = ["no_graph", "reversed"])]
+    interactive: bool,
     #[command(flatten)]
     diff_format: DiffFormatArgs,
 }
@@ -126,33 +141,22 @@ pub(crate) fn cmd_log(
     command: &CommandHelper,
     args: &LogArgs,
 ) -> Result<(), CommandError> {
-    let workspace_command = command.workspace_helper(ui)?;
-    let settings = workspace_command.settings();
-
+    let mut workspace_command = command.workspace_helper(ui)?;
     let fileset_expression = workspace_command.parse_file_patterns(ui, &args.paths)?;
-    let revset_expression = {
-        // only use default revset if neither revset nor path are specified
-        let mut expression = if args.revisions.is_empty() && args.paths.is_empty() {
-            let revset_string = settings.get_string("revsets.log")?;
-            workspace_command.parse_revset(ui, &RevisionArg::from(revset_string))?
-        } else if !args.revisions.is_empty() {
-            workspace_command.parse_union_revsets(ui, &args.revisions)?
-        } else {
-            // a path was specified so we use all() and add path filter later
-            workspace_command.attach_revset_evaluator(RevsetExpression::all())
-        };
-        if !args.paths.is_empty() {
-            // Beware that args.paths = ["root:."] is not identical to []. The
-            // former will filter out empty commits.
-            let predicate = RevsetFilterPredicate::File(fileset_expression.clone());
-            expression.intersect_with(&RevsetExpression::filter(predicate));
-        }
-        expression
-    };
+    let matcher = fileset_expression.to_matcher();
+    if args.interactive {
+        return browse_log(
+            ui,
+            &mut workspace_command,
+            args,
+            &fileset_expression,
+            &*matcher,
+        );
+    }
+    let settings = workspace_command.settings();
 
     let repo = workspace_command.repo();
-    let matcher = fileset_expression.to_matcher();
-    let revset = revset_expression.evaluate()?;
+    let revset = parse_log_revset(ui, &workspace_command, args, &fileset_expression)?.evaluate()?;
 
     let store = repo.store();
     let diff_renderer = workspace_command.diff_renderer_for_log(&args.diff_format, args.patch)?;
@@ -342,6 +346,66 @@ pub(crate) fn cmd_log(
     Ok(())
 }
 
+fn parse_log_revset<'a>(
+    ui: &Ui,
+    workspace_command: &'a WorkspaceCommandHelper,
+    args: &LogArgs,
+    fileset_expression: &FilesetExpression,
+) -> Result<RevsetExpressionEvaluator<'a>, CommandError> {
+    // only use default revset if neither revset nor path are specified
+    let mut expression = if args.revisions.is_empty() && args.paths.is_empty() {
+        let revset_string = workspace_command.settings().get_string("revsets.log")?;
+        workspace_command.parse_revset(ui, &RevisionArg::from(revset_string))?
+    } else if !args.revisions.is_empty() {
+        workspace_command.parse_union_revsets(ui, &args.revisions)?
+    } else {
+        // a path was specified so we use all() and add path filter later
+        workspace_command.attach_revset_evaluator(RevsetExpression::all())
+    };
+    if !args.paths.is_empty() {
+        // Beware that args.paths = ["root:."] is not identical to []. The
+        // former will filter out empty commits.
+        let predicate = RevsetFilterPredicate::File(fileset_expression.clone());
+        expression.intersect_with(&RevsetExpression::filter(predicate));
+    }
+    Ok(expression)
+}
+
+fn browse_log(
+    ui: &Ui,
+    workspace_command: &mut WorkspaceCommandHelper,
+    args: &LogArgs,
+    fileset_expression: &FilesetExpression,
+    matcher: &dyn Matcher,
+) -> Result<(), CommandError> {
+    let settings = workspace_command.settings();
+    let graph_style = GraphStyle::from_settings(settings)?;
+    let options = LogBrowserOptions {
+        template: match &args.template {
+            Some(value) => value.clone(),
+            None => settings.get_string("templates.log")?,
+        },
+        node_template: get_node_template(graph_style, settings)?,
+        graph_style,
+        diff_format: &args.diff_format,
+        matcher,
+    };
+    let load_graph = |ui: &Ui, workspace_command: &WorkspaceCommandHelper| {
+        let revset =
+            parse_log_revset(ui, workspace_command, args, fileset_expression)?.evaluate()?;
+        let mut iter = TopoGroupedGraphIterator::new(revset.iter_graph());
+        if let Some(id) = workspace_command.get_wc_commit_id() {
+            let has_commit = revset.containing_fn();
+            if has_commit(id)? {
+                iter.prioritize_branch(id.clone());
+            }
+        }
+        let nodes = iter.take(args.limit.unwrap_or(usize::MAX)).try_collect()?;
+        Ok(nodes)
+    };
+    log_browser::run_log_browser(ui, workspace_command, &options, &load_graph)
+}
+
 pub fn get_node_template(
     style: GraphStyle,
     settings: &UserSettings,
@@ -19,7 +19,7 @@ use clap_complete::ArgValueCandidates;
 use itertools::Itertools as _;
 use jj_lib::backend::CommitId;
 use jj_lib::repo::Repo as _;
-use jj_lib::rewrite::merge_commit_trees;
+use jj_lib::rewrite::merge_commit_trees_with_drivers;
 use jj_lib::rewrite::rebase_commit;
 use tracing::instrument;
 
@@ -128,7 +128,7 @@ pub(crate) fn cmd_new(
     let parent_commit_ids_set: HashSet<CommitId> = parent_commit_ids.iter().cloned().collect();
 
     let mut tx = workspace_command.start_transaction();
-    let merged_tree = merge_commit_trees(tx.repo(), &parent_commits)?;
+    let merged_tree = merge_commit_trees_with_drivers(tx.repo(), &parent_commits)?;
     let new_commit = tx
         .repo_mut()
         .new_commit(parent_commit_ids, merged_tree.id())
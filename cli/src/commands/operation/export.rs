@@ -0,0 +1,69 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::io::Write as _;
+use std::path::PathBuf;
+
+use clap_complete::ArgValueCandidates;
+use jj_lib::op_bundle::export_operations;
+
+use crate::cli_util::CommandHelper;
+use crate::command_error::user_error_with_message;
+use crate::command_error::CommandError;
+use crate::complete;
+use crate::ui::Ui;
+
+/// Export operations to a bundle file
+///
+/// The bundle contains the operation, its ancestors, and all commits and file
+/// contents referenced by them. It can be imported to another repo by `jj op
+/// import`, e.g. to share the repo state in a bug report.
+#[derive(clap::Args, Clone, Debug)]
+pub struct OperationExportArgs {
+    /// The operation to export
+    #[arg(default_value = "@", add = ArgValueCandidates::new(complete::operations))]
+    operation: String,
+    /// File to write the bundle to
+    #[arg(long, short, value_hint = clap::ValueHint::FilePath)]
+    output: PathBuf,
+}
+
+pub fn cmd_op_export(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &OperationExportArgs,
+) -> Result<(), CommandError> {
+    let workspace_command = command.workspace_helper(ui)?;
+    let repo_loader = workspace_command.workspace().repo_loader();
+    let op = workspace_command.resolve_single_op(&args.operation)?;
+    let file = File::create(&args.output).map_err(|err| {
+        user_error_with_message(
+            format!("Failed to create bundle file {}", args.output.display()),
+            err,
+        )
+    })?;
+    let mut output = BufWriter::new(file);
+    let stats = export_operations(repo_loader, &[op], &mut output)?;
+    output.flush()?;
+    writeln!(
+        ui.status(),
+        "Exported {} operations and {} commits to {}",
+        stats.num_operations,
+        stats.commit_ids.len(),
+        args.output.display()
+    )?;
+    Ok(())
+}
@@ -0,0 +1,162 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::io;
+use std::io::BufReader;
+use std::io::Write as _;
+use std::path::PathBuf;
+use std::slice;
+
+use itertools::Itertools as _;
+use jj_lib::backend::CommitId;
+use jj_lib::op_bundle::import_operations;
+use jj_lib::op_store::OperationId;
+use jj_lib::op_walk;
+use jj_lib::repo::Repo as _;
+
+use crate::cli_util::short_commit_hash;
+use crate::cli_util::CommandHelper;
+use crate::cli_util::WorkspaceCommandHelper;
+use crate::command_error::cli_error;
+use crate::command_error::user_error_with_message;
+use crate::command_error::CommandError;
+use crate::ui::Ui;
+
+/// Import operations from a bundle file
+///
+/// The bundle should be created by `jj op export`. The imported operations are
+/// merged with the current operation as if they were run concurrently in this
+/// repo. Working-copy commits of the exported repo are not checked out.
+#[derive(clap::Args, Clone, Debug)]
+pub struct OperationImportArgs {
+    /// The bundle file to import
+    #[arg(value_hint = clap::ValueHint::FilePath)]
+    bundle: PathBuf,
+}
+
+pub fn cmd_op_import(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &OperationImportArgs,
+) -> Result<(), CommandError> {
+    // The imported operations will be merged with the current head.
+    if command.global_args().at_operation.is_some() {
+        return Err(cli_error("--at-op is not respected"));
+    }
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let repo_loader = workspace_command.workspace().repo_loader().clone();
+    let file = File::open(&args.bundle).map_err(|err| {
+        user_error_with_message(
+            format!("Failed to open bundle file {}", args.bundle.display()),
+            err,
+        )
+    })?;
+    let stats = import_operations(&repo_loader, &mut BufReader::new(file))?;
+    writeln!(
+        ui.status(),
+        "Imported {} operations and {} new commits",
+        stats.num_operations,
+        stats.commit_ids.len()
+    )?;
+    print_conflicting_commits(ui, &stats.conflicting_commit_ids)?;
+
+    merge_imported_operations(
+        ui,
+        &mut workspace_command,
+        &stats.head_ids,
+        format!("import operations from {}", args.bundle.display()),
+    )
+}
+
+/// Warns about imported commits whose metadata differed from the local
+/// metadata, which was kept.
+pub fn print_conflicting_commits(ui: &Ui, commit_ids: &[CommitId]) -> io::Result<()> {
+    if commit_ids.is_empty() {
+        return Ok(());
+    }
+    writeln!(
+        ui.warning_default(),
+        "Kept the local change ids and predecessors of {} commits, which differ from the \
+         imported ones:",
+        commit_ids.len()
+    )?;
+    for id in commit_ids {
+        writeln!(ui.warning_no_heading(), "  {}", short_commit_hash(id))?;
+    }
+    Ok(())
+}
+
+/// Merges the imported operations `head_ids` with the current operation.
+///
+/// Working copies of the source repo don't belong to this repo. The local
+/// working-copy commits are kept, and the imported ones are abandoned if they
+/// are discardable heads.
+pub fn merge_imported_operations(
+    ui: &mut Ui,
+    workspace_command: &mut WorkspaceCommandHelper,
+    head_ids: &[OperationId],
+    description: String,
+) -> Result<(), CommandError> {
+    let repo_loader = workspace_command.workspace().repo_loader().clone();
+    let current_op = workspace_command.repo().operation().clone();
+    let ancestor_op_ids: HashSet<OperationId> =
+        op_walk::walk_ancestors(slice::from_ref(&current_op))
+            .map_ok(|op| op.id().clone())
+            .try_collect()?;
+    let new_head_ids = head_ids
+        .iter()
+        .filter(|id| !ancestor_op_ids.contains(id))
+        .collect_vec();
+    if new_head_ids.is_empty() {
+        writeln!(ui.status(), "Nothing changed.")?;
+        return Ok(());
+    }
+    let wc_commit_ids = current_op.view()?.wc_commit_ids().clone();
+    let mut imported_wc_commit_ids = HashSet::new();
+    let mut tx = workspace_command.start_transaction();
+    for id in new_head_ids {
+        let op = repo_loader.load_operation(id)?;
+        imported_wc_commit_ids.extend(op.view()?.wc_commit_ids().values().cloned());
+        tx.merge_operation(op)?;
+    }
+    let merged_wc_commit_ids = tx.repo().view().wc_commit_ids().clone();
+    for workspace_id in merged_wc_commit_ids.keys() {
+        if !wc_commit_ids.contains_key(workspace_id) {
+            tx.repo_mut().remove_wc_commit(workspace_id)?;
+        }
+    }
+    for (workspace_id, commit_id) in &wc_commit_ids {
+        tx.repo_mut()
+            .set_wc_commit(workspace_id.clone(), commit_id.clone())?;
+    }
+    for commit_id in &imported_wc_commit_ids {
+        let view = tx.repo().view();
+        let is_referenced = wc_commit_ids.values().contains(commit_id)
+            || view
+                .local_bookmarks()
+                .any(|(_, target)| target.added_ids().contains(commit_id));
+        if is_referenced || !view.heads().contains(commit_id) {
+            continue;
+        }
+        let commit = tx.repo().store().get_commit(commit_id)?;
+        if commit.is_discardable(tx.repo())? {
+            tx.repo_mut().record_abandoned_commit(&commit);
+        }
+    }
+    tx.repo_mut().rebase_descendants()?;
+    tx.finish(ui, description)?;
+    Ok(())
+}
@@ -14,6 +14,8 @@
 
 mod abandon;
 mod diff;
+mod export;
+pub mod import;
 mod log;
 mod restore;
 mod show;
@@ -24,6 +26,10 @@ use abandon::OperationAbandonArgs;
 use clap::Subcommand;
 use diff::cmd_op_diff;
 use diff::OperationDiffArgs;
+use export::cmd_op_export;
+use export::OperationExportArgs;
+use import::cmd_op_import;
+use import::OperationImportArgs;
 use log::cmd_op_log;
 use log::OperationLogArgs;
 use restore::cmd_op_restore;
@@ -47,6 +53,8 @@ use crate::ui::Ui;
 pub enum OperationCommand {
     Abandon(OperationAbandonArgs),
     Diff(OperationDiffArgs),
+    Export(OperationExportArgs),
+    Import(OperationImportArgs),
     Log(OperationLogArgs),
     Restore(OperationRestoreArgs),
     Show(OperationShowArgs),
@@ -61,6 +69,8 @@ pub fn cmd_operation(
     match subcommand {
         OperationCommand::Abandon(args) => cmd_op_abandon(ui, command, args),
         OperationCommand::Diff(args) => cmd_op_diff(ui, command, args),
+        OperationCommand::Export(args) => cmd_op_export(ui, command, args),
+        OperationCommand::Import(args) => cmd_op_import(ui, command, args),
         OperationCommand::Log(args) => cmd_op_log(ui, command, args),
         OperationCommand::Restore(args) => cmd_op_restore(ui, command, args),
         OperationCommand::Show(args) => cmd_op_show(ui, command, args),
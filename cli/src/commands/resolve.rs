@@ -12,16 +12,24 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::slice;
+
 use clap_complete::ArgValueCandidates;
 use clap_complete::ArgValueCompleter;
 use itertools::Itertools as _;
+use jj_lib::commit::Commit;
+use jj_lib::matchers::Matcher;
 use jj_lib::object_id::ObjectId as _;
+use jj_lib::rerere;
+use jj_lib::rewrite::rebase_to_dest_parent;
 use tracing::instrument;
 
 use crate::cli_util::print_conflicted_paths;
 use crate::cli_util::CommandHelper;
 use crate::cli_util::RevisionArg;
+use crate::cli_util::WorkspaceCommandHelper;
 use crate::command_error::cli_error;
+use crate::command_error::user_error;
 use crate::command_error::CommandError;
 use crate::complete;
 use crate::ui::Ui;
@@ -37,6 +45,11 @@ use crate::ui::Ui;
 /// Note that conflicts can also be resolved without using this command. You may
 /// edit the conflict markers in the conflicted file directly with a text
 /// editor.
+///
+/// If `rerere.enabled` is set, resolutions of conflicts are recorded and
+/// applied again when the same conflicts reappear after rebasing. Use
+/// `--forget` to drop the recorded resolutions of the conflicts at the given
+/// revision, e.g. after a wrong resolution was applied.
 //  TODOs:
 //   - `jj resolve --editor` to resolve a conflict in the default text editor. Should work for
 //     conflicts with 3+ adds. Useful to resolve conflicts in a commit other than the current one.
@@ -61,6 +74,13 @@ pub(crate) struct ResolveArgs {
     /// Specify 3-way merge tool to be used
     #[arg(long, conflicts_with = "list", value_name = "NAME")]
     tool: Option<String>,
+    /// Instead of resolving conflicts, forget the recorded resolutions of the
+    /// conflicts in the revision
+    ///
+    /// This also applies to conflicts which were resolved automatically by
+    /// using the recorded resolutions when the revision was rebased.
+    #[arg(long, conflicts_with_all = ["list", "tool"])]
+    forget: bool,
     /// Only resolve conflicts in these paths. You can use the `--list` argument
     /// to find paths to use here.
     #[arg(
@@ -82,6 +102,9 @@ pub(crate) fn cmd_resolve(
         .parse_file_patterns(ui, &args.paths)?
         .to_matcher();
     let commit = workspace_command.resolve_single_rev(ui, &args.revision)?;
+    if args.forget {
+        return forget_resolutions(ui, &workspace_command, &commit, matcher.as_ref());
+    }
     let tree = commit.tree()?;
     let conflicts = tree
         .conflicts()
@@ -143,3 +166,39 @@ pub(crate) fn cmd_resolve(
     }
     Ok(())
 }
+
+fn forget_resolutions(
+    ui: &mut Ui,
+    workspace_command: &WorkspaceCommandHelper,
+    commit: &Commit,
+    matcher: &dyn Matcher,
+) -> Result<(), CommandError> {
+    let repo = workspace_command.repo();
+    let Some(resolution_store) = repo.loader().resolution_store() else {
+        return Err(user_error(
+            "This repository doesn't store conflict resolutions",
+        ));
+    };
+    // The conflicts might have been resolved by the recorded resolutions when
+    // the commit was rebased, so look at the trees the predecessors would have
+    // without them, too.
+    let mut trees = vec![commit.tree()?];
+    for predecessor in commit.predecessors() {
+        let predecessor = predecessor?;
+        trees.push(rebase_to_dest_parent(
+            repo.as_ref(),
+            slice::from_ref(&predecessor),
+            commit,
+        )?);
+    }
+    let mut num_forgotten = 0;
+    for tree in &trees {
+        num_forgotten += rerere::forget_resolutions(resolution_store, tree, matcher)?;
+    }
+    if num_forgotten == 0 {
+        writeln!(ui.status(), "No recorded resolutions found")?;
+    } else {
+        writeln!(ui.status(), "Forgot {num_forgotten} recorded resolutions")?;
+    }
+    Ok(())
+}
@@ -13,18 +13,26 @@
 // limitations under the License.
 
 use std::io;
+use std::rc::Rc;
 
+use clap_complete::ArgValueCandidates;
 use itertools::Itertools as _;
+use jj_lib::config::ConfigGetResultExt as _;
 use jj_lib::copies::CopyRecords;
+use jj_lib::matchers::Matcher;
 use jj_lib::repo::Repo as _;
 use jj_lib::revset::RevsetExpression;
 use jj_lib::revset::RevsetFilterPredicate;
+use jj_lib::working_copy::WorkingCopyFreshness;
 use tracing::instrument;
 
 use crate::cli_util::print_conflicted_paths;
 use crate::cli_util::print_snapshot_stats;
 use crate::cli_util::CommandHelper;
 use crate::command_error::CommandError;
+use crate::commit_templater::CommitTemplateLanguage;
+use crate::commit_templater::WorkingCopyStatus;
+use crate::complete;
 use crate::diff_util::get_copy_records;
 use crate::diff_util::DiffFormat;
 use crate::ui::Ui;
@@ -45,6 +53,23 @@ pub(crate) struct StatusArgs {
     /// Restrict the status display to these paths
     #[arg(value_name = "FILESETS", value_hint = clap::ValueHint::AnyPath)]
     paths: Vec<String>,
+
+    /// Render the status using the given template
+    ///
+    /// All 0-argument methods of the [`WorkingCopyStatus` type] are available
+    /// as keywords in the template expression. See [`jj help -k templates`]
+    /// for more information.
+    ///
+    /// If not specified, this defaults to the `templates.status` setting, or
+    /// the built-in layout if the setting is unset.
+    ///
+    /// [`WorkingCopyStatus` type]:
+    ///     https://jj-vcs.github.io/jj/latest/templates/#workingcopystatus-type
+    ///
+    /// [`jj help -k templates`]:
+    ///     https://jj-vcs.github.io/jj/latest/templates/
+    #[arg(long, short = 'T', add = ArgValueCandidates::new(complete::template_aliases))]
+    template: Option<String>,
 }
 
 #[instrument(skip_all)]
@@ -67,6 +92,59 @@ pub(crate) fn cmd_status(
     let matcher = workspace_command
         .parse_file_patterns(ui, &args.paths)?
         .to_matcher();
+    let template_text = match &args.template {
+        Some(value) => Some(value.to_owned()),
+        None => workspace_command
+            .settings()
+            .get_string("templates.status")
+            .optional()?,
+    };
+    if let Some(text) = template_text {
+        let language = workspace_command.commit_template_language();
+        let template = workspace_command
+            .parse_template(
+                ui,
+                &language,
+                &text,
+                CommitTemplateLanguage::wrap_working_copy_status,
+            )?
+            .labeled("status");
+        ui.request_pager();
+        let mut formatter = ui.stdout_formatter();
+        let Some(wc_commit) = maybe_wc_commit else {
+            writeln!(formatter, "No working copy")?;
+            return Ok(());
+        };
+        let matcher: Rc<dyn Matcher> = matcher.into();
+        let conflicts = wc_commit
+            .tree()?
+            .conflicts()
+            .map(|(path, _)| path)
+            .filter(|path| matcher.matches(path))
+            .collect();
+        let working_copy = workspace_command.working_copy();
+        let freshness = WorkingCopyFreshness::check_stale_at(
+            working_copy.tree_id()?,
+            working_copy.operation_id(),
+            &wc_commit,
+            repo,
+        )?;
+        let stale = matches!(
+            freshness,
+            WorkingCopyFreshness::WorkingCopyStale | WorkingCopyFreshness::SiblingOperation
+        );
+        let status = WorkingCopyStatus {
+            parents: wc_commit.parents().try_collect()?,
+            working_copy: wc_commit,
+            matcher,
+            conflicts,
+            untracked_paths: snapshot_stats.untracked_paths.keys().cloned().collect(),
+            stale,
+        };
+        template.format(&status, formatter.as_mut())?;
+        return Ok(());
+    }
+
     ui.request_pager();
     let mut formatter = ui.stdout_formatter();
     let formatter = formatter.as_mut();
@@ -59,6 +59,11 @@ use crate::ui::Ui;
 /// # This last empty string will become "$0" in bash, so your actual arguments
 /// # are all included in "$@" and start at "$1" as expected.
 /// ```
+///
+/// The command is run with `$JJ` set to the path of the running `jj` executable
+/// and, if a repository is found, `$JJ_REPO` set to its workspace root. Use
+/// `"$JJ" -R "$JJ_REPO" ...` to run jj against the same repository, including
+/// the one given by `-R`.
 #[derive(clap::Args, Clone, Debug)]
 #[command(verbatim_doc_comment)]
 pub(crate) struct UtilExecArgs {
@@ -70,18 +75,25 @@ pub(crate) struct UtilExecArgs {
 
 pub fn cmd_util_exec(
     _ui: &mut Ui,
-    _command: &CommandHelper,
+    command: &CommandHelper,
     args: &UtilExecArgs,
 ) -> Result<(), CommandError> {
-    let status = std::process::Command::new(&args.command)
-        .args(&args.args)
-        .status()
-        .map_err(|err| {
-            user_error_with_message(
-                format!("Failed to execute external command '{}'", &args.command),
-                err,
-            )
-        })?;
+    let mut cmd = std::process::Command::new(&args.command);
+    cmd.args(&args.args);
+    if let Ok(exe) = std::env::current_exe() {
+        cmd.env("JJ", exe);
+    }
+    if let Ok(loader) = command.workspace_loader() {
+        cmd.env("JJ_REPO", loader.workspace_root());
+    } else {
+        cmd.env_remove("JJ_REPO");
+    }
+    let status = cmd.status().map_err(|err| {
+        user_error_with_message(
+            format!("Failed to execute external command '{}'", &args.command),
+            err,
+        )
+    })?;
     if !status.success() {
         let error_msg = if let Some(exit_code) = status.code() {
             format!("External command exited with {exit_code}")
@@ -20,6 +20,7 @@ use std::io;
 use std::rc::Rc;
 
 use bstr::BString;
+use bstr::ByteSlice as _;
 use futures::stream::BoxStream;
 use futures::StreamExt as _;
 use futures::TryStreamExt as _;
@@ -66,7 +67,9 @@ use once_cell::unsync::OnceCell;
 use pollster::FutureExt as _;
 
 use crate::diff_util;
+use crate::diff_util::DiffLineType;
 use crate::diff_util::DiffStats;
+use crate::diff_util::FileDiffHunk;
 use crate::formatter::Formatter;
 use crate::revset_util;
 use crate::template_builder;
@@ -84,6 +87,7 @@ use crate::template_parser::TemplateDiagnostics;
 use crate::template_parser::TemplateParseError;
 use crate::template_parser::TemplateParseResult;
 use crate::templater;
+use crate::templater::IntoJsonValue;
 use crate::templater::PlainTextFormattedProperty;
 use crate::templater::SizeHint;
 use crate::templater::Template;
@@ -266,6 +270,18 @@ impl<'repo> TemplateLanguage<'repo> for CommitTemplateLanguage<'repo> {
                     function,
                 )
             }
+            CommitTemplatePropertyKind::RepoPathList(property) => {
+                // TODO: migrate to table?
+                template_builder::build_formattable_list_method(
+                    self,
+                    diagnostics,
+                    build_ctx,
+                    property,
+                    function,
+                    Self::wrap_repo_path,
+                    Self::wrap_repo_path_list,
+                )
+            }
             CommitTemplatePropertyKind::CommitOrChangeId(property) => {
                 let table = &self.build_fn_table.commit_or_change_id_methods;
                 let build = template_parser::lookup_method(type_name, table, function)?;
@@ -303,6 +319,23 @@ impl<'repo> TemplateLanguage<'repo> for CommitTemplateLanguage<'repo> {
                 let build = template_parser::lookup_method(type_name, table, function)?;
                 build(self, diagnostics, build_ctx, property, function)
             }
+            CommitTemplatePropertyKind::DiffHunk(property) => {
+                let table = &self.build_fn_table.diff_hunk_methods;
+                let build = template_parser::lookup_method(type_name, table, function)?;
+                build(self, diagnostics, build_ctx, property, function)
+            }
+            CommitTemplatePropertyKind::DiffHunkList(property) => {
+                // TODO: migrate to table?
+                template_builder::build_unformattable_list_method(
+                    self,
+                    diagnostics,
+                    build_ctx,
+                    property,
+                    function,
+                    Self::wrap_diff_hunk,
+                    Self::wrap_diff_hunk_list,
+                )
+            }
             CommitTemplatePropertyKind::DiffStats(property) => {
                 let table = &self.build_fn_table.diff_stats_methods;
                 let build = template_parser::lookup_method(type_name, table, function)?;
@@ -330,6 +363,11 @@ impl<'repo> TemplateLanguage<'repo> for CommitTemplateLanguage<'repo> {
                 let build = template_parser::lookup_method(type_name, table, function)?;
                 build(self, diagnostics, build_ctx, property, function)
             }
+            CommitTemplatePropertyKind::WorkingCopyStatus(property) => {
+                let table = &self.build_fn_table.working_copy_status_methods;
+                let build = template_parser::lookup_method(type_name, table, function)?;
+                build(self, diagnostics, build_ctx, property, function)
+            }
         }
     }
 }
@@ -401,6 +439,12 @@ impl<'repo> CommitTemplateLanguage<'repo> {
         CommitTemplatePropertyKind::RepoPathOpt(Box::new(property))
     }
 
+    pub fn wrap_repo_path_list(
+        property: impl TemplateProperty<Output = Vec<RepoPathBuf>> + 'repo,
+    ) -> CommitTemplatePropertyKind<'repo> {
+        CommitTemplatePropertyKind::RepoPathList(Box::new(property))
+    }
+
     pub fn wrap_commit_or_change_id(
         property: impl TemplateProperty<Output = CommitOrChangeId> + 'repo,
     ) -> CommitTemplatePropertyKind<'repo> {
@@ -437,6 +481,18 @@ impl<'repo> CommitTemplateLanguage<'repo> {
         CommitTemplatePropertyKind::TreeEntry(Box::new(property))
     }
 
+    pub fn wrap_diff_hunk(
+        property: impl TemplateProperty<Output = FileDiffHunk> + 'repo,
+    ) -> CommitTemplatePropertyKind<'repo> {
+        CommitTemplatePropertyKind::DiffHunk(Box::new(property))
+    }
+
+    pub fn wrap_diff_hunk_list(
+        property: impl TemplateProperty<Output = Vec<FileDiffHunk>> + 'repo,
+    ) -> CommitTemplatePropertyKind<'repo> {
+        CommitTemplatePropertyKind::DiffHunkList(Box::new(property))
+    }
+
     pub fn wrap_diff_stats(
         property: impl TemplateProperty<Output = DiffStatsFormatted<'repo>> + 'repo,
     ) -> CommitTemplatePropertyKind<'repo> {
@@ -454,6 +510,12 @@ impl<'repo> CommitTemplateLanguage<'repo> {
     ) -> CommitTemplatePropertyKind<'repo> {
         CommitTemplatePropertyKind::AnnotationLine(Box::new(property))
     }
+
+    pub fn wrap_working_copy_status(
+        property: impl TemplateProperty<Output = WorkingCopyStatus> + 'repo,
+    ) -> CommitTemplatePropertyKind<'repo> {
+        CommitTemplatePropertyKind::WorkingCopyStatus(Box::new(property))
+    }
 }
 
 pub enum CommitTemplatePropertyKind<'repo> {
@@ -466,17 +528,21 @@ pub enum CommitTemplatePropertyKind<'repo> {
     CommitRefList(Box<dyn TemplateProperty<Output = Vec<Rc<CommitRef>>> + 'repo>),
     RepoPath(Box<dyn TemplateProperty<Output = RepoPathBuf> + 'repo>),
     RepoPathOpt(Box<dyn TemplateProperty<Output = Option<RepoPathBuf>> + 'repo>),
+    RepoPathList(Box<dyn TemplateProperty<Output = Vec<RepoPathBuf>> + 'repo>),
     CommitOrChangeId(Box<dyn TemplateProperty<Output = CommitOrChangeId> + 'repo>),
     ShortestIdPrefix(Box<dyn TemplateProperty<Output = ShortestIdPrefix> + 'repo>),
     TreeDiff(Box<dyn TemplateProperty<Output = TreeDiff> + 'repo>),
     TreeDiffEntry(Box<dyn TemplateProperty<Output = TreeDiffEntry> + 'repo>),
     TreeDiffEntryList(Box<dyn TemplateProperty<Output = Vec<TreeDiffEntry>> + 'repo>),
     TreeEntry(Box<dyn TemplateProperty<Output = TreeEntry> + 'repo>),
+    DiffHunk(Box<dyn TemplateProperty<Output = FileDiffHunk> + 'repo>),
+    DiffHunkList(Box<dyn TemplateProperty<Output = Vec<FileDiffHunk>> + 'repo>),
     DiffStats(Box<dyn TemplateProperty<Output = DiffStatsFormatted<'repo>> + 'repo>),
     CryptographicSignatureOpt(
         Box<dyn TemplateProperty<Output = Option<CryptographicSignature>> + 'repo>,
     ),
     AnnotationLine(Box<dyn TemplateProperty<Output = AnnotationLine> + 'repo>),
+    WorkingCopyStatus(Box<dyn TemplateProperty<Output = WorkingCopyStatus> + 'repo>),
 }
 
 impl<'repo> IntoTemplateProperty<'repo> for CommitTemplatePropertyKind<'repo> {
@@ -491,17 +557,21 @@ impl<'repo> IntoTemplateProperty<'repo> for CommitTemplatePropertyKind<'repo> {
             CommitTemplatePropertyKind::CommitRefList(_) => "List<CommitRef>",
             CommitTemplatePropertyKind::RepoPath(_) => "RepoPath",
             CommitTemplatePropertyKind::RepoPathOpt(_) => "Option<RepoPath>",
+            CommitTemplatePropertyKind::RepoPathList(_) => "List<RepoPath>",
             CommitTemplatePropertyKind::CommitOrChangeId(_) => "CommitOrChangeId",
             CommitTemplatePropertyKind::ShortestIdPrefix(_) => "ShortestIdPrefix",
             CommitTemplatePropertyKind::TreeDiff(_) => "TreeDiff",
             CommitTemplatePropertyKind::TreeDiffEntry(_) => "TreeDiffEntry",
             CommitTemplatePropertyKind::TreeDiffEntryList(_) => "List<TreeDiffEntry>",
             CommitTemplatePropertyKind::TreeEntry(_) => "TreeEntry",
+            CommitTemplatePropertyKind::DiffHunk(_) => "DiffHunk",
+            CommitTemplatePropertyKind::DiffHunkList(_) => "List<DiffHunk>",
             CommitTemplatePropertyKind::DiffStats(_) => "DiffStats",
             CommitTemplatePropertyKind::CryptographicSignatureOpt(_) => {
                 "Option<CryptographicSignature>"
             }
             CommitTemplatePropertyKind::AnnotationLine(_) => "AnnotationLine",
+            CommitTemplatePropertyKind::WorkingCopyStatus(_) => "WorkingCopyStatus",
         }
     }
 
@@ -526,6 +596,9 @@ impl<'repo> IntoTemplateProperty<'repo> for CommitTemplatePropertyKind<'repo> {
             CommitTemplatePropertyKind::RepoPathOpt(property) => {
                 Some(Box::new(property.map(|opt| opt.is_some())))
             }
+            CommitTemplatePropertyKind::RepoPathList(property) => {
+                Some(Box::new(property.map(|l| !l.is_empty())))
+            }
             CommitTemplatePropertyKind::CommitOrChangeId(_) => None,
             CommitTemplatePropertyKind::ShortestIdPrefix(_) => None,
             // TODO: boolean cast could be implemented, but explicit
@@ -536,11 +609,16 @@ impl<'repo> IntoTemplateProperty<'repo> for CommitTemplatePropertyKind<'repo> {
                 Some(Box::new(property.map(|l| !l.is_empty())))
             }
             CommitTemplatePropertyKind::TreeEntry(_) => None,
+            CommitTemplatePropertyKind::DiffHunk(_) => None,
+            CommitTemplatePropertyKind::DiffHunkList(property) => {
+                Some(Box::new(property.map(|l| !l.is_empty())))
+            }
             CommitTemplatePropertyKind::DiffStats(_) => None,
             CommitTemplatePropertyKind::CryptographicSignatureOpt(property) => {
                 Some(Box::new(property.map(|sig| sig.is_some())))
             }
             CommitTemplatePropertyKind::AnnotationLine(_) => None,
+            CommitTemplatePropertyKind::WorkingCopyStatus(_) => None,
         }
     }
 
@@ -572,6 +650,7 @@ impl<'repo> IntoTemplateProperty<'repo> for CommitTemplatePropertyKind<'repo> {
             CommitTemplatePropertyKind::CommitRefList(property) => Some(property.into_template()),
             CommitTemplatePropertyKind::RepoPath(property) => Some(property.into_template()),
             CommitTemplatePropertyKind::RepoPathOpt(property) => Some(property.into_template()),
+            CommitTemplatePropertyKind::RepoPathList(property) => Some(property.into_template()),
             CommitTemplatePropertyKind::CommitOrChangeId(property) => {
                 Some(property.into_template())
             }
@@ -582,9 +661,44 @@ impl<'repo> IntoTemplateProperty<'repo> for CommitTemplatePropertyKind<'repo> {
             CommitTemplatePropertyKind::TreeDiffEntry(_) => None,
             CommitTemplatePropertyKind::TreeDiffEntryList(_) => None,
             CommitTemplatePropertyKind::TreeEntry(_) => None,
+            CommitTemplatePropertyKind::DiffHunk(_) => None,
+            CommitTemplatePropertyKind::DiffHunkList(_) => None,
             CommitTemplatePropertyKind::DiffStats(property) => Some(property.into_template()),
             CommitTemplatePropertyKind::CryptographicSignatureOpt(_) => None,
             CommitTemplatePropertyKind::AnnotationLine(_) => None,
+            CommitTemplatePropertyKind::WorkingCopyStatus(_) => None,
+        }
+    }
+
+    fn try_into_json(
+        self,
+    ) -> Option<Box<dyn TemplateProperty<Output = serde_json::Value> + 'repo>> {
+        match self {
+            CommitTemplatePropertyKind::Core(property) => property.try_into_json(),
+            CommitTemplatePropertyKind::Commit(property) => Some(property.into_json()),
+            CommitTemplatePropertyKind::CommitOpt(property) => Some(property.into_json()),
+            CommitTemplatePropertyKind::CommitList(property) => Some(property.into_json()),
+            CommitTemplatePropertyKind::CommitRef(property) => Some(property.into_json()),
+            CommitTemplatePropertyKind::CommitRefOpt(property) => Some(property.into_json()),
+            CommitTemplatePropertyKind::CommitRefList(property) => Some(property.into_json()),
+            CommitTemplatePropertyKind::RepoPath(property) => Some(property.into_json()),
+            CommitTemplatePropertyKind::RepoPathOpt(property) => Some(property.into_json()),
+            CommitTemplatePropertyKind::RepoPathList(property) => Some(property.into_json()),
+            CommitTemplatePropertyKind::CommitOrChangeId(property) => Some(property.into_json()),
+            CommitTemplatePropertyKind::ShortestIdPrefix(property) => Some(property.into_json()),
+            // Tree diff is evaluated lazily. Its entries can be serialized.
+            CommitTemplatePropertyKind::TreeDiff(_) => None,
+            CommitTemplatePropertyKind::TreeDiffEntry(property) => Some(property.into_json()),
+            CommitTemplatePropertyKind::TreeDiffEntryList(property) => Some(property.into_json()),
+            CommitTemplatePropertyKind::TreeEntry(property) => Some(property.into_json()),
+            CommitTemplatePropertyKind::DiffHunk(property) => Some(property.into_json()),
+            CommitTemplatePropertyKind::DiffHunkList(property) => Some(property.into_json()),
+            CommitTemplatePropertyKind::DiffStats(property) => Some(property.into_json()),
+            // Signature verification may be expensive, and its result isn't
+            // stable across machines.
+            CommitTemplatePropertyKind::CryptographicSignatureOpt(_) => None,
+            CommitTemplatePropertyKind::AnnotationLine(property) => Some(property.into_json()),
+            CommitTemplatePropertyKind::WorkingCopyStatus(property) => Some(property.into_json()),
         }
     }
 
@@ -602,15 +716,19 @@ impl<'repo> IntoTemplateProperty<'repo> for CommitTemplatePropertyKind<'repo> {
             (CommitTemplatePropertyKind::CommitRefList(_), _) => None,
             (CommitTemplatePropertyKind::RepoPath(_), _) => None,
             (CommitTemplatePropertyKind::RepoPathOpt(_), _) => None,
+            (CommitTemplatePropertyKind::RepoPathList(_), _) => None,
             (CommitTemplatePropertyKind::CommitOrChangeId(_), _) => None,
             (CommitTemplatePropertyKind::ShortestIdPrefix(_), _) => None,
             (CommitTemplatePropertyKind::TreeDiff(_), _) => None,
             (CommitTemplatePropertyKind::TreeDiffEntry(_), _) => None,
             (CommitTemplatePropertyKind::TreeDiffEntryList(_), _) => None,
             (CommitTemplatePropertyKind::TreeEntry(_), _) => None,
+            (CommitTemplatePropertyKind::DiffHunk(_), _) => None,
+            (CommitTemplatePropertyKind::DiffHunkList(_), _) => None,
             (CommitTemplatePropertyKind::DiffStats(_), _) => None,
             (CommitTemplatePropertyKind::CryptographicSignatureOpt(_), _) => None,
             (CommitTemplatePropertyKind::AnnotationLine(_), _) => None,
+            (CommitTemplatePropertyKind::WorkingCopyStatus(_), _) => None,
         }
     }
 
@@ -631,15 +749,19 @@ impl<'repo> IntoTemplateProperty<'repo> for CommitTemplatePropertyKind<'repo> {
             (CommitTemplatePropertyKind::CommitRefList(_), _) => None,
             (CommitTemplatePropertyKind::RepoPath(_), _) => None,
             (CommitTemplatePropertyKind::RepoPathOpt(_), _) => None,
+            (CommitTemplatePropertyKind::RepoPathList(_), _) => None,
             (CommitTemplatePropertyKind::CommitOrChangeId(_), _) => None,
             (CommitTemplatePropertyKind::ShortestIdPrefix(_), _) => None,
             (CommitTemplatePropertyKind::TreeDiff(_), _) => None,
             (CommitTemplatePropertyKind::TreeDiffEntry(_), _) => None,
             (CommitTemplatePropertyKind::TreeDiffEntryList(_), _) => None,
             (CommitTemplatePropertyKind::TreeEntry(_), _) => None,
+            (CommitTemplatePropertyKind::DiffHunk(_), _) => None,
+            (CommitTemplatePropertyKind::DiffHunkList(_), _) => None,
             (CommitTemplatePropertyKind::DiffStats(_), _) => None,
             (CommitTemplatePropertyKind::CryptographicSignatureOpt(_), _) => None,
             (CommitTemplatePropertyKind::AnnotationLine(_), _) => None,
+            (CommitTemplatePropertyKind::WorkingCopyStatus(_), _) => None,
         }
     }
 }
@@ -659,10 +781,12 @@ pub struct CommitTemplateBuildFnTable<'repo> {
     pub tree_diff_methods: CommitTemplateBuildMethodFnMap<'repo, TreeDiff>,
     pub tree_diff_entry_methods: CommitTemplateBuildMethodFnMap<'repo, TreeDiffEntry>,
     pub tree_entry_methods: CommitTemplateBuildMethodFnMap<'repo, TreeEntry>,
+    pub diff_hunk_methods: CommitTemplateBuildMethodFnMap<'repo, FileDiffHunk>,
     pub diff_stats_methods: CommitTemplateBuildMethodFnMap<'repo, DiffStats>,
     pub cryptographic_signature_methods:
         CommitTemplateBuildMethodFnMap<'repo, CryptographicSignature>,
     pub annotation_line_methods: CommitTemplateBuildMethodFnMap<'repo, AnnotationLine>,
+    pub working_copy_status_methods: CommitTemplateBuildMethodFnMap<'repo, WorkingCopyStatus>,
 }
 
 impl<'repo> CommitTemplateBuildFnTable<'repo> {
@@ -678,9 +802,11 @@ impl<'repo> CommitTemplateBuildFnTable<'repo> {
             tree_diff_methods: builtin_tree_diff_methods(),
             tree_diff_entry_methods: builtin_tree_diff_entry_methods(),
             tree_entry_methods: builtin_tree_entry_methods(),
+            diff_hunk_methods: builtin_diff_hunk_methods(),
             diff_stats_methods: builtin_diff_stats_methods(),
             cryptographic_signature_methods: builtin_cryptographic_signature_methods(),
             annotation_line_methods: builtin_annotation_line_methods(),
+            working_copy_status_methods: builtin_working_copy_status_methods(),
         }
     }
 
@@ -695,9 +821,11 @@ impl<'repo> CommitTemplateBuildFnTable<'repo> {
             tree_diff_methods: HashMap::new(),
             tree_diff_entry_methods: HashMap::new(),
             tree_entry_methods: HashMap::new(),
+            diff_hunk_methods: HashMap::new(),
             diff_stats_methods: HashMap::new(),
             cryptographic_signature_methods: HashMap::new(),
             annotation_line_methods: HashMap::new(),
+            working_copy_status_methods: HashMap::new(),
         }
     }
 
@@ -712,9 +840,11 @@ impl<'repo> CommitTemplateBuildFnTable<'repo> {
             tree_diff_methods,
             tree_diff_entry_methods,
             tree_entry_methods,
+            diff_hunk_methods,
             diff_stats_methods,
             cryptographic_signature_methods,
             annotation_line_methods,
+            working_copy_status_methods,
         } = extension;
 
         self.core.merge(core);
@@ -732,12 +862,17 @@ impl<'repo> CommitTemplateBuildFnTable<'repo> {
         merge_fn_map(&mut self.tree_diff_methods, tree_diff_methods);
         merge_fn_map(&mut self.tree_diff_entry_methods, tree_diff_entry_methods);
         merge_fn_map(&mut self.tree_entry_methods, tree_entry_methods);
+        merge_fn_map(&mut self.diff_hunk_methods, diff_hunk_methods);
         merge_fn_map(&mut self.diff_stats_methods, diff_stats_methods);
         merge_fn_map(
             &mut self.cryptographic_signature_methods,
             cryptographic_signature_methods,
         );
         merge_fn_map(&mut self.annotation_line_methods, annotation_line_methods);
+        merge_fn_map(
+            &mut self.working_copy_status_methods,
+            working_copy_status_methods,
+        );
     }
 }
 
@@ -782,6 +917,20 @@ impl<'repo> CommitKeywordCache<'repo> {
     }
 }
 
+impl IntoJsonValue for Commit {
+    fn into_json_value(self) -> Result<serde_json::Value, TemplatePropertyError> {
+        let parent_ids = self.parent_ids().iter().map(|id| id.hex()).collect_vec();
+        Ok(serde_json::json!({
+            "commit_id": self.id().hex(),
+            "parents": parent_ids,
+            "change_id": self.change_id().reverse_hex(),
+            "description": self.description(),
+            "author": self.author().clone().into_json_value()?,
+            "committer": self.committer().clone().into_json_value()?,
+        }))
+    }
+}
+
 fn builtin_commit_methods<'repo>() -> CommitTemplateBuildMethodFnMap<'repo, Commit> {
     type L<'repo> = CommitTemplateLanguage<'repo>;
     // Not using maplit::hashmap!{} or custom declarative macro here because
@@ -969,11 +1118,8 @@ fn builtin_commit_methods<'repo>() -> CommitTemplateBuildMethodFnMap<'repo, Comm
         |language, _diagnostics, _build_ctx, self_property, function| {
             function.expect_no_arguments()?;
             let repo = language.repo;
-            let out_property = self_property.map(|commit| {
-                // The given commit could be hidden in e.g. `jj evolog`.
-                let maybe_entries = repo.resolve_change_id(commit.change_id());
-                maybe_entries.map_or(0, |entries| entries.len()) > 1
-            });
+            // The given commit could be hidden in e.g. `jj evolog`.
+            let out_property = self_property.map(|commit| commit.is_divergent(repo));
             Ok(L::wrap_boolean(out_property))
         },
     );
@@ -1343,6 +1489,30 @@ impl Template for Vec<Rc<CommitRef>> {
     }
 }
 
+impl IntoJsonValue for Rc<CommitRef> {
+    fn into_json_value(self) -> Result<serde_json::Value, TemplatePropertyError> {
+        // Conflicted targets are serialized as [add, remove, add, ..] ids.
+        let target_to_json = |target: &RefTarget| {
+            target
+                .as_merge()
+                .iter()
+                .map(|id| id.as_ref().map(|id| id.hex()))
+                .collect_vec()
+        };
+        let mut value = serde_json::json!({
+            "name": self.name,
+            "target": target_to_json(&self.target),
+        });
+        if let Some(remote) = &self.remote {
+            value["remote"] = remote.clone().into();
+        }
+        if let Some(tracking_ref) = &self.tracking_ref {
+            value["tracking_target"] = target_to_json(&tracking_ref.target).into();
+        }
+        Ok(value)
+    }
+}
+
 fn builtin_commit_ref_methods<'repo>() -> CommitTemplateBuildMethodFnMap<'repo, Rc<CommitRef>> {
     type L<'repo> = CommitTemplateLanguage<'repo>;
     // Not using maplit::hashmap!{} or custom declarative macro here because
@@ -1514,6 +1684,18 @@ impl Template for RepoPathBuf {
     }
 }
 
+impl Template for Vec<RepoPathBuf> {
+    fn format(&self, formatter: &mut TemplateFormatter) -> io::Result<()> {
+        templater::format_joined(formatter, self, " ")
+    }
+}
+
+impl IntoJsonValue for RepoPathBuf {
+    fn into_json_value(self) -> Result<serde_json::Value, TemplatePropertyError> {
+        Ok(self.into_internal_string().into())
+    }
+}
+
 fn builtin_repo_path_methods<'repo>() -> CommitTemplateBuildMethodFnMap<'repo, RepoPathBuf> {
     type L<'repo> = CommitTemplateLanguage<'repo>;
     // Not using maplit::hashmap!{} or custom declarative macro here because
@@ -1584,6 +1766,12 @@ impl Template for CommitOrChangeId {
     }
 }
 
+impl IntoJsonValue for CommitOrChangeId {
+    fn into_json_value(self) -> Result<serde_json::Value, TemplatePropertyError> {
+        Ok(self.hex().into())
+    }
+}
+
 fn builtin_commit_or_change_id_methods<'repo>(
 ) -> CommitTemplateBuildMethodFnMap<'repo, CommitOrChangeId> {
     type L<'repo> = CommitTemplateLanguage<'repo>;
@@ -1675,6 +1863,12 @@ impl Template for ShortestIdPrefix {
     }
 }
 
+impl IntoJsonValue for ShortestIdPrefix {
+    fn into_json_value(self) -> Result<serde_json::Value, TemplatePropertyError> {
+        Ok(serde_json::json!({ "prefix": self.prefix, "rest": self.rest }))
+    }
+}
+
 impl ShortestIdPrefix {
     fn to_upper(&self) -> Self {
         Self {
@@ -1857,6 +2051,54 @@ fn builtin_tree_diff_methods<'repo>() -> CommitTemplateBuildMethodFnMap<'repo, T
             Ok(L::wrap_template(template))
         },
     );
+    map.insert(
+        "structural",
+        |language, diagnostics, build_ctx, self_property, function| {
+            let ([], [context_node]) = function.expect_arguments()?;
+            #[cfg(not(feature = "tree-sitter"))]
+            diagnostics.add_warning(TemplateParseError::expression(
+                "structural() is not supported by this build of jj; color-words diff is shown \
+                 instead",
+                function.name_span,
+            ));
+            let context_property = context_node
+                .map(|node| {
+                    template_builder::expect_usize_expression(
+                        language,
+                        diagnostics,
+                        build_ctx,
+                        node,
+                    )
+                })
+                .transpose()?;
+            let path_converter = language.path_converter;
+            let options = diff_util::ColorWordsDiffOptions::from_settings(language.settings())
+                .map_err(|err| {
+                    let message = "Failed to load diff settings";
+                    TemplateParseError::expression(message, function.name_span).with_source(err)
+                })?;
+            let conflict_marker_style = language.conflict_marker_style;
+            let template = (self_property, context_property)
+                .map(move |(diff, context)| {
+                    let mut options = options.clone();
+                    if let Some(context) = context {
+                        options.context = context;
+                    }
+                    diff.into_formatted(move |formatter, store, tree_diff| {
+                        diff_util::show_structural_diff(
+                            formatter,
+                            store,
+                            tree_diff,
+                            path_converter,
+                            &options,
+                            conflict_marker_style,
+                        )
+                    })
+                })
+                .into_template();
+            Ok(L::wrap_template(template))
+        },
+    );
     map.insert(
         "git",
         |language, diagnostics, build_ctx, self_property, function| {
@@ -1912,8 +2154,11 @@ fn builtin_tree_diff_methods<'repo>() -> CommitTemplateBuildMethodFnMap<'repo, T
                 })
                 .transpose()?;
             let path_converter = language.path_converter;
-            // No user configuration exists for diff stat.
-            let options = diff_util::DiffStatOptions::default();
+            let options =
+                diff_util::DiffStatOptions::from_settings(language.settings()).map_err(|err| {
+                    let message = "Failed to load diff settings";
+                    TemplateParseError::expression(message, function.name_span).with_source(err)
+                })?;
             let conflict_marker_style = language.conflict_marker_style;
             // TODO: cache and reuse stats within the current evaluation?
             let out_property = (self_property, width_property).and_then(move |(diff, width)| {
@@ -1992,6 +2237,19 @@ impl TreeDiffEntry {
     }
 }
 
+impl IntoJsonValue for TreeDiffEntry {
+    fn into_json_value(self) -> Result<serde_json::Value, TemplatePropertyError> {
+        let status = self.status_label();
+        let source = self.clone().into_source_entry();
+        let target = self.into_target_entry();
+        Ok(serde_json::json!({
+            "status": status,
+            "source": source.into_json_value()?,
+            "target": target.into_json_value()?,
+        }))
+    }
+}
+
 fn builtin_tree_diff_entry_methods<'repo>() -> CommitTemplateBuildMethodFnMap<'repo, TreeDiffEntry>
 {
     type L<'repo> = CommitTemplateLanguage<'repo>;
@@ -2031,6 +2289,57 @@ fn builtin_tree_diff_entry_methods<'repo>() -> CommitTemplateBuildMethodFnMap<'r
             Ok(L::wrap_tree_entry(out_property))
         },
     );
+    map.insert(
+        "copy_source",
+        |_language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let out_property = self_property.map(|entry| entry.path.source.map(|(path, _)| path));
+            Ok(L::wrap_repo_path_opt(out_property))
+        },
+    );
+    map.insert(
+        "hunks",
+        |language, diagnostics, build_ctx, self_property, function| {
+            let ([], [context_node]) = function.expect_arguments()?;
+            let context_property = context_node
+                .map(|node| {
+                    template_builder::expect_usize_expression(
+                        language,
+                        diagnostics,
+                        build_ctx,
+                        node,
+                    )
+                })
+                .transpose()?;
+            let options = diff_util::UnifiedDiffOptions::from_settings(language.settings())
+                .map_err(|err| {
+                    let message = "Failed to load diff settings";
+                    TemplateParseError::expression(message, function.name_span).with_source(err)
+                })?;
+            let store = language.repo.store().clone();
+            let conflict_marker_style = language.conflict_marker_style;
+            // TODO: cache and reuse materialized contents within the current
+            // evaluation?
+            let out_property =
+                (self_property, context_property).and_then(move |(entry, context)| {
+                    let mut options = options.clone();
+                    if let Some(context) = context {
+                        options.context = context;
+                    }
+                    let hunks = diff_util::file_diff_hunks(
+                        &store,
+                        &entry.path,
+                        entry.source_value,
+                        entry.target_value,
+                        &options,
+                        conflict_marker_style,
+                    )
+                    .block_on()?;
+                    Ok(hunks)
+                });
+            Ok(L::wrap_diff_hunk_list(out_property))
+        },
+    );
     map
 }
 
@@ -2041,6 +2350,17 @@ pub struct TreeEntry {
     pub value: MergedTreeValue,
 }
 
+impl IntoJsonValue for TreeEntry {
+    fn into_json_value(self) -> Result<serde_json::Value, TemplatePropertyError> {
+        Ok(serde_json::json!({
+            "path": self.path.into_internal_string(),
+            "conflict": !self.value.is_resolved(),
+            "file_type": describe_file_type(&self.value),
+            "executable": is_executable_file(&self.value).unwrap_or_default(),
+        }))
+    }
+}
+
 fn builtin_tree_entry_methods<'repo>() -> CommitTemplateBuildMethodFnMap<'repo, TreeEntry> {
     type L<'repo> = CommitTemplateLanguage<'repo>;
     // Not using maplit::hashmap!{} or custom declarative macro here because
@@ -2080,6 +2400,15 @@ fn builtin_tree_entry_methods<'repo>() -> CommitTemplateBuildMethodFnMap<'repo,
             Ok(L::wrap_boolean(out_property))
         },
     );
+    map.insert(
+        "mode",
+        |_language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let out_property =
+                self_property.map(|entry| describe_file_mode(&entry.value).to_owned());
+            Ok(L::wrap_string(out_property))
+        },
+    );
     map
 }
 
@@ -2099,6 +2428,136 @@ fn is_executable_file(value: &MergedTreeValue) -> Option<bool> {
     executable.resolve_trivial().copied()
 }
 
+/// Returns Git-style octal mode string, or empty string if the entry is
+/// absent or conflicted.
+fn describe_file_mode(value: &MergedTreeValue) -> &'static str {
+    match value.as_resolved() {
+        Some(Some(TreeValue::File { executable, .. })) => {
+            if *executable {
+                "100755"
+            } else {
+                "100644"
+            }
+        }
+        Some(Some(TreeValue::Symlink(_))) => "120000",
+        Some(Some(TreeValue::Tree(_))) => "040000",
+        Some(Some(TreeValue::GitSubmodule(_))) => "160000",
+        Some(None) | None | Some(Some(TreeValue::Conflict(_))) => "",
+    }
+}
+
+impl IntoJsonValue for FileDiffHunk {
+    fn into_json_value(self) -> Result<serde_json::Value, TemplatePropertyError> {
+        let lines = diff_hunk_lines_with_sigil(&self);
+        Ok(serde_json::json!({
+            "old_start": diff_util::to_unified_diff_line_number(self.left_line_range.clone()),
+            "old_count": self.left_line_range.len(),
+            "new_start": diff_util::to_unified_diff_line_number(self.right_line_range.clone()),
+            "new_count": self.right_line_range.len(),
+            "lines": lines,
+        }))
+    }
+}
+
+fn builtin_diff_hunk_methods<'repo>() -> CommitTemplateBuildMethodFnMap<'repo, FileDiffHunk> {
+    type L<'repo> = CommitTemplateLanguage<'repo>;
+    // Not using maplit::hashmap!{} or custom declarative macro here because
+    // code completion inside macro is quite restricted.
+    let mut map = CommitTemplateBuildMethodFnMap::<FileDiffHunk>::new();
+    map.insert(
+        "old_start",
+        |_language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let out_property = self_property.and_then(|hunk| {
+                Ok(diff_util::to_unified_diff_line_number(hunk.left_line_range).try_into()?)
+            });
+            Ok(L::wrap_integer(out_property))
+        },
+    );
+    map.insert(
+        "old_count",
+        |_language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let out_property =
+                self_property.and_then(|hunk| Ok(hunk.left_line_range.len().try_into()?));
+            Ok(L::wrap_integer(out_property))
+        },
+    );
+    map.insert(
+        "new_start",
+        |_language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let out_property = self_property.and_then(|hunk| {
+                Ok(diff_util::to_unified_diff_line_number(hunk.right_line_range).try_into()?)
+            });
+            Ok(L::wrap_integer(out_property))
+        },
+    );
+    map.insert(
+        "new_count",
+        |_language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let out_property =
+                self_property.and_then(|hunk| Ok(hunk.right_line_range.len().try_into()?));
+            Ok(L::wrap_integer(out_property))
+        },
+    );
+    map.insert(
+        "lines",
+        |_language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let out_property = self_property.map(|hunk| diff_hunk_lines_with_sigil(&hunk));
+            Ok(L::wrap_string_list(out_property))
+        },
+    );
+    map.insert(
+        "removed_lines",
+        |_language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let out_property =
+                self_property.map(|hunk| diff_hunk_lines_of_type(&hunk, DiffLineType::Removed));
+            Ok(L::wrap_string_list(out_property))
+        },
+    );
+    map.insert(
+        "added_lines",
+        |_language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let out_property =
+                self_property.map(|hunk| diff_hunk_lines_of_type(&hunk, DiffLineType::Added));
+            Ok(L::wrap_string_list(out_property))
+        },
+    );
+    map
+}
+
+fn diff_hunk_line_to_string(content: &[u8]) -> String {
+    let content = content.strip_suffix(b"\n").unwrap_or(content);
+    content.to_str_lossy().into_owned()
+}
+
+fn diff_hunk_lines_with_sigil(hunk: &FileDiffHunk) -> Vec<String> {
+    hunk.lines
+        .iter()
+        .map(|(line_type, content)| {
+            let sigil = match line_type {
+                DiffLineType::Context => ' ',
+                DiffLineType::Removed => '-',
+                DiffLineType::Added => '+',
+            };
+            format!("{sigil}{}", diff_hunk_line_to_string(content))
+        })
+        .collect()
+}
+
+fn diff_hunk_lines_of_type(hunk: &FileDiffHunk, line_type: DiffLineType) -> Vec<String> {
+    hunk.lines
+        .iter()
+        .filter(|(ty, _)| *ty == line_type)
+        .map(|(_, content)| diff_hunk_line_to_string(content))
+        .collect()
+}
+
 /// [`DiffStats`] with rendering parameters.
 #[derive(Clone, Debug)]
 pub struct DiffStatsFormatted<'a> {
@@ -2118,6 +2577,28 @@ impl Template for DiffStatsFormatted<'_> {
     }
 }
 
+impl IntoJsonValue for DiffStatsFormatted<'_> {
+    fn into_json_value(self) -> Result<serde_json::Value, TemplatePropertyError> {
+        let files = self
+            .stats
+            .entries()
+            .iter()
+            .map(|entry| {
+                serde_json::json!({
+                    "path": entry.path.target.as_internal_file_string(),
+                    "added": entry.added,
+                    "removed": entry.removed,
+                })
+            })
+            .collect_vec();
+        Ok(serde_json::json!({
+            "total_added": self.stats.count_total_added(),
+            "total_removed": self.stats.count_total_removed(),
+            "files": files,
+        }))
+    }
+}
+
 fn builtin_diff_stats_methods<'repo>() -> CommitTemplateBuildMethodFnMap<'repo, DiffStats> {
     type L<'repo> = CommitTemplateLanguage<'repo>;
     // Not using maplit::hashmap!{} or custom declarative macro here because
@@ -2220,10 +2701,23 @@ pub fn builtin_cryptographic_signature_methods<'repo>(
 pub struct AnnotationLine {
     pub commit: Commit,
     pub content: BString,
+    pub original_path: RepoPathBuf,
     pub line_number: usize,
     pub first_line_in_hunk: bool,
 }
 
+impl IntoJsonValue for AnnotationLine {
+    fn into_json_value(self) -> Result<serde_json::Value, TemplatePropertyError> {
+        Ok(serde_json::json!({
+            "commit": self.commit.into_json_value()?,
+            "content": self.content.to_str_lossy(),
+            "original_path": self.original_path.into_internal_string(),
+            "line_number": self.line_number,
+            "first_line_in_hunk": self.first_line_in_hunk,
+        }))
+    }
+}
+
 pub fn builtin_annotation_line_methods<'repo>(
 ) -> CommitTemplateBuildMethodFnMap<'repo, AnnotationLine> {
     type L<'repo> = CommitTemplateLanguage<'repo>;
@@ -2245,6 +2739,14 @@ pub fn builtin_annotation_line_methods<'repo>(
             Ok(L::wrap_template(out_property.into_template()))
         },
     );
+    map.insert(
+        "original_path",
+        |_language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let out_property = self_property.map(|line| line.original_path);
+            Ok(L::wrap_repo_path(out_property))
+        },
+    );
     map.insert(
         "line_number",
         |_language, _diagnostics, _build_ctx, self_property, function| {
@@ -2263,3 +2765,92 @@ pub fn builtin_annotation_line_methods<'repo>(
     );
     map
 }
+
+/// Working-copy state to be rendered by `jj status`.
+#[derive(Clone, Debug)]
+pub struct WorkingCopyStatus {
+    pub working_copy: Commit,
+    pub parents: Vec<Commit>,
+    /// Paths to restrict the changes and conflicts to.
+    pub matcher: Rc<dyn Matcher>,
+    pub conflicts: Vec<RepoPathBuf>,
+    pub untracked_paths: Vec<RepoPathBuf>,
+    /// Whether the working copy on disk is out of sync with the repo view.
+    pub stale: bool,
+}
+
+impl IntoJsonValue for WorkingCopyStatus {
+    fn into_json_value(self) -> Result<serde_json::Value, TemplatePropertyError> {
+        Ok(serde_json::json!({
+            "working_copy": self.working_copy.into_json_value()?,
+            "parents": self.parents.into_json_value()?,
+            "conflicts": self.conflicts.into_json_value()?,
+            "untracked_paths": self.untracked_paths.into_json_value()?,
+            "stale": self.stale,
+        }))
+    }
+}
+
+fn builtin_working_copy_status_methods<'repo>(
+) -> CommitTemplateBuildMethodFnMap<'repo, WorkingCopyStatus> {
+    type L<'repo> = CommitTemplateLanguage<'repo>;
+    // Not using maplit::hashmap!{} or custom declarative macro here because
+    // code completion inside macro is quite restricted.
+    let mut map = CommitTemplateBuildMethodFnMap::<WorkingCopyStatus>::new();
+    map.insert(
+        "working_copy",
+        |_language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let out_property = self_property.map(|status| status.working_copy);
+            Ok(L::wrap_commit(out_property))
+        },
+    );
+    map.insert(
+        "parents",
+        |_language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let out_property = self_property.map(|status| status.parents);
+            Ok(L::wrap_commit_list(out_property))
+        },
+    );
+    map.insert(
+        "diff",
+        |language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let repo = language.repo;
+            let out_property = self_property.and_then(|status| {
+                Ok(TreeDiff::from_commit(
+                    repo,
+                    &status.working_copy,
+                    status.matcher,
+                )?)
+            });
+            Ok(L::wrap_tree_diff(out_property))
+        },
+    );
+    map.insert(
+        "conflicts",
+        |_language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let out_property = self_property.map(|status| status.conflicts);
+            Ok(L::wrap_repo_path_list(out_property))
+        },
+    );
+    map.insert(
+        "untracked_paths",
+        |_language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let out_property = self_property.map(|status| status.untracked_paths);
+            Ok(L::wrap_repo_path_list(out_property))
+        },
+    );
+    map.insert(
+        "stale",
+        |_language, _diagnostics, _build_ctx, self_property, function| {
+            function.expect_no_arguments()?;
+            let out_property = self_property.map(|status| status.stale);
+            Ok(L::wrap_boolean(out_property))
+        },
+    );
+    map
+}
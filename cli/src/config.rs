@@ -618,6 +618,10 @@ pub fn default_config_migrations() -> Vec<ConfigMigrationRule> {
         ),
         // TODO: Delete in jj 0.34+
         ConfigMigrationRule::rename_value("diff.format", "ui.diff.format"),
+        // TODO: Delete in jj 0.36+
+        ConfigMigrationRule::rename_value("ui.streampager.interface", "ui.builtin-pager.interface"),
+        // TODO: Delete in jj 0.36+
+        ConfigMigrationRule::rename_value("ui.streampager.wrapping", "ui.builtin-pager.wrapping"),
         // TODO: Delete with the `git.subprocess` setting.
         #[cfg(not(feature = "git2"))]
         ConfigMigrationRule::custom(
@@ -20,8 +20,12 @@ use std::mem;
 use std::ops::Range;
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 use bstr::BStr;
+use bstr::ByteSlice as _;
+use clap::builder::PossibleValuesParser;
+use clap::builder::TypedValueParser as _;
 use futures::executor::block_on_stream;
 use futures::stream::BoxStream;
 use futures::StreamExt as _;
@@ -36,6 +40,7 @@ use jj_lib::commit::Commit;
 use jj_lib::config::ConfigGetError;
 use jj_lib::config::ConfigGetResultExt as _;
 use jj_lib::conflicts::materialize_merge_result_to_bytes;
+use jj_lib::conflicts::materialize_tree_value;
 use jj_lib::conflicts::materialized_diff_stream;
 use jj_lib::conflicts::ConflictMarkerStyle;
 use jj_lib::conflicts::MaterializedFileValue;
@@ -45,11 +50,13 @@ use jj_lib::copies::CopiesTreeDiffEntry;
 use jj_lib::copies::CopiesTreeDiffEntryPath;
 use jj_lib::copies::CopyOperation;
 use jj_lib::copies::CopyRecords;
-use jj_lib::diff::find_line_ranges;
+#[cfg(feature = "tree-sitter")]
+use jj_lib::diff::find_nonword_ranges;
 use jj_lib::diff::CompareBytesExactly;
 use jj_lib::diff::CompareBytesIgnoreAllWhitespace;
 use jj_lib::diff::CompareBytesIgnoreWhitespaceAmount;
 use jj_lib::diff::Diff;
+use jj_lib::diff::DiffAlgorithm;
 use jj_lib::diff::DiffHunk;
 use jj_lib::diff::DiffHunkContentVec;
 use jj_lib::diff::DiffHunkKind;
@@ -70,6 +77,7 @@ use jj_lib::store::Store;
 use pollster::FutureExt as _;
 use thiserror::Error;
 use tracing::instrument;
+use unicode_width::UnicodeWidthChar as _;
 use unicode_width::UnicodeWidthStr as _;
 
 use crate::config::CommandNameAndArgs;
@@ -81,13 +89,17 @@ use crate::merge_tools::new_utf8_temp_dir;
 use crate::merge_tools::DiffGenerateError;
 use crate::merge_tools::DiffToolMode;
 use crate::merge_tools::ExternalMergeTool;
+#[cfg(feature = "tree-sitter")]
+use crate::structural_diff::StructuralDiff;
+#[cfg(feature = "tree-sitter")]
+use crate::structural_diff::SyntaxItemChange;
 use crate::text_util;
 use crate::ui::Ui;
 
 #[derive(clap::Args, Clone, Debug)]
 #[command(next_help_heading = "Diff Formatting Options")]
 #[command(group(clap::ArgGroup::new("short-format").args(&["summary", "stat", "types", "name_only"])))]
-#[command(group(clap::ArgGroup::new("long-format").args(&["git", "color_words", "tool"])))]
+#[command(group(clap::ArgGroup::new("long-format").args(&["git", "color_words", "structural", "side_by_side", "tool"])))]
 pub struct DiffFormatArgs {
     /// For each path, show only whether it was modified, added, or deleted
     #[arg(long, short)]
@@ -116,6 +128,17 @@ pub struct DiffFormatArgs {
     /// Show a word-level diff with changes indicated only by color
     #[arg(long)]
     pub color_words: bool,
+    /// Show a syntax-aware diff of top-level items such as functions
+    ///
+    /// Moved items are reported as such, and changed items are compared token
+    /// by token. Files in unsupported languages are shown as color-words diff.
+    #[arg(long)]
+    pub structural: bool,
+    /// Show old and new contents in two columns with changed words highlighted
+    ///
+    /// The columns fit in the terminal width, and long lines are wrapped.
+    #[arg(long)]
+    pub side_by_side: bool,
     /// Generate diff by external command
     #[arg(long)]
     pub tool: Option<String>,
@@ -130,6 +153,14 @@ pub struct DiffFormatArgs {
     /// Ignore changes in amount of whitespace when comparing lines.
     #[arg(long, conflicts_with = "ignore_all_space")] // short = 'b'
     ignore_space_change: bool,
+    /// Algorithm used to compare lines [default: `diff.algorithm` config]
+    #[arg(
+        long,
+        value_name = "ALGORITHM",
+        value_parser = PossibleValuesParser::new(["default", "histogram", "patience"])
+            .map(|s| s.parse::<DiffAlgorithm>().unwrap()),
+    )]
+    diff_algorithm: Option<DiffAlgorithm>,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -141,6 +172,9 @@ pub enum DiffFormat {
     NameOnly,
     Git(Box<UnifiedDiffOptions>),
     ColorWords(Box<ColorWordsDiffOptions>),
+    /// Syntax-aware diff, which falls back to color-words diff.
+    Structural(Box<ColorWordsDiffOptions>),
+    SideBySide(Box<SideBySideDiffOptions>),
     Tool(Box<ExternalMergeTool>),
 }
 
@@ -151,7 +185,11 @@ impl DiffFormat {
             | DiffFormat::Stat(_)
             | DiffFormat::Types
             | DiffFormat::NameOnly => true,
-            DiffFormat::Git(_) | DiffFormat::ColorWords(_) | DiffFormat::Tool(_) => false,
+            DiffFormat::Git(_)
+            | DiffFormat::ColorWords(_)
+            | DiffFormat::Structural(_)
+            | DiffFormat::SideBySide(_)
+            | DiffFormat::Tool(_) => false,
         }
     }
 }
@@ -197,7 +235,7 @@ fn diff_formats_from_args(
         formats.push(DiffFormat::Summary);
     }
     if args.stat {
-        let mut options = DiffStatOptions::default();
+        let mut options = DiffStatOptions::from_settings(settings)?;
         options.merge_args(args);
         formats.push(DiffFormat::Stat(Box::new(options)));
     }
@@ -218,6 +256,16 @@ fn diff_formats_from_args(
         options.merge_args(args);
         formats.push(DiffFormat::ColorWords(Box::new(options)));
     }
+    if args.structural {
+        let mut options = ColorWordsDiffOptions::from_settings(settings)?;
+        options.merge_args(args);
+        formats.push(DiffFormat::Structural(Box::new(options)));
+    }
+    if args.side_by_side {
+        let mut options = SideBySideDiffOptions::from_settings(settings)?;
+        options.merge_args(args);
+        formats.push(DiffFormat::SideBySide(Box::new(options)));
+    }
     if let Some(name) = &args.tool {
         let tool = merge_tools::get_external_tool_config(settings, name)?
             .unwrap_or_else(|| ExternalMergeTool::with_program(name));
@@ -243,7 +291,7 @@ fn default_diff_format(
     match settings.get_string("ui.diff.format")?.as_ref() {
         "summary" => Ok(DiffFormat::Summary),
         "stat" => {
-            let mut options = DiffStatOptions::default();
+            let mut options = DiffStatOptions::from_settings(settings)?;
             options.merge_args(args);
             Ok(DiffFormat::Stat(Box::new(options)))
         }
@@ -259,6 +307,16 @@ fn default_diff_format(
             options.merge_args(args);
             Ok(DiffFormat::ColorWords(Box::new(options)))
         }
+        "structural" => {
+            let mut options = ColorWordsDiffOptions::from_settings(settings)?;
+            options.merge_args(args);
+            Ok(DiffFormat::Structural(Box::new(options)))
+        }
+        "side-by-side" => {
+            let mut options = SideBySideDiffOptions::from_settings(settings)?;
+            options.merge_args(args);
+            Ok(DiffFormat::SideBySide(Box::new(options)))
+        }
         name => Err(ConfigGetError::Type {
             name: "ui.diff.format".to_owned(),
             error: format!("Invalid diff format: {name}").into(),
@@ -393,6 +451,32 @@ impl<'a> DiffRenderer<'a> {
                         self.conflict_marker_style,
                     )?;
                 }
+                DiffFormat::Structural(options) => {
+                    warn_if_structural_diff_unsupported(ui)?;
+                    let tree_diff =
+                        from_tree.diff_stream_with_copies(to_tree, matcher, copy_records);
+                    show_structural_diff(
+                        formatter,
+                        store,
+                        tree_diff,
+                        path_converter,
+                        options,
+                        self.conflict_marker_style,
+                    )?;
+                }
+                DiffFormat::SideBySide(options) => {
+                    let tree_diff =
+                        from_tree.diff_stream_with_copies(to_tree, matcher, copy_records);
+                    show_side_by_side_diff(
+                        formatter,
+                        store,
+                        tree_diff,
+                        path_converter,
+                        options,
+                        self.conflict_marker_style,
+                        width,
+                    )?;
+                }
                 DiffFormat::Tool(tool) => {
                     match tool.diff_invocation_mode {
                         DiffToolMode::FileByFile => {
@@ -483,7 +567,7 @@ impl<'a> DiffRenderer<'a> {
 }
 
 pub fn get_copy_records<'a>(
-    store: &'a Store,
+    store: &'a Arc<Store>,
     root: &CommitId,
     head: &CommitId,
     matcher: &'a dyn Matcher,
@@ -498,11 +582,23 @@ pub fn get_copy_records<'a>(
 pub struct LineDiffOptions {
     /// How equivalence of lines is tested.
     pub compare_mode: LineCompareMode,
+    /// Algorithm used to find the matching lines.
+    pub algorithm: DiffAlgorithm,
     // TODO: add --ignore-blank-lines, etc. which aren't mutually exclusive.
 }
 
 impl LineDiffOptions {
+    pub fn from_settings(settings: &UserSettings) -> Result<Self, ConfigGetError> {
+        Ok(LineDiffOptions {
+            compare_mode: LineCompareMode::default(),
+            algorithm: settings.get("diff.algorithm")?,
+        })
+    }
+
     fn merge_args(&mut self, args: &DiffFormatArgs) {
+        if let Some(algorithm) = args.diff_algorithm {
+            self.algorithm = algorithm;
+        }
         self.compare_mode = if args.ignore_all_space {
             LineCompareMode::IgnoreAllSpace
         } else if args.ignore_space_change {
@@ -532,15 +628,14 @@ fn diff_by_line<'input, T: AsRef<[u8]> + ?Sized + 'input>(
     // blank lines to the preceding range. Maybe it can also be implemented as a
     // post-process (similar to refine_changed_regions()) that expands unchanged
     // regions across blank lines.
+    let algorithm = options.algorithm;
     match options.compare_mode {
-        LineCompareMode::Exact => {
-            Diff::for_tokenizer(inputs, find_line_ranges, CompareBytesExactly)
-        }
+        LineCompareMode::Exact => Diff::by_line_with(inputs, CompareBytesExactly, algorithm),
         LineCompareMode::IgnoreAllSpace => {
-            Diff::for_tokenizer(inputs, find_line_ranges, CompareBytesIgnoreAllWhitespace)
+            Diff::by_line_with(inputs, CompareBytesIgnoreAllWhitespace, algorithm)
         }
         LineCompareMode::IgnoreSpaceChange => {
-            Diff::for_tokenizer(inputs, find_line_ranges, CompareBytesIgnoreWhitespaceAmount)
+            Diff::by_line_with(inputs, CompareBytesIgnoreWhitespaceAmount, algorithm)
         }
     }
 }
@@ -570,7 +665,7 @@ impl ColorWordsDiffOptions {
         };
         Ok(ColorWordsDiffOptions {
             context: settings.get("diff.color-words.context")?,
-            line_diff: LineDiffOptions::default(),
+            line_diff: LineDiffOptions::from_settings(settings)?,
             max_inline_alternation,
         })
     }
@@ -631,6 +726,168 @@ fn show_color_words_diff_hunks(
     Ok(())
 }
 
+/// Warns once if structural diffs fall back to color-words diffs because jj
+/// was built without the `tree-sitter` feature.
+fn warn_if_structural_diff_unsupported(ui: &Ui) -> io::Result<()> {
+    #[cfg(not(feature = "tree-sitter"))]
+    {
+        use std::sync::atomic::AtomicBool;
+        use std::sync::atomic::Ordering;
+
+        static WARNED: AtomicBool = AtomicBool::new(false);
+        if !WARNED.swap(true, Ordering::Relaxed) {
+            writeln!(
+                ui.warning_default(),
+                "Structural diff is not supported by this build of jj. Showing color-words diff \
+                 instead."
+            )?;
+            writeln!(
+                ui.hint_default(),
+                "Build jj with the `tree-sitter` feature to enable structural diff."
+            )?;
+        }
+    }
+    #[cfg(feature = "tree-sitter")]
+    let _ = ui;
+    Ok(())
+}
+
+/// Prints changes in top-level syntax items, or color-words diff if the file
+/// type isn't supported.
+fn show_structural_diff_hunks(
+    formatter: &mut dyn Formatter,
+    path: &RepoPath,
+    left: &[u8],
+    right: &[u8],
+    options: &ColorWordsDiffOptions,
+) -> io::Result<()> {
+    #[cfg(feature = "tree-sitter")]
+    if let Some(diff) = StructuralDiff::new(path, left, right) {
+        if !diff.changes.is_empty() {
+            return show_syntax_item_changes(formatter, &diff, left, right, options);
+        }
+    }
+    #[cfg(not(feature = "tree-sitter"))]
+    let _ = path;
+    show_color_words_diff_hunks(formatter, left, right, options)
+}
+
+#[cfg(feature = "tree-sitter")]
+fn show_syntax_item_changes(
+    formatter: &mut dyn Formatter,
+    diff: &StructuralDiff,
+    left: &[u8],
+    right: &[u8],
+    options: &ColorWordsDiffOptions,
+) -> io::Result<()> {
+    for change in &diff.changes {
+        match *change {
+            SyntaxItemChange::Moved { left, right } => {
+                let left_item = &diff.left_items[left];
+                let right_item = &diff.right_items[right];
+                writeln!(
+                    formatter.labeled("hunk_header"),
+                    "    Moved {} from line {} to line {}",
+                    left_item.describe(),
+                    left_item.line_number,
+                    right_item.line_number
+                )?;
+            }
+            SyntaxItemChange::Changed {
+                left: left_index,
+                right: right_index,
+            } => {
+                let left_item = &diff.left_items[left_index];
+                let right_item = &diff.right_items[right_index];
+                writeln!(
+                    formatter.labeled("hunk_header"),
+                    "    Changed {}:",
+                    right_item.describe()
+                )?;
+                let mut token_diff = Diff::for_tokenizer_with_algorithm(
+                    [
+                        &left[left_item.range.clone()],
+                        &right[right_item.range.clone()],
+                    ],
+                    |text| diff.tokenize(text),
+                    CompareBytesExactly,
+                    options.line_diff.algorithm,
+                );
+                // Whitespace between tokens isn't tokenized
+                token_diff.refine_changed_regions(find_nonword_ranges, CompareBytesExactly);
+                let line_number = DiffLineNumber {
+                    left: left_item.line_number,
+                    right: right_item.line_number,
+                };
+                show_structural_token_diff_lines(formatter, &token_diff, line_number, options)?;
+            }
+            SyntaxItemChange::Added { right: right_index } => {
+                let right_item = &diff.right_items[right_index];
+                writeln!(
+                    formatter.labeled("hunk_header"),
+                    "    Added {}:",
+                    right_item.describe()
+                )?;
+                let contents = [BStr::new(""), BStr::new(&right[right_item.range.clone()])];
+                let line_number = DiffLineNumber {
+                    left: 1,
+                    right: right_item.line_number,
+                };
+                show_color_words_diff_lines(formatter, &contents, line_number, options)?;
+            }
+            SyntaxItemChange::Removed { left: left_index } => {
+                let left_item = &diff.left_items[left_index];
+                writeln!(
+                    formatter.labeled("hunk_header"),
+                    "    Removed {}:",
+                    left_item.describe()
+                )?;
+                let contents = [BStr::new(&left[left_item.range.clone()]), BStr::new("")];
+                let line_number = DiffLineNumber {
+                    left: left_item.line_number,
+                    right: 1,
+                };
+                show_color_words_diff_lines(formatter, &contents, line_number, options)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Prints lines of token-level diff, omitting lines that are more than
+/// `options.context` lines away from changes.
+#[cfg(feature = "tree-sitter")]
+fn show_structural_token_diff_lines(
+    formatter: &mut dyn Formatter,
+    token_diff: &Diff,
+    line_number: DiffLineNumber,
+    options: &ColorWordsDiffOptions,
+) -> io::Result<()> {
+    const SKIPPED_CONTEXT_LINE: &str = "    ...\n";
+    let hunks = token_diff.hunks().collect_vec();
+    let lines = DiffLineIterator::with_line_number(hunks.iter(), line_number).collect_vec();
+    let changed = lines.iter().map(|line| !line.is_unmodified()).collect_vec();
+    let mut last_shown: Option<usize> = None;
+    for (index, line) in lines.iter().enumerate() {
+        let context_range =
+            index.saturating_sub(options.context)..(index + options.context + 1).min(lines.len());
+        if !changed[context_range].contains(&true) {
+            continue;
+        }
+        if last_shown.is_some_and(|last| last + 1 < index) {
+            write!(formatter, "{SKIPPED_CONTEXT_LINE}")?;
+        }
+        show_color_words_line_number(
+            formatter,
+            line.has_left_content().then_some(line.line_number.left),
+            line.has_right_content().then_some(line.line_number.right),
+        )?;
+        show_color_words_inline_hunks(formatter, &line.hunks)?;
+        last_shown = Some(index);
+    }
+    Ok(())
+}
+
 /// Prints `num_after` lines, ellipsis, and `num_before` lines.
 fn show_color_words_context_lines(
     formatter: &mut dyn Formatter,
@@ -917,7 +1174,14 @@ fn diff_content(
             executable: _,
         } => Ok(FileContent {
             is_binary: false,
-            contents: materialize_merge_result_to_bytes(&contents, conflict_marker_style).into(),
+            // Conflicts are materialized the same way regardless of the
+            // algorithm used to compare the materialized contents.
+            contents: materialize_merge_result_to_bytes(
+                &contents,
+                conflict_marker_style,
+                DiffAlgorithm::Default,
+            )
+            .into(),
         }),
         MaterializedTreeValue::OtherConflict { id } => Ok(FileContent {
             is_binary: false,
@@ -950,6 +1214,64 @@ fn basic_diff_file_type(value: &MaterializedTreeValue) -> &'static str {
     }
 }
 
+/// Writes the header line of a file diff. If the file content can't be read,
+/// writes an error message instead and returns false.
+fn show_file_header(
+    formatter: &mut dyn Formatter,
+    path: &CopiesTreeDiffEntryPath,
+    path_converter: &RepoPathUiConverter,
+    left_value: &MaterializedTreeValue,
+    right_value: &MaterializedTreeValue,
+) -> io::Result<bool> {
+    let left_ui_path = path_converter.format_file_path(path.source());
+    let right_ui_path = path_converter.format_file_path(path.target());
+    match (left_value, right_value) {
+        (MaterializedTreeValue::AccessDenied(source), _) => {
+            write!(
+                formatter.labeled("access-denied"),
+                "Access denied to {left_ui_path}:"
+            )?;
+            writeln!(formatter, " {source}")?;
+            return Ok(false);
+        }
+        (_, MaterializedTreeValue::AccessDenied(source)) => {
+            write!(
+                formatter.labeled("access-denied"),
+                "Access denied to {right_ui_path}:"
+            )?;
+            writeln!(formatter, " {source}")?;
+            return Ok(false);
+        }
+        _ => {}
+    }
+    if left_value.is_absent() {
+        let description = basic_diff_file_type(right_value);
+        writeln!(
+            formatter.labeled("header"),
+            "Added {description} {right_ui_path}:"
+        )?;
+    } else if right_value.is_absent() {
+        let description = basic_diff_file_type(left_value);
+        writeln!(
+            formatter.labeled("header"),
+            "Removed {description} {right_ui_path}:"
+        )?;
+    } else if path.source() == path.target() {
+        let description = describe_modification(left_value, right_value);
+        writeln!(
+            formatter.labeled("header"),
+            "{description} {right_ui_path}:"
+        )?;
+    } else {
+        let description = describe_modification(left_value, right_value);
+        writeln!(
+            formatter.labeled("header"),
+            "{description} {right_ui_path} ({left_ui_path} => {right_ui_path}):"
+        )?;
+    }
+    Ok(true)
+}
+
 pub fn show_color_words_diff(
     formatter: &mut dyn Formatter,
     store: &Store,
@@ -957,41 +1279,58 @@ pub fn show_color_words_diff(
     path_converter: &RepoPathUiConverter,
     options: &ColorWordsDiffOptions,
     conflict_marker_style: ConflictMarkerStyle,
+) -> Result<(), DiffRenderError> {
+    show_color_words_or_structural_diff(
+        formatter,
+        store,
+        tree_diff,
+        path_converter,
+        options,
+        conflict_marker_style,
+        false,
+    )
+}
+
+/// Shows changes in top-level syntax items of the modified files. Other files
+/// are shown in color-words format.
+pub fn show_structural_diff(
+    formatter: &mut dyn Formatter,
+    store: &Store,
+    tree_diff: BoxStream<CopiesTreeDiffEntry>,
+    path_converter: &RepoPathUiConverter,
+    options: &ColorWordsDiffOptions,
+    conflict_marker_style: ConflictMarkerStyle,
+) -> Result<(), DiffRenderError> {
+    show_color_words_or_structural_diff(
+        formatter,
+        store,
+        tree_diff,
+        path_converter,
+        options,
+        conflict_marker_style,
+        true,
+    )
+}
+
+fn show_color_words_or_structural_diff(
+    formatter: &mut dyn Formatter,
+    store: &Store,
+    tree_diff: BoxStream<CopiesTreeDiffEntry>,
+    path_converter: &RepoPathUiConverter,
+    options: &ColorWordsDiffOptions,
+    conflict_marker_style: ConflictMarkerStyle,
+    structural: bool,
 ) -> Result<(), DiffRenderError> {
     let mut diff_stream = materialized_diff_stream(store, tree_diff);
     async {
         while let Some(MaterializedTreeDiffEntry { path, values }) = diff_stream.next().await {
             let left_path = path.source();
             let right_path = path.target();
-            let left_ui_path = path_converter.format_file_path(left_path);
-            let right_ui_path = path_converter.format_file_path(right_path);
             let (left_value, right_value) = values?;
-
-            match (&left_value, &right_value) {
-                (MaterializedTreeValue::AccessDenied(source), _) => {
-                    write!(
-                        formatter.labeled("access-denied"),
-                        "Access denied to {left_ui_path}:"
-                    )?;
-                    writeln!(formatter, " {source}")?;
-                    continue;
-                }
-                (_, MaterializedTreeValue::AccessDenied(source)) => {
-                    write!(
-                        formatter.labeled("access-denied"),
-                        "Access denied to {right_ui_path}:"
-                    )?;
-                    writeln!(formatter, " {source}")?;
-                    continue;
-                }
-                _ => {}
+            if !show_file_header(formatter, &path, path_converter, &left_value, &right_value)? {
+                continue;
             }
             if left_value.is_absent() {
-                let description = basic_diff_file_type(&right_value);
-                writeln!(
-                    formatter.labeled("header"),
-                    "Added {description} {right_ui_path}:"
-                )?;
                 let right_content = diff_content(right_path, right_value, conflict_marker_style)?;
                 if right_content.is_empty() {
                     writeln!(formatter.labeled("empty"), "    (empty)")?;
@@ -1001,65 +1340,18 @@ pub fn show_color_words_diff(
                     show_color_words_diff_hunks(formatter, &[], &right_content.contents, options)?;
                 }
             } else if right_value.is_present() {
-                let description = match (&left_value, &right_value) {
-                    (MaterializedTreeValue::File(left), MaterializedTreeValue::File(right)) => {
-                        if left.executable && right.executable {
-                            "Modified executable file".to_string()
-                        } else if left.executable {
-                            "Executable file became non-executable at".to_string()
-                        } else if right.executable {
-                            "Non-executable file became executable at".to_string()
-                        } else {
-                            "Modified regular file".to_string()
-                        }
-                    }
-                    (
-                        MaterializedTreeValue::FileConflict { .. }
-                        | MaterializedTreeValue::OtherConflict { .. },
-                        MaterializedTreeValue::FileConflict { .. }
-                        | MaterializedTreeValue::OtherConflict { .. },
-                    ) => "Modified conflict in".to_string(),
-                    (
-                        MaterializedTreeValue::FileConflict { .. }
-                        | MaterializedTreeValue::OtherConflict { .. },
-                        _,
-                    ) => "Resolved conflict in".to_string(),
-                    (
-                        _,
-                        MaterializedTreeValue::FileConflict { .. }
-                        | MaterializedTreeValue::OtherConflict { .. },
-                    ) => "Created conflict in".to_string(),
-                    (
-                        MaterializedTreeValue::Symlink { .. },
-                        MaterializedTreeValue::Symlink { .. },
-                    ) => "Symlink target changed at".to_string(),
-                    (_, _) => {
-                        let left_type = basic_diff_file_type(&left_value);
-                        let right_type = basic_diff_file_type(&right_value);
-                        let (first, rest) = left_type.split_at(1);
-                        format!(
-                            "{}{} became {} at",
-                            first.to_ascii_uppercase(),
-                            rest,
-                            right_type
-                        )
-                    }
-                };
                 let left_content = diff_content(left_path, left_value, conflict_marker_style)?;
                 let right_content = diff_content(right_path, right_value, conflict_marker_style)?;
-                if left_path == right_path {
-                    writeln!(
-                        formatter.labeled("header"),
-                        "{description} {right_ui_path}:"
-                    )?;
-                } else {
-                    writeln!(
-                        formatter.labeled("header"),
-                        "{description} {right_ui_path} ({left_ui_path} => {right_ui_path}):"
-                    )?;
-                }
                 if left_content.is_binary || right_content.is_binary {
                     writeln!(formatter.labeled("binary"), "    (binary)")?;
+                } else if structural {
+                    show_structural_diff_hunks(
+                        formatter,
+                        right_path,
+                        &left_content.contents,
+                        &right_content.contents,
+                        options,
+                    )?;
                 } else {
                     show_color_words_diff_hunks(
                         formatter,
@@ -1069,11 +1361,6 @@ pub fn show_color_words_diff(
                     )?;
                 }
             } else {
-                let description = basic_diff_file_type(&left_value);
-                writeln!(
-                    formatter.labeled("header"),
-                    "Removed {description} {right_ui_path}:"
-                )?;
                 let left_content = diff_content(left_path, left_value, conflict_marker_style)?;
                 if left_content.is_empty() {
                     writeln!(formatter.labeled("empty"), "    (empty)")?;
@@ -1089,6 +1376,324 @@ pub fn show_color_words_diff(
     .block_on()
 }
 
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SideBySideDiffOptions {
+    /// Number of context lines to show.
+    pub context: usize,
+    /// How lines are tokenized and compared.
+    pub line_diff: LineDiffOptions,
+}
+
+impl SideBySideDiffOptions {
+    pub fn from_settings(settings: &UserSettings) -> Result<Self, ConfigGetError> {
+        Ok(SideBySideDiffOptions {
+            context: settings.get("diff.side-by-side.context")?,
+            line_diff: LineDiffOptions::from_settings(settings)?,
+        })
+    }
+
+    fn merge_args(&mut self, args: &DiffFormatArgs) {
+        if let Some(context) = args.context {
+            self.context = context;
+        }
+        self.line_diff.merge_args(args);
+    }
+}
+
+/// Shows old and new file contents in two columns fitting in `width`. Changed
+/// words are highlighted, and long lines are wrapped.
+pub fn show_side_by_side_diff(
+    formatter: &mut dyn Formatter,
+    store: &Store,
+    tree_diff: BoxStream<CopiesTreeDiffEntry>,
+    path_converter: &RepoPathUiConverter,
+    options: &SideBySideDiffOptions,
+    conflict_marker_style: ConflictMarkerStyle,
+    width: usize,
+) -> Result<(), DiffRenderError> {
+    let mut diff_stream = materialized_diff_stream(store, tree_diff);
+    async {
+        while let Some(MaterializedTreeDiffEntry { path, values }) = diff_stream.next().await {
+            let left_path = path.source();
+            let right_path = path.target();
+            let (left_value, right_value) = values?;
+            if !show_file_header(formatter, &path, path_converter, &left_value, &right_value)? {
+                continue;
+            }
+            let left_content = diff_content(left_path, left_value, conflict_marker_style)?;
+            let right_content = diff_content(right_path, right_value, conflict_marker_style)?;
+            if left_content.is_binary || right_content.is_binary {
+                writeln!(formatter.labeled("binary"), "    (binary)")?;
+            } else if left_content.is_empty() && right_content.is_empty() {
+                writeln!(formatter.labeled("empty"), "    (empty)")?;
+            } else {
+                show_side_by_side_diff_hunks(
+                    formatter,
+                    &left_content.contents,
+                    &right_content.contents,
+                    options,
+                    width,
+                )?;
+            }
+        }
+        Ok(())
+    }
+    .block_on()
+}
+
+/// Line of a side-by-side diff. Either side may be absent.
+struct SideBySideLine<'content> {
+    left: Option<(usize, DiffTokenVec<'content>)>,
+    right: Option<(usize, DiffTokenVec<'content>)>,
+    changed: bool,
+}
+
+fn show_side_by_side_diff_hunks(
+    formatter: &mut dyn Formatter,
+    left: &[u8],
+    right: &[u8],
+    options: &SideBySideDiffOptions,
+    width: usize,
+) -> io::Result<()> {
+    const SKIPPED_CONTEXT_LINE: &str = "    ...\n";
+    const SEPARATOR: &str = " | ";
+    const MIN_COLUMN_WIDTH: usize = 10;
+
+    let mut lines: Vec<SideBySideLine> = vec![];
+    let mut left_line_number = 1;
+    let mut right_line_number = 1;
+    let diff = diff_by_line([left, right], &options.line_diff);
+    for hunk in diff.hunks() {
+        let (left_lines, right_lines, changed) = match hunk.kind {
+            DiffHunkKind::Matching => {
+                let [left, right] = hunk.contents[..].try_into().unwrap();
+                let left_lines = left
+                    .split_inclusive(|b| *b == b'\n')
+                    .map(|line| vec![(DiffTokenType::Matching, line)])
+                    .collect_vec();
+                let right_lines = right
+                    .split_inclusive(|b| *b == b'\n')
+                    .map(|line| vec![(DiffTokenType::Matching, line)])
+                    .collect_vec();
+                (left_lines, right_lines, false)
+            }
+            DiffHunkKind::Different => {
+                let (left_lines, right_lines) =
+                    unzip_diff_hunks_to_lines(Diff::by_word(hunk.contents).hunks());
+                (left_lines, right_lines, true)
+            }
+        };
+        let mut left_lines = left_lines.into_iter();
+        let mut right_lines = right_lines.into_iter();
+        loop {
+            let left = left_lines.next().map(|tokens| {
+                left_line_number += 1;
+                (left_line_number - 1, tokens)
+            });
+            let right = right_lines.next().map(|tokens| {
+                right_line_number += 1;
+                (right_line_number - 1, tokens)
+            });
+            if left.is_none() && right.is_none() {
+                break;
+            }
+            lines.push(SideBySideLine {
+                left,
+                right,
+                changed,
+            });
+        }
+    }
+
+    // Each column consists of line number, space, and content. The line
+    // numbers are padded to the width of the largest one.
+    let max_line_number = max(left_line_number, right_line_number) - 1;
+    let number_width = max_line_number.to_string().len();
+    let column_width = (width.saturating_sub(SEPARATOR.len()) / 2)
+        .saturating_sub(number_width + 1)
+        .max(MIN_COLUMN_WIDTH);
+
+    let mut last_shown: Option<usize> = None;
+    for (index, line) in lines.iter().enumerate() {
+        let context_range =
+            index.saturating_sub(options.context)..(index + options.context + 1).min(lines.len());
+        if !lines[context_range].iter().any(|line| line.changed) {
+            continue;
+        }
+        if last_shown.is_some_and(|last| last + 1 < index) {
+            write!(formatter, "{SKIPPED_CONTEXT_LINE}")?;
+        }
+        let left_rows = line.left.as_ref().map_or_else(Vec::new, |(_, tokens)| {
+            wrap_diff_line_tokens(tokens, column_width)
+        });
+        let right_rows = line.right.as_ref().map_or_else(Vec::new, |(_, tokens)| {
+            wrap_diff_line_tokens(tokens, column_width)
+        });
+        for (row_index, rows) in left_rows.iter().zip_longest(&right_rows).enumerate() {
+            let (left_row, right_row) = rows.left_and_right();
+            let left_number = line.left.as_ref().map(|(number, _)| *number);
+            let right_number = line.right.as_ref().map(|(number, _)| *number);
+            let show_number = row_index == 0;
+            show_side_by_side_column(
+                formatter,
+                show_number.then_some(left_number).flatten(),
+                left_row,
+                line.changed.then_some("removed"),
+                number_width,
+                Some(column_width),
+            )?;
+            if right_row.is_some() {
+                write!(formatter, "{SEPARATOR}")?;
+                show_side_by_side_column(
+                    formatter,
+                    show_number.then_some(right_number).flatten(),
+                    right_row,
+                    line.changed.then_some("added"),
+                    number_width,
+                    None,
+                )?;
+            } else {
+                write!(formatter, "{}", SEPARATOR.trim_end())?;
+            }
+            writeln!(formatter)?;
+        }
+        last_shown = Some(index);
+    }
+    Ok(())
+}
+
+/// Prints line number padded to `number_width` and content of one side. The
+/// content is padded to `pad_width` if specified.
+fn show_side_by_side_column(
+    formatter: &mut dyn Formatter,
+    line_number: Option<usize>,
+    row: Option<&(Vec<(DiffTokenType, String)>, usize)>,
+    label: Option<&str>,
+    number_width: usize,
+    pad_width: Option<usize>,
+) -> io::Result<()> {
+    let label = label.filter(|_| row.is_some());
+    match (line_number, label) {
+        (Some(number), Some(label)) => formatter.with_label(label, |formatter| {
+            write!(formatter.labeled("line_number"), "{number:>number_width$}")
+        })?,
+        (Some(number), None) => write!(formatter, "{number:>number_width$}")?,
+        (None, _) => write!(formatter, "{:number_width$}", "")?,
+    }
+    write!(formatter, " ")?;
+    let mut content_width = 0;
+    if let Some((segments, width)) = row {
+        content_width = *width;
+        for (token_type, text) in segments {
+            match (token_type, label) {
+                (_, None) => write!(formatter, "{text}")?,
+                (DiffTokenType::Matching, Some(label)) => {
+                    write!(formatter.labeled(label), "{text}")?;
+                }
+                (DiffTokenType::Different, Some(label)) => {
+                    formatter.with_label(label, |formatter| {
+                        write!(formatter.labeled("token"), "{text}")
+                    })?;
+                }
+            }
+        }
+    }
+    if let Some(pad_width) = pad_width {
+        write!(
+            formatter,
+            "{:1$}",
+            "",
+            pad_width.saturating_sub(content_width)
+        )?;
+    }
+    Ok(())
+}
+
+/// Splits line tokens into rows of at most `width` columns. Line terminator is
+/// stripped, and tabs are expanded to spaces.
+fn wrap_diff_line_tokens(
+    tokens: &[(DiffTokenType, &[u8])],
+    width: usize,
+) -> Vec<(Vec<(DiffTokenType, String)>, usize)> {
+    const TAB_WIDTH: usize = 4;
+    let mut rows = vec![(vec![], 0)];
+    for (token_type, content) in tokens {
+        let content = content.strip_suffix(b"\n").unwrap_or(content);
+        let content = content.strip_suffix(b"\r").unwrap_or(content);
+        for c in content.to_str_lossy().chars() {
+            let (text, char_width) = match c {
+                '\t' => (" ".repeat(TAB_WIDTH), TAB_WIDTH),
+                c if c.is_control() => (char::REPLACEMENT_CHARACTER.to_string(), 1),
+                c => (c.to_string(), c.width().unwrap_or(0)),
+            };
+            let (segments, row_width): &mut (Vec<(DiffTokenType, String)>, usize) =
+                rows.last_mut().unwrap();
+            if *row_width + char_width > width && *row_width > 0 {
+                rows.push((vec![(*token_type, text)], char_width));
+                continue;
+            }
+            match segments.last_mut() {
+                Some((last_type, last_text)) if last_type == token_type => {
+                    last_text.push_str(&text);
+                }
+                _ => segments.push((*token_type, text)),
+            }
+            *row_width += char_width;
+        }
+    }
+    rows
+}
+
+/// Describes how the file was modified, e.g. "Modified regular file".
+fn describe_modification(
+    left_value: &MaterializedTreeValue,
+    right_value: &MaterializedTreeValue,
+) -> String {
+    match (left_value, right_value) {
+        (MaterializedTreeValue::File(left), MaterializedTreeValue::File(right)) => {
+            if left.executable && right.executable {
+                "Modified executable file".to_string()
+            } else if left.executable {
+                "Executable file became non-executable at".to_string()
+            } else if right.executable {
+                "Non-executable file became executable at".to_string()
+            } else {
+                "Modified regular file".to_string()
+            }
+        }
+        (
+            MaterializedTreeValue::FileConflict { .. }
+            | MaterializedTreeValue::OtherConflict { .. },
+            MaterializedTreeValue::FileConflict { .. }
+            | MaterializedTreeValue::OtherConflict { .. },
+        ) => "Modified conflict in".to_string(),
+        (
+            MaterializedTreeValue::FileConflict { .. }
+            | MaterializedTreeValue::OtherConflict { .. },
+            _,
+        ) => "Resolved conflict in".to_string(),
+        (
+            _,
+            MaterializedTreeValue::FileConflict { .. }
+            | MaterializedTreeValue::OtherConflict { .. },
+        ) => "Created conflict in".to_string(),
+        (MaterializedTreeValue::Symlink { .. }, MaterializedTreeValue::Symlink { .. }) => {
+            "Symlink target changed at".to_string()
+        }
+        (_, _) => {
+            let left_type = basic_diff_file_type(left_value);
+            let right_type = basic_diff_file_type(right_value);
+            let (first, rest) = left_type.split_at(1);
+            format!(
+                "{}{} became {} at",
+                first.to_ascii_uppercase(),
+                rest,
+                right_type
+            )
+        }
+    }
+}
+
 pub fn show_file_by_file_diff(
     ui: &Ui,
     formatter: &mut dyn Formatter,
@@ -1219,8 +1824,12 @@ fn git_diff_part(
             hash = DUMMY_HASH.to_owned();
             content = FileContent {
                 is_binary: false, // TODO: are we sure this is never binary?
-                contents: materialize_merge_result_to_bytes(&contents, conflict_marker_style)
-                    .into(),
+                contents: materialize_merge_result_to_bytes(
+                    &contents,
+                    conflict_marker_style,
+                    DiffAlgorithm::Default,
+                )
+                .into(),
             };
         }
         MaterializedTreeValue::OtherConflict { id } => {
@@ -1255,7 +1864,7 @@ impl UnifiedDiffOptions {
     pub fn from_settings(settings: &UserSettings) -> Result<Self, ConfigGetError> {
         Ok(UnifiedDiffOptions {
             context: settings.get("diff.git.context")?,
-            line_diff: LineDiffOptions::default(),
+            line_diff: LineDiffOptions::from_settings(settings)?,
         })
     }
 
@@ -1268,7 +1877,7 @@ impl UnifiedDiffOptions {
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
-enum DiffLineType {
+pub enum DiffLineType {
     Context,
     Removed,
     Added,
@@ -1374,6 +1983,52 @@ fn unified_diff_hunks<'content>(
     hunks
 }
 
+/// Unified diff hunk with owned line contents.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FileDiffHunk {
+    /// 0-based range of lines in the left (or old) file.
+    pub left_line_range: Range<usize>,
+    /// 0-based range of lines in the right (or new) file.
+    pub right_line_range: Range<usize>,
+    /// Context, removed, and added lines including line terminators.
+    pub lines: Vec<(DiffLineType, Vec<u8>)>,
+}
+
+/// Calculates unified diff hunks of the file at `path`. Binary files produce
+/// no hunks.
+pub async fn file_diff_hunks(
+    store: &Store,
+    path: &CopiesTreeDiffEntryPath,
+    left: MergedTreeValue,
+    right: MergedTreeValue,
+    options: &UnifiedDiffOptions,
+    conflict_marker_style: ConflictMarkerStyle,
+) -> BackendResult<Vec<FileDiffHunk>> {
+    let left = materialize_tree_value(store, path.source(), left).await?;
+    let right = materialize_tree_value(store, path.target(), right).await?;
+    let left_content = diff_content(path.source(), left, conflict_marker_style)?;
+    let right_content = diff_content(path.target(), right, conflict_marker_style)?;
+    if left_content.is_binary || right_content.is_binary {
+        return Ok(vec![]);
+    }
+    let hunks = unified_diff_hunks(&left_content.contents, &right_content.contents, options)
+        .into_iter()
+        .map(|hunk| FileDiffHunk {
+            left_line_range: hunk.left_line_range,
+            right_line_range: hunk.right_line_range,
+            lines: hunk
+                .lines
+                .into_iter()
+                .map(|(line_type, tokens)| {
+                    let content = tokens.iter().flat_map(|(_, token)| *token).copied();
+                    (line_type, content.collect())
+                })
+                .collect(),
+        })
+        .collect();
+    Ok(hunks)
+}
+
 /// Splits `(left, right)` hunk pairs into `(left_lines, right_lines)`.
 fn unzip_diff_hunks_to_lines<'content, I>(
     diff_hunks: I,
@@ -1431,12 +2086,9 @@ where
     (left_lines, right_lines)
 }
 
-fn show_unified_diff_hunks(
-    formatter: &mut dyn Formatter,
-    left_content: &[u8],
-    right_content: &[u8],
-    options: &UnifiedDiffOptions,
-) -> io::Result<()> {
+/// Converts 0-based line range to the line number shown in the unified diff
+/// hunk header.
+pub fn to_unified_diff_line_number(range: Range<usize>) -> usize {
     // "If the chunk size is 0, the first number is one lower than one would
     // expect." - https://www.artima.com/weblogs/viewpost.jsp?thread=164293
     //
@@ -1444,21 +2096,26 @@ fn show_unified_diff_hunks(
     // shall be the number of the preceding line, or 0 if the range is at the
     // start of the file."
     // - https://pubs.opengroup.org/onlinepubs/9799919799/utilities/diff.html
-    fn to_line_number(range: Range<usize>) -> usize {
-        if range.is_empty() {
-            range.start
-        } else {
-            range.start + 1
-        }
+    if range.is_empty() {
+        range.start
+    } else {
+        range.start + 1
     }
+}
 
+fn show_unified_diff_hunks(
+    formatter: &mut dyn Formatter,
+    left_content: &[u8],
+    right_content: &[u8],
+    options: &UnifiedDiffOptions,
+) -> io::Result<()> {
     for hunk in unified_diff_hunks(left_content, right_content, options) {
         writeln!(
             formatter.labeled("hunk_header"),
             "@@ -{},{} +{},{} @@",
-            to_line_number(hunk.left_line_range.clone()),
+            to_unified_diff_line_number(hunk.left_line_range.clone()),
             hunk.left_line_range.len(),
-            to_line_number(hunk.right_line_range.clone()),
+            to_unified_diff_line_number(hunk.right_line_range.clone()),
             hunk.right_line_range.len()
         )?;
         for (line_type, tokens) in &hunk.lines {
@@ -1641,6 +2298,12 @@ pub struct DiffStatOptions {
 }
 
 impl DiffStatOptions {
+    pub fn from_settings(settings: &UserSettings) -> Result<Self, ConfigGetError> {
+        Ok(DiffStatOptions {
+            line_diff: LineDiffOptions::from_settings(settings)?,
+        })
+    }
+
     fn merge_args(&mut self, args: &DiffFormatArgs) {
         self.line_diff.merge_args(args);
     }
@@ -44,6 +44,12 @@ pub trait Formatter: Write {
     fn push_label(&mut self, label: &str) -> io::Result<()>;
 
     fn pop_label(&mut self) -> io::Result<()>;
+
+    /// Starts a hyperlink to the `url`. The text written until the matching
+    /// `pop_hyperlink()` call becomes the link text.
+    fn push_hyperlink(&mut self, url: &str) -> io::Result<()>;
+
+    fn pop_hyperlink(&mut self) -> io::Result<()>;
 }
 
 impl dyn Formatter + '_ {
@@ -149,7 +155,11 @@ pub struct FormatterFactory {
 enum FormatterFactoryKind {
     PlainText,
     Sanitized,
-    Color { rules: Arc<Rules>, debug: bool },
+    Color {
+        rules: Arc<Rules>,
+        debug: bool,
+        hyperlinks: bool,
+    },
 }
 
 impl FormatterFactory {
@@ -163,9 +173,17 @@ impl FormatterFactory {
         FormatterFactory { kind }
     }
 
-    pub fn color(config: &StackedConfig, debug: bool) -> Result<Self, ConfigGetError> {
+    pub fn color(
+        config: &StackedConfig,
+        debug: bool,
+        hyperlinks: bool,
+    ) -> Result<Self, ConfigGetError> {
         let rules = Arc::new(rules_from_config(config)?);
-        let kind = FormatterFactoryKind::Color { rules, debug };
+        let kind = FormatterFactoryKind::Color {
+            rules,
+            debug,
+            hyperlinks,
+        };
         Ok(FormatterFactory { kind })
     }
 
@@ -176,9 +194,13 @@ impl FormatterFactory {
         match &self.kind {
             FormatterFactoryKind::PlainText => Box::new(PlainTextFormatter::new(output)),
             FormatterFactoryKind::Sanitized => Box::new(SanitizingFormatter::new(output)),
-            FormatterFactoryKind::Color { rules, debug } => {
-                Box::new(ColorFormatter::new(output, rules.clone(), *debug))
-            }
+            FormatterFactoryKind::Color {
+                rules,
+                debug,
+                hyperlinks,
+            } => Box::new(
+                ColorFormatter::new(output, rules.clone(), *debug).with_hyperlinks(*hyperlinks),
+            ),
         }
     }
 
@@ -219,6 +241,14 @@ impl<W: Write> Formatter for PlainTextFormatter<W> {
     fn pop_label(&mut self) -> io::Result<()> {
         Ok(())
     }
+
+    fn push_hyperlink(&mut self, _url: &str) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn pop_hyperlink(&mut self) -> io::Result<()> {
+        Ok(())
+    }
 }
 
 pub struct SanitizingFormatter<W> {
@@ -254,6 +284,14 @@ impl<W: Write> Formatter for SanitizingFormatter<W> {
     fn pop_label(&mut self) -> io::Result<()> {
         Ok(())
     }
+
+    fn push_hyperlink(&mut self, _url: &str) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn pop_hyperlink(&mut self) -> io::Result<()> {
+        Ok(())
+    }
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize)]
@@ -293,6 +331,12 @@ pub struct ColorFormatter<W: Write> {
     /// The debug string (space-separated labels) we last wrote to the output.
     /// Initialize to None to turn debug strings off.
     current_debug: Option<String>,
+    /// Whether to emit OSC 8 hyperlink escape sequences.
+    hyperlinks_enabled: bool,
+    /// The stack of currently requested hyperlink URLs.
+    hyperlinks: Vec<String>,
+    /// The hyperlink URL we last wrote to the output.
+    current_hyperlink: Option<String>,
 }
 
 impl<W: Write> ColorFormatter<W> {
@@ -304,9 +348,19 @@ impl<W: Write> ColorFormatter<W> {
             cached_styles: HashMap::new(),
             current_style: Style::default(),
             current_debug: debug.then(String::new),
+            hyperlinks_enabled: false,
+            hyperlinks: vec![],
+            current_hyperlink: None,
         }
     }
 
+    /// Enables or disables emission of hyperlinks. If disabled, only the link
+    /// text will be written.
+    pub fn with_hyperlinks(mut self, enabled: bool) -> Self {
+        self.hyperlinks_enabled = enabled;
+        self
+    }
+
     pub fn for_config(
         output: W,
         config: &StackedConfig,
@@ -425,6 +479,20 @@ impl<W: Write> ColorFormatter<W> {
             }
             self.current_debug = Some(d);
         }
+        self.write_new_hyperlink()
+    }
+
+    fn write_new_hyperlink(&mut self) -> io::Result<()> {
+        if !self.hyperlinks_enabled {
+            return Ok(());
+        }
+        let new_hyperlink = self.hyperlinks.last();
+        if new_hyperlink != self.current_hyperlink.as_ref() {
+            // A new link implicitly ends the previous one.
+            let url = new_hyperlink.map_or("", |url| url);
+            write!(self.output, "\x1b]8;;{url}\x1b\\")?;
+            self.current_hyperlink = new_hyperlink.cloned();
+        }
         Ok(())
     }
 }
@@ -547,9 +615,11 @@ impl<W: Write> Write for ColorFormatter<W> {
                 self.write_new_style()?;
                 write_sanitized(&mut self.output, &line[..line.len() - 1])?;
                 let labels = mem::take(&mut self.labels);
+                let hyperlinks = mem::take(&mut self.hyperlinks);
                 self.write_new_style()?;
                 self.output.write_all(b"\n")?;
                 self.labels = labels;
+                self.hyperlinks = hyperlinks;
             } else {
                 self.write_new_style()?;
                 write_sanitized(&mut self.output, line)?;
@@ -582,6 +652,19 @@ impl<W: Write> Formatter for ColorFormatter<W> {
         }
         Ok(())
     }
+
+    fn push_hyperlink(&mut self, url: &str) -> io::Result<()> {
+        self.hyperlinks.push(sanitize_hyperlink_url(url));
+        Ok(())
+    }
+
+    fn pop_hyperlink(&mut self) -> io::Result<()> {
+        self.hyperlinks.pop();
+        if self.hyperlinks.is_empty() {
+            self.write_new_hyperlink()?;
+        }
+        Ok(())
+    }
 }
 
 impl<W: Write> Drop for ColorFormatter<W> {
@@ -589,6 +672,7 @@ impl<W: Write> Drop for ColorFormatter<W> {
         // If a `ColorFormatter` was dropped without popping all labels first (perhaps
         // because of an error), let's still try to reset any currently active style.
         self.labels.clear();
+        self.hyperlinks.clear();
         self.write_new_style().ok();
     }
 }
@@ -610,6 +694,8 @@ pub struct FormatRecorder {
 enum FormatOp {
     PushLabel(String),
     PopLabel,
+    PushHyperlink(String),
+    PopHyperlink,
     RawEscapeSequence(Vec<u8>),
 }
 
@@ -658,6 +744,8 @@ impl FormatRecorder {
             match op {
                 FormatOp::PushLabel(label) => formatter.push_label(label)?,
                 FormatOp::PopLabel => formatter.pop_label()?,
+                FormatOp::PushHyperlink(url) => formatter.push_hyperlink(url)?,
+                FormatOp::PopHyperlink => formatter.pop_hyperlink()?,
                 FormatOp::RawEscapeSequence(raw_escape_sequence) => {
                     formatter.raw()?.write_all(raw_escape_sequence)?;
                 }
@@ -705,6 +793,22 @@ impl Formatter for FormatRecorder {
         self.push_op(FormatOp::PopLabel);
         Ok(())
     }
+
+    fn push_hyperlink(&mut self, url: &str) -> io::Result<()> {
+        self.push_op(FormatOp::PushHyperlink(url.to_owned()));
+        Ok(())
+    }
+
+    fn pop_hyperlink(&mut self) -> io::Result<()> {
+        self.push_op(FormatOp::PopHyperlink);
+        Ok(())
+    }
+}
+
+/// Strips control characters which would otherwise terminate the OSC 8
+/// sequence early.
+fn sanitize_hyperlink_url(url: &str) -> String {
+    url.chars().filter(|c| !c.is_control()).collect()
 }
 
 fn write_sanitized(output: &mut impl Write, buf: &[u8]) -> Result<(), Error> {
@@ -1255,6 +1359,41 @@ mod tests {
             to_snapshot_string(output), @"[38;5;2m<<outer inner:: inside >>[39m[EOF]");
     }
 
+    #[test]
+    fn test_color_formatter_hyperlink() {
+        let config = config_from_string(r#" colors.inner = "red" "#);
+        let mut output: Vec<u8> = vec![];
+        let mut formatter = ColorFormatter::for_config(&mut output, &config, false)
+            .unwrap()
+            .with_hyperlinks(true);
+        write!(formatter, " before ").unwrap();
+        formatter.push_hyperlink("http://outer").unwrap();
+        write!(formatter, " outer ").unwrap();
+        formatter.push_hyperlink("http://inner").unwrap();
+        formatter.push_label("inner").unwrap();
+        write!(formatter, " inner\n inner ").unwrap();
+        formatter.pop_label().unwrap();
+        formatter.pop_hyperlink().unwrap();
+        write!(formatter, " outer ").unwrap();
+        formatter.pop_hyperlink().unwrap();
+        write!(formatter, " after ").unwrap();
+        drop(formatter);
+        insta::assert_snapshot!(
+            to_snapshot_string(output), @r"
+         before ]8;;http://outer\ outer [38;5;1m]8;;http://inner\ inner[39m]8;;\
+        [38;5;1m]8;;http://inner\ inner [39m]8;;http://outer\ outer ]8;;\ after [EOF]
+        ");
+
+        // Hyperlinks are ignored unless enabled.
+        let mut output: Vec<u8> = vec![];
+        let mut formatter = ColorFormatter::for_config(&mut output, &config, false).unwrap();
+        formatter.push_hyperlink("http://outer").unwrap();
+        write!(formatter, " outer ").unwrap();
+        formatter.pop_hyperlink().unwrap();
+        drop(formatter);
+        insta::assert_snapshot!(to_snapshot_string(output), @" outer [EOF]");
+    }
+
     #[test]
     fn test_heading_labeled_writer() {
         let config = config_from_string(
@@ -14,6 +14,7 @@
 
 #![deny(unused_must_use)]
 
+mod builtin_pager;
 pub mod cleanup_guard;
 pub mod cli_util;
 pub mod command_error;
@@ -39,14 +40,18 @@ pub mod git_util {
     }
 }
 pub mod graphlog;
+mod log_browser;
 pub mod merge_tools;
 pub mod movement_util;
 pub mod operation_templater;
 mod progress;
 pub mod revset_util;
+#[cfg(feature = "tree-sitter")]
+mod structural_diff;
 pub mod template_builder;
 pub mod template_parser;
 pub mod templater;
 pub mod text_util;
 pub mod time_util;
+mod tui_util;
 pub mod ui;
@@ -0,0 +1,842 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Interactive log browser for `jj log --interactive`.
+//!
+//! The log graph is shown above the diff of the selected revision. Common
+//! operations can be run on the selected revision, each of which is recorded
+//! as an ordinary operation.
+
+use std::cell::RefCell;
+use std::io;
+use std::io::IsTerminal as _;
+use std::io::Write;
+use std::ops::Range;
+use std::rc::Rc;
+
+use crossterm::event;
+use crossterm::event::Event;
+use crossterm::event::KeyCode;
+use crossterm::event::KeyEvent;
+use crossterm::event::KeyEventKind;
+use crossterm::event::KeyModifiers;
+use itertools::Itertools as _;
+use jj_lib::backend::CommitId;
+use jj_lib::commit::Commit;
+use jj_lib::graph::GraphEdge;
+use jj_lib::graph::GraphEdgeType;
+use jj_lib::graph::GraphNode;
+use jj_lib::matchers::Matcher;
+use jj_lib::object_id::ObjectId as _;
+use jj_lib::repo::Repo as _;
+use jj_lib::rewrite;
+use jj_lib::rewrite::move_commits;
+use jj_lib::rewrite::CommitWithSelection;
+use jj_lib::rewrite::MoveCommitsTarget;
+use jj_lib::rewrite::RebaseOptions;
+use ratatui::layout::Constraint;
+use ratatui::layout::Layout;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+use ratatui::text::Line;
+use ratatui::widgets::Block;
+use ratatui::widgets::Paragraph;
+use ratatui::DefaultTerminal;
+use ratatui::Frame;
+
+use crate::builtin_pager::ansi_to_lines;
+use crate::cli_util::format_template;
+use crate::cli_util::short_change_hash;
+use crate::cli_util::short_commit_hash;
+use crate::cli_util::LogContentFormat;
+use crate::cli_util::WorkspaceCommandHelper;
+use crate::command_error::user_error;
+use crate::command_error::CommandError;
+use crate::commit_templater::CommitTemplateLanguage;
+use crate::description_util::combine_messages_for_editing;
+use crate::description_util::description_template;
+use crate::description_util::edit_description;
+use crate::description_util::try_combine_messages;
+use crate::diff_util::DiffFormatArgs;
+use crate::graphlog::get_graphlog;
+use crate::graphlog::GraphStyle;
+use crate::tui_util;
+use crate::tui_util::TuiState;
+use crate::ui::Ui;
+
+/// Evaluates the revisions to show in the log graph.
+pub type GraphLoader<'a> =
+    dyn Fn(&Ui, &WorkspaceCommandHelper) -> Result<Vec<GraphNode<CommitId>>, CommandError> + 'a;
+
+/// How to render the log and the diff of the selected revision.
+pub struct LogBrowserOptions<'a> {
+    pub template: String,
+    pub node_template: String,
+    pub graph_style: GraphStyle,
+    pub diff_format: &'a DiffFormatArgs,
+    pub matcher: &'a dyn Matcher,
+}
+
+/// Operation to run on the repository.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Action {
+    Describe(CommitId),
+    New(CommitId),
+    Squash(CommitId),
+    Rebase {
+        source: CommitId,
+        destination: CommitId,
+        with_descendants: bool,
+    },
+    Abandon(CommitId),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Outcome {
+    Continue,
+    Run(Action),
+    Quit,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Mode {
+    Normal,
+    /// Selecting the destination to rebase the `source` onto.
+    Rebase {
+        source: CommitId,
+        with_descendants: bool,
+    },
+    ConfirmAbandon(CommitId),
+}
+
+/// Revision shown in the log, and the lines it occupies.
+#[derive(Clone, Debug)]
+struct LogEntry {
+    commit_id: CommitId,
+    lines: Range<usize>,
+}
+
+struct BrowserState {
+    lines: Vec<Line<'static>>,
+    entries: Vec<LogEntry>,
+    current: usize,
+    mode: Mode,
+    /// Diff of the revision it was rendered for.
+    preview: Option<(CommitId, Vec<Line<'static>>)>,
+    preview_scroll: u16,
+    message: Option<String>,
+}
+
+impl BrowserState {
+    fn new(lines: Vec<Line<'static>>, entries: Vec<LogEntry>) -> Self {
+        BrowserState {
+            lines,
+            entries,
+            current: 0,
+            mode: Mode::Normal,
+            preview: None,
+            preview_scroll: 0,
+            message: None,
+        }
+    }
+
+    /// Replaces the log with a reloaded one, and selects the given revision
+    /// if it is still shown.
+    fn set_log(
+        &mut self,
+        lines: Vec<Line<'static>>,
+        entries: Vec<LogEntry>,
+        select: Option<&CommitId>,
+    ) {
+        let selected = select.or(self.selected()).cloned();
+        self.lines = lines;
+        self.entries = entries;
+        let index = selected.and_then(|id| self.entries.iter().position(|e| e.commit_id == id));
+        self.current = index.unwrap_or(self.current.min(self.entries.len().saturating_sub(1)));
+        self.preview = None;
+        self.preview_scroll = 0;
+    }
+
+    fn selected(&self) -> Option<&CommitId> {
+        self.entries.get(self.current).map(|entry| &entry.commit_id)
+    }
+
+    fn select(&mut self, index: usize) {
+        if index < self.entries.len() && index != self.current {
+            self.current = index;
+            self.preview_scroll = 0;
+        }
+    }
+
+    fn handle_mode_key(&mut self, key: KeyEvent) -> Outcome {
+        let Some(selected) = self.selected().cloned() else {
+            return match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => Outcome::Quit,
+                _ => Outcome::Continue,
+            };
+        };
+        match &self.mode {
+            Mode::Normal => match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Outcome::Quit,
+                KeyCode::Char('d') => return Outcome::Run(Action::Describe(selected)),
+                KeyCode::Char('n') => return Outcome::Run(Action::New(selected)),
+                KeyCode::Char('s') => return Outcome::Run(Action::Squash(selected)),
+                KeyCode::Char(c @ ('r' | 'R')) => {
+                    self.mode = Mode::Rebase {
+                        source: selected,
+                        with_descendants: c == 'R',
+                    };
+                }
+                KeyCode::Char('a') => self.mode = Mode::ConfirmAbandon(selected),
+                _ => {}
+            },
+            Mode::Rebase {
+                source,
+                with_descendants,
+            } => match key.code {
+                KeyCode::Enter if *source == selected => {
+                    self.message = Some("Cannot rebase a revision onto itself".to_owned());
+                }
+                KeyCode::Enter => {
+                    let action = Action::Rebase {
+                        source: source.clone(),
+                        destination: selected,
+                        with_descendants: *with_descendants,
+                    };
+                    self.mode = Mode::Normal;
+                    return Outcome::Run(action);
+                }
+                KeyCode::Char('q') | KeyCode::Esc => self.mode = Mode::Normal,
+                _ => {}
+            },
+            Mode::ConfirmAbandon(commit_id) => {
+                let commit_id = commit_id.clone();
+                self.mode = Mode::Normal;
+                if key.code == KeyCode::Char('y') {
+                    return Outcome::Run(Action::Abandon(commit_id));
+                }
+            }
+        }
+        Outcome::Continue
+    }
+}
+
+impl TuiState for BrowserState {
+    type Outcome = Outcome;
+
+    fn handle_key(&mut self, key: KeyEvent) -> Outcome {
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            return Outcome::Quit;
+        }
+        self.message = None;
+        if matches!(self.mode, Mode::ConfirmAbandon(_)) {
+            return self.handle_mode_key(key);
+        }
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => self.select(self.current + 1),
+            KeyCode::Char('k') | KeyCode::Up => {
+                if let Some(index) = self.current.checked_sub(1) {
+                    self.select(index);
+                }
+            }
+            KeyCode::Char('g') | KeyCode::Home => self.select(0),
+            KeyCode::Char('G') | KeyCode::End => {
+                self.select(self.entries.len().saturating_sub(1));
+            }
+            KeyCode::Char('J') => self.preview_scroll = self.preview_scroll.saturating_add(1),
+            KeyCode::Char('K') => self.preview_scroll = self.preview_scroll.saturating_sub(1),
+            KeyCode::PageDown => self.preview_scroll = self.preview_scroll.saturating_add(10),
+            KeyCode::PageUp => self.preview_scroll = self.preview_scroll.saturating_sub(10),
+            _ => return self.handle_mode_key(key),
+        }
+        Outcome::Continue
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let [log_area, preview_area, footer_area] = Layout::vertical([
+            Constraint::Percentage(50),
+            Constraint::Min(3),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let log_block = Block::bordered().title(format!(
+            " Log ({} of {}) ",
+            (self.current + 1).min(self.entries.len()),
+            self.entries.len()
+        ));
+        let lines = match self.entries.get(self.current) {
+            Some(entry) => {
+                let mut lines = self.lines.clone();
+                let highlight = Style::new().add_modifier(Modifier::REVERSED);
+                for line in &mut lines[entry.lines.clone()] {
+                    *line = std::mem::take(line).patch_style(highlight);
+                }
+                lines
+            }
+            None => vec![Line::from("No revisions to show")],
+        };
+        // Keep the selected revision in the upper part of the view
+        let log_height = log_block.inner(log_area).height as usize;
+        let log_offset = self
+            .entries
+            .get(self.current)
+            .map_or(0, |entry| entry.lines.start.saturating_sub(log_height / 3));
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(log_block)
+                .scroll((log_offset as u16, 0)),
+            log_area,
+        );
+
+        let preview_lines = self
+            .preview
+            .as_ref()
+            .map(|(_, lines)| lines.clone())
+            .unwrap_or_default();
+        frame.render_widget(
+            Paragraph::new(preview_lines)
+                .block(Block::bordered())
+                .scroll((self.preview_scroll, 0)),
+            preview_area,
+        );
+
+        let footer = if let Some(message) = &self.message {
+            Line::from(message.as_str())
+        } else {
+            let hint = match &self.mode {
+                Mode::Normal => {
+                    "j/k: move  J/K: scroll diff  d: describe  n: new  s: squash  r/R: rebase  a: \
+                     abandon  q: quit"
+                        .to_owned()
+                }
+                Mode::Rebase {
+                    source,
+                    with_descendants,
+                } => {
+                    let what = if *with_descendants {
+                        " and descendants"
+                    } else {
+                        ""
+                    };
+                    format!(
+                        "Rebase {}{what} onto: select destination, enter: confirm, esc: cancel",
+                        short_commit_hash(source)
+                    )
+                }
+                Mode::ConfirmAbandon(commit_id) => {
+                    format!("Abandon {}? y: confirm", short_commit_hash(commit_id))
+                }
+            };
+            Line::styled(hint, Style::new().add_modifier(Modifier::DIM))
+        };
+        frame.render_widget(Paragraph::new(footer), footer_area);
+    }
+}
+
+/// Writer of the log graph, which can be read while the graph holds it.
+#[derive(Clone, Default)]
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Renders the log graph. Template warnings are reported to `parse_ui`.
+fn load_log(
+    ui: &Ui,
+    parse_ui: &Ui,
+    workspace_command: &WorkspaceCommandHelper,
+    options: &LogBrowserOptions,
+    load_graph: &GraphLoader,
+) -> Result<(Vec<Line<'static>>, Vec<LogEntry>), CommandError> {
+    let language = workspace_command.commit_template_language();
+    let template = workspace_command
+        .parse_template(
+            parse_ui,
+            &language,
+            &options.template,
+            CommitTemplateLanguage::wrap_commit,
+        )?
+        .labeled("log");
+    let node_template = workspace_command
+        .parse_template(
+            parse_ui,
+            &language,
+            &options.node_template,
+            CommitTemplateLanguage::wrap_commit_opt,
+        )?
+        .labeled("node");
+    let store = workspace_command.repo().store();
+    // The log pane is surrounded by borders
+    let with_content_format = LogContentFormat::new(ui, workspace_command.settings())?.sub_width(2);
+
+    let output = SharedBuffer::default();
+    let mut writer = output.clone();
+    let mut graph = get_graphlog(options.graph_style, &mut writer);
+    let mut entries = vec![];
+    let mut line_count = 0;
+    let mut counted_len = 0;
+    for (commit_id, edges) in load_graph(parse_ui, workspace_command)? {
+        // Synthetic nodes for elided revisions aren't selectable, so elided
+        // revisions are always shown as indirect edges.
+        let graphlog_edges = edges
+            .into_iter()
+            .map(|edge| match edge.edge_type {
+                GraphEdgeType::Missing => GraphEdge::missing(edge.target),
+                GraphEdgeType::Direct => GraphEdge::direct(edge.target),
+                GraphEdgeType::Indirect => GraphEdge::indirect(edge.target),
+            })
+            .collect_vec();
+        let commit = store.get_commit(&commit_id)?;
+        let mut buffer = vec![];
+        let within_graph = with_content_format.sub_width(graph.width(&commit_id, &graphlog_edges));
+        within_graph.write(ui.new_formatter(&mut buffer).as_mut(), |formatter| {
+            template.format(&commit, formatter)
+        })?;
+        if !buffer.ends_with(b"\n") {
+            buffer.push(b'\n');
+        }
+        let node_symbol = format_template(ui, &Some(commit), &node_template);
+        graph.add_node(
+            &commit_id,
+            &graphlog_edges,
+            &node_symbol,
+            &String::from_utf8_lossy(&buffer),
+        )?;
+        let start = line_count;
+        {
+            let data = output.0.borrow();
+            line_count += data[counted_len..].iter().filter(|&&b| b == b'\n').count();
+            counted_len = data.len();
+        }
+        entries.push(LogEntry {
+            commit_id,
+            lines: start..line_count,
+        });
+    }
+    drop(graph);
+    let lines = ansi_to_lines(&output.0.borrow());
+    Ok((lines, entries))
+}
+
+fn load_preview(
+    ui: &Ui,
+    workspace_command: &WorkspaceCommandHelper,
+    options: &LogBrowserOptions,
+    commit_id: &CommitId,
+    width: usize,
+) -> Result<Vec<Line<'static>>, CommandError> {
+    let commit = workspace_command.repo().store().get_commit(commit_id)?;
+    let template_string = workspace_command.settings().get_string("templates.show")?;
+    let template = workspace_command.parse_commit_template(&Ui::null(), &template_string)?;
+    let diff_renderer = workspace_command.diff_renderer_for(options.diff_format)?;
+    let mut output = vec![];
+    {
+        let mut formatter = ui.new_formatter(&mut output);
+        template.format(&commit, formatter.as_mut())?;
+        diff_renderer.show_patch(ui, formatter.as_mut(), &commit, options.matcher, width)?;
+    }
+    Ok(ansi_to_lines(&output))
+}
+
+/// Runs `f` with the terminal restored to normal mode, so that an editor can
+/// take over the screen.
+fn with_suspended_terminal<T>(
+    terminal: &mut DefaultTerminal,
+    f: impl FnOnce() -> Result<T, CommandError>,
+) -> Result<T, CommandError> {
+    ratatui::try_restore()?;
+    let result = f();
+    *terminal = ratatui::try_init()?;
+    terminal.clear()?;
+    result
+}
+
+/// Runs the `action` in a new operation. Returns a message to show, and the
+/// revision to select afterwards.
+fn run_action(
+    workspace_command: &mut WorkspaceCommandHelper,
+    terminal: &mut DefaultTerminal,
+    action: &Action,
+) -> Result<(String, Option<CommitId>), CommandError> {
+    // Output of the commands would mess up the screen
+    let quiet_ui = Ui::null();
+    workspace_command.maybe_snapshot(&quiet_ui)?;
+    let text_editor = workspace_command.text_editor()?;
+    let store = workspace_command.repo().store().clone();
+    match action {
+        Action::Describe(commit_id) => {
+            let commit = store.get_commit(commit_id)?;
+            workspace_command.check_rewritable([commit.id()])?;
+            let mut tx = workspace_command.start_transaction();
+            let mut commit_builder = tx.repo_mut().rewrite_commit(&commit).detach();
+            if commit_builder.description().is_empty() {
+                commit_builder.set_description(tx.settings().get_string("ui.default-description")?);
+            }
+            let temp_commit = commit_builder.write_hidden()?;
+            let template = description_template(&quiet_ui, &tx, "", &temp_commit)?;
+            let description =
+                with_suspended_terminal(terminal, || edit_description(&text_editor, &template))?;
+            if description == commit.description() {
+                return Ok(("Description unchanged".to_owned(), None));
+            }
+            commit_builder.set_description(description);
+            let new_commit = commit_builder.write(tx.repo_mut())?;
+            tx.finish(&quiet_ui, format!("describe commit {}", commit.id().hex()))?;
+            let message = format!("Described {}", short_change_hash(new_commit.change_id()));
+            Ok((message, Some(new_commit.id().clone())))
+        }
+        Action::New(commit_id) => {
+            let commit = store.get_commit(commit_id)?;
+            let mut tx = workspace_command.start_transaction();
+            let new_commit = tx
+                .repo_mut()
+                .new_commit(vec![commit.id().clone()], commit.tree_id().clone())
+                .write()?;
+            tx.edit(&new_commit)?;
+            tx.finish(&quiet_ui, "new empty commit")?;
+            let message = format!(
+                "Working copy now at {}",
+                short_change_hash(new_commit.change_id())
+            );
+            Ok((message, Some(new_commit.id().clone())))
+        }
+        Action::Squash(commit_id) => {
+            let commit = store.get_commit(commit_id)?;
+            let mut parents: Vec<Commit> = commit.parents().try_collect()?;
+            if parents.len() != 1 {
+                return Err(user_error("Cannot squash merge commits"));
+            }
+            let destination = parents.pop().unwrap();
+            workspace_command.check_rewritable([commit.id(), destination.id()])?;
+            let mut tx = workspace_command.start_transaction();
+            let source = CommitWithSelection {
+                selected_tree: commit.tree()?,
+                parent_tree: commit.parent_tree(tx.repo())?,
+                commit,
+            };
+            let Some(squashed) =
+                rewrite::squash_commits(tx.repo_mut(), &[source], &destination, false)?
+            else {
+                return Ok(("Nothing changed".to_owned(), None));
+            };
+            let mut commit_builder = squashed.commit_builder.detach();
+            let abandoned_commits = &squashed.abandoned_commits;
+            let description = match try_combine_messages(abandoned_commits, &destination) {
+                Some(description) => description,
+                None => {
+                    let intro = "Enter a description for the combined commit.";
+                    let combined = combine_messages_for_editing(abandoned_commits, &destination);
+                    commit_builder.set_description(combined);
+                    let temp_commit = commit_builder.write_hidden()?;
+                    let template = description_template(&quiet_ui, &tx, intro, &temp_commit)?;
+                    with_suspended_terminal(terminal, || edit_description(&text_editor, &template))?
+                }
+            };
+            commit_builder.set_description(description);
+            let new_commit = commit_builder.write(tx.repo_mut())?;
+            tx.finish(
+                &quiet_ui,
+                format!("squash commits into {}", destination.id().hex()),
+            )?;
+            let message = format!(
+                "Squashed into {}",
+                short_change_hash(new_commit.change_id())
+            );
+            Ok((message, Some(new_commit.id().clone())))
+        }
+        Action::Rebase {
+            source,
+            destination,
+            with_descendants,
+        } => {
+            let commit = store.get_commit(source)?;
+            if *with_descendants
+                && workspace_command
+                    .repo()
+                    .index()
+                    .is_ancestor(source, destination)
+            {
+                return Err(user_error(
+                    "Cannot rebase a revision onto its descendant with its descendants",
+                ));
+            }
+            workspace_command.check_rewritable([source])?;
+            let (target, tx_description) = if *with_descendants {
+                (
+                    MoveCommitsTarget::Roots(vec![commit]),
+                    format!("rebase commit {} and descendants", source.hex()),
+                )
+            } else {
+                (
+                    MoveCommitsTarget::Commits(vec![commit]),
+                    format!("rebase commit {}", source.hex()),
+                )
+            };
+            let mut tx = workspace_command.start_transaction();
+            move_commits(
+                tx.repo_mut(),
+                std::slice::from_ref(destination),
+                &[],
+                &target,
+                &RebaseOptions::default(),
+            )?;
+            let new_id = tx.repo().new_parents(std::slice::from_ref(source)).pop();
+            tx.finish(&quiet_ui, tx_description)?;
+            let message = format!("Rebased onto {}", short_commit_hash(destination));
+            Ok((message, new_id))
+        }
+        Action::Abandon(commit_id) => {
+            let commit = store.get_commit(commit_id)?;
+            workspace_command.check_rewritable([commit.id()])?;
+            let mut tx = workspace_command.start_transaction();
+            tx.repo_mut().record_abandoned_commit(&commit);
+            tx.finish(&quiet_ui, format!("abandon commit {}", commit.id().hex()))?;
+            let message = format!("Abandoned {}", short_change_hash(commit.change_id()));
+            Ok((message, None))
+        }
+    }
+}
+
+fn run_browser(
+    ui: &Ui,
+    workspace_command: &mut WorkspaceCommandHelper,
+    options: &LogBrowserOptions,
+    load_graph: &GraphLoader,
+    state: &mut BrowserState,
+    terminal: &mut DefaultTerminal,
+) -> Result<(), CommandError> {
+    loop {
+        let selected = state.selected().cloned();
+        if let Some(commit_id) = selected {
+            if state
+                .preview
+                .as_ref()
+                .is_none_or(|(id, _)| *id != commit_id)
+            {
+                let width = usize::from(terminal.size()?.width.saturating_sub(2));
+                let lines = load_preview(ui, workspace_command, options, &commit_id, width)?;
+                state.preview = Some((commit_id, lines));
+            }
+        }
+        terminal.draw(|frame| state.render(frame))?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match state.handle_key(key) {
+            Outcome::Continue => {}
+            Outcome::Quit => return Ok(()),
+            Outcome::Run(action) => match run_action(workspace_command, terminal, &action) {
+                Ok((message, select)) => {
+                    let (lines, entries) =
+                        load_log(ui, &Ui::null(), workspace_command, options, load_graph)?;
+                    state.set_log(lines, entries, select.as_ref());
+                    state.message = Some(message);
+                }
+                Err(err) => state.message = Some(format!("Error: {}", err.error)),
+            },
+        }
+    }
+}
+
+/// Shows the log graph in a full-screen interface, where revisions can be
+/// inspected and modified.
+pub fn run_log_browser(
+    ui: &Ui,
+    workspace_command: &mut WorkspaceCommandHelper,
+    options: &LogBrowserOptions,
+    load_graph: &GraphLoader,
+) -> Result<(), CommandError> {
+    if !io::stdout().is_terminal() {
+        return Err(user_error("The interactive log requires a terminal"));
+    }
+    let (lines, entries) = load_log(ui, ui, workspace_command, options, load_graph)?;
+    let mut state = BrowserState::new(lines, entries);
+    tui_util::with_terminal(|terminal| {
+        run_browser(
+            ui,
+            workspace_command,
+            options,
+            load_graph,
+            &mut state,
+            terminal,
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tui_util::testing::key;
+    use crate::tui_util::testing::render;
+
+    fn commit_id(hex: &'static str) -> CommitId {
+        CommitId::from_hex(hex)
+    }
+
+    fn make_state() -> BrowserState {
+        let lines = [
+            "@  aaaa first",
+            "│  description",
+            "○  bbbb second",
+            "│  description",
+            "◆  cccc root",
+        ]
+        .map(Line::from)
+        .to_vec();
+        let entries = vec![
+            LogEntry {
+                commit_id: commit_id("aaaa"),
+                lines: 0..2,
+            },
+            LogEntry {
+                commit_id: commit_id("bbbb"),
+                lines: 2..4,
+            },
+            LogEntry {
+                commit_id: commit_id("cccc"),
+                lines: 4..5,
+            },
+        ];
+        BrowserState::new(lines, entries)
+    }
+
+    #[test]
+    fn test_move_selection() {
+        let mut state = make_state();
+        assert_eq!(state.selected(), Some(&commit_id("aaaa")));
+        state.handle_key(key(KeyCode::Char('j')));
+        assert_eq!(state.selected(), Some(&commit_id("bbbb")));
+        state.handle_key(key(KeyCode::Char('G')));
+        assert_eq!(state.selected(), Some(&commit_id("cccc")));
+        state.handle_key(key(KeyCode::Down));
+        assert_eq!(state.selected(), Some(&commit_id("cccc")));
+        state.handle_key(key(KeyCode::Char('k')));
+        assert_eq!(state.selected(), Some(&commit_id("bbbb")));
+        state.handle_key(key(KeyCode::Char('g')));
+        assert_eq!(state.selected(), Some(&commit_id("aaaa")));
+        assert_eq!(state.handle_key(key(KeyCode::Char('q'))), Outcome::Quit);
+    }
+
+    #[test]
+    fn test_actions() {
+        let mut state = make_state();
+        assert_eq!(
+            state.handle_key(key(KeyCode::Char('d'))),
+            Outcome::Run(Action::Describe(commit_id("aaaa")))
+        );
+        state.handle_key(key(KeyCode::Char('j')));
+        assert_eq!(
+            state.handle_key(key(KeyCode::Char('n'))),
+            Outcome::Run(Action::New(commit_id("bbbb")))
+        );
+        assert_eq!(
+            state.handle_key(key(KeyCode::Char('s'))),
+            Outcome::Run(Action::Squash(commit_id("bbbb")))
+        );
+
+        // Abandoning requires confirmation
+        assert_eq!(state.handle_key(key(KeyCode::Char('a'))), Outcome::Continue);
+        assert_eq!(
+            state.handle_key(key(KeyCode::Char('y'))),
+            Outcome::Run(Action::Abandon(commit_id("bbbb")))
+        );
+        state.handle_key(key(KeyCode::Char('a')));
+        assert_eq!(state.handle_key(key(KeyCode::Char('n'))), Outcome::Continue);
+        assert_eq!(state.mode, Mode::Normal);
+    }
+
+    #[test]
+    fn test_rebase() {
+        let mut state = make_state();
+        state.handle_key(key(KeyCode::Char('R')));
+        assert_eq!(state.handle_key(key(KeyCode::Enter)), Outcome::Continue);
+        assert!(state.message.is_some());
+        state.handle_key(key(KeyCode::Char('G')));
+        assert_eq!(
+            state.handle_key(key(KeyCode::Enter)),
+            Outcome::Run(Action::Rebase {
+                source: commit_id("aaaa"),
+                destination: commit_id("cccc"),
+                with_descendants: true,
+            })
+        );
+        assert_eq!(state.mode, Mode::Normal);
+
+        // Escape cancels the rebase instead of quitting
+        state.handle_key(key(KeyCode::Char('r')));
+        assert_eq!(state.handle_key(key(KeyCode::Esc)), Outcome::Continue);
+        assert_eq!(state.mode, Mode::Normal);
+    }
+
+    #[test]
+    fn test_set_log_keeps_selection() {
+        let mut state = make_state();
+        state.handle_key(key(KeyCode::Char('j')));
+        let lines = state.lines[2..].to_vec();
+        let entries = vec![
+            LogEntry {
+                commit_id: commit_id("bbbb"),
+                lines: 0..2,
+            },
+            LogEntry {
+                commit_id: commit_id("cccc"),
+                lines: 2..3,
+            },
+        ];
+        state.set_log(lines.clone(), entries.clone(), None);
+        assert_eq!(state.selected(), Some(&commit_id("bbbb")));
+        state.set_log(lines, entries, Some(&commit_id("cccc")));
+        assert_eq!(state.selected(), Some(&commit_id("cccc")));
+    }
+
+    #[test]
+    fn test_render() {
+        let mut state = make_state();
+        state.preview = Some((
+            commit_id("aaaa"),
+            vec![Line::from("Commit ID: aaaa"), Line::from("Added a file")],
+        ));
+        insta::assert_snapshot!(render(&state, 60, 14), @r"
+        ┌ Log (1 of 3) ────────────────────────────────────────────┐
+        │@  aaaa first                                             │
+        ││  description                                            │
+        │○  bbbb second                                            │
+        ││  description                                            │
+        │◆  cccc root                                              │
+        └──────────────────────────────────────────────────────────┘
+        ┌──────────────────────────────────────────────────────────┐
+        │Commit ID: aaaa                                           │
+        │Added a file                                              │
+        │                                                          │
+        │                                                          │
+        └──────────────────────────────────────────────────────────┘
+        j/k: move  J/K: scroll diff  d: describe  n: new  s: squash
+        ");
+
+        state.handle_key(key(KeyCode::Char('r')));
+        insta::assert_snapshot!(render(&state, 60, 14).lines().last().unwrap(), @"Rebase aaaa onto: select destination, enter: confirm, esc: c");
+    }
+}
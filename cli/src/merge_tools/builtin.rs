@@ -13,10 +13,10 @@ use jj_lib::conflicts::materialize_merge_result_to_bytes;
 use jj_lib::conflicts::materialize_tree_value;
 use jj_lib::conflicts::ConflictMarkerStyle;
 use jj_lib::conflicts::MaterializedTreeValue;
+use jj_lib::diff::CompareBytesExactly;
 use jj_lib::diff::Diff;
+use jj_lib::diff::DiffAlgorithm;
 use jj_lib::diff::DiffHunkKind;
-use jj_lib::files;
-use jj_lib::files::MergeResult;
 use jj_lib::matchers::Matcher;
 use jj_lib::merge::Merge;
 use jj_lib::merged_tree::MergedTree;
@@ -29,7 +29,8 @@ use jj_lib::store::Store;
 use pollster::FutureExt as _;
 use thiserror::Error;
 
-use super::MergeToolFile;
+use crate::tui_util;
+use crate::tui_util::TuiState;
 
 #[derive(Debug, Error)]
 pub enum BuiltinToolError {
@@ -44,6 +45,37 @@ pub enum BuiltinToolError {
     Unimplemented { item: &'static str, id: String },
     #[error("Backend error")]
     BackendError(#[from] jj_lib::backend::BackendError),
+    #[error("Failed to run the builtin merge tool")]
+    Io(#[source] std::io::Error),
+    #[error("Cancelled by user")]
+    Cancelled,
+}
+
+/// What the interactive tools should do after a key press.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Continue,
+    Apply,
+    Quit,
+}
+
+/// Runs the interactive tool until the user applies the changes or quits.
+pub fn run_interactive_tool(
+    state: &mut impl TuiState<Outcome = Outcome>,
+) -> Result<(), BuiltinToolError> {
+    let applied = tui_util::with_terminal(|terminal| {
+        tui_util::run_event_loop(terminal, state, |outcome| match outcome {
+            Outcome::Continue => None,
+            Outcome::Apply => Some(true),
+            Outcome::Quit => Some(false),
+        })
+    })
+    .map_err(BuiltinToolError::Io)?;
+    if applied {
+        Ok(())
+    } else {
+        Err(BuiltinToolError::Cancelled)
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -170,7 +202,12 @@ fn read_file_contents(
             contents,
             executable: _,
         } => {
-            let buf = materialize_merge_result_to_bytes(&contents, conflict_marker_style).into();
+            let buf = materialize_merge_result_to_bytes(
+                &contents,
+                conflict_marker_style,
+                DiffAlgorithm::Default,
+            )
+            .into();
             // TODO: Render the ID somehow?
             let contents = buf_to_file_contents(None, buf);
             Ok(FileInfo {
@@ -206,8 +243,13 @@ fn make_section_changed_lines(
 fn make_diff_sections(
     left_contents: &str,
     right_contents: &str,
+    algorithm: DiffAlgorithm,
 ) -> Result<Vec<scm_record::Section<'static>>, BuiltinToolError> {
-    let diff = Diff::by_line([left_contents.as_bytes(), right_contents.as_bytes()]);
+    let diff = Diff::by_line_with(
+        [left_contents.as_bytes(), right_contents.as_bytes()],
+        CompareBytesExactly,
+        algorithm,
+    );
     let mut sections = Vec::new();
     for hunk in diff.hunks() {
         match hunk.kind {
@@ -337,7 +379,11 @@ pub fn make_diff_files(
                     num_bytes: _,
                 },
             ) => {
-                sections.extend(make_diff_sections(&old_contents, &new_contents)?);
+                sections.extend(make_diff_sections(
+                    &old_contents,
+                    &new_contents,
+                    store.diff_algorithm(),
+                )?);
             }
 
             (
@@ -487,137 +533,8 @@ pub fn edit_diff_builtin(
     Ok(tree_id)
 }
 
-fn make_merge_sections(
-    merge_result: MergeResult,
-) -> Result<Vec<scm_record::Section<'static>>, BuiltinToolError> {
-    let mut sections = Vec::new();
-    match merge_result {
-        MergeResult::Resolved(buf) => {
-            let contents = buf_to_file_contents(None, buf.into());
-            let section = match contents {
-                FileContents::Absent => None,
-                FileContents::Text {
-                    contents,
-                    hash: _,
-                    num_bytes: _,
-                } => Some(scm_record::Section::Unchanged {
-                    lines: contents
-                        .split_inclusive('\n')
-                        .map(|line| Cow::Owned(line.to_owned()))
-                        .collect(),
-                }),
-                FileContents::Binary { hash, num_bytes } => Some(scm_record::Section::Binary {
-                    is_checked: false,
-                    old_description: None,
-                    new_description: Some(Cow::Owned(describe_binary(hash.as_deref(), num_bytes))),
-                }),
-            };
-            if let Some(section) = section {
-                sections.push(section);
-            }
-        }
-        MergeResult::Conflict(hunks) => {
-            for hunk in hunks {
-                let section = match hunk.into_resolved() {
-                    Ok(contents) => {
-                        let contents = std::str::from_utf8(&contents).map_err(|err| {
-                            BuiltinToolError::DecodeUtf8 {
-                                source: err,
-                                item: "unchanged hunk",
-                            }
-                        })?;
-                        scm_record::Section::Unchanged {
-                            lines: contents
-                                .split_inclusive('\n')
-                                .map(|line| Cow::Owned(line.to_owned()))
-                                .collect(),
-                        }
-                    }
-                    Err(merge) => {
-                        let lines: Vec<scm_record::SectionChangedLine> = merge
-                            .iter()
-                            .zip(
-                                [
-                                    scm_record::ChangeType::Added,
-                                    scm_record::ChangeType::Removed,
-                                ]
-                                .into_iter()
-                                .cycle(),
-                            )
-                            .map(|(contents, change_type)| -> Result<_, BuiltinToolError> {
-                                let contents = std::str::from_utf8(contents).map_err(|err| {
-                                    BuiltinToolError::DecodeUtf8 {
-                                        source: err,
-                                        item: "conflicting hunk",
-                                    }
-                                })?;
-                                let changed_lines =
-                                    make_section_changed_lines(contents, change_type);
-                                Ok(changed_lines)
-                            })
-                            .flatten_ok()
-                            .try_collect()?;
-                        scm_record::Section::Changed { lines }
-                    }
-                };
-                sections.push(section);
-            }
-        }
-    }
-    Ok(sections)
-}
-
-fn make_merge_file(
-    merge_tool_file: &MergeToolFile,
-) -> Result<scm_record::File<'static>, BuiltinToolError> {
-    let merge_result = files::merge(&merge_tool_file.content);
-    let sections = make_merge_sections(merge_result)?;
-    Ok(scm_record::File {
-        old_path: None,
-        // Path for displaying purposes, not for file access.
-        path: Cow::Owned(
-            merge_tool_file
-                .repo_path
-                .to_fs_path_unchecked(Path::new("")),
-        ),
-        file_mode: mode::NORMAL,
-        sections,
-    })
-}
-
-pub fn edit_merge_builtin(
-    tree: &MergedTree,
-    merge_tool_files: &[MergeToolFile],
-) -> Result<MergedTreeId, BuiltinToolError> {
-    let mut input = scm_record::helpers::CrosstermInput;
-    let recorder = scm_record::Recorder::new(
-        scm_record::RecordState {
-            is_read_only: false,
-            files: merge_tool_files.iter().map(make_merge_file).try_collect()?,
-            commits: Default::default(),
-        },
-        &mut input,
-    );
-    let state = recorder.run()?;
-
-    apply_diff_builtin(
-        tree.store(),
-        tree,
-        tree,
-        merge_tool_files
-            .iter()
-            .map(|file| file.repo_path.clone())
-            .collect_vec(),
-        &state.files,
-    )
-    .map_err(BuiltinToolError::BackendError)
-}
-
 #[cfg(test)]
 mod tests {
-    use jj_lib::backend::FileId;
-    use jj_lib::conflicts::extract_as_single_hunk;
-    use jj_lib::merge::MergedTreeValue;
     use jj_lib::repo::Repo as _;
     use testutils::TestRepo;
 
@@ -1202,92 +1119,4 @@ mod tests {
             "all-changes tree was different",
         );
     }
-
-    #[test]
-    fn test_make_merge_sections() {
-        let test_repo = TestRepo::init();
-        let store = test_repo.repo.store();
-
-        let path = RepoPath::from_internal_string("file");
-        let base_tree = testutils::create_tree(
-            &test_repo.repo,
-            &[(path, "base 1\nbase 2\nbase 3\nbase 4\nbase 5\n")],
-        );
-        let left_tree = testutils::create_tree(
-            &test_repo.repo,
-            &[(path, "left 1\nbase 2\nbase 3\nbase 4\nleft 5\n")],
-        );
-        let right_tree = testutils::create_tree(
-            &test_repo.repo,
-            &[(path, "right 1\nbase 2\nbase 3\nbase 4\nright 5\n")],
-        );
-
-        fn to_file_id(tree_value: MergedTreeValue) -> Option<FileId> {
-            match tree_value.into_resolved() {
-                Ok(Some(TreeValue::File { id, executable: _ })) => Some(id.clone()),
-                other => {
-                    panic!("merge should have been a FileId: {other:?}")
-                }
-            }
-        }
-        let merge = Merge::from_vec(vec![
-            to_file_id(left_tree.path_value(path).unwrap()),
-            to_file_id(base_tree.path_value(path).unwrap()),
-            to_file_id(right_tree.path_value(path).unwrap()),
-        ]);
-        let content = extract_as_single_hunk(&merge, store, path)
-            .block_on()
-            .unwrap();
-        let merge_result = files::merge(&content);
-        let sections = make_merge_sections(merge_result).unwrap();
-        insta::assert_debug_snapshot!(sections, @r#"
-        [
-            Changed {
-                lines: [
-                    SectionChangedLine {
-                        is_checked: false,
-                        change_type: Added,
-                        line: "left 1\n",
-                    },
-                    SectionChangedLine {
-                        is_checked: false,
-                        change_type: Removed,
-                        line: "base 1\n",
-                    },
-                    SectionChangedLine {
-                        is_checked: false,
-                        change_type: Added,
-                        line: "right 1\n",
-                    },
-                ],
-            },
-            Unchanged {
-                lines: [
-                    "base 2\n",
-                    "base 3\n",
-                    "base 4\n",
-                ],
-            },
-            Changed {
-                lines: [
-                    SectionChangedLine {
-                        is_checked: false,
-                        change_type: Added,
-                        line: "left 5\n",
-                    },
-                    SectionChangedLine {
-                        is_checked: false,
-                        change_type: Removed,
-                        line: "base 5\n",
-                    },
-                    SectionChangedLine {
-                        is_checked: false,
-                        change_type: Added,
-                        line: "right 5\n",
-                    },
-                ],
-            },
-        ]
-        "#);
-    }
 }
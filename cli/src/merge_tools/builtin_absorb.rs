@@ -0,0 +1,429 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Interactive UI to review where `jj absorb` moves each hunk.
+//!
+//! The hunks are listed with their destination commits, and the diff of the
+//! selected hunk is shown below. The destination of a hunk can be changed to
+//! any of the candidate commits, or the hunk can be left in the source commit.
+
+use std::iter::zip;
+
+use bstr::ByteSlice as _;
+use crossterm::event::KeyCode;
+use crossterm::event::KeyEvent;
+use crossterm::event::KeyModifiers;
+use jj_lib::absorb::AbsorbFile;
+use jj_lib::absorb::AbsorbHunk;
+use jj_lib::absorb::AbsorbPlan;
+use jj_lib::backend::CommitId;
+use jj_lib::repo_path::RepoPathUiConverter;
+use ratatui::layout::Constraint;
+use ratatui::layout::Layout;
+use ratatui::style::Color;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+use ratatui::text::Line;
+use ratatui::text::Span;
+use ratatui::text::Text;
+use ratatui::widgets::Block;
+use ratatui::widgets::Paragraph;
+use ratatui::Frame;
+
+use super::builtin::run_interactive_tool;
+use super::builtin::BuiltinToolError;
+use super::builtin::Outcome;
+use crate::tui_util::TuiState;
+
+/// Formats the line ranges of the hunk in unified diff style.
+pub fn format_hunk_header(file: &AbsorbFile, hunk: &AbsorbHunk) -> String {
+    let [left_lines, right_lines] = file.hunk_line_ranges(hunk);
+    // Empty range points to the line before, as in unified diff
+    let start = |lines: &std::ops::Range<usize>| {
+        if lines.is_empty() {
+            lines.start
+        } else {
+            lines.start + 1
+        }
+    };
+    format!(
+        "@@ -{},{} +{},{} @@",
+        start(&left_lines),
+        left_lines.len(),
+        start(&right_lines),
+        right_lines.len()
+    )
+}
+
+struct HunkItem {
+    path: String,
+    header: String,
+    removed: String,
+    added: String,
+    original: Option<CommitId>,
+    destination: Option<CommitId>,
+}
+
+struct AbsorbState<'a> {
+    items: Vec<HunkItem>,
+    // Destination commits and their labels
+    candidates: &'a [(CommitId, String)],
+    current: usize,
+    scroll: u16,
+}
+
+impl<'a> AbsorbState<'a> {
+    fn new(
+        plan: &AbsorbPlan,
+        candidates: &'a [(CommitId, String)],
+        path_converter: &RepoPathUiConverter,
+    ) -> Self {
+        let items = plan
+            .hunks()
+            .map(|(file, hunk)| {
+                let [left, right] = file.hunk_contents(hunk);
+                HunkItem {
+                    path: path_converter.format_file_path(&file.path),
+                    header: format_hunk_header(file, hunk),
+                    removed: left.to_str_lossy().into_owned(),
+                    added: right.to_str_lossy().into_owned(),
+                    original: hunk.destination.clone(),
+                    destination: hunk.destination.clone(),
+                }
+            })
+            .collect();
+        AbsorbState {
+            items,
+            candidates,
+            current: 0,
+            scroll: 0,
+        }
+    }
+
+    fn select(&mut self, index: usize) {
+        if index < self.items.len() {
+            self.current = index;
+            self.scroll = 0;
+        }
+    }
+
+    fn label(&self, destination: Option<&CommitId>) -> &str {
+        let Some(commit_id) = destination else {
+            return "(not absorbed)";
+        };
+        self.candidates
+            .iter()
+            .find(|(id, _)| id == commit_id)
+            .map_or("(unknown)", |(_, label)| label)
+    }
+
+    /// Moves the destination of the current hunk by `offset` in the list of
+    /// candidates. The source commit is at the position before the first
+    /// candidate.
+    fn cycle_destination(&mut self, offset: isize) {
+        let Some(item) = self.items.get_mut(self.current) else {
+            return;
+        };
+        let num_options = self.candidates.len() as isize + 1;
+        let position = item
+            .destination
+            .as_ref()
+            .and_then(|commit_id| self.candidates.iter().position(|(id, _)| id == commit_id))
+            .map_or(0, |i| i as isize + 1);
+        let new_position = (position + offset).rem_euclid(num_options) as usize;
+        item.destination = new_position
+            .checked_sub(1)
+            .map(|i| self.candidates[i].0.clone());
+    }
+
+    fn toggle_skip(&mut self) {
+        let Some(item) = self.items.get_mut(self.current) else {
+            return;
+        };
+        item.destination = if item.destination.is_some() {
+            None
+        } else if item.original.is_some() {
+            item.original.clone()
+        } else {
+            self.candidates.first().map(|(id, _)| id.clone())
+        };
+    }
+
+    /// Writes the selected destinations back to the `plan`.
+    fn update_plan(&self, plan: &mut AbsorbPlan) {
+        let hunks = plan.files.iter_mut().flat_map(|file| &mut file.hunks);
+        for (hunk, item) in zip(hunks, &self.items) {
+            hunk.destination = item.destination.clone();
+        }
+    }
+}
+
+impl TuiState for AbsorbState<'_> {
+    type Outcome = Outcome;
+
+    fn handle_key(&mut self, key: KeyEvent) -> Outcome {
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Outcome::Quit;
+            }
+            KeyCode::Char('q') | KeyCode::Esc => return Outcome::Quit,
+            KeyCode::Char('c') | KeyCode::Enter => return Outcome::Apply,
+            KeyCode::Char('n' | 'j') | KeyCode::Down | KeyCode::Tab => {
+                self.select(self.current + 1);
+            }
+            KeyCode::Char('p' | 'k') | KeyCode::Up | KeyCode::BackTab => {
+                if let Some(index) = self.current.checked_sub(1) {
+                    self.select(index);
+                }
+            }
+            KeyCode::Char('l') | KeyCode::Right => self.cycle_destination(1),
+            KeyCode::Char('h') | KeyCode::Left => self.cycle_destination(-1),
+            KeyCode::Char(' ') => self.toggle_skip(),
+            KeyCode::Char('r') => {
+                if let Some(item) = self.items.get_mut(self.current) {
+                    item.destination = item.original.clone();
+                }
+            }
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_add(10),
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(10),
+            _ => {}
+        }
+        Outcome::Continue
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let [list_area, diff_area, footer_area] = Layout::vertical([
+            Constraint::Percentage(40),
+            Constraint::Min(3),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let Some(current_item) = self.items.get(self.current) else {
+            frame.render_widget(Paragraph::new("No hunks to absorb"), list_area);
+            return;
+        };
+
+        let list_block = Block::bordered().title(format!(
+            " Hunks ({} of {}) ",
+            self.current + 1,
+            self.items.len()
+        ));
+        // Keep the current hunk visible
+        let list_height = list_block.inner(list_area).height as usize;
+        let list_offset = (self.current + 1).saturating_sub(list_height);
+        let lines: Vec<Line> = self
+            .items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                let is_current = i == self.current;
+                let marker = if is_current { "> " } else { "  " };
+                let destination_style = if item.destination.is_some() {
+                    Style::new()
+                } else {
+                    Style::new().add_modifier(Modifier::DIM)
+                };
+                let line = Line::from(vec![
+                    Span::raw(format!("{marker}{} {} -> ", item.path, item.header)),
+                    Span::styled(self.label(item.destination.as_ref()), destination_style),
+                ]);
+                if is_current {
+                    line.style(Style::new().add_modifier(Modifier::BOLD))
+                } else {
+                    line
+                }
+            })
+            .collect();
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(list_block)
+                .scroll((list_offset as u16, 0)),
+            list_area,
+        );
+
+        let removed_style = Style::new().fg(Color::Red);
+        let added_style = Style::new().fg(Color::Green);
+        let diff_lines: Vec<Line> = itertools::chain(
+            zip(
+                current_item.removed.lines(),
+                std::iter::repeat(("-", removed_style)),
+            ),
+            zip(
+                current_item.added.lines(),
+                std::iter::repeat(("+", added_style)),
+            ),
+        )
+        .map(|(line, (prefix, style))| Line::styled(format!("{prefix}{line}"), style))
+        .collect();
+        let diff_block =
+            Block::bordered().title(format!(" {} {} ", current_item.path, current_item.header));
+        frame.render_widget(
+            Paragraph::new(Text::from(diff_lines))
+                .block(diff_block)
+                .scroll((self.scroll, 0)),
+            diff_area,
+        );
+
+        frame.render_widget(
+            Paragraph::new(
+                "n/p: next/prev  h/l: change destination  space: skip  r: reset  c: apply  q: \
+                 quit",
+            )
+            .style(Style::new().add_modifier(Modifier::DIM)),
+            footer_area,
+        );
+    }
+}
+
+/// Lets the user review and change the destinations of the hunks in the
+/// `plan`. Hunks can be moved to any of the `candidates` commits.
+pub fn edit_absorb_builtin(
+    plan: &mut AbsorbPlan,
+    candidates: &[(CommitId, String)],
+    path_converter: &RepoPathUiConverter,
+) -> Result<(), BuiltinToolError> {
+    let mut state = AbsorbState::new(plan, candidates, path_converter);
+    run_interactive_tool(&mut state)?;
+    state.update_plan(plan);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use jj_lib::repo_path::RepoPathBuf;
+
+    use super::*;
+    use crate::tui_util::testing::key;
+    use crate::tui_util::testing::render;
+    use crate::tui_util::testing::type_keys;
+
+    fn make_plan(commit_id: &CommitId) -> AbsorbPlan {
+        let file = AbsorbFile {
+            path: RepoPathBuf::from_internal_string("file"),
+            executable: false,
+            left_text: "a\nb\nc\n".into(),
+            right_text: "A\nb\nX\nc\n".into(),
+            hunks: vec![
+                AbsorbHunk {
+                    left_range: 0..2,
+                    right_range: 0..2,
+                    destination: Some(commit_id.clone()),
+                },
+                AbsorbHunk {
+                    left_range: 4..4,
+                    right_range: 4..6,
+                    destination: None,
+                },
+            ],
+        };
+        AbsorbPlan {
+            files: vec![file],
+            skipped_paths: vec![],
+        }
+    }
+
+    #[test]
+    fn test_change_destinations() {
+        let commit_id1 = CommitId::from_hex("111111");
+        let commit_id2 = CommitId::from_hex("222222");
+        let candidates = vec![
+            (commit_id1.clone(), "commit 1".to_owned()),
+            (commit_id2.clone(), "commit 2".to_owned()),
+        ];
+        let mut plan = make_plan(&commit_id1);
+        let path_converter = RepoPathUiConverter::Fs {
+            cwd: "/repo".into(),
+            base: "/repo".into(),
+        };
+        let mut state = AbsorbState::new(&plan, &candidates, &path_converter);
+        let destinations = |state: &AbsorbState| {
+            state
+                .items
+                .iter()
+                .map(|item| item.destination.clone())
+                .collect::<Vec<_>>()
+        };
+
+        type_keys(&mut state, "l");
+        assert_eq!(destinations(&state), [Some(commit_id2.clone()), None]);
+        type_keys(&mut state, "l");
+        assert_eq!(destinations(&state), [None, None]);
+        type_keys(&mut state, "hh");
+        assert_eq!(destinations(&state), [Some(commit_id1.clone()), None]);
+        type_keys(&mut state, " ");
+        assert_eq!(destinations(&state), [None, None]);
+        type_keys(&mut state, "r");
+        assert_eq!(destinations(&state), [Some(commit_id1.clone()), None]);
+
+        // Unmapped hunk is moved to the first candidate
+        type_keys(&mut state, "j ");
+        assert_eq!(
+            destinations(&state),
+            [Some(commit_id1.clone()), Some(commit_id1.clone())]
+        );
+        type_keys(&mut state, "lj");
+        assert_eq!(state.current, 1);
+        assert_eq!(state.handle_key(key(KeyCode::Enter)), Outcome::Apply);
+        assert_eq!(state.handle_key(key(KeyCode::Char('q'))), Outcome::Quit);
+
+        state.update_plan(&mut plan);
+        let new_destinations = plan
+            .hunks()
+            .map(|(_, hunk)| hunk.destination.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(new_destinations, [Some(commit_id1), Some(commit_id2)]);
+    }
+
+    #[test]
+    fn test_render() {
+        let commit_id1 = CommitId::from_hex("111111");
+        let candidates = vec![(commit_id1.clone(), "commit 1".to_owned())];
+        let plan = make_plan(&commit_id1);
+        let path_converter = RepoPathUiConverter::Fs {
+            cwd: "/repo".into(),
+            base: "/repo".into(),
+        };
+        let mut state = AbsorbState::new(&plan, &candidates, &path_converter);
+        insta::assert_snapshot!(render(&state, 60, 12), @r"
+        ┌ Hunks (1 of 2) ──────────────────────────────────────────┐
+        │> file @@ -1,1 +1,1 @@ -> commit 1                        │
+        │  file @@ -2,0 +3,1 @@ -> (not absorbed)                  │
+        │                                                          │
+        └──────────────────────────────────────────────────────────┘
+        ┌ file @@ -1,1 +1,1 @@ ────────────────────────────────────┐
+        │-a                                                        │
+        │+A                                                        │
+        │                                                          │
+        │                                                          │
+        └──────────────────────────────────────────────────────────┘
+        n/p: next/prev  h/l: change destination  space: skip  r: res
+        ");
+        type_keys(&mut state, "j");
+        insta::assert_snapshot!(render(&state, 60, 12), @r"
+        ┌ Hunks (2 of 2) ──────────────────────────────────────────┐
+        │  file @@ -1,1 +1,1 @@ -> commit 1                        │
+        │> file @@ -2,0 +3,1 @@ -> (not absorbed)                  │
+        │                                                          │
+        └──────────────────────────────────────────────────────────┘
+        ┌ file @@ -2,0 +3,1 @@ ────────────────────────────────────┐
+        │+X                                                        │
+        │                                                          │
+        │                                                          │
+        │                                                          │
+        └──────────────────────────────────────────────────────────┘
+        n/p: next/prev  h/l: change destination  space: skip  r: res
+        ");
+    }
+}
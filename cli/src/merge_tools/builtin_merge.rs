@@ -0,0 +1,860 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Interactive conflict resolution UI of the builtin merge tool.
+//!
+//! Each conflicting hunk is shown with its sides and bases next to each other,
+//! and a result pane below. The result of a hunk can be taken from the sides or
+//! bases, or edited in place.
+
+use std::iter::zip;
+use std::path::Path;
+
+use bstr::BString;
+use bstr::ByteSlice as _;
+use crossterm::event::KeyCode;
+use crossterm::event::KeyEvent;
+use crossterm::event::KeyModifiers;
+use itertools::Itertools as _;
+use jj_lib::backend::BackendError;
+use jj_lib::backend::BackendResult;
+use jj_lib::backend::MergedTreeId;
+use jj_lib::diff::DiffAlgorithm;
+use jj_lib::files;
+use jj_lib::files::MergeResult;
+use jj_lib::merge::Merge;
+use jj_lib::merged_tree::MergedTree;
+use jj_lib::merged_tree::MergedTreeBuilder;
+use pollster::FutureExt as _;
+use ratatui::layout::Constraint;
+use ratatui::layout::Layout;
+use ratatui::layout::Rect;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+use ratatui::text::Line;
+use ratatui::text::Span;
+use ratatui::widgets::Block;
+use ratatui::widgets::Clear;
+use ratatui::widgets::Paragraph;
+use ratatui::Frame;
+use unicode_width::UnicodeWidthChar as _;
+
+use super::builtin::run_interactive_tool;
+use super::builtin::BuiltinToolError;
+use super::builtin::Outcome;
+use super::MergeToolFile;
+use crate::tui_util::TuiState;
+
+const HELP_TEXT: &str = "\
+n, j, Down      Go to the next conflict
+p, k, Up        Go to the previous conflict
+1-9             Take the numbered side
+l, r            Take the first (left) or the last (right) side
+b               Take all sides, one after another
+o               Take the base (repeat to cycle through the bases)
+e, Enter        Edit the result (press Esc to finish editing)
+u               Mark the conflict as unresolved
+PageDown/PageUp Scroll the sides and bases
+c               Apply the resolutions and quit
+q, Esc          Quit without applying the resolutions
+?               Show or hide this help";
+
+/// Hunk of a file being resolved.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum ResolverHunk {
+    Resolved(BString),
+    Conflict {
+        terms: Merge<BString>,
+        resolution: Option<BString>,
+    },
+}
+
+#[derive(Clone, Debug)]
+struct ResolverFile {
+    path: String,
+    hunks: Vec<ResolverHunk>,
+}
+
+impl ResolverFile {
+    fn new(merge_tool_file: &MergeToolFile) -> Self {
+        // Path for displaying purposes, not for file access.
+        let path = merge_tool_file
+            .repo_path
+            .to_fs_path_unchecked(Path::new(""))
+            .to_string_lossy()
+            .into_owned();
+        let hunks = match files::merge(&merge_tool_file.content, DiffAlgorithm::Default) {
+            MergeResult::Resolved(content) => vec![ResolverHunk::Resolved(content)],
+            MergeResult::Conflict(hunks) => hunks
+                .into_iter()
+                .map(|hunk| match hunk.into_resolved() {
+                    Ok(content) => ResolverHunk::Resolved(content),
+                    Err(terms) => ResolverHunk::Conflict {
+                        terms,
+                        resolution: None,
+                    },
+                })
+                .collect(),
+        };
+        ResolverFile { path, hunks }
+    }
+
+    /// Returns the contents of the file if all conflicts are resolved.
+    fn resolved_content(&self) -> Option<BString> {
+        let mut content = BString::default();
+        for hunk in &self.hunks {
+            match hunk {
+                ResolverHunk::Resolved(text)
+                | ResolverHunk::Conflict {
+                    resolution: Some(text),
+                    ..
+                } => content.extend_from_slice(text),
+                ResolverHunk::Conflict {
+                    resolution: None, ..
+                } => return None,
+            }
+        }
+        Some(content)
+    }
+
+    /// Returns the contents of each term of the file, with the resolved
+    /// conflicts replaced by their resolutions.
+    fn term_contents(&self, num_terms: usize) -> Merge<BString> {
+        let mut contents = vec![BString::default(); num_terms];
+        for hunk in &self.hunks {
+            for (i, content) in contents.iter_mut().enumerate() {
+                let text = match hunk {
+                    ResolverHunk::Resolved(text)
+                    | ResolverHunk::Conflict {
+                        resolution: Some(text),
+                        ..
+                    } => text,
+                    ResolverHunk::Conflict {
+                        terms,
+                        resolution: None,
+                    } => &terms.as_slice()[i],
+                };
+                content.extend_from_slice(text);
+            }
+        }
+        Merge::from_vec(contents)
+    }
+}
+
+/// Minimal multi-line text editor for the result pane.
+#[derive(Clone, Debug)]
+struct TextEditor {
+    lines: Vec<String>,
+    row: usize,
+    // Cursor position in chars
+    col: usize,
+}
+
+impl TextEditor {
+    fn new(text: &str) -> Self {
+        TextEditor {
+            lines: text.split('\n').map(ToOwned::to_owned).collect(),
+            row: 0,
+            col: 0,
+        }
+    }
+
+    fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    fn byte_offset(&self) -> usize {
+        let line = &self.lines[self.row];
+        line.char_indices()
+            .nth(self.col)
+            .map_or(line.len(), |(i, _)| i)
+    }
+
+    fn line_len(&self, row: usize) -> usize {
+        self.lines[row].chars().count()
+    }
+
+    /// Handles the key. Returns true if editing is finished.
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Esc => return true,
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                let offset = self.byte_offset();
+                self.lines[self.row].insert(offset, c);
+                self.col += 1;
+            }
+            KeyCode::Tab => {
+                let offset = self.byte_offset();
+                self.lines[self.row].insert(offset, '\t');
+                self.col += 1;
+            }
+            KeyCode::Enter => {
+                let offset = self.byte_offset();
+                let rest = self.lines[self.row].split_off(offset);
+                self.row += 1;
+                self.col = 0;
+                self.lines.insert(self.row, rest);
+            }
+            KeyCode::Backspace => {
+                if self.col > 0 {
+                    self.col -= 1;
+                    let offset = self.byte_offset();
+                    self.lines[self.row].remove(offset);
+                } else if self.row > 0 {
+                    let line = self.lines.remove(self.row);
+                    self.row -= 1;
+                    self.col = self.line_len(self.row);
+                    self.lines[self.row].push_str(&line);
+                }
+            }
+            KeyCode::Delete => {
+                if self.col < self.line_len(self.row) {
+                    let offset = self.byte_offset();
+                    self.lines[self.row].remove(offset);
+                } else if self.row + 1 < self.lines.len() {
+                    let line = self.lines.remove(self.row + 1);
+                    self.lines[self.row].push_str(&line);
+                }
+            }
+            KeyCode::Left => {
+                if self.col > 0 {
+                    self.col -= 1;
+                } else if self.row > 0 {
+                    self.row -= 1;
+                    self.col = self.line_len(self.row);
+                }
+            }
+            KeyCode::Right => {
+                if self.col < self.line_len(self.row) {
+                    self.col += 1;
+                } else if self.row + 1 < self.lines.len() {
+                    self.row += 1;
+                    self.col = 0;
+                }
+            }
+            KeyCode::Up => {
+                self.row = self.row.saturating_sub(1);
+                self.col = self.col.min(self.line_len(self.row));
+            }
+            KeyCode::Down => {
+                self.row = (self.row + 1).min(self.lines.len() - 1);
+                self.col = self.col.min(self.line_len(self.row));
+            }
+            KeyCode::Home => self.col = 0,
+            KeyCode::End => self.col = self.line_len(self.row),
+            _ => {}
+        }
+        false
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Mode {
+    Normal,
+    Edit(TextEditor),
+    Help,
+}
+
+struct ResolverState {
+    files: Vec<ResolverFile>,
+    // (file index, hunk index) of each conflict
+    conflicts: Vec<(usize, usize)>,
+    current: usize,
+    scroll: u16,
+    mode: Mode,
+    message: Option<String>,
+}
+
+impl ResolverState {
+    fn new(files: Vec<ResolverFile>) -> Self {
+        let conflicts = files
+            .iter()
+            .enumerate()
+            .flat_map(|(file_index, file)| {
+                file.hunks
+                    .iter()
+                    .positions(|hunk| matches!(hunk, ResolverHunk::Conflict { .. }))
+                    .map(move |hunk_index| (file_index, hunk_index))
+            })
+            .collect();
+        ResolverState {
+            files,
+            conflicts,
+            current: 0,
+            scroll: 0,
+            mode: Mode::Normal,
+            message: None,
+        }
+    }
+
+    fn current_conflict(&self) -> Option<(&Merge<BString>, Option<&BString>)> {
+        let &(file_index, hunk_index) = self.conflicts.get(self.current)?;
+        match &self.files[file_index].hunks[hunk_index] {
+            ResolverHunk::Conflict { terms, resolution } => Some((terms, resolution.as_ref())),
+            ResolverHunk::Resolved(_) => None,
+        }
+    }
+
+    fn set_resolution(&mut self, new_resolution: Option<BString>) {
+        let Some(&(file_index, hunk_index)) = self.conflicts.get(self.current) else {
+            return;
+        };
+        if let ResolverHunk::Conflict { resolution, .. } =
+            &mut self.files[file_index].hunks[hunk_index]
+        {
+            *resolution = new_resolution;
+        }
+    }
+
+    fn num_unresolved(&self) -> usize {
+        self.conflicts
+            .iter()
+            .filter(|&&(file_index, hunk_index)| {
+                matches!(
+                    self.files[file_index].hunks[hunk_index],
+                    ResolverHunk::Conflict {
+                        resolution: None,
+                        ..
+                    }
+                )
+            })
+            .count()
+    }
+
+    fn select(&mut self, index: usize) {
+        if index < self.conflicts.len() {
+            self.current = index;
+            self.scroll = 0;
+        }
+    }
+
+    fn take_side(&mut self, side: usize) {
+        if let Some((terms, _)) = self.current_conflict() {
+            if let Some(content) = terms.get_add(side) {
+                self.set_resolution(Some(content.clone()));
+            }
+        }
+    }
+
+    fn take_all_sides(&mut self) {
+        if let Some((terms, _)) = self.current_conflict() {
+            let content = terms.adds().flat_map(|content| content.iter().copied());
+            self.set_resolution(Some(content.collect::<Vec<u8>>().into()));
+        }
+    }
+
+    fn take_base(&mut self) {
+        if let Some((terms, resolution)) = self.current_conflict() {
+            // Cycle through the bases if the resolution is already taken from one
+            let next_base = resolution
+                .and_then(|resolution| terms.removes().position(|base| base == resolution))
+                .map_or(0, |i| (i + 1) % terms.removes().len());
+            let content = terms.get_remove(next_base).unwrap().clone();
+            self.set_resolution(Some(content));
+        }
+    }
+}
+
+impl TuiState for ResolverState {
+    type Outcome = Outcome;
+
+    fn handle_key(&mut self, key: KeyEvent) -> Outcome {
+        match &mut self.mode {
+            Mode::Edit(editor) => {
+                if editor.handle_key(key) {
+                    let text = editor.text();
+                    self.set_resolution(Some(text.into()));
+                    self.mode = Mode::Normal;
+                }
+                return Outcome::Continue;
+            }
+            Mode::Help => {
+                self.mode = Mode::Normal;
+                return Outcome::Continue;
+            }
+            Mode::Normal => {}
+        }
+        self.message = None;
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Outcome::Quit;
+            }
+            KeyCode::Char('q') | KeyCode::Esc => return Outcome::Quit,
+            KeyCode::Char('c') => return Outcome::Apply,
+            KeyCode::Char('n' | 'j') | KeyCode::Down | KeyCode::Tab => {
+                self.select(self.current + 1);
+            }
+            KeyCode::Char('p' | 'k') | KeyCode::Up | KeyCode::BackTab => {
+                if let Some(index) = self.current.checked_sub(1) {
+                    self.select(index);
+                }
+            }
+            KeyCode::Char(c @ '1'..='9') => {
+                self.take_side(c.to_digit(10).unwrap() as usize - 1);
+            }
+            KeyCode::Char('l') => self.take_side(0),
+            KeyCode::Char('r') => {
+                if let Some((terms, _)) = self.current_conflict() {
+                    self.take_side(terms.num_sides() - 1);
+                }
+            }
+            KeyCode::Char('b') => self.take_all_sides(),
+            KeyCode::Char('o') => self.take_base(),
+            KeyCode::Char('u') => self.set_resolution(None),
+            KeyCode::Char('e') | KeyCode::Enter => {
+                if let Some((terms, resolution)) = self.current_conflict() {
+                    // Start from all sides if the conflict isn't resolved yet.
+                    // Non-UTF-8 content can't be edited without corrupting it.
+                    let text: Result<String, _> = match resolution {
+                        Some(resolution) => std::str::from_utf8(resolution).map(str::to_owned),
+                        None => terms
+                            .adds()
+                            .map(|content| std::str::from_utf8(content))
+                            .collect(),
+                    };
+                    match text {
+                        Ok(text) => self.mode = Mode::Edit(TextEditor::new(&text)),
+                        Err(_) => {
+                            self.message = Some("Cannot edit non-UTF-8 content".to_owned());
+                        }
+                    }
+                }
+            }
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_add(10),
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(10),
+            KeyCode::Char('?') => self.mode = Mode::Help,
+            _ => {}
+        }
+        Outcome::Continue
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let [header_area, terms_area, result_area, footer_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(3),
+            Constraint::Percentage(40),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let Some(&(file_index, _)) = self.conflicts.get(self.current) else {
+            frame.render_widget(Paragraph::new("No conflicts to resolve"), header_area);
+            return;
+        };
+        let (terms, resolution) = self.current_conflict().unwrap();
+        let header = format!(
+            "{} ({}/{}) - conflict {} of {}, {} unresolved",
+            self.files[file_index].path,
+            file_index + 1,
+            self.files.len(),
+            self.current + 1,
+            self.conflicts.len(),
+            self.num_unresolved(),
+        );
+        frame.render_widget(
+            Paragraph::new(header).style(Style::new().add_modifier(Modifier::BOLD)),
+            header_area,
+        );
+
+        let term_areas =
+            Layout::horizontal(vec![Constraint::Fill(1); terms.as_slice().len()]).split(terms_area);
+        for (i, (content, area)) in zip(terms.iter(), term_areas.iter()).enumerate() {
+            let title = if i % 2 == 0 {
+                format!(" Side #{} ", i / 2 + 1)
+            } else if terms.num_sides() == 2 {
+                " Base ".to_owned()
+            } else {
+                format!(" Base #{} ", i / 2 + 1)
+            };
+            let is_taken = resolution == Some(content);
+            let block = Block::bordered().title(title).border_style(if is_taken {
+                Style::new().add_modifier(Modifier::BOLD)
+            } else {
+                Style::new()
+            });
+            let text = content.to_str_lossy().into_owned();
+            frame.render_widget(
+                Paragraph::new(text).block(block).scroll((self.scroll, 0)),
+                *area,
+            );
+        }
+
+        match &self.mode {
+            Mode::Edit(editor) => {
+                let block = Block::bordered().title(" Result (editing, press Esc to finish) ");
+                let inner = block.inner(result_area);
+                let scroll = (editor.row as u16).saturating_sub(inner.height.saturating_sub(1));
+                frame.render_widget(
+                    Paragraph::new(editor.text())
+                        .block(block)
+                        .scroll((scroll, 0)),
+                    result_area,
+                );
+                let col: usize = editor.lines[editor.row]
+                    .chars()
+                    .take(editor.col)
+                    .map(|c| c.width().unwrap_or(0))
+                    .sum();
+                frame.set_cursor_position((
+                    inner.x + col as u16,
+                    inner.y + editor.row as u16 - scroll,
+                ));
+            }
+            Mode::Normal | Mode::Help => {
+                let paragraph = if let Some(resolution) = resolution {
+                    Paragraph::new(resolution.to_str_lossy().into_owned())
+                        .block(Block::bordered().title(" Result "))
+                } else {
+                    let hint = Line::from(Span::styled(
+                        "Unresolved. Take a side with 1-9, l, r, b, or o, or press e to edit.",
+                        Style::new().add_modifier(Modifier::ITALIC | Modifier::DIM),
+                    ));
+                    Paragraph::new(hint).block(Block::bordered().title(" Result (unresolved) "))
+                };
+                frame.render_widget(paragraph, result_area);
+            }
+        }
+
+        let footer = if let Some(message) = &self.message {
+            Paragraph::new(message.as_str()).style(Style::new().add_modifier(Modifier::BOLD))
+        } else {
+            Paragraph::new(
+                "n/p: next/prev  1-9/l/r: take side  b: both  o: base  e: edit  u: unresolve  \
+                 c: apply  q: quit  ?: help",
+            )
+            .style(Style::new().add_modifier(Modifier::DIM))
+        };
+        frame.render_widget(footer, footer_area);
+
+        if let Mode::Help = self.mode {
+            let area = centered_rect(frame.area(), 64, HELP_TEXT.lines().count() as u16 + 2);
+            frame.render_widget(Clear, area);
+            frame.render_widget(
+                Paragraph::new(HELP_TEXT).block(Block::bordered().title(" Help ")),
+                area,
+            );
+        }
+    }
+}
+
+fn centered_rect(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+fn write_resolutions(
+    tree: &MergedTree,
+    merge_tool_files: &[MergeToolFile],
+    files: &[ResolverFile],
+) -> BackendResult<MergedTreeId> {
+    let store = tree.store();
+    let mut tree_builder = MergedTreeBuilder::new(tree.id());
+    for (merge_tool_file, file) in zip(merge_tool_files, files) {
+        let MergeToolFile {
+            repo_path,
+            conflict,
+            file_merge,
+            content,
+        } = merge_tool_file;
+        let new_file_ids = if let Some(resolved) = file.resolved_content() {
+            let new_file_id = store
+                .write_file(repo_path, &mut resolved.as_slice())
+                .block_on()?;
+            // Update the file ids only, leaving the executable flags unchanged
+            Merge::from_vec(vec![Some(new_file_id); conflict.as_slice().len()])
+        } else {
+            // Keep the conflict, but with the resolved hunks replaced
+            let simplified_file_merge = file_merge.clone().simplify();
+            let new_contents = file.term_contents(content.as_slice().len());
+            if new_contents == *content {
+                continue;
+            }
+            let new_file_ids = zip(
+                simplified_file_merge.iter(),
+                zip(new_contents, content.iter()),
+            )
+            .map(|(file_id, (new_content, old_content))| {
+                if new_content == *old_content {
+                    Ok(file_id.clone())
+                } else {
+                    let new_file_id = store
+                        .write_file(repo_path, &mut new_content.as_slice())
+                        .block_on()?;
+                    Ok(Some(new_file_id))
+                }
+            })
+            .try_collect::<_, Vec<_>, BackendError>()?;
+            file_merge
+                .clone()
+                .update_from_simplified(Merge::from_vec(new_file_ids))
+        };
+        let new_tree_value = conflict.with_new_file_ids(&new_file_ids);
+        tree_builder.set_or_remove(repo_path.clone(), new_tree_value);
+    }
+    tree_builder.write_tree(store)
+}
+
+pub fn edit_merge_builtin(
+    tree: &MergedTree,
+    merge_tool_files: &[MergeToolFile],
+) -> Result<MergedTreeId, BuiltinToolError> {
+    let files = merge_tool_files.iter().map(ResolverFile::new).collect();
+    let mut state = ResolverState::new(files);
+    run_interactive_tool(&mut state)?;
+    write_resolutions(tree, merge_tool_files, &state.files).map_err(BuiltinToolError::BackendError)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read as _;
+
+    use jj_lib::backend::TreeValue;
+    use jj_lib::conflicts::extract_as_single_hunk;
+    use jj_lib::repo::Repo as _;
+    use jj_lib::repo_path::RepoPath;
+    use testutils::TestRepo;
+
+    use super::*;
+    use crate::tui_util::testing::key;
+    use crate::tui_util::testing::render;
+    use crate::tui_util::testing::type_keys;
+
+    fn make_file<T: AsRef<[u8]>>(contents: &[T]) -> ResolverFile {
+        let content = Merge::from_vec(
+            contents
+                .iter()
+                .map(|s| BString::from(s.as_ref()))
+                .collect_vec(),
+        );
+        let hunks = match files::merge(&content, DiffAlgorithm::default()) {
+            MergeResult::Resolved(content) => vec![ResolverHunk::Resolved(content)],
+            MergeResult::Conflict(hunks) => hunks
+                .into_iter()
+                .map(|hunk| match hunk.into_resolved() {
+                    Ok(content) => ResolverHunk::Resolved(content),
+                    Err(terms) => ResolverHunk::Conflict {
+                        terms,
+                        resolution: None,
+                    },
+                })
+                .collect(),
+        };
+        ResolverFile {
+            path: "file".to_owned(),
+            hunks,
+        }
+    }
+
+    #[test]
+    fn test_take_sides_and_bases() {
+        let file = make_file(&[
+            "a\nleft 1\nb\nc\nd\nleft 2\n",
+            "a\nbase 1\nb\nc\nd\nbase 2\n",
+            "a\nright 1\nb\nc\nd\nright 2\n",
+        ]);
+        let mut state = ResolverState::new(vec![file]);
+        assert_eq!(state.conflicts.len(), 2);
+        assert_eq!(state.num_unresolved(), 2);
+
+        type_keys(&mut state, "r");
+        assert_eq!(state.num_unresolved(), 1);
+        assert_eq!(state.files[0].resolved_content(), None);
+        assert_eq!(
+            state.files[0].term_contents(3),
+            Merge::from_vec(vec![
+                BString::from("a\nright 1\nb\nc\nd\nleft 2\n"),
+                BString::from("a\nright 1\nb\nc\nd\nbase 2\n"),
+                BString::from("a\nright 1\nb\nc\nd\nright 2\n"),
+            ])
+        );
+
+        type_keys(&mut state, "nb");
+        assert_eq!(
+            state.files[0].resolved_content(),
+            Some(BString::from("a\nright 1\nb\nc\nd\nleft 2\nright 2\n"))
+        );
+        type_keys(&mut state, "o");
+        assert_eq!(
+            state.files[0].resolved_content(),
+            Some(BString::from("a\nright 1\nb\nc\nd\nbase 2\n"))
+        );
+        type_keys(&mut state, "1");
+        assert_eq!(
+            state.files[0].resolved_content(),
+            Some(BString::from("a\nright 1\nb\nc\nd\nleft 2\n"))
+        );
+        type_keys(&mut state, "pu");
+        assert_eq!(state.num_unresolved(), 1);
+        assert_eq!(state.handle_key(key(KeyCode::Char('c'))), Outcome::Apply);
+        assert_eq!(state.handle_key(key(KeyCode::Char('q'))), Outcome::Quit);
+    }
+
+    #[test]
+    fn test_take_base_cycles_through_bases() {
+        let file = make_file(&["a\n", "base 1\n", "b\n", "base 2\n", "c\n"]);
+        let mut state = ResolverState::new(vec![file]);
+        let resolved = |state: &ResolverState| state.files[0].resolved_content().unwrap();
+        type_keys(&mut state, "o");
+        assert_eq!(resolved(&state), "base 1\n");
+        type_keys(&mut state, "o");
+        assert_eq!(resolved(&state), "base 2\n");
+        type_keys(&mut state, "o");
+        assert_eq!(resolved(&state), "base 1\n");
+        type_keys(&mut state, "3");
+        assert_eq!(resolved(&state), "c\n");
+        type_keys(&mut state, "r");
+        assert_eq!(resolved(&state), "c\n");
+        type_keys(&mut state, "b");
+        assert_eq!(resolved(&state), "a\nb\nc\n");
+        // There's no 4th side
+        type_keys(&mut state, "4");
+        assert_eq!(resolved(&state), "a\nb\nc\n");
+    }
+
+    #[test]
+    fn test_edit_resolution() {
+        let file = make_file(&["left\n", "base\n", "right\n"]);
+        let mut state = ResolverState::new(vec![file]);
+        // Editing starts from all sides
+        state.handle_key(key(KeyCode::Char('e')));
+        assert_matches::assert_matches!(state.mode, Mode::Edit(_));
+        // Keys are inserted as text while editing
+        type_keys(&mut state, "q");
+        state.handle_key(key(KeyCode::Down));
+        state.handle_key(key(KeyCode::End));
+        type_keys(&mut state, "!");
+        state.handle_key(key(KeyCode::Enter));
+        type_keys(&mut state, "new");
+        state.handle_key(key(KeyCode::Up));
+        state.handle_key(key(KeyCode::Home));
+        state.handle_key(key(KeyCode::Backspace));
+        state.handle_key(key(KeyCode::Delete));
+        state.handle_key(key(KeyCode::Esc));
+        assert_matches::assert_matches!(state.mode, Mode::Normal);
+        assert_eq!(
+            state.files[0].resolved_content(),
+            Some(BString::from("qleftight!\nnew\n"))
+        );
+    }
+
+    #[test]
+    fn test_edit_non_utf8_content() {
+        let file = make_file(&[&b"left\xff\n"[..], b"base\n", b"right\n"]);
+        let mut state = ResolverState::new(vec![file]);
+        state.handle_key(key(KeyCode::Char('e')));
+        assert_matches::assert_matches!(state.mode, Mode::Normal);
+        assert_eq!(
+            state.message.as_deref(),
+            Some("Cannot edit non-UTF-8 content")
+        );
+        // A UTF-8 resolution can be edited
+        type_keys(&mut state, "r");
+        assert_eq!(state.message, None);
+        state.handle_key(key(KeyCode::Char('e')));
+        assert_matches::assert_matches!(state.mode, Mode::Edit(_));
+    }
+
+    #[test]
+    fn test_render() {
+        let file = make_file(&["a\nleft\n", "a\nbase\n", "a\nright\n"]);
+        let mut state = ResolverState::new(vec![file]);
+        insta::assert_snapshot!(render(&state, 60, 12), @r"
+        file (1/1) - conflict 1 of 1, 1 unresolved
+        ┌ Side #1 ─────────┐┌ Base ────────────┐┌ Side #2 ─────────┐
+        │left              ││base              ││right             │
+        │                  ││                  ││                  │
+        │                  ││                  ││                  │
+        └──────────────────┘└──────────────────┘└──────────────────┘
+        ┌ Result (unresolved) ─────────────────────────────────────┐
+        │Unresolved. Take a side with 1-9, l, r, b, or o, or press │
+        │                                                          │
+        │                                                          │
+        └──────────────────────────────────────────────────────────┘
+        n/p: next/prev  1-9/l/r: take side  b: both  o: base  e: edi
+        ");
+        type_keys(&mut state, "l");
+        insta::assert_snapshot!(render(&state, 60, 12), @r"
+        file (1/1) - conflict 1 of 1, 0 unresolved
+        ┌ Side #1 ─────────┐┌ Base ────────────┐┌ Side #2 ─────────┐
+        │left              ││base              ││right             │
+        │                  ││                  ││                  │
+        │                  ││                  ││                  │
+        └──────────────────┘└──────────────────┘└──────────────────┘
+        ┌ Result ──────────────────────────────────────────────────┐
+        │left                                                      │
+        │                                                          │
+        │                                                          │
+        └──────────────────────────────────────────────────────────┘
+        n/p: next/prev  1-9/l/r: take side  b: both  o: base  e: edi
+        ");
+    }
+
+    #[test]
+    fn test_write_resolutions() {
+        let test_repo = TestRepo::init();
+        let store = test_repo.repo.store();
+        let path = RepoPath::from_internal_string("file");
+        let base_tree =
+            testutils::create_tree(&test_repo.repo, &[(path, "base 1\nb\nc\nd\nbase 2\n")]);
+        let left_tree =
+            testutils::create_tree(&test_repo.repo, &[(path, "left 1\nb\nc\nd\nleft 2\n")]);
+        let right_tree =
+            testutils::create_tree(&test_repo.repo, &[(path, "right 1\nb\nc\nd\nright 2\n")]);
+        let tree = left_tree.merge(&base_tree, &right_tree).unwrap();
+        let merge_tool_files = [MergeToolFile::from_tree_and_path(&tree, path).unwrap()];
+        let mut state =
+            ResolverState::new(merge_tool_files.iter().map(ResolverFile::new).collect());
+
+        // Partially resolved file remains conflicted
+        type_keys(&mut state, "r");
+        let tree_id = write_resolutions(&tree, &merge_tool_files, &state.files).unwrap();
+        let new_tree = store.get_root_tree(&tree_id).unwrap();
+        let file_merge = new_tree.path_value(path).unwrap().to_file_merge().unwrap();
+        let content = extract_as_single_hunk(&file_merge, store, path)
+            .block_on()
+            .unwrap();
+        insta::assert_debug_snapshot!(content, @r#"
+        Conflicted(
+            [
+                "right 1\nb\nc\nd\nleft 2\n",
+                "right 1\nb\nc\nd\nbase 2\n",
+                "right 1\nb\nc\nd\nright 2\n",
+            ],
+        )
+        "#);
+
+        // Fully resolved file
+        type_keys(&mut state, "no");
+        let tree_id = write_resolutions(&tree, &merge_tool_files, &state.files).unwrap();
+        let new_tree = store.get_root_tree(&tree_id).unwrap();
+        let Ok(Some(TreeValue::File { id, .. })) =
+            new_tree.path_value(path).unwrap().into_resolved()
+        else {
+            panic!("file should have been resolved");
+        };
+        let mut content = String::new();
+        store
+            .read_file(path, &id)
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "right 1\nb\nc\nd\nbase 2\n");
+    }
+}
@@ -14,6 +14,7 @@ use jj_lib::conflicts::choose_materialized_conflict_marker_len;
 use jj_lib::conflicts::materialize_merge_result_to_bytes_with_marker_len;
 use jj_lib::conflicts::ConflictMarkerStyle;
 use jj_lib::conflicts::MIN_CONFLICT_MARKER_LEN;
+use jj_lib::diff::DiffAlgorithm;
 use jj_lib::gitignore::GitIgnoreFile;
 use jj_lib::matchers::Matcher;
 use jj_lib::merge::Merge;
@@ -207,6 +208,7 @@ fn run_mergetool_external_single_file(
             content,
             conflict_marker_style,
             conflict_marker_len,
+            DiffAlgorithm::Default,
         )
     } else {
         BString::default()
@@ -13,6 +13,8 @@
 // limitations under the License.
 
 mod builtin;
+mod builtin_absorb;
+mod builtin_merge;
 mod diff_working_copies;
 mod external;
 
@@ -20,6 +22,8 @@ use std::sync::Arc;
 
 use bstr::BString;
 use itertools::Itertools as _;
+use jj_lib::absorb::AbsorbPlan;
+use jj_lib::backend::CommitId;
 use jj_lib::backend::FileId;
 use jj_lib::backend::MergedTreeId;
 use jj_lib::config::ConfigGetError;
@@ -42,8 +46,10 @@ use pollster::FutureExt as _;
 use thiserror::Error;
 
 use self::builtin::edit_diff_builtin;
-use self::builtin::edit_merge_builtin;
 use self::builtin::BuiltinToolError;
+use self::builtin_absorb::edit_absorb_builtin;
+pub use self::builtin_absorb::format_hunk_header;
+use self::builtin_merge::edit_merge_builtin;
 pub(crate) use self::diff_working_copies::new_utf8_temp_dir;
 use self::diff_working_copies::DiffCheckoutError;
 use self::external::edit_diff_external;
@@ -279,6 +285,16 @@ impl DiffEditor {
     }
 }
 
+/// Starts the builtin editor to review and change the destinations of the
+/// hunks to be absorbed.
+pub fn edit_absorb_plan(
+    plan: &mut AbsorbPlan,
+    candidates: &[(CommitId, String)],
+    path_converter: &RepoPathUiConverter,
+) -> Result<(), DiffEditError> {
+    Ok(edit_absorb_builtin(plan, candidates, path_converter).map_err(Box::new)?)
+}
+
 /// A file to be merged by a merge tool.
 struct MergeToolFile {
     repo_path: RepoPathBuf,
@@ -302,13 +318,6 @@ impl MergeToolFile {
             ConflictResolveError::NotNormalFiles(repo_path.to_owned(), summary)
         })?;
         let simplified_file_merge = file_merge.clone().simplify();
-        // We only support conflicts with 2 sides (3-way conflicts)
-        if simplified_file_merge.num_sides() > 2 {
-            return Err(ConflictResolveError::ConflictTooComplicated {
-                path: repo_path.to_owned(),
-                sides: simplified_file_merge.num_sides(),
-            });
-        };
         let content =
             extract_as_single_hunk(&simplified_file_merge, tree.store(), repo_path).block_on()?;
         Ok(MergeToolFile {
@@ -394,14 +403,27 @@ impl MergeEditor {
                 let tree_id = edit_merge_builtin(tree, &merge_tool_files).map_err(Box::new)?;
                 Ok((tree_id, None))
             }
-            MergeTool::External(editor) => external::run_mergetool_external(
-                ui,
-                &self.path_converter,
-                editor,
-                tree,
-                &merge_tool_files,
-                self.conflict_marker_style,
-            ),
+            MergeTool::External(editor) => {
+                // External tools only support conflicts with 2 sides (3-way
+                // conflicts)
+                if let Some(file) = merge_tool_files
+                    .iter()
+                    .find(|file| file.content.num_sides() > 2)
+                {
+                    return Err(ConflictResolveError::ConflictTooComplicated {
+                        path: file.repo_path.clone(),
+                        sides: file.content.num_sides(),
+                    });
+                }
+                external::run_mergetool_external(
+                    ui,
+                    &self.path_converter,
+                    editor,
+                    tree,
+                    &merge_tool_files,
+                    self.conflict_marker_style,
+                )
+            }
         }
     }
 }
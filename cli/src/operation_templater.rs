@@ -37,10 +37,12 @@ use crate::template_parser;
 use crate::template_parser::FunctionCallNode;
 use crate::template_parser::TemplateDiagnostics;
 use crate::template_parser::TemplateParseResult;
+use crate::templater::IntoJsonValue;
 use crate::templater::PlainTextFormattedProperty;
 use crate::templater::Template;
 use crate::templater::TemplateFormatter;
 use crate::templater::TemplateProperty;
+use crate::templater::TemplatePropertyError;
 use crate::templater::TemplatePropertyExt as _;
 use crate::templater::TimestampRange;
 
@@ -197,6 +199,14 @@ impl IntoTemplateProperty<'static> for OperationTemplatePropertyKind {
         }
     }
 
+    fn try_into_json(self) -> Option<Box<dyn TemplateProperty<Output = serde_json::Value>>> {
+        match self {
+            OperationTemplatePropertyKind::Core(property) => property.try_into_json(),
+            OperationTemplatePropertyKind::Operation(property) => Some(property.into_json()),
+            OperationTemplatePropertyKind::OperationId(property) => Some(property.into_json()),
+        }
+    }
+
     fn try_into_eq(self, other: Self) -> Option<Box<dyn TemplateProperty<Output = bool>>> {
         match (self, other) {
             (
@@ -351,12 +361,38 @@ fn builtin_operation_methods() -> OperationTemplateBuildMethodFnMap<Operation> {
     map
 }
 
+impl IntoJsonValue for Operation {
+    fn into_json_value(self) -> Result<serde_json::Value, TemplatePropertyError> {
+        let metadata = self.metadata();
+        let time = TimestampRange {
+            start: metadata.start_time,
+            end: metadata.end_time,
+        };
+        Ok(serde_json::json!({
+            "id": self.id().clone().into_json_value()?,
+            "parents": self.parent_ids().to_vec().into_json_value()?,
+            "time": time.into_json_value()?,
+            "description": metadata.description,
+            "hostname": metadata.hostname,
+            "username": metadata.username,
+            "is_snapshot": metadata.is_snapshot,
+            "tags": metadata.tags,
+        }))
+    }
+}
+
 impl Template for OperationId {
     fn format(&self, formatter: &mut TemplateFormatter) -> io::Result<()> {
         write!(formatter, "{}", self.hex())
     }
 }
 
+impl IntoJsonValue for OperationId {
+    fn into_json_value(self) -> Result<serde_json::Value, TemplatePropertyError> {
+        Ok(self.hex().into())
+    }
+}
+
 fn builtin_operation_id_methods() -> OperationTemplateBuildMethodFnMap<OperationId> {
     type L = OperationTemplateLanguage;
     // Not using maplit::hashmap!{} or custom declarative macro here because
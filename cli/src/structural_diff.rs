@@ -0,0 +1,566 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Syntax-aware comparison of source files.
+//!
+//! Files are parsed with tree-sitter, and the top-level items (functions,
+//! types, statements, etc.) of both sides are matched up. Items that only
+//! changed position are reported as moved, and changed items can be compared
+//! token by token.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::iter;
+use std::ops::Range;
+
+use itertools::Itertools as _;
+use jj_lib::repo_path::RepoPath;
+use tree_sitter::Language;
+use tree_sitter::Node;
+use tree_sitter::Parser;
+use tree_sitter::Tree;
+
+/// Maximum number of item pairs to compare when aligning the items.
+const MAX_ALIGNMENT_SIZE: usize = 4_000_000;
+
+/// Returns the tree-sitter grammar for the file at `path`.
+fn language_for_path(path: &RepoPath) -> Option<Language> {
+    let name = path.components().next_back()?.as_internal_str();
+    let (_, extension) = name.rsplit_once('.')?;
+    let language = match extension {
+        "c" | "h" => tree_sitter_c::LANGUAGE,
+        "go" => tree_sitter_go::LANGUAGE,
+        "js" | "jsx" | "mjs" | "cjs" => tree_sitter_javascript::LANGUAGE,
+        "json" => tree_sitter_json::LANGUAGE,
+        "py" | "pyi" => tree_sitter_python::LANGUAGE,
+        "rs" => tree_sitter_rust::LANGUAGE,
+        _ => return None,
+    };
+    Some(language.into())
+}
+
+fn parse(language: &Language, text: &[u8]) -> Option<Tree> {
+    let mut parser = Parser::new();
+    parser.set_language(language).ok()?;
+    parser.parse(text, None)
+}
+
+/// Top-level syntax node of a source file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SyntaxItem {
+    /// Node kind as defined by the grammar, e.g. `function_item`.
+    pub kind: &'static str,
+    /// Name of the defined function, type, key, etc. if any.
+    pub name: Option<String>,
+    /// Byte range of the whole lines spanned by the item.
+    pub range: Range<usize>,
+    /// Byte range of the syntax nodes, excluding separators such as `,`
+    /// between the items. Items are compared by this range.
+    pub node_range: Range<usize>,
+    /// 1-based line number of the first line.
+    pub line_number: u32,
+}
+
+impl SyntaxItem {
+    /// Returns a human-readable description of the item.
+    pub fn describe(&self) -> String {
+        let kind = self.kind.replace('_', " ");
+        match &self.name {
+            Some(name) => format!("{kind} `{name}`"),
+            None => kind,
+        }
+    }
+}
+
+/// How a top-level item changed between the two sides.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SyntaxItemChange {
+    /// Left and right items are identical, but at different positions.
+    Moved {
+        left: usize,
+        right: usize,
+    },
+    /// Left item was modified to the right item.
+    Changed {
+        left: usize,
+        right: usize,
+    },
+    Added {
+        right: usize,
+    },
+    Removed {
+        left: usize,
+    },
+}
+
+/// Changes in top-level items between two versions of a source file.
+#[derive(Debug)]
+pub struct StructuralDiff {
+    language: Language,
+    pub left_items: Vec<SyntaxItem>,
+    pub right_items: Vec<SyntaxItem>,
+    /// Changes in order of appearance. Unchanged items are omitted.
+    pub changes: Vec<SyntaxItemChange>,
+}
+
+impl StructuralDiff {
+    /// Compares `left` and `right` contents of the file at `path`.
+    ///
+    /// Returns `None` if the file type isn't supported, or if either side
+    /// can't be parsed without errors.
+    pub fn new(path: &RepoPath, left: &[u8], right: &[u8]) -> Option<Self> {
+        let language = language_for_path(path)?;
+        let left_items = top_level_items(&language, left)?;
+        let right_items = top_level_items(&language, right)?;
+        let changes = match_items(left, &left_items, right, &right_items)?;
+        Some(StructuralDiff {
+            language,
+            left_items,
+            right_items,
+            changes,
+        })
+    }
+
+    /// Splits `text` into ranges of syntax tokens.
+    pub fn tokenize(&self, text: &[u8]) -> Vec<Range<usize>> {
+        let Some(tree) = parse(&self.language, text) else {
+            let whole_range = 0..text.len();
+            return vec![whole_range];
+        };
+        let mut ranges = Vec::new();
+        let mut cursor = tree.walk();
+        loop {
+            let node = cursor.node();
+            if node.child_count() == 0 {
+                if !node.byte_range().is_empty() {
+                    ranges.push(node.byte_range());
+                }
+            } else if cursor.goto_first_child() {
+                continue;
+            }
+            while !cursor.goto_next_sibling() {
+                if !cursor.goto_parent() {
+                    return ranges;
+                }
+            }
+        }
+    }
+}
+
+fn top_level_items(language: &Language, text: &[u8]) -> Option<Vec<SyntaxItem>> {
+    let tree = parse(language, text)?;
+    let mut root = tree.root_node();
+    if root.has_error() {
+        return None;
+    }
+    // Compare the entries of the top-level JSON object or array.
+    if let Ok(child) = root.named_children(&mut root.walk()).exactly_one() {
+        if matches!(child.kind(), "object" | "array") {
+            root = child;
+        }
+    }
+
+    let mut items: Vec<SyntaxItem> = Vec::new();
+    for node in root.named_children(&mut root.walk()) {
+        let start = line_start(text, node.start_byte());
+        let end = line_end(text, node.end_byte());
+        match items.last_mut() {
+            // Items sharing a line are merged
+            Some(last) if start < last.range.end => {
+                last.range.end = last.range.end.max(end);
+                last.node_range.end = node.end_byte();
+            }
+            _ => items.push(SyntaxItem {
+                kind: node.kind(),
+                name: item_name(node, text),
+                range: start..end,
+                node_range: node.byte_range(),
+                line_number: u32::try_from(node.start_position().row + 1).unwrap(),
+            }),
+        }
+    }
+    Some(items)
+}
+
+fn item_name(node: Node, text: &[u8]) -> Option<String> {
+    let name_node = ["name", "key", "type"]
+        .iter()
+        .find_map(|field| node.child_by_field_name(field))
+        .or_else(|| {
+            // Look through wrapper nodes such as decorated definitions.
+            let definition = node.child_by_field_name("definition")?;
+            definition.child_by_field_name("name")
+        })?;
+    let name = name_node.utf8_text(text).ok()?;
+    (!name.contains('\n')).then(|| name.to_owned())
+}
+
+fn line_start(text: &[u8], pos: usize) -> usize {
+    text[..pos]
+        .iter()
+        .rposition(|&b| b == b'\n')
+        .map_or(0, |i| i + 1)
+}
+
+fn line_end(text: &[u8], pos: usize) -> usize {
+    if pos > 0 && text[pos - 1] == b'\n' {
+        return pos;
+    }
+    text[pos..]
+        .iter()
+        .position(|&b| b == b'\n')
+        .map_or(text.len(), |i| pos + i + 1)
+}
+
+/// Identity of an item used to align the items of both sides.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum ItemKey<'a> {
+    Named(&'static str, &'a str),
+    Unnamed(&'a [u8]),
+}
+
+impl<'a> ItemKey<'a> {
+    fn new(item: &'a SyntaxItem, text: &'a [u8]) -> Self {
+        match &item.name {
+            Some(name) => ItemKey::Named(item.kind, name),
+            None => ItemKey::Unnamed(text),
+        }
+    }
+}
+
+/// Aligns the items of both sides, and classifies the unaligned items.
+fn match_items(
+    left: &[u8],
+    left_items: &[SyntaxItem],
+    right: &[u8],
+    right_items: &[SyntaxItem],
+) -> Option<Vec<SyntaxItemChange>> {
+    if left_items.len().saturating_mul(right_items.len()) > MAX_ALIGNMENT_SIZE {
+        return None;
+    }
+    let left_texts = left_items
+        .iter()
+        .map(|item| &left[item.node_range.clone()])
+        .collect_vec();
+    let right_texts = right_items
+        .iter()
+        .map(|item| &right[item.node_range.clone()])
+        .collect_vec();
+    // Items defining the same name are aligned even if they were modified, so
+    // that a modified item doesn't make its neighbors look moved.
+    let left_keys = iter::zip(left_items, &left_texts)
+        .map(|(item, text)| ItemKey::new(item, text))
+        .collect_vec();
+    let right_keys = iter::zip(right_items, &right_texts)
+        .map(|(item, text)| ItemKey::new(item, text))
+        .collect_vec();
+    let anchors = longest_common_subsequence(&left_keys, &right_keys);
+
+    // Gaps between aligned items, as (left_range, right_range) pairs.
+    let gaps = itertools::chain(
+        [(0, 0)],
+        anchors.iter().map(|&(left, right)| (left + 1, right + 1)),
+    )
+    .zip(itertools::chain(
+        anchors.iter().copied(),
+        [(left_items.len(), right_items.len())],
+    ))
+    .map(|((left_start, right_start), (left_end, right_end))| {
+        (left_start..left_end, right_start..right_end)
+    })
+    .collect_vec();
+
+    // Identical items in different gaps are moves. Remaining items defining
+    // the same name are changes.
+    let mut left_pairs: HashMap<usize, SyntaxItemChange> = HashMap::new();
+    let mut right_pairs: HashMap<usize, SyntaxItemChange> = HashMap::new();
+    let unaligned_left = gaps.iter().flat_map(|(left, _)| left.clone()).collect_vec();
+    let unaligned_right = gaps
+        .iter()
+        .flat_map(|(_, right)| right.clone())
+        .collect_vec();
+    let mut left_by_text: HashMap<&[u8], Vec<usize>> = HashMap::new();
+    for &left in unaligned_left.iter().rev() {
+        left_by_text.entry(left_texts[left]).or_default().push(left);
+    }
+    for &right in &unaligned_right {
+        if let Some(left) = left_by_text
+            .get_mut(right_texts[right])
+            .and_then(|lefts| lefts.pop())
+        {
+            let change = SyntaxItemChange::Moved { left, right };
+            left_pairs.insert(left, change);
+            right_pairs.insert(right, change);
+        }
+    }
+    let named_key = |item: &SyntaxItem| Some((item.kind, item.name.clone()?));
+    let left_by_name: HashMap<_, usize> = unaligned_left
+        .iter()
+        .filter(|left| !left_pairs.contains_key(left))
+        .filter_map(|&left| Some((named_key(&left_items[left])?, left)))
+        .into_group_map()
+        .into_iter()
+        .filter_map(|(key, lefts)| Some((key, lefts.into_iter().exactly_one().ok()?)))
+        .collect();
+    for &right in &unaligned_right {
+        if right_pairs.contains_key(&right) {
+            continue;
+        }
+        let Some(key) = named_key(&right_items[right]) else {
+            continue;
+        };
+        if let Some(&left) = left_by_name.get(&key) {
+            if let Entry::Vacant(entry) = left_pairs.entry(left) {
+                let change = SyntaxItemChange::Changed { left, right };
+                entry.insert(change);
+                right_pairs.insert(right, change);
+            }
+        }
+    }
+
+    let mut changes = Vec::new();
+    for (gap_index, (left_range, right_range)) in gaps.into_iter().enumerate() {
+        // Pair up the remaining items of the same kind within the gap.
+        let mut lefts = left_range
+            .filter(|left| !left_pairs.contains_key(left))
+            .collect_vec();
+        for right in right_range.clone() {
+            if right_pairs.contains_key(&right) {
+                continue;
+            }
+            if let Some(pos) = lefts
+                .iter()
+                .position(|&left| left_items[left].kind == right_items[right].kind)
+            {
+                let left = lefts.remove(pos);
+                let change = SyntaxItemChange::Changed { left, right };
+                left_pairs.insert(left, change);
+                right_pairs.insert(right, change);
+            }
+        }
+        // Report removed items next to their neighbors where possible, and
+        // before added items otherwise.
+        let mut removed = lefts.into_iter().peekable();
+        for right in right_range {
+            let change = right_pairs
+                .get(&right)
+                .copied()
+                .unwrap_or(SyntaxItemChange::Added { right });
+            let left_bound = match change {
+                SyntaxItemChange::Moved { left, .. } | SyntaxItemChange::Changed { left, .. } => {
+                    left
+                }
+                SyntaxItemChange::Added { .. } | SyntaxItemChange::Removed { .. } => usize::MAX,
+            };
+            while let Some(left) = removed.next_if(|&left| left < left_bound) {
+                changes.push(SyntaxItemChange::Removed { left });
+            }
+            changes.push(change);
+        }
+        changes.extend(removed.map(|left| SyntaxItemChange::Removed { left }));
+        if let Some(&(left, right)) = anchors.get(gap_index) {
+            if left_texts[left] != right_texts[right] {
+                changes.push(SyntaxItemChange::Changed { left, right });
+            }
+        }
+    }
+    Some(changes)
+}
+
+/// Returns indices of the longest common subsequence of `left` and `right`.
+fn longest_common_subsequence<T: Eq>(left: &[T], right: &[T]) -> Vec<(usize, usize)> {
+    // lengths[i][j] is the length of the LCS of left[i..] and right[j..]
+    let width = right.len() + 1;
+    let mut lengths = vec![0_u32; (left.len() + 1) * width];
+    for i in (0..left.len()).rev() {
+        for j in (0..right.len()).rev() {
+            lengths[i * width + j] = if left[i] == right[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < left.len() && j < right.len() {
+        if left[i] == right[j] {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+
+    fn describe_changes(path: &str, left: &str, right: &str) -> Option<Vec<String>> {
+        let path = RepoPath::from_internal_string(path);
+        let diff = StructuralDiff::new(path, left.as_bytes(), right.as_bytes())?;
+        let changes = diff
+            .changes
+            .iter()
+            .map(|change| match *change {
+                SyntaxItemChange::Moved { left, right } => format!(
+                    "moved {} {}->{}",
+                    diff.left_items[left].describe(),
+                    diff.left_items[left].line_number,
+                    diff.right_items[right].line_number
+                ),
+                SyntaxItemChange::Changed { left, right } => format!(
+                    "changed {} -> {}",
+                    diff.left_items[left].describe(),
+                    diff.right_items[right].describe()
+                ),
+                SyntaxItemChange::Added { right } => {
+                    format!("added {}", diff.right_items[right].describe())
+                }
+                SyntaxItemChange::Removed { left } => {
+                    format!("removed {}", diff.left_items[left].describe())
+                }
+            })
+            .collect();
+        Some(changes)
+    }
+
+    #[test]
+    fn test_unsupported() {
+        assert_eq!(describe_changes("file.txt", "a\n", "b\n"), None);
+        assert_eq!(describe_changes("Makefile", "a\n", "b\n"), None);
+        // Syntax error
+        assert_eq!(
+            describe_changes("file.rs", "fn a() {}\n", "fn a( {}\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_rust_items() {
+        let left = indoc! {"
+            fn a() {}
+
+            fn b() -> i32 {
+                1
+            }
+
+            struct S;
+        "};
+        let right = indoc! {"
+            fn b() -> i32 {
+                2
+            }
+
+            struct S;
+
+            fn a() {}
+
+            fn c() {}
+        "};
+        insta::assert_debug_snapshot!(describe_changes("src/lib.rs", left, right), @r#"
+        Some(
+            [
+                "changed function item `b` -> function item `b`",
+                "moved function item `a` 1->7",
+                "added function item `c`",
+            ],
+        )
+        "#);
+    }
+
+    #[test]
+    fn test_unnamed_items() {
+        let left = indoc! {"
+            import os
+            print(1)
+            print(2)
+        "};
+        let right = indoc! {"
+            import os
+            print(1 + 1)
+            print(2)
+            x = 3
+        "};
+        insta::assert_debug_snapshot!(describe_changes("main.py", left, right), @r#"
+        Some(
+            [
+                "changed expression statement -> expression statement",
+                "added expression statement",
+            ],
+        )
+        "#);
+    }
+
+    #[test]
+    fn test_json_items() {
+        let left = indoc! {r#"
+            {
+              "a": 1,
+              "b": [1, 2],
+              "c": null
+            }
+        "#};
+        let right = indoc! {r#"
+            {
+              "c": null,
+              "a": 1,
+              "b": [1, 3]
+            }
+        "#};
+        insta::assert_debug_snapshot!(describe_changes("data.json", left, right), @r#"
+        Some(
+            [
+                "moved pair `\"c\"` 4->2",
+                "changed pair `\"b\"` -> pair `\"b\"`",
+            ],
+        )
+        "#);
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let path = RepoPath::from_internal_string("file.rs");
+        let diff = StructuralDiff::new(path, b"", b"").unwrap();
+        let text = b"fn f(x: u32) { \"a b\" }";
+        let tokens = diff
+            .tokenize(text)
+            .into_iter()
+            .map(|range| std::str::from_utf8(&text[range]).unwrap())
+            .collect_vec();
+        insta::assert_debug_snapshot!(tokens, @r#"
+        [
+            "fn",
+            "f",
+            "(",
+            "x",
+            ":",
+            "u32",
+            ")",
+            "{",
+            "\"",
+            "a b",
+            "\"",
+            "}",
+        ]
+        "#);
+    }
+}
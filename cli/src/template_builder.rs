@@ -24,7 +24,9 @@ use jj_lib::config::ConfigNamePathBuf;
 use jj_lib::config::ConfigValue;
 use jj_lib::dsl_util::AliasExpandError as _;
 use jj_lib::settings::UserSettings;
+use jj_lib::str_util::StringPattern;
 use jj_lib::time_util::DatePattern;
+use regex::NoExpand;
 use serde::de::IntoDeserializer as _;
 use serde::Deserialize;
 
@@ -46,6 +48,7 @@ use crate::templater::CoalesceTemplate;
 use crate::templater::ConcatTemplate;
 use crate::templater::ConditionalTemplate;
 use crate::templater::Email;
+use crate::templater::HyperlinkTemplate;
 use crate::templater::LabelTemplate;
 use crate::templater::ListPropertyTemplate;
 use crate::templater::ListTemplate;
@@ -180,6 +183,9 @@ pub trait IntoTemplateProperty<'a> {
     fn try_into_plain_text(self) -> Option<Box<dyn TemplateProperty<Output = String> + 'a>>;
     fn try_into_template(self) -> Option<Box<dyn Template + 'a>>;
 
+    /// Transforms into a property that will evaluate to a JSON value.
+    fn try_into_json(self) -> Option<Box<dyn TemplateProperty<Output = serde_json::Value> + 'a>>;
+
     /// Transforms into a property that will evaluate to `self == other`.
     fn try_into_eq(self, other: Self) -> Option<Box<dyn TemplateProperty<Output = bool> + 'a>>;
 
@@ -301,6 +307,24 @@ impl<'a> IntoTemplateProperty<'a> for CoreTemplatePropertyKind<'a> {
         }
     }
 
+    fn try_into_json(self) -> Option<Box<dyn TemplateProperty<Output = serde_json::Value> + 'a>> {
+        match self {
+            CoreTemplatePropertyKind::String(property) => Some(property.into_json()),
+            CoreTemplatePropertyKind::StringList(property) => Some(property.into_json()),
+            CoreTemplatePropertyKind::Boolean(property) => Some(property.into_json()),
+            CoreTemplatePropertyKind::Integer(property) => Some(property.into_json()),
+            CoreTemplatePropertyKind::IntegerOpt(property) => Some(property.into_json()),
+            CoreTemplatePropertyKind::ConfigValue(property) => Some(property.into_json()),
+            CoreTemplatePropertyKind::Signature(property) => Some(property.into_json()),
+            CoreTemplatePropertyKind::Email(property) => Some(property.into_json()),
+            CoreTemplatePropertyKind::SizeHint(property) => Some(property.into_json()),
+            CoreTemplatePropertyKind::Timestamp(property) => Some(property.into_json()),
+            CoreTemplatePropertyKind::TimestampRange(property) => Some(property.into_json()),
+            CoreTemplatePropertyKind::Template(_) => None,
+            CoreTemplatePropertyKind::ListTemplate(_) => None,
+        }
+    }
+
     fn try_into_eq(self, other: Self) -> Option<Box<dyn TemplateProperty<Output = bool> + 'a>> {
         match (self, other) {
             (CoreTemplatePropertyKind::String(lhs), CoreTemplatePropertyKind::String(rhs)) => {
@@ -422,7 +446,7 @@ impl<'a, L: TemplateLanguage<'a> + ?Sized> CoreTemplateBuildFnTable<'a, L> {
             functions: builtin_functions(),
             string_methods: builtin_string_methods(),
             boolean_methods: HashMap::new(),
-            integer_methods: HashMap::new(),
+            integer_methods: builtin_integer_methods(),
             config_value_methods: builtin_config_value_methods(),
             signature_methods: builtin_signature_methods(),
             email_methods: builtin_email_methods(),
@@ -624,6 +648,12 @@ impl<'a, P: IntoTemplateProperty<'a>> Expression<P> {
         }
     }
 
+    pub fn try_into_json(
+        self,
+    ) -> Option<Box<dyn TemplateProperty<Output = serde_json::Value> + 'a>> {
+        self.property.try_into_json()
+    }
+
     pub fn try_into_eq(self, other: Self) -> Option<Box<dyn TemplateProperty<Output = bool> + 'a>> {
         self.property.try_into_eq(other.property)
     }
@@ -764,6 +794,24 @@ fn build_binary_operation<'a, L: TemplateLanguage<'a> + ?Sized>(
                 _ => unreachable!(),
             }
         }
+        BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => {
+            let lhs = expect_integer_expression(language, diagnostics, build_ctx, lhs_node)?;
+            let rhs = expect_integer_expression(language, diagnostics, build_ctx, rhs_node)?;
+            let out = (lhs, rhs).and_then(move |(l, r)| {
+                let (value, message) = match op {
+                    BinaryOp::Add => (l.checked_add(r), "Attempt to add with overflow"),
+                    BinaryOp::Sub => (l.checked_sub(r), "Attempt to subtract with overflow"),
+                    BinaryOp::Mul => (l.checked_mul(r), "Attempt to multiply with overflow"),
+                    BinaryOp::Div if r == 0 => (None, "Attempt to divide by zero"),
+                    BinaryOp::Div => (l.checked_div(r), "Attempt to divide with overflow"),
+                    BinaryOp::Rem if r == 0 => (None, "Attempt to divide by zero"),
+                    BinaryOp::Rem => (l.checked_rem(r), "Attempt to divide with overflow"),
+                    _ => unreachable!(),
+                };
+                value.ok_or_else(|| TemplatePropertyError(message.into()))
+            });
+            Ok(L::wrap_integer(out))
+        }
     }
 }
 
@@ -903,6 +951,56 @@ fn builtin_string_methods<'a, L: TemplateLanguage<'a> + ?Sized>(
             Ok(L::wrap_string_list(out_property))
         },
     );
+    map.insert(
+        "match",
+        |_language, _diagnostics, _build_ctx, self_property, function| {
+            let [pattern_node] = function.expect_exact_arguments()?;
+            let pattern = template_parser::expect_string_pattern(pattern_node)?;
+            let regex = string_pattern_to_regex(&pattern, pattern_node)?;
+            let out_property = self_property.map(move |s| {
+                let Some(captures) = regex.captures(&s) else {
+                    return vec![];
+                };
+                captures
+                    .iter()
+                    .map(|m| m.map_or("", |m| m.as_str()).to_owned())
+                    .collect()
+            });
+            Ok(L::wrap_string_list(out_property))
+        },
+    );
+    map.insert(
+        "replace",
+        |language, diagnostics, build_ctx, self_property, function| {
+            let [pattern_node, replacement_node] = function.expect_exact_arguments()?;
+            let pattern = template_parser::expect_string_pattern(pattern_node)?;
+            let replacement_property =
+                expect_plain_text_expression(language, diagnostics, build_ctx, replacement_node)?;
+            // Capture groups can be referenced only from regex patterns.
+            let expand = matches!(pattern, StringPattern::Regex(_));
+            let regex = string_pattern_to_regex(&pattern, pattern_node)?;
+            let out_property =
+                (self_property, replacement_property).map(move |(s, replacement)| {
+                    if expand {
+                        regex.replace_all(&s, replacement).into_owned()
+                    } else {
+                        regex.replace_all(&s, NoExpand(&replacement)).into_owned()
+                    }
+                });
+            Ok(L::wrap_string(out_property))
+        },
+    );
+    map.insert(
+        "split",
+        |_language, _diagnostics, _build_ctx, self_property, function| {
+            let [separator_node] = function.expect_exact_arguments()?;
+            let pattern = template_parser::expect_string_pattern(separator_node)?;
+            let regex = string_pattern_to_regex(&pattern, separator_node)?;
+            let out_property =
+                self_property.map(move |s| regex.split(&s).map(ToOwned::to_owned).collect());
+            Ok(L::wrap_string_list(out_property))
+        },
+    );
     map.insert(
         "upper",
         |_language, _diagnostics, _build_ctx, self_property, function| {
@@ -946,6 +1044,43 @@ fn string_index_to_char_boundary(s: &str, i: isize) -> usize {
     }
 }
 
+fn builtin_integer_methods<'a, L: TemplateLanguage<'a> + ?Sized>(
+) -> TemplateBuildMethodFnMap<'a, L, i64> {
+    // Not using maplit::hashmap!{} or custom declarative macro here because
+    // code completion inside macro is quite restricted.
+    let mut map = TemplateBuildMethodFnMap::<L, i64>::new();
+    map.insert(
+        "format_number",
+        |language, diagnostics, build_ctx, self_property, function| {
+            let ([], [separator_node]) = function.expect_arguments()?;
+            let separator_property = separator_node
+                .map(|node| expect_plain_text_expression(language, diagnostics, build_ctx, node))
+                .transpose()?;
+            let out_property = (self_property, separator_property).map(|(value, separator)| {
+                format_number_with_separator(value, separator.as_deref().unwrap_or(","))
+            });
+            Ok(L::wrap_string(out_property))
+        },
+    );
+    map
+}
+
+/// Formats the given `value` with `separator` inserted every three digits.
+fn format_number_with_separator(value: i64, separator: &str) -> String {
+    let digits = value.unsigned_abs().to_string();
+    let mut out = String::new();
+    if value < 0 {
+        out.push('-');
+    }
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            out.push_str(separator);
+        }
+        out.push(c);
+    }
+    out
+}
+
 fn builtin_config_value_methods<'a, L: TemplateLanguage<'a> + ?Sized>(
 ) -> TemplateBuildMethodFnMap<'a, L, ConfigValue> {
     fn extract<'de, T: Deserialize<'de>>(value: ConfigValue) -> Result<T, TemplatePropertyError> {
@@ -1555,6 +1690,25 @@ fn builtin_functions<'a, L: TemplateLanguage<'a> + ?Sized>() -> TemplateBuildFun
             Ok(L::wrap_template(template))
         },
     );
+    map.insert("min", |language, diagnostics, build_ctx, function| {
+        let values = expect_integer_arguments(language, diagnostics, build_ctx, function)?;
+        let out_property = values.map(|values| values.into_iter().min().unwrap());
+        Ok(L::wrap_integer(out_property))
+    });
+    map.insert("max", |language, diagnostics, build_ctx, function| {
+        let values = expect_integer_arguments(language, diagnostics, build_ctx, function)?;
+        let out_property = values.map(|values| values.into_iter().max().unwrap());
+        Ok(L::wrap_integer(out_property))
+    });
+    map.insert("abs", |language, diagnostics, build_ctx, function| {
+        let [value_node] = function.expect_exact_arguments()?;
+        let value = expect_integer_expression(language, diagnostics, build_ctx, value_node)?;
+        let out_property = value.and_then(|v| {
+            v.checked_abs()
+                .ok_or_else(|| TemplatePropertyError("Attempt to negate with overflow".into()))
+        });
+        Ok(L::wrap_integer(out_property))
+    });
     map.insert("label", |language, diagnostics, build_ctx, function| {
         let [label_node, content_node] = function.expect_exact_arguments()?;
         let label_property =
@@ -1566,6 +1720,16 @@ fn builtin_functions<'a, L: TemplateLanguage<'a> + ?Sized>() -> TemplateBuildFun
             content, labels,
         ))))
     });
+    map.insert("hyperlink", |language, diagnostics, build_ctx, function| {
+        let [url_node, content_node] = function.expect_exact_arguments()?;
+        let url_property =
+            expect_plain_text_expression(language, diagnostics, build_ctx, url_node)?;
+        let content = expect_template_expression(language, diagnostics, build_ctx, content_node)?;
+        Ok(L::wrap_template(Box::new(HyperlinkTemplate::new(
+            content,
+            url_property,
+        ))))
+    });
     map.insert(
         "raw_escape_sequence",
         |language, diagnostics, build_ctx, function| {
@@ -1582,6 +1746,12 @@ fn builtin_functions<'a, L: TemplateLanguage<'a> + ?Sized>() -> TemplateBuildFun
         let content = expect_plain_text_expression(language, diagnostics, build_ctx, content_node)?;
         Ok(L::wrap_string(content))
     });
+    map.insert("json", |language, diagnostics, build_ctx, function| {
+        let [value_node] = function.expect_exact_arguments()?;
+        let value_property = expect_json_expression(language, diagnostics, build_ctx, value_node)?;
+        let out_property = value_property.and_then(|value| Ok(serde_json::to_string(&value)?));
+        Ok(L::wrap_string(out_property))
+    });
     map.insert("if", |language, diagnostics, build_ctx, function| {
         let ([condition_node, true_node], [false_node]) = function.expect_arguments()?;
         let condition =
@@ -1659,6 +1829,31 @@ fn builtin_functions<'a, L: TemplateLanguage<'a> + ?Sized>() -> TemplateBuildFun
     map
 }
 
+/// Builds one or more `Integer` arguments of the given `function`.
+fn expect_integer_arguments<'a, L: TemplateLanguage<'a> + ?Sized>(
+    language: &L,
+    diagnostics: &mut TemplateDiagnostics,
+    build_ctx: &BuildContext<L::Property>,
+    function: &FunctionCallNode,
+) -> TemplateParseResult<Vec<Box<dyn TemplateProperty<Output = i64> + 'a>>> {
+    let ([first_node], rest_nodes) = function.expect_some_arguments()?;
+    iter::once(first_node)
+        .chain(rest_nodes)
+        .map(|node| expect_integer_expression(language, diagnostics, build_ctx, node))
+        .try_collect()
+}
+
+/// Compiles the string `pattern` parsed from the given `node`.
+fn string_pattern_to_regex(
+    pattern: &StringPattern,
+    node: &ExpressionNode,
+) -> TemplateParseResult<regex::Regex> {
+    pattern.to_regex().map_err(|err| {
+        TemplateParseError::expression("Failed to compile string pattern", node.span)
+            .with_source(err)
+    })
+}
+
 fn new_pad_template<'a, W>(
     content: Box<dyn Template + 'a>,
     fill_char: Option<Box<dyn Template + 'a>>,
@@ -1757,6 +1952,10 @@ pub fn build_expression<'a, L: TemplateLanguage<'a> + ?Sized>(
             let property = L::wrap_string(Literal(value.clone()));
             Ok(Expression::unlabeled(property))
         }
+        ExpressionKind::StringPattern { .. } => Err(TemplateParseError::expression(
+            "String patterns may not be used as expression items",
+            node.span,
+        )),
         ExpressionKind::Unary(op, arg_node) => {
             let property = build_unary_operation(language, diagnostics, build_ctx, *op, arg_node)?;
             Ok(Expression::unlabeled(property))
@@ -1937,6 +2136,22 @@ pub fn expect_template_expression<'a, L: TemplateLanguage<'a> + ?Sized>(
     )
 }
 
+pub fn expect_json_expression<'a, L: TemplateLanguage<'a> + ?Sized>(
+    language: &L,
+    diagnostics: &mut TemplateDiagnostics,
+    build_ctx: &BuildContext<L::Property>,
+    node: &ExpressionNode,
+) -> TemplateParseResult<Box<dyn TemplateProperty<Output = serde_json::Value> + 'a>> {
+    expect_expression_of_type(
+        language,
+        diagnostics,
+        build_ctx,
+        node,
+        "Serialize",
+        |expression| expression.try_into_json(),
+    )
+}
+
 fn expect_expression_of_type<'a, L: TemplateLanguage<'a> + ?Sized, T>(
     language: &L,
     diagnostics: &mut TemplateDiagnostics,
@@ -2045,7 +2260,8 @@ mod tests {
             let template = self.parse(template).unwrap();
             let mut output = Vec::new();
             let mut formatter =
-                ColorFormatter::new(&mut output, self.color_rules.clone().into(), false);
+                ColorFormatter::new(&mut output, self.color_rules.clone().into(), false)
+                    .with_hyperlinks(true);
             template.format(&(), &mut formatter).unwrap();
             drop(formatter);
             String::from_utf8(output).unwrap()
@@ -2115,7 +2331,7 @@ mod tests {
         1 | description ()
           |             ^---
           |
-          = expected <EOI>, `++`, `||`, `&&`, `==`, `!=`, `>=`, `>`, `<=`, or `<`
+          = expected <EOI>, `++`, `||`, `&&`, `==`, `!=`, `>=`, `>`, `<=`, `<`, `+`, `-`, `*`, `/`, or `%`
         ");
 
         insta::assert_snapshot!(env.parse_err(r#"foo"#), @r"
@@ -2441,6 +2657,50 @@ mod tests {
         insta::assert_snapshot!(
             env.render_ok(r#"-i64_min"#),
             @"<Error: Attempt to negate with overflow>");
+
+        insta::assert_snapshot!(env.render_ok(r#"1 + 2 * 3"#), @"7");
+        insta::assert_snapshot!(env.render_ok(r#"(1 + 2) * 3"#), @"9");
+        insta::assert_snapshot!(env.render_ok(r#"10 - 3 - 2"#), @"5");
+        insta::assert_snapshot!(env.render_ok(r#"1 -2"#), @"-1");
+        insta::assert_snapshot!(env.render_ok(r#"7 / 2 ++ " " ++ -7 / 2"#), @"3 -3");
+        insta::assert_snapshot!(env.render_ok(r#"7 % 3 ++ " " ++ -7 % 3"#), @"1 -1");
+        insta::assert_snapshot!(env.render_ok(r#"1++2"#), @"12");
+        insta::assert_snapshot!(env.render_ok(r#"some_i64 + 1"#), @"2");
+        insta::assert_snapshot!(env.render_ok(r#"none_i64 + 1"#), @"<Error: No Integer available>");
+        insta::assert_snapshot!(
+            env.render_ok(r#"i64_min - 1"#),
+            @"<Error: Attempt to subtract with overflow>");
+        insta::assert_snapshot!(
+            env.render_ok(r#"i64_min * -1"#),
+            @"<Error: Attempt to multiply with overflow>");
+        insta::assert_snapshot!(
+            env.render_ok(r#"i64_min / -1"#),
+            @"<Error: Attempt to divide with overflow>");
+        insta::assert_snapshot!(env.render_ok(r#"1 / 0"#), @"<Error: Attempt to divide by zero>");
+        insta::assert_snapshot!(env.render_ok(r#"1 % 0"#), @"<Error: Attempt to divide by zero>");
+
+        insta::assert_snapshot!(env.parse_err(r#"1 + "a""#), @r#"
+         --> 1:5
+          |
+        1 | 1 + "a"
+          |     ^-^
+          |
+          = Expected expression of type `Integer`, but actual type is `String`
+        "#);
+    }
+
+    #[test]
+    fn test_integer_method() {
+        let env = TestTemplateEnv::new();
+
+        insta::assert_snapshot!(env.render_ok(r#"0.format_number()"#), @"0");
+        insta::assert_snapshot!(env.render_ok(r#"123.format_number()"#), @"123");
+        insta::assert_snapshot!(env.render_ok(r#"1234.format_number()"#), @"1,234");
+        insta::assert_snapshot!(env.render_ok(r#"(-1234567).format_number()"#), @"-1,234,567");
+        insta::assert_snapshot!(env.render_ok(r#"123456.format_number("_")"#), @"123_456");
+        insta::assert_snapshot!(
+            env.render_ok(r#"(0 - 9223372036854775807 - 1).format_number(" ")"#),
+            @"-9 223 372 036 854 775 808");
     }
 
     #[test]
@@ -2713,6 +2973,79 @@ mod tests {
         insta::assert_snapshot!(env.render_ok(r#""he \n ll \n \" o".escape_json()"#), @r#""he \n ll \n \" o""#);
     }
 
+    #[test]
+    fn test_string_pattern_method() {
+        let mut env = TestTemplateEnv::new();
+        env.add_keyword("description", || {
+            L::wrap_string(Literal("Fix FOO-12 and BAR-345\n".to_owned()))
+        });
+
+        insta::assert_snapshot!(env.render_ok(r#""a.b.c".match(".")"#), @".");
+        insta::assert_snapshot!(env.render_ok(r#""abc".match("x")"#), @"");
+        insta::assert_snapshot!(
+            env.render_ok(r#"description.match(regex:'([A-Z]+)-(\d+)')"#),
+            @"FOO-12 FOO 12");
+        insta::assert_snapshot!(
+            env.render_ok(r#"description.match(regex:'(x)?FOO').map(|s| "[" ++ s ++ "]")"#),
+            @"[FOO] []");
+        insta::assert_snapshot!(
+            env.render_ok(r#"if(description.match(glob-i:"fix*"), "fix")"#),
+            @"fix");
+        insta::assert_snapshot!(env.render_ok(r#""a-b".match(exact:"a-b")"#), @"a-b");
+        insta::assert_snapshot!(env.render_ok(r#""a-b".match(exact:"a")"#), @"");
+
+        insta::assert_snapshot!(env.render_ok(r#""a.b.a".replace("a", "$0")"#), @"$0.b.$0");
+        insta::assert_snapshot!(env.render_ok(r#""a.b".replace(".", "_")"#), @"a_b");
+        insta::assert_snapshot!(
+            env.render_ok(r#"description.replace(regex:'([A-Z]+)-(\d+)', "<$1#$2>")"#),
+            @"Fix <FOO#12> and <BAR#345>");
+        insta::assert_snapshot!(
+            env.render_ok(r#"description.replace(regex:'(?<id>[A-Z]+-\d+)', "${id}!")"#),
+            @"Fix FOO-12! and BAR-345!");
+        insta::assert_snapshot!(
+            env.render_ok(r#"description.replace(substring-i:"fix", "Fixed")"#),
+            @"Fixed FOO-12 and BAR-345");
+
+        insta::assert_snapshot!(env.render_ok(r#""a,b,,c".split(",")"#), @"a b  c");
+        insta::assert_snapshot!(
+            env.render_ok(r#""a, b,c".split(regex:',\s*').join("|")"#),
+            @"a|b|c");
+        insta::assert_snapshot!(env.render_ok(r#""".split(",").len()"#), @"1");
+
+        insta::assert_snapshot!(env.parse_err(r#""a".match(foo:"a")"#), @r#"
+         --> 1:11
+          |
+        1 | "a".match(foo:"a")
+          |           ^-----^
+          |
+          = Failed to parse string pattern
+        "#);
+        insta::assert_snapshot!(env.parse_err(r#""a".match(regex:"(")"#), @r#"
+         --> 1:11
+          |
+        1 | "a".match(regex:"(")
+          |           ^-------^
+          |
+          = Failed to parse string pattern
+        "#);
+        insta::assert_snapshot!(env.parse_err(r#""a".match(description)"#), @r#"
+         --> 1:11
+          |
+        1 | "a".match(description)
+          |           ^---------^
+          |
+          = Expected string pattern
+        "#);
+        insta::assert_snapshot!(env.parse_err(r#"regex:"a""#), @r#"
+         --> 1:1
+          |
+        1 | regex:"a"
+          | ^-------^
+          |
+          = String patterns may not be used as expression items
+        "#);
+    }
+
     #[test]
     fn test_config_value_method() {
         let mut env = TestTemplateEnv::new();
@@ -3110,6 +3443,41 @@ mod tests {
             @"[38;5;9mfo[39mbaz");
     }
 
+    #[test]
+    fn test_min_max_abs_function() {
+        let mut env = TestTemplateEnv::new();
+        env.add_keyword("none_i64", || L::wrap_integer_opt(Literal(None)));
+        env.add_keyword("i64_min", || L::wrap_integer(Literal(i64::MIN)));
+
+        insta::assert_snapshot!(env.render_ok(r#"min(3)"#), @"3");
+        insta::assert_snapshot!(env.render_ok(r#"min(3, -1, 2)"#), @"-1");
+        insta::assert_snapshot!(env.render_ok(r#"max(3, -1, 2)"#), @"3");
+        insta::assert_snapshot!(
+            env.render_ok(r#"max(1, none_i64)"#),
+            @"<Error: No Integer available>");
+        insta::assert_snapshot!(env.render_ok(r#"abs(-3) ++ abs(3)"#), @"33");
+        insta::assert_snapshot!(
+            env.render_ok(r#"abs(i64_min)"#),
+            @"<Error: Attempt to negate with overflow>");
+
+        insta::assert_snapshot!(env.parse_err(r#"min()"#), @r"
+         --> 1:5
+          |
+        1 | min()
+          |     ^
+          |
+          = Function `min`: Expected at least 1 arguments
+        ");
+        insta::assert_snapshot!(env.parse_err(r#"max(1, "a")"#), @r#"
+         --> 1:8
+          |
+        1 | max(1, "a")
+          |        ^-^
+          |
+          = Expected expression of type `Integer`, but actual type is `String`
+        "#);
+    }
+
     #[test]
     fn test_label_function() {
         let mut env = TestTemplateEnv::new();
@@ -3133,6 +3501,34 @@ mod tests {
             @"[38;5;1mtext[39m");
     }
 
+    #[test]
+    fn test_hyperlink_function() {
+        let mut env = TestTemplateEnv::new();
+        env.add_color("error", crossterm::style::Color::DarkRed);
+
+        insta::assert_snapshot!(
+            env.render_ok(r#"hyperlink("http://example.com", "Example")"#),
+            @r"]8;;http://example.com\Example]8;;\");
+
+        // Evaluated URL, labeled content
+        insta::assert_snapshot!(
+            env.render_ok(r#"hyperlink("http://example.com/" ++ 42, label("error", "42"))"#),
+            @r"[38;5;1m]8;;http://example.com/42\42[39m]8;;\");
+
+        // Control characters in URL are stripped
+        insta::assert_snapshot!(
+            env.render_ok(r#"hyperlink("http://\e\\x", "x")"#),
+            @r"]8;;http://\x\x]8;;\");
+
+        // Link is closed at newline
+        insta::assert_snapshot!(
+            env.render_ok(r#"hyperlink("http://a", "x\ny")"#),
+            @r"
+        ]8;;http://a\x]8;;\
+        ]8;;http://a\y]8;;\
+        ");
+    }
+
     #[test]
     fn test_raw_escape_sequence_function_strip_labels() {
         let mut env = TestTemplateEnv::new();
@@ -3174,6 +3570,61 @@ mod tests {
             @r"]8;;http://example.com\Example]8;;\");
     }
 
+    #[test]
+    fn test_json_function() {
+        let mut env = TestTemplateEnv::new();
+        env.add_keyword("none_i64", || L::wrap_integer_opt(Literal(None)));
+        env.add_keyword("string_list", || {
+            L::wrap_string_list(Literal(vec!["foo".to_owned(), "bar".to_owned()]))
+        });
+        env.add_keyword("config_value_table", || {
+            let table: ConfigValue = "{ foo = [1, true, 'x'], bar = 0.5 }".parse().unwrap();
+            L::wrap_config_value(Literal(table))
+        });
+        env.add_keyword("signature", || {
+            L::wrap_signature(Literal(Signature {
+                name: "Test User".to_owned(),
+                email: "test.user@example.com".to_owned(),
+                timestamp: new_timestamp(1234567890123, -480),
+            }))
+        });
+        env.add_keyword("size_hint", || L::wrap_size_hint(Literal((5, None))));
+        env.add_keyword("timestamp_range", || {
+            L::wrap_timestamp_range(Literal(TimestampRange {
+                start: new_timestamp(0, 0),
+                end: new_timestamp(1000, 60),
+            }))
+        });
+
+        insta::assert_snapshot!(env.render_ok(r#"json('"quoted"')"#), @r#""\"quoted\"""#);
+        insta::assert_snapshot!(env.render_ok("json(42)"), @"42");
+        insta::assert_snapshot!(env.render_ok("json(-1 < 1)"), @"true");
+        insta::assert_snapshot!(env.render_ok("json(none_i64)"), @"null");
+        insta::assert_snapshot!(env.render_ok("json(string_list)"), @r#"["foo","bar"]"#);
+        insta::assert_snapshot!(
+            env.render_ok("json(config_value_table)"),
+            @r#"{"bar":0.5,"foo":[1,true,"x"]}"#);
+        insta::assert_snapshot!(
+            env.render_ok("json(signature)"),
+            @r#"{"email":"test.user@example.com","name":"Test User","timestamp":"2009-02-13T15:31:30.123-08:00"}"#);
+        insta::assert_snapshot!(
+            env.render_ok("json(signature.email())"),
+            @r#""test.user@example.com""#);
+        insta::assert_snapshot!(env.render_ok("json(size_hint)"), @r#"{"lower":5,"upper":null}"#);
+        insta::assert_snapshot!(
+            env.render_ok("json(timestamp_range)"),
+            @r#"{"end":"1970-01-01T01:00:01+01:00","start":"1970-01-01T00:00:00+00:00"}"#);
+
+        insta::assert_snapshot!(env.parse_err(r#"json(label("x", "y"))"#), @r#"
+         --> 1:6
+          |
+        1 | json(label("x", "y"))
+          |      ^-------------^
+          |
+          = Expected expression of type `Serialize`, but actual type is `Template`
+        "#);
+    }
+
     #[test]
     fn test_stringify_function() {
         let mut env = TestTemplateEnv::new();
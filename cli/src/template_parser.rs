@@ -32,6 +32,7 @@ use jj_lib::dsl_util::FoldableExpression;
 use jj_lib::dsl_util::FunctionCallParser;
 use jj_lib::dsl_util::InvalidArguments;
 use jj_lib::dsl_util::StringLiteralParser;
+use jj_lib::str_util::StringPattern;
 use once_cell::sync::Lazy;
 use pest::iterators::Pair;
 use pest::iterators::Pairs;
@@ -71,6 +72,9 @@ impl Rule {
             Rule::raw_string_literal => None,
             Rule::integer_literal => None,
             Rule::identifier => None,
+            Rule::string_pattern_kind => None,
+            Rule::pattern_kind_op => Some(":"),
+            Rule::string_pattern => None,
             Rule::concat_op => Some("++"),
             Rule::logical_or_op => Some("||"),
             Rule::logical_and_op => Some("&&"),
@@ -80,6 +84,11 @@ impl Rule {
             Rule::gt_op => Some(">"),
             Rule::le_op => Some("<="),
             Rule::lt_op => Some("<"),
+            Rule::add_op => Some("+"),
+            Rule::sub_op => Some("-"),
+            Rule::mul_op => Some("*"),
+            Rule::div_op => Some("/"),
+            Rule::rem_op => Some("%"),
             Rule::logical_not_op => Some("!"),
             Rule::negate_op => Some("-"),
             Rule::prefix_ops => None,
@@ -295,6 +304,11 @@ pub enum ExpressionKind<'i> {
     Boolean(bool),
     Integer(i64),
     String(String),
+    /// `kind:"value"` pattern, which can only appear as a function argument.
+    StringPattern {
+        kind: &'i str,
+        value: String,
+    },
     Unary(UnaryOp, Box<ExpressionNode<'i>>),
     Binary(BinaryOp, Box<ExpressionNode<'i>>, Box<ExpressionNode<'i>>),
     Concat(Vec<ExpressionNode<'i>>),
@@ -312,9 +326,10 @@ impl<'i> FoldableExpression<'i> for ExpressionKind<'i> {
     {
         match self {
             ExpressionKind::Identifier(name) => folder.fold_identifier(name, span),
-            ExpressionKind::Boolean(_) | ExpressionKind::Integer(_) | ExpressionKind::String(_) => {
-                Ok(self)
-            }
+            ExpressionKind::Boolean(_)
+            | ExpressionKind::Integer(_)
+            | ExpressionKind::String(_)
+            | ExpressionKind::StringPattern { .. } => Ok(self),
             ExpressionKind::Unary(op, arg) => {
                 let arg = Box::new(folder.fold_expression(*arg)?);
                 Ok(ExpressionKind::Unary(op, arg))
@@ -392,6 +407,16 @@ pub enum BinaryOp {
     Le,
     /// `<`
     Lt,
+    /// `+`
+    Add,
+    /// `-`
+    Sub,
+    /// `*`
+    Mul,
+    /// `/`
+    Div,
+    /// `%`
+    Rem,
 }
 
 pub type ExpressionNode<'i> = dsl_util::ExpressionNode<'i, ExpressionKind<'i>>;
@@ -476,6 +501,22 @@ fn parse_term_node(pair: Pair<Rule>) -> TemplateParseResult<ExpressionNode> {
             let text = content.as_str().to_owned();
             ExpressionNode::new(ExpressionKind::String(text), span)
         }
+        Rule::string_pattern => {
+            let (lhs, op, rhs) = expr.into_inner().collect_tuple().unwrap();
+            assert_eq!(lhs.as_rule(), Rule::string_pattern_kind);
+            assert_eq!(op.as_rule(), Rule::pattern_kind_op);
+            let kind = lhs.as_str();
+            let value = match rhs.as_rule() {
+                Rule::string_literal => STRING_LITERAL_PARSER.parse(rhs.into_inner()),
+                Rule::raw_string_literal => {
+                    let (content,) = rhs.into_inner().collect_tuple().unwrap();
+                    assert_eq!(content.as_rule(), Rule::raw_string_content);
+                    content.as_str().to_owned()
+                }
+                r => panic!("unexpected string pattern rule {r:?}"),
+            };
+            ExpressionNode::new(ExpressionKind::StringPattern { kind, value }, span)
+        }
         Rule::integer_literal => {
             let value = expr.as_str().parse().map_err(|err| {
                 TemplateParseError::expression("Invalid integer literal", span).with_source(err)
@@ -527,6 +568,10 @@ fn parse_expression_node(pair: Pair<Rule>) -> TemplateParseResult<ExpressionNode
                 | Op::infix(Rule::gt_op, Assoc::Left)
                 | Op::infix(Rule::le_op, Assoc::Left)
                 | Op::infix(Rule::lt_op, Assoc::Left))
+            .op(Op::infix(Rule::add_op, Assoc::Left) | Op::infix(Rule::sub_op, Assoc::Left))
+            .op(Op::infix(Rule::mul_op, Assoc::Left)
+                | Op::infix(Rule::div_op, Assoc::Left)
+                | Op::infix(Rule::rem_op, Assoc::Left))
             .op(Op::prefix(Rule::logical_not_op) | Op::prefix(Rule::negate_op))
     });
     PRATT
@@ -552,6 +597,11 @@ fn parse_expression_node(pair: Pair<Rule>) -> TemplateParseResult<ExpressionNode
                 Rule::gt_op => BinaryOp::Gt,
                 Rule::le_op => BinaryOp::Le,
                 Rule::lt_op => BinaryOp::Lt,
+                Rule::add_op => BinaryOp::Add,
+                Rule::sub_op => BinaryOp::Sub,
+                Rule::mul_op => BinaryOp::Mul,
+                Rule::div_op => BinaryOp::Div,
+                Rule::rem_op => BinaryOp::Rem,
                 r => panic!("unexpected infix operator rule {r:?}"),
             };
             let lhs = Box::new(lhs?);
@@ -655,6 +705,7 @@ pub fn expect_string_literal_with<'a, 'i, T>(
         ExpressionKind::Identifier(_)
         | ExpressionKind::Boolean(_)
         | ExpressionKind::Integer(_)
+        | ExpressionKind::StringPattern { .. }
         | ExpressionKind::Unary(..)
         | ExpressionKind::Binary(..)
         | ExpressionKind::Concat(_)
@@ -669,6 +720,35 @@ pub fn expect_string_literal_with<'a, 'i, T>(
     }
 }
 
+/// Parses the `node` as a string pattern. A plain string literal is parsed as
+/// a substring pattern.
+pub fn expect_string_pattern(node: &ExpressionNode) -> TemplateParseResult<StringPattern> {
+    let parse_error = |err| {
+        TemplateParseError::expression("Failed to parse string pattern", node.span).with_source(err)
+    };
+    match &node.kind {
+        ExpressionKind::String(value) => Ok(StringPattern::substring(value)),
+        ExpressionKind::StringPattern { kind, value } => {
+            StringPattern::from_str_kind(value, kind).map_err(parse_error)
+        }
+        ExpressionKind::Identifier(_)
+        | ExpressionKind::Boolean(_)
+        | ExpressionKind::Integer(_)
+        | ExpressionKind::Unary(..)
+        | ExpressionKind::Binary(..)
+        | ExpressionKind::Concat(_)
+        | ExpressionKind::FunctionCall(_)
+        | ExpressionKind::MethodCall(_)
+        | ExpressionKind::Lambda(_) => Err(TemplateParseError::expression(
+            "Expected string pattern",
+            node.span,
+        )),
+        ExpressionKind::AliasExpanded(id, subst) => {
+            expect_string_pattern(subst).map_err(|e| e.within_alias_expansion(*id, node.span))
+        }
+    }
+}
+
 /// Applies the given function if the `node` is a lambda.
 pub fn expect_lambda_with<'a, 'i, T>(
     node: &'a ExpressionNode<'i>,
@@ -680,6 +760,7 @@ pub fn expect_lambda_with<'a, 'i, T>(
         | ExpressionKind::Boolean(_)
         | ExpressionKind::Integer(_)
         | ExpressionKind::String(_)
+        | ExpressionKind::StringPattern { .. }
         | ExpressionKind::Unary(..)
         | ExpressionKind::Binary(..)
         | ExpressionKind::Concat(_)
@@ -806,7 +887,8 @@ mod tests {
             ExpressionKind::Identifier(_)
             | ExpressionKind::Boolean(_)
             | ExpressionKind::Integer(_)
-            | ExpressionKind::String(_) => node.kind,
+            | ExpressionKind::String(_)
+            | ExpressionKind::StringPattern { .. } => node.kind,
             ExpressionKind::Unary(op, arg) => {
                 let arg = Box::new(normalize_tree(*arg));
                 ExpressionKind::Unary(op, arg)
@@ -901,6 +983,15 @@ mod tests {
             parse_normalized("x == y || y != z && !z"),
             parse_normalized("(x == y) || ((y != z) && (!z))"),
         );
+        assert_eq!(
+            parse_normalized("x + y * z - -x / y % z"),
+            parse_normalized("(x + (y * z)) - (((-x) / y) % z)"),
+        );
+        assert_eq!(
+            parse_normalized("x - y - z >= x * y + z"),
+            parse_normalized("((x - y) - z) >= ((x * y) + z)"),
+        );
+        assert_eq!(parse_normalized("x -1"), parse_normalized("x - 1"));
 
         // Logical operator bounds more tightly than concatenation. This might
         // not be so intuitive, but should be harmless.
@@ -920,6 +1011,15 @@ mod tests {
             parse_normalized(r"x != y ++ z"),
             parse_normalized(r"(x != y) ++ z"),
         );
+        assert_eq!(
+            parse_normalized(r"x + y ++ z"),
+            parse_normalized(r"(x + y) ++ z"),
+        );
+        assert_eq!(
+            parse_normalized(r"x ++ y + z"),
+            parse_normalized(r"x ++ (y + z)"),
+        );
+        assert_eq!(parse_normalized(r"x++y"), parse_normalized(r"x ++ y"));
 
         // Expression span
         assert_eq!(parse_template(" ! x ").unwrap().span.as_str(), "! x");
@@ -1042,6 +1142,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_string_pattern() {
+        assert_eq!(
+            parse_into_kind(r#"regex:"a.*""#),
+            Ok(ExpressionKind::StringPattern {
+                kind: "regex",
+                value: "a.*".to_owned(),
+            }),
+        );
+        assert_eq!(
+            parse_into_kind(r#"glob-i:'\n*'"#),
+            Ok(ExpressionKind::StringPattern {
+                kind: "glob-i",
+                value: "\\n*".to_owned(),
+            }),
+        );
+        // Whitespace isn't allowed around the kind separator
+        assert_eq!(
+            parse_into_kind(r#"regex: "a""#),
+            Err(TemplateParseErrorKind::SyntaxError),
+        );
+        // Pattern kind is not an identifier, so "-" is parsed as an operator
+        assert_eq!(
+            parse_normalized("glob - i"),
+            parse_normalized("(glob) - (i)"),
+        );
+    }
+
     #[test]
     fn test_string_literal() {
         // "\<char>" escapes
@@ -22,6 +22,7 @@ use std::rc::Rc;
 
 use bstr::BStr;
 use bstr::BString;
+use itertools::Itertools as _;
 use jj_lib::backend::Signature;
 use jj_lib::backend::Timestamp;
 use jj_lib::config::ConfigValue;
@@ -201,6 +202,107 @@ impl Template for i64 {
     }
 }
 
+/// Value that can be serialized by the `json()` template function.
+pub trait IntoJsonValue {
+    fn into_json_value(self) -> Result<serde_json::Value, TemplatePropertyError>;
+}
+
+impl<T: IntoJsonValue> IntoJsonValue for Option<T> {
+    fn into_json_value(self) -> Result<serde_json::Value, TemplatePropertyError> {
+        self.map_or(Ok(serde_json::Value::Null), T::into_json_value)
+    }
+}
+
+impl<T: IntoJsonValue> IntoJsonValue for Vec<T> {
+    fn into_json_value(self) -> Result<serde_json::Value, TemplatePropertyError> {
+        self.into_iter().map(T::into_json_value).collect()
+    }
+}
+
+impl IntoJsonValue for String {
+    fn into_json_value(self) -> Result<serde_json::Value, TemplatePropertyError> {
+        Ok(self.into())
+    }
+}
+
+impl IntoJsonValue for bool {
+    fn into_json_value(self) -> Result<serde_json::Value, TemplatePropertyError> {
+        Ok(self.into())
+    }
+}
+
+impl IntoJsonValue for i64 {
+    fn into_json_value(self) -> Result<serde_json::Value, TemplatePropertyError> {
+        Ok(self.into())
+    }
+}
+
+impl IntoJsonValue for usize {
+    fn into_json_value(self) -> Result<serde_json::Value, TemplatePropertyError> {
+        Ok(self.into())
+    }
+}
+
+impl IntoJsonValue for ConfigValue {
+    fn into_json_value(self) -> Result<serde_json::Value, TemplatePropertyError> {
+        let value = match self {
+            ConfigValue::String(v) => v.into_value().into(),
+            ConfigValue::Integer(v) => v.into_value().into(),
+            // NaN and infinity can't be represented in JSON, and become null.
+            ConfigValue::Float(v) => v.into_value().into(),
+            ConfigValue::Boolean(v) => v.into_value().into(),
+            ConfigValue::Datetime(v) => v.into_value().to_string().into(),
+            ConfigValue::Array(array) => array
+                .into_iter()
+                .map(IntoJsonValue::into_json_value)
+                .try_collect()?,
+            ConfigValue::InlineTable(table) => table
+                .into_iter()
+                .map(|(k, v)| Ok::<_, TemplatePropertyError>((k.to_string(), v.into_json_value()?)))
+                .try_collect()?,
+        };
+        Ok(value)
+    }
+}
+
+impl IntoJsonValue for Signature {
+    fn into_json_value(self) -> Result<serde_json::Value, TemplatePropertyError> {
+        Ok(serde_json::json!({
+            "name": self.name,
+            "email": self.email,
+            "timestamp": self.timestamp.into_json_value()?,
+        }))
+    }
+}
+
+impl IntoJsonValue for Email {
+    fn into_json_value(self) -> Result<serde_json::Value, TemplatePropertyError> {
+        Ok(self.0.into())
+    }
+}
+
+impl IntoJsonValue for SizeHint {
+    fn into_json_value(self) -> Result<serde_json::Value, TemplatePropertyError> {
+        let (lower, upper) = self;
+        Ok(serde_json::json!({ "lower": lower, "upper": upper }))
+    }
+}
+
+impl IntoJsonValue for Timestamp {
+    fn into_json_value(self) -> Result<serde_json::Value, TemplatePropertyError> {
+        Ok(time_util::format_rfc3339_timestamp(&self)?.into())
+    }
+}
+
+impl IntoJsonValue for TimestampRange {
+    fn into_json_value(self) -> Result<serde_json::Value, TemplatePropertyError> {
+        Ok(serde_json::json!({
+            "start": self.start.into_json_value()?,
+            "end": self.end.into_json_value()?,
+        }))
+    }
+}
+
 pub struct LabelTemplate<T, L> {
     content: T,
     labels: L,
@@ -229,6 +331,38 @@ where
     }
 }
 
+pub struct HyperlinkTemplate<T, U> {
+    content: T,
+    url: U,
+}
+
+impl<T, U> HyperlinkTemplate<T, U> {
+    pub fn new(content: T, url: U) -> Self
+    where
+        T: Template,
+        U: TemplateProperty<Output = String>,
+    {
+        HyperlinkTemplate { content, url }
+    }
+}
+
+impl<T, U> Template for HyperlinkTemplate<T, U>
+where
+    T: Template,
+    U: TemplateProperty<Output = String>,
+{
+    fn format(&self, formatter: &mut TemplateFormatter) -> io::Result<()> {
+        match self.url.extract() {
+            Ok(url) => {
+                formatter.push_hyperlink(&url)?;
+                self.content.format(formatter)?;
+                formatter.pop_hyperlink()
+            }
+            Err(err) => formatter.handle_error(err),
+        }
+    }
+}
+
 pub struct RawEscapeSequenceTemplate<T>(pub T);
 
 impl<T: Template> Template for RawEscapeSequenceTemplate<T> {
@@ -372,6 +506,14 @@ impl<P: TemplateProperty> TemplateProperty for Option<P> {
     }
 }
 
+impl<P: TemplateProperty> TemplateProperty for Vec<P> {
+    type Output = Vec<P::Output>;
+
+    fn extract(&self) -> Result<Self::Output, TemplatePropertyError> {
+        self.iter().map(|property| property.extract()).collect()
+    }
+}
+
 // Implement TemplateProperty for tuples
 macro_rules! tuple_impls {
     ($( ( $($n:tt $T:ident),+ ) )+) => {
@@ -435,6 +577,15 @@ pub trait TemplatePropertyExt: TemplateProperty {
     {
         Box::new(FormattablePropertyTemplate::new(self))
     }
+
+    /// Converts this property into a property that evaluates to JSON value.
+    fn into_json<'a>(self) -> Box<dyn TemplateProperty<Output = serde_json::Value> + 'a>
+    where
+        Self: Sized + 'a,
+        Self::Output: IntoJsonValue,
+    {
+        Box::new(self.and_then(IntoJsonValue::into_json_value))
+    }
 }
 
 impl<P: TemplateProperty + ?Sized> TemplatePropertyExt for P {}
@@ -771,6 +922,14 @@ impl<'a> TemplateFormatter<'a> {
         self.formatter.pop_label()
     }
 
+    pub fn push_hyperlink(&mut self, url: &str) -> io::Result<()> {
+        self.formatter.push_hyperlink(url)
+    }
+
+    pub fn pop_hyperlink(&mut self) -> io::Result<()> {
+        self.formatter.pop_hyperlink()
+    }
+
     pub fn write_fmt(&mut self, args: fmt::Arguments<'_>) -> io::Result<()> {
         self.formatter.write_fmt(args)
     }
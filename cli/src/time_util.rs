@@ -2,6 +2,7 @@ use chrono::format::StrftimeItems;
 use chrono::DateTime;
 use chrono::FixedOffset;
 use chrono::LocalResult;
+use chrono::SecondsFormat;
 use chrono::TimeZone as _;
 use chrono::Utc;
 use jj_lib::backend::Timestamp;
@@ -76,6 +77,12 @@ pub fn format_absolute_timestamp(timestamp: &Timestamp) -> Result<String, Timest
     format_absolute_timestamp_with(timestamp, &DEFAULT_FORMAT)
 }
 
+/// Formats the timestamp in RFC 3339 format, preserving the time zone offset.
+pub fn format_rfc3339_timestamp(timestamp: &Timestamp) -> Result<String, TimestampOutOfRange> {
+    let datetime = datetime_from_timestamp(timestamp)?;
+    Ok(datetime.to_rfc3339_opts(SecondsFormat::AutoSi, false))
+}
+
 pub fn format_absolute_timestamp_with(
     timestamp: &Timestamp,
     format: &FormattingItems,
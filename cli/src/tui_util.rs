@@ -0,0 +1,103 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Utilities for full-screen terminal interfaces.
+
+use std::io;
+
+use crossterm::event;
+use crossterm::event::Event;
+use crossterm::event::KeyEvent;
+use crossterm::event::KeyEventKind;
+use ratatui::DefaultTerminal;
+use ratatui::Frame;
+
+/// State of a full-screen interface which is driven by key presses.
+pub trait TuiState {
+    /// What the caller should do after a key press.
+    type Outcome;
+
+    fn handle_key(&mut self, key: KeyEvent) -> Self::Outcome;
+
+    fn render(&self, frame: &mut Frame);
+}
+
+/// Switches the terminal to a full-screen interface while `f` runs. The
+/// terminal is restored even if `f` fails.
+pub fn with_terminal<T, E: From<io::Error>>(
+    f: impl FnOnce(&mut DefaultTerminal) -> Result<T, E>,
+) -> Result<T, E> {
+    let mut terminal = ratatui::try_init().inspect_err(|_| {
+        ratatui::try_restore().ok();
+    })?;
+    let result = f(&mut terminal);
+    ratatui::try_restore()?;
+    result
+}
+
+/// Draws the `state` and passes key presses to it until `finish()` maps the
+/// outcome of a key press to a result.
+pub fn run_event_loop<S: TuiState, T>(
+    terminal: &mut DefaultTerminal,
+    state: &mut S,
+    mut finish: impl FnMut(S::Outcome) -> Option<T>,
+) -> io::Result<T> {
+    loop {
+        terminal.draw(|frame| state.render(frame))?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if let Some(result) = finish(state.handle_key(key)) {
+                return Ok(result);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod testing {
+    use crossterm::event::KeyCode;
+    use crossterm::event::KeyEvent;
+    use crossterm::event::KeyModifiers;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    use super::TuiState;
+
+    pub fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    pub fn type_keys(state: &mut impl TuiState, keys: &str) {
+        for c in keys.chars() {
+            state.handle_key(key(KeyCode::Char(c)));
+        }
+    }
+
+    /// Renders the `state` to a string with trailing spaces trimmed.
+    pub fn render(state: &impl TuiState, width: u16, height: u16) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|frame| state.render(frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.height)
+            .map(|y| {
+                let line: String = (0..buffer.area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect();
+                line.trim_end().to_owned() + "\n"
+            })
+            .collect()
+    }
+}
@@ -36,6 +36,10 @@ use jj_lib::config::StackedConfig;
 use os_pipe::PipeWriter;
 use tracing::instrument;
 
+use crate::builtin_pager;
+use crate::builtin_pager::BuiltinPagerConfig;
+use crate::builtin_pager::PagerWriter;
+use crate::builtin_pager::SectionMarkingFormatter;
 use crate::command_error::CommandError;
 use crate::config::CommandNameAndArgs;
 use crate::formatter::Formatter;
@@ -56,9 +60,9 @@ enum UiOutput {
         child_stdin: ChildStdin,
     },
     BuiltinPaged {
-        out_wr: PipeWriter,
+        out_wr: PagerWriter,
         err_wr: PipeWriter,
-        pager_thread: JoinHandle<streampager::Result<()>>,
+        pager_thread: JoinHandle<io::Result<()>>,
     },
     Null,
 }
@@ -79,31 +83,20 @@ impl UiOutput {
         Ok(UiOutput::Paged { child, child_stdin })
     }
 
-    fn new_builtin_paged(config: &StreampagerConfig) -> streampager::Result<UiOutput> {
-        let streampager_config = streampager::config::Config {
-            wrapping_mode: config.wrapping.into(),
-            interface_mode: config.streampager_interface_mode(),
-            // We could make scroll-past-eof configurable, but I'm guessing people
-            // will not miss it. If we do make it configurable, we should mention
-            // that it's a bad idea to turn this on if `interface=quit-if-one-page`,
-            // as it can leave a lot of empty lines on the screen after exiting.
-            scroll_past_eof: false,
-            ..Default::default()
-        };
-        let mut pager = streampager::Pager::new_using_stdio_with_config(streampager_config)?;
-
+    fn new_builtin_paged(config: &BuiltinPagerConfig) -> io::Result<UiOutput> {
         // Use native pipe, which can be attached to child process. The stdout
         // stream could be an in-process channel, but the cost of extra syscalls
         // wouldn't matter.
         let (out_rd, out_wr) = os_pipe::pipe()?;
         let (err_rd, err_wr) = os_pipe::pipe()?;
-        pager.add_stream(out_rd, "")?;
-        pager.add_error_stream(err_rd, "stderr")?;
-
+        let (out_wr, sections) = PagerWriter::new(out_wr);
+        let config = *config;
         Ok(UiOutput::BuiltinPaged {
             out_wr,
             err_wr,
-            pager_thread: thread::spawn(|| pager.run()),
+            pager_thread: thread::spawn(move || {
+                builtin_pager::run_pager(config, out_rd, sections, err_rd)
+            }),
         })
     }
 
@@ -157,7 +150,7 @@ impl UiOutput {
 pub enum UiStdout<'a> {
     Terminal(StdoutLock<'static>),
     Paged(&'a ChildStdin),
-    Builtin(&'a PipeWriter),
+    Builtin(&'a PagerWriter),
     Null(io::Sink),
 }
 
@@ -236,6 +229,37 @@ impl fmt::Display for ColorChoice {
     }
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Deserialize)]
+#[serde(rename_all(deserialize = "kebab-case"))]
+pub enum HyperlinkChoice {
+    Always,
+    Never,
+    Auto,
+}
+
+/// Guesses whether the terminal understands OSC 8 hyperlinks. Terminals that
+/// don't may print the escape sequences verbatim, so this errs on the side of
+/// disabling them.
+fn terminal_supports_hyperlinks() -> bool {
+    let var = |name| env::var(name).unwrap_or_default();
+    if !var("CI").is_empty() || var("TERM") == "dumb" {
+        return false;
+    }
+    if var("VTE_VERSION")
+        .parse::<u32>()
+        .is_ok_and(|version| version >= 5000)
+    {
+        return true;
+    }
+    let term_program = var("TERM_PROGRAM");
+    let term = var("TERM");
+    ["WT_SESSION", "KONSOLE_VERSION", "DOMTERM"]
+        .iter()
+        .any(|name| env::var_os(name).is_some())
+        || ["ghostty", "Hyper", "iTerm.app", "vscode", "WezTerm"].contains(&term_program.as_str())
+        || ["alacritty", "foot", "xterm-kitty", "xterm-ghostty"].contains(&term.as_str())
+}
+
 fn prepare_formatter_factory(
     config: &StackedConfig,
     stdout: &Stdout,
@@ -247,8 +271,13 @@ fn prepare_formatter_factory(
         ColorChoice::Debug => (true, true),
         ColorChoice::Auto => (terminal, false),
     };
+    let hyperlinks = match config.get("ui.hyperlinks")? {
+        HyperlinkChoice::Always => true,
+        HyperlinkChoice::Never => false,
+        HyperlinkChoice::Auto => terminal && terminal_supports_hyperlinks(),
+    };
     if color {
-        FormatterFactory::color(config, debug)
+        FormatterFactory::color(config, debug, hyperlinks)
     } else if terminal {
         // Sanitize ANSI escape codes if we're printing to a terminal. Doesn't
         // affect ANSI escape codes that originate from the formatter itself.
@@ -265,59 +294,9 @@ pub enum PaginationChoice {
     Auto,
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Deserialize)]
-#[serde(rename_all(deserialize = "kebab-case"))]
-pub enum StreampagerAlternateScreenMode {
-    QuitIfOnePage,
-    FullScreenClearOutput,
-    QuitQuicklyOrClearOutput,
-}
-
-#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Deserialize)]
-#[serde(rename_all(deserialize = "kebab-case"))]
-enum StreampagerWrappingMode {
-    None,
-    Word,
-    Anywhere,
-}
-
-impl From<StreampagerWrappingMode> for streampager::config::WrappingMode {
-    fn from(val: StreampagerWrappingMode) -> Self {
-        use streampager::config::WrappingMode;
-        match val {
-            StreampagerWrappingMode::None => WrappingMode::Unwrapped,
-            StreampagerWrappingMode::Word => WrappingMode::WordBoundary,
-            StreampagerWrappingMode::Anywhere => WrappingMode::GraphemeBoundary,
-        }
-    }
-}
-
-#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Deserialize)]
-#[serde(rename_all(deserialize = "kebab-case"))]
-struct StreampagerConfig {
-    interface: StreampagerAlternateScreenMode,
-    wrapping: StreampagerWrappingMode,
-    // TODO: Add an `quit-quickly-delay-seconds` floating point option or a
-    // `quit-quickly-delay` option that takes a 's' or 'ms' suffix. Note that as
-    // of this writing, floating point numbers do not work with `--config`
-}
-
-impl StreampagerConfig {
-    fn streampager_interface_mode(&self) -> streampager::config::InterfaceMode {
-        use streampager::config::InterfaceMode;
-        use StreampagerAlternateScreenMode::*;
-        match self.interface {
-            // InterfaceMode::Direct not implemented
-            FullScreenClearOutput => InterfaceMode::FullScreen,
-            QuitIfOnePage => InterfaceMode::Hybrid,
-            QuitQuicklyOrClearOutput => InterfaceMode::Delayed(std::time::Duration::from_secs(2)),
-        }
-    }
-}
-
 enum PagerConfig {
     Disabled,
-    Builtin(StreampagerConfig),
+    Builtin(BuiltinPagerConfig),
     External(CommandNameAndArgs),
 }
 
@@ -328,7 +307,7 @@ impl PagerConfig {
         };
         match config.get("ui.pager")? {
             CommandNameAndArgs::String(name) if name == BUILTIN_PAGER_NAME => {
-                Ok(PagerConfig::Builtin(config.get("ui.streampager")?))
+                Ok(PagerConfig::Builtin(config.get("ui.builtin-pager")?))
             }
             pager_command => Ok(PagerConfig::External(pager_command)),
         }
@@ -376,8 +355,8 @@ impl Ui {
             PagerConfig::Disabled => {
                 return;
             }
-            PagerConfig::Builtin(streampager_config) => {
-                UiOutput::new_builtin_paged(streampager_config)
+            PagerConfig::Builtin(builtin_pager_config) => {
+                UiOutput::new_builtin_paged(builtin_pager_config)
                     .inspect_err(|err| {
                         writeln!(
                             self.warning_default(),
@@ -417,7 +396,13 @@ impl Ui {
         &self,
         output: W,
     ) -> Box<dyn Formatter + 'output> {
-        self.formatter_factory.new_formatter(output)
+        let formatter = self.formatter_factory.new_formatter(output);
+        if matches!(self.output, UiOutput::BuiltinPaged { .. }) {
+            // Let the pager find commits and files in the output
+            Box::new(SectionMarkingFormatter::new(formatter))
+        } else {
+            formatter
+        }
     }
 
     /// Locked stdout stream.
@@ -42,6 +42,7 @@ mod test_git_colocated;
 mod test_git_fetch;
 mod test_git_import_export;
 mod test_git_init;
+mod test_git_op_log;
 mod test_git_private_commits;
 mod test_git_push;
 mod test_git_remotes;
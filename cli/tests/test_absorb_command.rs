@@ -83,6 +83,8 @@ fn test_absorb_simple() {
     let output = work_dir.run_jj(["absorb"]);
     insta::assert_snapshot!(output, @r"
     ------- stderr -------
+    Hunks left in the source revision:
+      file1 @@ -3,0 +4,1 @@
     Nothing changed.
     [EOF]
     ");
@@ -557,6 +559,8 @@ fn test_absorb_from_into() {
     let output = work_dir.run_jj(["absorb", "--into=@-"]);
     insta::assert_snapshot!(output, @r"
     ------- stderr -------
+    Hunks left in the source revision:
+      file1 @@ -3,0 +5,1 @@
     Absorbed changes into 1 revisions:
       kkmpptxz 91df4543 2
     Rebased 1 descendant commits.
@@ -709,6 +713,39 @@ fn test_absorb_paths() {
     ");
 }
 
+#[test]
+fn test_absorb_dry_run() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    work_dir.run_jj(["describe", "-m1"]).success();
+    work_dir.write_file("file1", "1a\n1b\n");
+
+    work_dir.run_jj(["new", "-m2"]).success();
+    work_dir.write_file("file1", "1a\n1b\n2a\n2b\n");
+    work_dir.write_file("file2", "2a\n");
+
+    work_dir.run_jj(["new"]).success();
+    work_dir.write_file("file1", "1A\n1b\nX\n2a\n2b\n");
+    work_dir.write_file("file2", "2A\n");
+
+    let output = work_dir.run_jj(["absorb", "--dry-run"]);
+    insta::assert_snapshot!(output, @r"
+    file1 @@ -1,1 +1,1 @@ -> qpvuntsm 3619e4e5 1
+    file1 @@ -2,0 +3,1 @@ -> (not absorbed)
+    file2 @@ -1,1 +1,1 @@ -> kkmpptxz 58a5ed87 2
+    [EOF]
+    ");
+
+    // Nothing should be changed
+    let output = work_dir.run_jj(["op", "log", "--limit=1", "-Tdescription"]);
+    insta::assert_snapshot!(output, @r"
+    @  snapshot working copy
+    [EOF]
+    ");
+}
+
 #[test]
 fn test_absorb_immutable() {
     let test_env = TestEnvironment::default();
@@ -732,6 +769,8 @@ fn test_absorb_immutable() {
     let output = work_dir.run_jj(["absorb"]);
     insta::assert_snapshot!(output, @r"
     ------- stderr -------
+    Hunks left in the source revision:
+      file1 @@ -1,1 +1,1 @@
     Absorbed changes into 1 revisions:
       kkmpptxz d80e3c2a 2
     Rebased 1 descendant commits.
@@ -297,6 +297,192 @@ fn test_alias_invalid_definition() {
     ");
 }
 
+#[test]
+fn test_alias_with_arguments() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    work_dir
+        .run_jj(["bookmark", "create", "-r@", "my-bookmark"])
+        .success();
+
+    test_env.add_config(
+        r#"
+    [aliases.show-rev]
+    command = ["log", "--no-graph", "-r", "$1", "-T", "$template ++ '\n'"]
+    args = ["rev", "template"]
+    [aliases.show-revs]
+    command = ["log", "$@", "--no-graph", "-T", "'$$ ' ++ commit_id.short() ++ '\n'"]
+    "#,
+    );
+    let output = work_dir.run_jj(["show-rev", "@", "bookmarks"]);
+    insta::assert_snapshot!(output, @r"
+    my-bookmark
+    [EOF]
+    ");
+
+    // Extra arguments are appended
+    let output = work_dir.run_jj(["show-rev", "@", "bookmarks", "--color=always"]);
+    insta::assert_snapshot!(output, @r"
+    [38;5;5mmy-bookmark[39m
+    [EOF]
+    ");
+
+    let output = work_dir.run_jj(["show-revs", "-r@", "-rroot()"]);
+    insta::assert_snapshot!(output, @r"
+    $ 230dd059e1b0
+    $ 000000000000
+    [EOF]
+    ");
+
+    let output = work_dir.run_jj(["show-rev", "@"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Missing argument `template` for alias `show-rev`
+    [EOF]
+    [exit status: 1]
+    ");
+}
+
+#[test]
+fn test_alias_with_arguments_invalid_definition() {
+    let test_env = TestEnvironment::default();
+
+    test_env.add_config(
+        r#"
+    [aliases.undefined-param]
+    command = ["log", "-r", "$rev"]
+    [aliases.bad-param-name]
+    command = ["log"]
+    args = ["foo bar"]
+    [aliases.string-command]
+    command = "log"
+    [aliases.unknown-field]
+    command = ["log"]
+    argument = ["rev"]
+    "#,
+    );
+    let output = test_env.run_jj_in(".", ["undefined-param"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Config error: Invalid alias `undefined-param`: Undefined parameter `rev` in `$rev`
+    For help, see https://jj-vcs.github.io/jj/latest/config/ or use `jj help -k config`.
+    [EOF]
+    [exit status: 1]
+    ");
+    let output = test_env.run_jj_in(".", ["bad-param-name"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Config error: Invalid alias `bad-param-name`: Invalid parameter name `foo bar`
+    For help, see https://jj-vcs.github.io/jj/latest/config/ or use `jj help -k config`.
+    [EOF]
+    [exit status: 1]
+    ");
+    let output = test_env.run_jj_in(".", ["string-command"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Config error: Invalid alias `string-command`: The command must be a list of arguments unless `shell = true` is set
+    For help, see https://jj-vcs.github.io/jj/latest/config/ or use `jj help -k config`.
+    [EOF]
+    [exit status: 1]
+    ");
+    let output = test_env.run_jj_in(".", ["unknown-field"]);
+    insta::assert_snapshot!(output.normalize_backslash(), @r"
+    ------- stderr -------
+    Config error: Invalid type or value for aliases.unknown-field
+    Caused by: unknown field `argument`, expected one of `command`, `args`, `shell`
+
+    Hint: Check the config file: $TEST_ENV/config/config0002.toml
+    For help, see https://jj-vcs.github.io/jj/latest/config/ or use `jj help -k config`.
+    [EOF]
+    [exit status: 1]
+    ");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_alias_shell() {
+    let test_env = TestEnvironment::default();
+
+    test_env.add_config(
+        r#"
+    [aliases.greet]
+    shell = true
+    command = 'echo "hello $name"; printf "<%s>\n" "$@"'
+    args = ["name"]
+    "#,
+    );
+    // Arguments are passed to the shell without being evaluated
+    let output = test_env.run_jj_in(".", ["greet", "world", "$HOME", "a b"]);
+    insta::assert_snapshot!(output, @r"
+    hello world
+    <world>
+    <$HOME>
+    <a b>
+    [EOF]
+    ");
+
+    let output = test_env.run_jj_in(".", ["greet"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Missing argument `name` for alias `greet`
+    [EOF]
+    [exit status: 1]
+    ");
+
+    // The shell can be configured
+    test_env.add_config(r#"ui.shell = ["env", "GREETING=hi", "sh", "-c"]"#);
+    test_env.add_config(
+        r#"
+    [aliases.greet-env]
+    shell = true
+    command = 'echo "$GREETING $1"'
+    "#,
+    );
+    let output = test_env.run_jj_in(".", ["greet-env", "world"]);
+    insta::assert_snapshot!(output, @r"
+    hi world
+    [EOF]
+    ");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_alias_shell_repository() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    work_dir.run_jj(["describe", "-m", "in repo"]).success();
+
+    test_env.add_config(
+        r#"
+    [aliases.show-desc]
+    shell = true
+    command = '"$JJ" -R "$JJ_REPO" log -r @ --no-graph -T description'
+    "#,
+    );
+    // The nested jj runs against the repository given by -R
+    let output = test_env.run_jj_in(".", ["-R", "repo", "show-desc"]);
+    insta::assert_snapshot!(output, @r"
+    in repo
+    [EOF]
+    ");
+
+    // JJ_REPO is unset outside a repository
+    test_env.add_config(
+        r#"
+    [aliases.repo]
+    shell = true
+    command = 'echo "<${JJ_REPO-unset}>"'
+    "#,
+    );
+    let output = test_env.run_jj_in(".", ["repo"]);
+    insta::assert_snapshot!(output, @r"
+    <unset>
+    [EOF]
+    ");
+}
+
 #[test]
 fn test_alias_in_repo_config() {
     let test_env = TestEnvironment::default();
@@ -1422,6 +1422,55 @@ fn test_log_diff_predefined_formats() {
     * total_added=0 total_removed=0
     [EOF]
     ");
+
+    // custom template with hunks()
+    let template = indoc! {r#"
+        concat(
+          "=== " ++ commit_id.short() ++ " ===\n",
+          diff.files().map(|e| concat(
+            separate(" ",
+              e.path(),
+              "[" ++ e.status() ++ "]",
+              "copy_source=" ++ e.copy_source(),
+              "mode=" ++ e.source().mode() ++ ".." ++ e.target().mode(),
+            ) ++ "\n",
+            e.hunks(0).map(|h| concat(
+              "@@ -" ++ h.old_start() ++ "," ++ h.old_count(),
+              " +" ++ h.new_start() ++ "," ++ h.new_count() ++ " @@",
+              " removed=" ++ h.removed_lines().join("|"),
+              " added=" ++ h.added_lines().join("|"),
+              "\n",
+              h.lines().join("\n") ++ "\n",
+            )).join(""),
+          )).join(""),
+        )
+    "#};
+    let output = work_dir.run_jj(["log", "--no-graph", "-T", template]);
+    insta::assert_snapshot!(output, @r"
+    === fbad2dd53d06 ===
+    file1 [modified] copy_source= mode=100644..100644
+    @@ -2,0 +3,1 @@ removed= added=c
+    +c
+    file2 [modified] copy_source= mode=100644..100644
+    @@ -1,1 +1,2 @@ removed=a added=b|c
+    -a
+    +b
+    +c
+    rename-target [renamed] copy_source=rename-source mode=100644..100644
+    === 3c9b3178609b ===
+    file1 [added] copy_source= mode=..100644
+    @@ -0,0 +1,2 @@ removed= added=a|b
+    +a
+    +b
+    file2 [added] copy_source= mode=..100644
+    @@ -0,0 +1,1 @@ removed= added=a
+    +a
+    rename-source [added] copy_source= mode=..100644
+    @@ -0,0 +1,1 @@ removed= added=rename
+    +rename
+    === 000000000000 ===
+    [EOF]
+    ");
 }
 
 #[test]
@@ -1609,3 +1658,53 @@ fn test_signature_templates() {
     [EOF]
     ");
 }
+
+#[test]
+fn test_log_json() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    work_dir.write_file("file", "a\nb\n");
+    work_dir.run_jj(["describe", "-m", "first"]).success();
+    work_dir
+        .run_jj(["bookmark", "create", "-r@", "main"])
+        .success();
+    work_dir.run_jj(["new", "-m", "second"]).success();
+
+    let template = r#"json(self) ++ "\n""#;
+    let output = work_dir.run_jj(["log", "--no-graph", "-r::@-", "-T", template]);
+    insta::assert_snapshot!(output, @r#"
+    {"author":{"email":"test.user@example.com","name":"Test User","timestamp":"2001-02-03T04:05:08+07:00"},"change_id":"qpvuntsmwlqtpsluzzsnyyzlmlwvmlnu","commit_id":"45dc9a3b968fdffde9f81d39d7d3d23537020f6a","committer":{"email":"test.user@example.com","name":"Test User","timestamp":"2001-02-03T04:05:08+07:00"},"description":"first\n","parents":["0000000000000000000000000000000000000000"]}
+    {"author":{"email":"","name":"","timestamp":"1970-01-01T00:00:00+00:00"},"change_id":"zzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzz","commit_id":"0000000000000000000000000000000000000000","committer":{"email":"","name":"","timestamp":"1970-01-01T00:00:00+00:00"},"description":"","parents":[]}
+    [EOF]
+    "#);
+
+    let template =
+        r#"json(bookmarks) ++ " " ++ json(diff.stat(80)) ++ " " ++ json(diff.files()) ++ "\n""#;
+    let output = work_dir.run_jj(["log", "--no-graph", "-r@-", "-T", template]);
+    insta::assert_snapshot!(output, @r#"
+    [{"name":"main","target":["45dc9a3b968fdffde9f81d39d7d3d23537020f6a"]}] {"files":[{"added":2,"path":"file","removed":0}],"total_added":2,"total_removed":0} [{"source":{"conflict":false,"executable":false,"file_type":"","path":"file"},"status":"added","target":{"conflict":false,"executable":false,"file_type":"file","path":"file"}}]
+    [EOF]
+    "#);
+
+    let template = r#"json(change_id.shortest()) ++ " " ++ json(parents.len())"#;
+    let output = work_dir.run_jj(["log", "--no-graph", "-r@", "-T", template]);
+    insta::assert_snapshot!(output, @r#"{"prefix":"zs","rest":""} 1[EOF]"#);
+
+    // ListTemplate can't be serialized
+    let template = r#"json(parents.map(|c| c.commit_id()))"#;
+    let output = work_dir.run_jj(["log", "--no-graph", "-r@", "-T", template]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Failed to parse template: Expected expression of type `Serialize`, but actual type is `ListTemplate`
+    Caused by:  --> 1:6
+      |
+    1 | json(parents.map(|c| c.commit_id()))
+      |      ^----------------------------^
+      |
+      = Expected expression of type `Serialize`, but actual type is `ListTemplate`
+    [EOF]
+    [exit status: 1]
+    ");
+}
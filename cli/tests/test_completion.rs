@@ -245,6 +245,50 @@ fn test_aliases_are_resolved() {
     ");
 }
 
+#[test]
+fn test_aliases_with_arguments_are_resolved() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    work_dir
+        .run_jj(["bookmark", "create", "-r@", "aaa"])
+        .success();
+    test_env.add_config(
+        r#"
+    [aliases.rn]
+    command = ["bookmark", "rename", "$old", "$new"]
+    args = ["old", "new"]
+    "#,
+    );
+
+    let mut test_env = test_env;
+    test_env.add_env_var("COMPLETE", "fish");
+    let test_env = test_env;
+    let work_dir = test_env.work_dir("repo");
+
+    // The argument is completed where it is substituted
+    let output = work_dir.run_jj(["--", "jj", "rn", "a"]);
+    insta::assert_snapshot!(output, @r"
+    aaa	(no description set)
+    [EOF]
+    ");
+
+    // Index-based completion is adjusted to the expanded arguments
+    let output = work_dir.run_jj_with(|cmd| {
+        cmd.args(["--", "jj", "rn", "a", "b"])
+            .env("COMPLETE", "zsh")
+            .env("_CLAP_COMPLETE_INDEX", "2")
+    });
+    insta::assert_snapshot!(output, @"aaa:(no description set)[EOF]");
+    let output = work_dir.run_jj_with(|cmd| {
+        cmd.args(["--", "jj", "rn", "a", "b"])
+            .env("COMPLETE", "bash")
+            .env("_CLAP_COMPLETE_INDEX", "2")
+    });
+    insta::assert_snapshot!(output, @"aaa[EOF]");
+}
+
 #[test]
 fn test_completions_are_generated() {
     let mut test_env = TestEnvironment::default();
@@ -652,6 +696,10 @@ fn test_config() {
 
     let output = test_env.run_jj_in(dir, ["--", "jj", "config", "get", "c"]);
     insta::assert_snapshot!(output, @r"
+    copy-tracking.detect-copies	Whether modified files are considered as copy sources. If false, only renames are detected.
+    copy-tracking.max-files	Skip inexact matching if the number of added files times the number of candidate source files exceeds the square of this
+    copy-tracking.method	How copies and renames are detected. `content` compares file contents regardless of the backend, `backend` uses the copy information provided by the backend.
+    copy-tracking.similarity-threshold	Minimum similarity, in percent, for a file to be considered a copy or rename of another
     core.fsmonitor	Whether to use an external filesystem monitor, useful for large repos
     core.watchman.register-snapshot-trigger	Whether to use triggers to monitor for changes in the background.
     [EOF]
@@ -660,6 +708,11 @@ fn test_config() {
     let output = test_env.run_jj_in(dir, ["--", "jj", "config", "list", "c"]);
     insta::assert_snapshot!(output, @r"
     colors	Mapping from jj formatter labels to colors
+    copy-tracking	Settings for detecting copied and renamed files
+    copy-tracking.detect-copies	Whether modified files are considered as copy sources. If false, only renames are detected.
+    copy-tracking.max-files	Skip inexact matching if the number of added files times the number of candidate source files exceeds the square of this
+    copy-tracking.method	How copies and renames are detected. `content` compares file contents regardless of the backend, `backend` uses the copy information provided by the backend.
+    copy-tracking.similarity-threshold	Minimum similarity, in percent, for a file to be considered a copy or rename of another
     core
     core.fsmonitor	Whether to use an external filesystem monitor, useful for large repos
     core.watchman
@@ -669,6 +722,10 @@ fn test_config() {
 
     let output = test_env.run_jj_in(dir, ["--", "jj", "log", "--config", "c"]);
     insta::assert_snapshot!(output, @r"
+    copy-tracking.detect-copies=	Whether modified files are considered as copy sources. If false, only renames are detected.
+    copy-tracking.max-files=	Skip inexact matching if the number of added files times the number of candidate source files exceeds the square of this
+    copy-tracking.method=	How copies and renames are detected. `content` compares file contents regardless of the backend, `backend` uses the copy information provided by the backend.
+    copy-tracking.similarity-threshold=	Minimum similarity, in percent, for a file to be considered a copy or rename of another
     core.fsmonitor=	Whether to use an external filesystem monitor, useful for large repos
     core.watchman.register-snapshot-trigger=	Whether to use triggers to monitor for changes in the background.
     [EOF]
@@ -682,6 +739,7 @@ fn test_config() {
     ui.conflict-marker-style=diff
     ui.conflict-marker-style=snapshot
     ui.conflict-marker-style=git
+    ui.conflict-marker-style=zdiff3
     [EOF]
     ");
     let output = test_env.run_jj_in(
@@ -101,6 +101,10 @@ fn test_debug_index() {
     Max generation number: 1
     Number of heads: 1
     Number of changes: 2
+    Number of changed-path filters: 0
+    Number of visible heads: 1
+    Number of visible commits: 2
+    Number of divergent changes: 0
     Stats per level:
       Level 0:
         Number of commits: 2
@@ -123,6 +127,10 @@ fn test_debug_reindex() {
     Max generation number: 3
     Number of heads: 1
     Number of changes: 4
+    Number of changed-path filters: 0
+    Number of visible heads: 1
+    Number of visible commits: 4
+    Number of divergent changes: 0
     Stats per level:
       Level 0:
         Number of commits: 3
@@ -145,6 +153,10 @@ fn test_debug_reindex() {
     Max generation number: 3
     Number of heads: 1
     Number of changes: 4
+    Number of changed-path filters: 0
+    Number of visible heads: 1
+    Number of visible commits: 4
+    Number of divergent changes: 0
     Stats per level:
       Level 0:
         Number of commits: 4
@@ -153,6 +165,73 @@ fn test_debug_reindex() {
     ");
 }
 
+#[test]
+fn test_debug_reindex_changed_paths() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    work_dir.write_file("file", "");
+    work_dir.run_jj(["commit", "-m", "add file"]).success();
+    test_env.add_config("index.changed-paths = true");
+    let output = work_dir.run_jj(["debug", "reindex"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Finished indexing 5 commits.
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["debug", "index"]);
+    assert_snapshot!(filter_index_stats(output), @r"
+    Number of commits: 5
+    Number of merges: 0
+    Max generation number: 2
+    Number of heads: 3
+    Number of changes: 3
+    Number of changed-path filters: 5
+    Number of visible heads: 1
+    Number of visible commits: 3
+    Number of divergent changes: 0
+    Stats per level:
+      Level 0:
+        Number of commits: 5
+        Name: [hash]
+    [EOF]
+    ");
+    // Filters are recorded for new commits, too
+    work_dir.run_jj(["new"]).success();
+    let output = work_dir.run_jj(["debug", "index"]);
+    assert_snapshot!(filter_index_stats(output), @r"
+    Number of commits: 6
+    Number of merges: 0
+    Max generation number: 3
+    Number of heads: 3
+    Number of changes: 4
+    Number of changed-path filters: 6
+    Number of visible heads: 1
+    Number of visible commits: 4
+    Number of divergent changes: 0
+    Stats per level:
+      Level 0:
+        Number of commits: 5
+        Name: [hash]
+      Level 1:
+        Number of commits: 1
+        Name: [hash]
+    [EOF]
+    ");
+    let output = work_dir.run_jj([
+        "log",
+        "--no-graph",
+        "-r",
+        "files(file)",
+        "-T",
+        "description",
+    ]);
+    insta::assert_snapshot!(output, @r"
+    add file
+    [EOF]
+    ");
+}
+
 #[test]
 fn test_debug_tree() {
     let test_env = TestEnvironment::default();
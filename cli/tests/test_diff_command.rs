@@ -16,6 +16,7 @@ use indoc::indoc;
 use itertools::Itertools as _;
 
 use crate::common::create_commit;
+use crate::common::create_commit_with_files;
 use crate::common::fake_diff_editor_path;
 use crate::common::to_toml_value;
 use crate::common::CommandOutput;
@@ -1690,6 +1691,375 @@ fn test_diff_ignore_whitespace() {
     ");
 }
 
+#[test]
+fn test_diff_algorithm() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    work_dir.write_file("file1", "a\nb\nc\nd\ne\n");
+    work_dir.run_jj(["new"]).success();
+    work_dir.write_file("file1", "a\nc\nb\nd\ne\n");
+
+    let output = work_dir.run_jj(["diff", "--git"]);
+    insta::assert_snapshot!(output, @r"
+    diff --git a/file1 b/file1
+    index 9405325339..eda2f7ecc5 100644
+    --- a/file1
+    +++ b/file1
+    @@ -1,5 +1,5 @@
+     a
+    +c
+     b
+    -c
+     d
+     e
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["diff", "--git", "--diff-algorithm=histogram"]);
+    insta::assert_snapshot!(output, @r"
+    diff --git a/file1 b/file1
+    index 9405325339..eda2f7ecc5 100644
+    --- a/file1
+    +++ b/file1
+    @@ -1,5 +1,5 @@
+     a
+    -b
+     c
+    +b
+     d
+     e
+    [EOF]
+    ");
+
+    // The setting applies to all diff formats, and can be overridden
+    test_env.add_config(r#"diff.algorithm = "patience""#);
+    let output = work_dir.run_jj(["diff"]);
+    insta::assert_snapshot!(output, @r"
+    Modified regular file file1:
+       1    1: a
+       2     : b
+       3    2: c
+            3: b
+       4    4: d
+       5    5: e
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["diff", "--git", "--diff-algorithm=default"]);
+    insta::assert_snapshot!(output, @r"
+    diff --git a/file1 b/file1
+    index 9405325339..eda2f7ecc5 100644
+    --- a/file1
+    +++ b/file1
+    @@ -1,5 +1,5 @@
+     a
+    +c
+     b
+    -c
+     d
+     e
+    [EOF]
+    ");
+
+    let output = work_dir.run_jj(["diff", "--diff-algorithm=myers"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    error: invalid value 'myers' for '--diff-algorithm <ALGORITHM>'
+      [possible values: default, histogram, patience]
+
+    For more information, try '--help'.
+    [EOF]
+    [exit status: 2]
+    ");
+}
+
+#[test]
+fn test_diff_algorithm_conflict() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    create_commit_with_files(&work_dir, "base", &[], &[("file", "a\nb\nc\nd\ne\n")]);
+    create_commit_with_files(&work_dir, "left", &["base"], &[("file", "a\nc\nb\nd\ne\n")]);
+    create_commit_with_files(
+        &work_dir,
+        "right",
+        &["base"],
+        &[("file", "a\nb\nX\nd\ne\n")],
+    );
+    create_commit(&work_dir, "merge", &["left", "right"]);
+
+    // Conflicts are materialized with the default algorithm, so the diff
+    // algorithm only affects how the materialized contents are compared
+    let output = work_dir.run_jj(["diff", "--git", "--from=root()", "--to=merge"]);
+    insta::assert_snapshot!(output, @r"
+    diff --git a/file b/file
+    new file mode 100644
+    index 0000000000..0000000000
+    --- /dev/null
+    +++ b/file
+    @@ -0,0 +1,11 @@
+    +a
+    +c
+    +b
+    +<<<<<<< Conflict 1 of 1
+    +%%%%%%% Changes from base to side #1
+    +-c
+    ++++++++ Contents of side #2
+    +X
+    +>>>>>>> Conflict 1 of 1 ends
+    +d
+    +e
+    diff --git a/merge b/merge
+    new file mode 100644
+    index 0000000000..a00af07ad2
+    --- /dev/null
+    +++ b/merge
+    @@ -0,0 +1,1 @@
+    +merge
+    [EOF]
+    ");
+    let histogram_output = work_dir.run_jj([
+        "diff",
+        "--git",
+        "--from=root()",
+        "--to=merge",
+        "--diff-algorithm=histogram",
+    ]);
+    assert_eq!(histogram_output.to_string(), output.to_string());
+    let histogram_output = work_dir.run_jj([
+        "diff",
+        "--from=root()",
+        "--to=merge",
+        "--diff-algorithm=histogram",
+    ]);
+    let default_output = work_dir.run_jj(["diff", "--from=root()", "--to=merge"]);
+    assert_eq!(histogram_output.to_string(), default_output.to_string());
+}
+
+#[cfg(not(feature = "tree-sitter"))]
+#[test]
+fn test_diff_structural_unsupported() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    work_dir.write_file("a.rs", "fn a() {}\n");
+    work_dir.write_file("b.rs", "fn b() {}\n");
+    work_dir.run_jj(["new"]).success();
+    work_dir.write_file("a.rs", "fn a() { 1 }\n");
+    work_dir.write_file("b.rs", "fn b() { 2 }\n");
+
+    // The warning is printed once
+    let output = work_dir.run_jj(["log", "-r@-::", "--no-graph", "-T=''", "--structural"]);
+    insta::assert_snapshot!(output, @r"
+    Modified regular file a.rs:
+       1    1: fn a() { 1 }
+    Modified regular file b.rs:
+       1    1: fn b() { 2 }
+    Added regular file a.rs:
+            1: fn a() {}
+    Added regular file b.rs:
+            1: fn b() {}
+    [EOF]
+    ------- stderr -------
+    Warning: Structural diff is not supported by this build of jj. Showing color-words diff instead.
+    Hint: Build jj with the `tree-sitter` feature to enable structural diff.
+    [EOF]
+    ");
+
+    let output = work_dir.run_jj(["log", "--no-graph", "-r@", "-T", "diff.structural()"]);
+    insta::assert_snapshot!(output, @r"
+    Modified regular file a.rs:
+       1    1: fn a() { 1 }
+    Modified regular file b.rs:
+       1    1: fn b() { 2 }
+    [EOF]
+    ------- stderr -------
+    Warning: In template expression
+     --> 1:6
+      |
+    1 | diff.structural()
+      |      ^--------^
+      |
+      = structural() is not supported by this build of jj; color-words diff is shown instead
+    [EOF]
+    ");
+}
+
+#[cfg(feature = "tree-sitter")]
+#[test]
+fn test_diff_structural() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    work_dir.write_file(
+        "lib.rs",
+        indoc! {"
+            fn a() -> u32 {
+                1
+            }
+
+            fn b() {}
+
+            fn d(x: u32) -> u32 {
+                x + 1
+            }
+
+            struct Removed;
+        "},
+    );
+    work_dir.write_file("file.txt", "foo\n");
+    work_dir.run_jj(["new"]).success();
+    work_dir.write_file(
+        "lib.rs",
+        indoc! {"
+            fn b() {}
+
+            fn a() -> u32 {
+                1
+            }
+
+            fn d(x: u32) -> u32 {
+                x + 2
+            }
+
+            fn c() {}
+        "},
+    );
+    work_dir.write_file("file.txt", "bar\n");
+
+    let output = work_dir.run_jj(["diff", "--structural"]);
+    insta::assert_snapshot!(output, @r"
+    Modified regular file file.txt:
+       1    1: foobar
+    Modified regular file lib.rs:
+        Moved function item `a` from line 1 to line 3
+        Changed function item `d`:
+       7    7: fn d(x: u32) -> u32 {
+       8    8:     x + 12
+       9    9: }
+        Removed struct item `Removed`:
+      11     : struct Removed;
+        Added function item `c`:
+           11: fn c() {}
+    [EOF]
+    ");
+
+    let output = work_dir.run_jj(["log", "--no-graph", "-r@", "-T", "diff.structural()"]);
+    insta::assert_snapshot!(output, @r"
+    Modified regular file file.txt:
+       1    1: foobar
+    Modified regular file lib.rs:
+        Moved function item `a` from line 1 to line 3
+        Changed function item `d`:
+       7    7: fn d(x: u32) -> u32 {
+       8    8:     x + 12
+       9    9: }
+        Removed struct item `Removed`:
+      11     : struct Removed;
+        Added function item `c`:
+           11: fn c() {}
+    [EOF]
+    ");
+
+    // Unparsable files fall back to color-words diff
+    work_dir.write_file("lib.rs", "fn d(\n");
+    let output = work_dir.run_jj(["diff", "--structural", "lib.rs"]);
+    insta::assert_snapshot!(output, @r"
+    Modified regular file lib.rs:
+       1    1: fn a() -> u32 {
+       2     :     1
+       3     : }
+       4     : 
+       5     : fn b() {}
+       6     : 
+       7    1: fn d(x: u32) -> u32 {
+       8     :     x + 1
+       9     : }
+      10     : 
+      11    1: struct Removed;
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_diff_side_by_side() {
+    let mut test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    test_env.add_env_var("COLUMNS", "50");
+    let work_dir = test_env.work_dir("repo");
+
+    work_dir.write_file(
+        "file1",
+        "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nthe quick brown fox\n",
+    );
+    work_dir.write_file("file2", "removed\n");
+    work_dir.write_file("binary", b"\0binary");
+    work_dir.run_jj(["new"]).success();
+    work_dir.write_file(
+        "file1",
+        "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nthe quick maroon fox jumped over the lazy dog\n",
+    );
+    work_dir.remove_file("file2");
+    work_dir.write_file("file3", "added\n");
+    work_dir.write_file("binary", b"\0binary2");
+
+    let output = work_dir.run_jj(["diff", "--side-by-side"]);
+    insta::assert_snapshot!(output, @r"
+    Modified regular file binary:
+        (binary)
+    Modified regular file file1:
+     1 a                    |  1 a
+     2 b                    |  2 B
+     3 c                    |  3 c
+     4 d                    |  4 d
+     5 e                    |  5 e
+        ...
+     8 h                    |  8 h
+     9 i                    |  9 i
+    10 j                    | 10 j
+    11 the quick brown fox  | 11 the quick maroon fox
+                            |     jumped over the laz
+                            |    y dog
+    Removed regular file file2:
+    1 removed               |
+    Added regular file file3:
+                            | 1 added
+    [EOF]
+    ");
+
+    let output = work_dir.run_jj(["diff", "--side-by-side", "--color=always", "file1"]);
+    insta::assert_snapshot!(output, @r"
+    [38;5;3mModified regular file file1:[39m
+     1 a                    |  1 a
+    [38;5;1m 2[39m [4m[38;5;1mb[24m[39m                    | [38;5;2m 2[39m [4m[38;5;2mB[24m[39m
+     3 c                    |  3 c
+     4 d                    |  4 d
+     5 e                    |  5 e
+        ...
+     8 h                    |  8 h
+     9 i                    |  9 i
+    10 j                    | 10 j
+    [38;5;1m11[39m [38;5;1mthe quick [4mbrown[24m fox[39m  | [38;5;2m11[39m [38;5;2mthe quick [4mmaroon[24m fox[39m
+                            |    [4m[38;5;2m jumped over the laz[24m[39m
+                            |    [4m[38;5;2my dog[24m[39m
+    [EOF]
+    ");
+
+    let output = work_dir.run_jj(["diff", "--side-by-side", "--context=0", "file1"]);
+    insta::assert_snapshot!(output, @r"
+    Modified regular file file1:
+     2 b                    |  2 B
+        ...
+    11 the quick brown fox  | 11 the quick maroon fox
+                            |     jumped over the laz
+                            |    y dog
+    [EOF]
+    ");
+}
+
 #[test]
 fn test_diff_skipped_context() {
     let test_env = TestEnvironment::default();
@@ -2839,3 +3209,36 @@ fn test_diff_revisions() {
     [EOF]
     ");
 }
+
+#[test]
+fn test_diff_merge_does_not_run_merge_drivers() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+    create_commit_with_files(&work_dir, "base", &[], &[("file", "a\n")]);
+    create_commit_with_files(&work_dir, "left", &["base"], &[("file", "left\n")]);
+    create_commit_with_files(&work_dir, "right", &["base"], &[("file", "right\n")]);
+    create_commit(&work_dir, "merge", &["left", "right"]);
+
+    // The driver leaves a marker file behind when it runs
+    let marker_path = test_env.env_root().join("driver-ran");
+    test_env.add_config(format!(
+        r#"
+        [merge-drivers.marker]
+        command = ["touch", {marker}]
+        patterns = ["file"]
+        "#,
+        marker = to_toml_value(marker_path.to_str().unwrap())
+    ));
+
+    // Neither the parent tree of the merge nor the snapshot of the conflicted
+    // working copy should run the driver
+    work_dir.write_file("other", "other\n");
+    work_dir.run_jj(["diff", "-r", "merge", "--summary"]).success();
+    work_dir.run_jj(["status"]).success();
+    assert!(!marker_path.exists());
+
+    // Creating a merge commit runs it
+    work_dir.run_jj(["new", "left", "right"]).success();
+    assert!(marker_path.exists());
+}
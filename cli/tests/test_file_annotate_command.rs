@@ -244,3 +244,115 @@ fn test_annotate_with_template() {
     [EOF]
     ");
 }
+
+#[test]
+fn test_annotate_renamed_file() {
+    let test_env = TestEnvironment::default();
+    test_env.add_config("copy-tracking.method = 'content'");
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    work_dir.write_file("old.txt", "line1\nline2\n");
+    work_dir.run_jj(["commit", "-m=initial"]).success();
+
+    work_dir.remove_file("old.txt");
+    work_dir.create_dir("dir");
+    work_dir.write_file("dir/new.txt", "line1\nline2\nline3\n");
+    work_dir.run_jj(["describe", "-m=rename"]).success();
+
+    let template =
+        r#"commit.description().first_line() ++ " " ++ original_path ++ ": " ++ content"#;
+    let output = work_dir.run_jj(["file", "annotate", "dir/new.txt", "-T", template]);
+    insta::assert_snapshot!(output.normalize_backslash(), @r"
+    initial old.txt: line1
+    initial old.txt: line2
+    rename dir/new.txt: line3
+    [EOF]
+    ");
+
+    // Renames aren't followed if the files aren't similar enough
+    let output = work_dir.run_jj([
+        "file",
+        "annotate",
+        "dir/new.txt",
+        "-T",
+        template,
+        "--config=copy-tracking.similarity-threshold=100",
+    ]);
+    insta::assert_snapshot!(output.normalize_backslash(), @r"
+    rename dir/new.txt: line1
+    rename dir/new.txt: line2
+    rename dir/new.txt: line3
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_annotate_ignore_revs() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let work_dir = test_env.work_dir("repo");
+
+    work_dir.write_file("file.txt", "line1\nline2\n");
+    work_dir.run_jj(["commit", "-m=initial"]).success();
+    work_dir.write_file("file.txt", "  line1\nLINE2\n");
+    work_dir.run_jj(["commit", "-m=reformat"]).success();
+    work_dir.write_file("file.txt", "  line1\nLINE2\nline3\n");
+    work_dir.run_jj(["describe", "-m=add"]).success();
+
+    let template = r#"commit.description().first_line() ++ ": " ++ content"#;
+    let output = work_dir.run_jj(["file", "annotate", "file.txt", "-T", template]);
+    insta::assert_snapshot!(output, @r"
+    reformat:   line1
+    reformat: LINE2
+    add: line3
+    [EOF]
+    ");
+
+    let output = work_dir.run_jj(["file", "annotate", "file.txt", "-T", template, "-w"]);
+    insta::assert_snapshot!(output, @r"
+    initial:   line1
+    reformat: LINE2
+    add: line3
+    [EOF]
+    ");
+
+    let output = work_dir.run_jj([
+        "file",
+        "annotate",
+        "file.txt",
+        "-T",
+        template,
+        "--ignore-rev=description(reformat)",
+    ]);
+    insta::assert_snapshot!(output, @r"
+    initial:   line1
+    initial: LINE2
+    add: line3
+    [EOF]
+    ");
+
+    work_dir.write_file(".ignore-revs", "# Reformatting\n@-\n\n");
+    let output = work_dir.run_jj([
+        "file",
+        "annotate",
+        "file.txt",
+        "-T",
+        template,
+        "--ignore-revs-file=.ignore-revs",
+    ]);
+    insta::assert_snapshot!(output, @r"
+    initial:   line1
+    initial: LINE2
+    add: line3
+    [EOF]
+    ");
+
+    let output = work_dir.run_jj(["file", "annotate", "file.txt", "--ignore-revs-file=missing"]);
+    insta::assert_snapshot!(output.normalize_backslash().strip_stderr_last_line(), @r"
+    ------- stderr -------
+    Error: Failed to read $TEST_ENV/repo/missing
+    [EOF]
+    [exit status: 1]
+    ");
+}
@@ -0,0 +1,175 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use test_case::test_case;
+use testutils::git;
+
+use crate::common::TestEnvironment;
+
+fn set_up(test_env: &TestEnvironment) {
+    git::init_bare(test_env.env_root().join("origin"));
+    for name in ["laptop", "server"] {
+        test_env.run_jj_in(".", ["git", "init", name]).success();
+        test_env
+            .work_dir(name)
+            .run_jj(["git", "remote", "add", "origin", "../origin"])
+            .success();
+    }
+}
+
+#[cfg_attr(feature = "git2", test_case(false; "use git2 for remote calls"))]
+#[test_case(true; "spawn a git subprocess for remote calls")]
+fn test_git_op_log_push_fetch(subprocess: bool) {
+    let test_env = TestEnvironment::default();
+    set_up(&test_env);
+    if !subprocess {
+        test_env.add_config("git.subprocess = false");
+    }
+    let laptop_dir = test_env.work_dir("laptop");
+    let server_dir = test_env.work_dir("server");
+
+    // The remote has no operation log yet
+    let output = server_dir.run_jj(["git", "op-log", "fetch"]);
+    insta::allow_duplicates! {
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Remote origin has no operation log
+    [EOF]
+    ");
+    }
+
+    laptop_dir.write_file("file", "a\n");
+    laptop_dir.run_jj(["describe", "-m", "first"]).success();
+    laptop_dir.write_file("file", "b\n");
+    laptop_dir.run_jj(["new", "-m", "second"]).success();
+    laptop_dir
+        .run_jj(["bookmark", "create", "-r@-", "feature"])
+        .success();
+    let output = laptop_dir.run_jj(["git", "op-log", "push"]);
+    insta::allow_duplicates! {
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Pushed 6 operations and 5 commits to remote origin
+    [EOF]
+    ");
+    }
+    let output = laptop_dir.run_jj(["git", "op-log", "push"]);
+    insta::allow_duplicates! {
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Nothing changed.
+    [EOF]
+    ");
+    }
+
+    // The change ids, the evolution, and the bookmarks are transferred
+    server_dir.write_file("file", "server\n");
+    server_dir.run_jj(["describe", "-m", "server"]).success();
+    let output = server_dir.run_jj(["git", "op-log", "fetch"]);
+    insta::allow_duplicates! {
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Fetched 6 operations and 5 new commits
+    [EOF]
+    ");
+    }
+    let output = server_dir.run_jj(["log"]);
+    insta::allow_duplicates! {
+    insta::assert_snapshot!(output, @r"
+    @  kkmpptxz test.user@example.com 2001-02-03 08:05:17 87a69d21
+    │  server
+    │ ○  yqosqzyt test.user@example.com 2001-02-03 08:05:13 b4eabbe3
+    │ │  (empty) second
+    │ ○  qpvuntsm test.user@example.com 2001-02-03 08:05:13 feature cf1e9e53
+    ├─╯  first
+    ◆  zzzzzzzz root() 00000000
+    [EOF]
+    ");
+    }
+    let output = server_dir.run_jj(["evolog", "-r", "feature"]);
+    insta::allow_duplicates! {
+    insta::assert_snapshot!(output, @r"
+    ○  qpvuntsm test.user@example.com 2001-02-03 08:05:13 feature cf1e9e53
+    │  first
+    ○  qpvuntsm hidden test.user@example.com 2001-02-03 08:05:12 c0fcad6a
+    │  first
+    ○  qpvuntsm hidden test.user@example.com 2001-02-03 08:05:12 ebe2b156
+    │  (no description set)
+    ○  qpvuntsm hidden test.user@example.com 2001-02-03 08:05:07 230dd059
+       (empty) (no description set)
+    [EOF]
+    ");
+    }
+    let output = server_dir.run_jj(["git", "op-log", "fetch"]);
+    insta::allow_duplicates! {
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Fetched 0 operations and 0 new commits
+    Nothing changed.
+    [EOF]
+    ");
+    }
+
+    // Concurrent updates have to be fetched before pushing
+    let output = server_dir.run_jj(["git", "op-log", "push"]);
+    insta::allow_duplicates! {
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Pushed 4 operations and 3 commits to remote origin
+    [EOF]
+    ");
+    }
+    laptop_dir
+        .run_jj(["describe", "-r", "feature", "-m", "laptop"])
+        .success();
+    let output = laptop_dir.run_jj(["git", "op-log", "push"]);
+    insta::allow_duplicates! {
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: The operation log on remote origin has changed since it was last fetched
+    Hint: Run `jj git op-log fetch` to merge the remote operations first.
+    [EOF]
+    [exit status: 1]
+    ");
+    }
+    let output = laptop_dir.run_jj(["git", "op-log", "fetch"]);
+    insta::allow_duplicates! {
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Fetched 4 operations and 3 new commits
+    [EOF]
+    ");
+    }
+    let output = laptop_dir.run_jj(["git", "op-log", "push"]);
+    insta::allow_duplicates! {
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Pushed 2 operations and 2 commits to remote origin
+    [EOF]
+    ");
+    }
+    let output = laptop_dir.run_jj(["log"]);
+    insta::allow_duplicates! {
+    insta::assert_snapshot!(output, @r"
+    @  yqosqzyt test.user@example.com 2001-02-03 08:05:23 8935ea06
+    │  (empty) second
+    ○  qpvuntsm test.user@example.com 2001-02-03 08:05:23 feature b6294843
+    │  laptop
+    │ ○  kkmpptxz test.user@example.com 2001-02-03 08:05:17 87a69d21
+    ├─╯  server
+    ◆  zzzzzzzz root() 00000000
+    [EOF]
+    ");
+    }
+}
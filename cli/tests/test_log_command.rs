@@ -1880,3 +1880,30 @@ fn test_log_full_description_template() {
     [EOF]
     ");
 }
+
+#[test]
+fn test_log_interactive_without_terminal() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let repo_path = test_env.env_root().join("repo");
+
+    let output = test_env.run_jj_in(&repo_path, ["log", "--interactive"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: The interactive log requires a terminal
+    [EOF]
+    [exit status: 1]
+    ");
+
+    let output = test_env.run_jj_in(&repo_path, ["log", "--interactive", "--no-graph"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    error: the argument '--interactive' cannot be used with '--no-graph'
+
+    Usage: jj log --interactive [FILESETS]...
+
+    For more information, try '--help'.
+    [EOF]
+    [exit status: 2]
+    ");
+}
@@ -2579,6 +2579,84 @@ fn test_op_show_patch() {
     ");
 }
 
+#[test]
+fn test_op_export_import() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo1"]).success();
+    test_env.run_jj_in(".", ["git", "init", "repo2"]).success();
+    let repo1_path = test_env.env_root().join("repo1");
+    let repo2_path = test_env.env_root().join("repo2");
+
+    std::fs::write(repo1_path.join("file"), "shared\n").unwrap();
+    test_env
+        .run_jj_in(&repo1_path, ["commit", "-m", "shared"])
+        .success();
+    test_env
+        .run_jj_in(&repo1_path, ["bookmark", "create", "-r@-", "shared"])
+        .success();
+    let output = test_env.run_jj_in(&repo1_path, ["op", "export", "-o", "../bundle"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Exported 4 operations and 4 commits to ../bundle
+    [EOF]
+    ");
+
+    std::fs::write(repo2_path.join("file"), "local\n").unwrap();
+    test_env
+        .run_jj_in(&repo2_path, ["describe", "-m", "local"])
+        .success();
+    let output = test_env.run_jj_in(&repo2_path, ["op", "import", "../bundle"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Imported 4 operations and 4 new commits
+    [EOF]
+    ");
+
+    // The working-copy commit of repo2 is preserved
+    let output = test_env.run_jj_in(&repo2_path, ["log"]);
+    insta::assert_snapshot!(output, @r"
+    @  rlvkpnrz test.user@example.com 2001-02-03 08:05:12 f7612aab
+    │  local
+    │ ○  qpvuntsm test.user@example.com 2001-02-03 08:05:09 shared 79d34863
+    ├─╯  shared
+    ◆  zzzzzzzz root() 00000000
+    [EOF]
+    ");
+    let output = test_env.run_jj_in(&repo2_path, ["op", "log", "--limit=2"]);
+    insta::assert_snapshot!(output, @r"
+    @    66d776f61dbd test-username@host.example.com 2001-02-03 04:05:13.000 +07:00 - 2001-02-03 04:05:13.000 +07:00
+    ├─╮  import operations from ../bundle
+    │ │  args: jj op import ../bundle
+    ○ │  632544293f84 test-username@host.example.com 2001-02-03 04:05:12.000 +07:00 - 2001-02-03 04:05:12.000 +07:00
+    │ │  describe commit 2d2736f145349c8884c24f58d5df94a230e12d17
+    │ │  args: jj describe -m local
+    [EOF]
+    ");
+    let output = test_env.run_jj_in(&repo2_path, ["file", "show", "-r", "shared", "file"]);
+    insta::assert_snapshot!(output, @r"
+    shared
+    [EOF]
+    ");
+
+    // Importing the same bundle again is a no-op
+    let output = test_env.run_jj_in(&repo2_path, ["op", "import", "../bundle"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Imported 4 operations and 0 new commits
+    Nothing changed.
+    [EOF]
+    ");
+
+    // Not a bundle file
+    let output = test_env.run_jj_in(&repo2_path, ["op", "import", "file"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Error: Not an operation bundle, or unsupported bundle format
+    [EOF]
+    [exit status: 1]
+    ");
+}
+
 fn init_bare_git_repo(git_repo_path: &Path) -> gix::Repository {
     let git_repo = git::init_bare(git_repo_path);
     let commit_result = git::add_commit(
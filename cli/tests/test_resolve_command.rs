@@ -685,7 +685,7 @@ fn test_baseless_conflict_input_files() {
 
 #[test]
 fn test_too_many_parents() {
-    let test_env = TestEnvironment::default();
+    let mut test_env = TestEnvironment::default();
     test_env.run_jj_in(".", ["git", "init", "repo"]).success();
     let repo_path = test_env.env_root().join("repo");
 
@@ -730,10 +730,11 @@ fn test_too_many_parents() {
     [EOF]
     ");
 
+    // External merge tools only support 2-sided conflicts
+    test_env.set_up_fake_editor();
     let output = test_env.run_jj_in(&repo_path, ["resolve"]);
     insta::assert_snapshot!(output, @r#"
     ------- stderr -------
-    Hint: Using default editor ':builtin'; run `jj config set --user ui.merge-editor :builtin` to disable this message.
     Error: Failed to resolve conflicts
     Caused by: The conflict at "file" has 3 sides. At most 2 sides are supported.
     [EOF]
@@ -1871,3 +1872,48 @@ fn test_multiple_conflicts_with_error() {
     [EOF]
     ");
 }
+
+#[test]
+fn test_resolve_forget_recorded_resolution() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    test_env.add_config("rerere.enabled = true");
+    let work_dir = test_env.work_dir("repo");
+
+    create_commit_with_files(&work_dir, "base", &[], &[("file", "base\n")]);
+    create_commit_with_files(&work_dir, "a", &["base"], &[("file", "a\n")]);
+    create_commit_with_files(&work_dir, "b", &["base"], &[("file", "b\n")]);
+    work_dir.run_jj(["rebase", "-r", "b", "-d", "a"]).success();
+    // Resolving the conflict in the working copy records the resolution
+    work_dir.write_file("file", "resolved\n");
+    work_dir.run_jj(["status"]).success();
+
+    // The same conflict is resolved automatically
+    create_commit_with_files(&work_dir, "c", &["base"], &[("file", "b\n")]);
+    work_dir.run_jj(["rebase", "-r", "c", "-d", "a"]).success();
+    insta::assert_snapshot!(work_dir.run_jj(["file", "show", "-r", "c", "file"]), @r"
+    resolved
+    [EOF]
+    ");
+
+    let output = work_dir.run_jj(["resolve", "--forget", "-r", "c"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    Forgot 1 recorded resolutions
+    [EOF]
+    ");
+    let output = work_dir.run_jj(["resolve", "--forget", "-r", "c"]);
+    insta::assert_snapshot!(output, @r"
+    ------- stderr -------
+    No recorded resolutions found
+    [EOF]
+    ");
+
+    // The conflict is no longer resolved automatically
+    create_commit_with_files(&work_dir, "d", &["base"], &[("file", "b\n")]);
+    work_dir.run_jj(["rebase", "-r", "d", "-d", "a"]).success();
+    insta::assert_snapshot!(work_dir.run_jj(["resolve", "--list", "-r", "d"]), @r"
+    file    2-sided conflict
+    [EOF]
+    ");
+}
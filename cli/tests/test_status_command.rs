@@ -543,3 +543,129 @@ fn test_status_untracked_files() {
     [EOF]
     ");
 }
+
+#[test]
+fn test_status_template() {
+    let test_env = TestEnvironment::default();
+    test_env.add_config(r#"snapshot.auto-track = "none()""#);
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file"), "base\n").unwrap();
+    std::fs::write(repo_path.join("untracked"), "...").unwrap();
+    test_env
+        .run_jj_in(&repo_path, ["file", "track", "file"])
+        .success();
+    test_env
+        .run_jj_in(&repo_path, ["bookmark", "create", "-r@", "base"])
+        .success();
+    test_env.run_jj_in(&repo_path, ["new", "-m=left"]).success();
+    std::fs::write(repo_path.join("file"), "left\n").unwrap();
+    test_env
+        .run_jj_in(&repo_path, ["new", "base", "-m=right"])
+        .success();
+    std::fs::write(repo_path.join("file"), "right\n").unwrap();
+    test_env
+        .run_jj_in(&repo_path, ["new", "all:base+"])
+        .success();
+    std::fs::write(repo_path.join("other"), "other\n").unwrap();
+    test_env
+        .run_jj_in(&repo_path, ["file", "track", "other"])
+        .success();
+
+    let template = r#"
+    concat(
+      "conflicts:" ++ conflicts.map(|p| " " ++ p.display()).join("") ++ "\n",
+      diff.summary(),
+      "untracked: " ++ untracked_paths ++ "\n",
+      "stale: " ++ stale ++ "\n",
+      "@ " ++ working_copy.change_id().short() ++ "\n",
+      parents.map(|c| "- " ++ c.description().first_line() ++ "\n").join(""),
+    )
+    "#;
+    let output = test_env.run_jj_in(&repo_path, ["status", "-T", template]);
+    insta::assert_snapshot!(output, @r"
+    conflicts: file
+    A other
+    untracked: untracked
+    stale: false
+    @ royxmykxtrkr
+    - right
+    - left
+    [EOF]
+    ");
+
+    // Paths restrict the diff and conflicts
+    let output = test_env.run_jj_in(&repo_path, ["status", "-T", template, "other"]);
+    insta::assert_snapshot!(output, @r"
+    conflicts:
+    A other
+    untracked: untracked
+    stale: false
+    @ royxmykxtrkr
+    - right
+    - left
+    [EOF]
+    ");
+
+    // Template can be configured
+    test_env
+        .add_config(r#"templates.status = '"wc=" ++ working_copy.change_id().short() ++ "\n"'"#);
+    let output = test_env.run_jj_in(&repo_path, ["status"]);
+    insta::assert_snapshot!(output, @r"
+    wc=royxmykxtrkr
+    [EOF]
+    ");
+
+    // Stale working copy can be detected with --ignore-working-copy
+    test_env
+        .run_jj_in(&repo_path, ["edit", "base", "--ignore-working-copy"])
+        .success();
+    let output = test_env.run_jj_in(
+        &repo_path,
+        ["status", "--ignore-working-copy", "-T", r#"stale ++ "\n""#],
+    );
+    insta::assert_snapshot!(output, @r"
+    true
+    [EOF]
+    ");
+}
+
+#[test]
+fn test_status_template_stale() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "main"]).success();
+    let main_dir = test_env.work_dir("main");
+    main_dir.write_file("file", "base\n");
+    main_dir
+        .run_jj(["workspace", "add", "../secondary"])
+        .success();
+    let secondary_dir = test_env.work_dir("secondary");
+    let template = r#"stale ++ "\n""#;
+
+    // Loading an operation before the working copy was updated doesn't make
+    // the working copy stale
+    let output = main_dir.run_jj(["status", "--at-op=@---", "-T", template]);
+    insta::assert_snapshot!(output, @r"
+    false
+    [EOF]
+    ");
+
+    // Unsnapshotted changes don't make the working copy stale
+    secondary_dir.write_file("file", "edited\n");
+    let output = secondary_dir.run_jj(["status", "--ignore-working-copy", "-T", template]);
+    insta::assert_snapshot!(output, @r"
+    false
+    [EOF]
+    ");
+
+    // The working-copy commit is rewritten from another workspace
+    main_dir
+        .run_jj(["squash", "--from=@", "--into=secondary@"])
+        .success();
+    let output = secondary_dir.run_jj(["status", "--ignore-working-copy", "-T", template]);
+    insta::assert_snapshot!(output, @r"
+    true
+    [EOF]
+    ");
+}
@@ -34,7 +34,7 @@ fn test_templater_parse_error() {
     1 | description ()
       |             ^---
       |
-      = expected <EOI>, `++`, `||`, `&&`, `==`, `!=`, `>=`, `>`, `<=`, or `<`
+      = expected <EOI>, `++`, `||`, `&&`, `==`, `!=`, `>=`, `>`, `<=`, `<`, `+`, `-`, `*`, `/`, or `%`
     [EOF]
     [exit status: 1]
     ");
@@ -196,6 +196,26 @@ fn test_templater_upper_lower() {
         render(r#""Hello".upper() ++ "Hello".lower()"#), @"HELLOhello[EOF]");
 }
 
+#[test]
+fn test_templater_hyperlink() {
+    let test_env = TestEnvironment::default();
+    test_env.run_jj_in(".", ["git", "init", "repo"]).success();
+    let repo_path = test_env.env_root().join("repo");
+    let template = r#"hyperlink("https://example.com/" ++ commit_id.short(), "link")"#;
+
+    // Not a terminal, so hyperlinks are disabled by default
+    let output = get_colored_template_output(&test_env, &repo_path, "@-", template);
+    insta::assert_snapshot!(output, @"link[EOF]");
+
+    test_env.add_config("ui.hyperlinks = 'always'");
+    let output = get_colored_template_output(&test_env, &repo_path, "@-", template);
+    insta::assert_snapshot!(output, @r"]8;;https://example.com/000000000000\link]8;;\[EOF]");
+
+    // Plain-text output never contains hyperlinks
+    let output = get_template_output(&test_env, &repo_path, "@-", template);
+    insta::assert_snapshot!(output, @"link[EOF]");
+}
+
 #[test]
 fn test_templater_alias() {
     let test_env = TestEnvironment::default();
@@ -20,6 +20,7 @@ fn main() -> Result<()> {
         "git_store.proto",
         "simple_store.proto",
         "op_store.proto",
+        "op_bundle.proto",
         "working_copy.proto",
     ];
 
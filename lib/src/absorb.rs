@@ -21,11 +21,13 @@ use std::ops::Range;
 use std::rc::Rc;
 
 use bstr::BString;
+use bstr::ByteSlice as _;
 use futures::StreamExt as _;
 use itertools::Itertools as _;
 use thiserror::Error;
 
 use crate::annotate::get_annotation_with_file_content;
+use crate::annotate::AnnotationOptions;
 use crate::backend::BackendError;
 use crate::backend::BackendResult;
 use crate::backend::CommitId;
@@ -35,6 +37,7 @@ use crate::conflicts::materialized_diff_stream;
 use crate::conflicts::MaterializedFileValue;
 use crate::conflicts::MaterializedTreeValue;
 use crate::copies::CopyRecords;
+use crate::diff::CompareBytesExactly;
 use crate::diff::Diff;
 use crate::diff::DiffHunkKind;
 use crate::matchers::Matcher;
@@ -86,6 +89,116 @@ pub struct SelectedTrees {
     pub skipped_paths: Vec<(RepoPathBuf, String)>,
 }
 
+/// Hunk-level absorb plan which maps each source hunk to its destination
+/// commit.
+///
+/// The destinations can be altered before the plan is turned into trees by
+/// [`AbsorbPlan::build_trees()`].
+#[derive(Clone, Debug, Default)]
+pub struct AbsorbPlan {
+    /// Files containing hunks to be absorbed.
+    pub files: Vec<AbsorbFile>,
+    /// Paths that were not absorbed for various error reasons.
+    pub skipped_paths: Vec<(RepoPathBuf, String)>,
+}
+
+/// Changes to a file in the source commit.
+#[derive(Clone, Debug)]
+pub struct AbsorbFile {
+    /// Path to the file.
+    pub path: RepoPathBuf,
+    /// Whether the parent (= left) file is executable.
+    pub executable: bool,
+    /// File content in the parent of the source commit.
+    pub left_text: BString,
+    /// File content in the source commit.
+    pub right_text: BString,
+    /// Changed hunks, sorted by position.
+    pub hunks: Vec<AbsorbHunk>,
+}
+
+/// Changed hunk and the commit it should be absorbed into.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AbsorbHunk {
+    /// Byte range in the parent (= left) content.
+    pub left_range: Range<usize>,
+    /// Byte range in the source (= right) content.
+    pub right_range: Range<usize>,
+    /// Commit to absorb this hunk into, or `None` if the hunk should be left
+    /// in the source commit.
+    pub destination: Option<CommitId>,
+}
+
+impl AbsorbFile {
+    /// Returns the parent (= left) and source (= right) contents of the hunk.
+    pub fn hunk_contents(&self, hunk: &AbsorbHunk) -> [&[u8]; 2] {
+        [
+            &self.left_text[hunk.left_range.clone()],
+            &self.right_text[hunk.right_range.clone()],
+        ]
+    }
+
+    /// Returns the 0-based left and right line ranges of the hunk.
+    pub fn hunk_line_ranges(&self, hunk: &AbsorbHunk) -> [Range<usize>; 2] {
+        let to_line_range = |text: &[u8], range: &Range<usize>| {
+            let start = text[..range.start].find_iter("\n").count();
+            start..start + text[range.clone()].lines().count()
+        };
+        [
+            to_line_range(&self.left_text, &hunk.left_range),
+            to_line_range(&self.right_text, &hunk.right_range),
+        ]
+    }
+}
+
+impl AbsorbPlan {
+    /// Iterates over all hunks with the files they belong to.
+    pub fn hunks(&self) -> impl Iterator<Item = (&AbsorbFile, &AbsorbHunk)> {
+        self.files
+            .iter()
+            .flat_map(|file| file.hunks.iter().map(move |hunk| (file, hunk)))
+    }
+
+    /// Builds trees to be merged into destination commits. The returned map
+    /// can be passed to [`absorb_hunks()`].
+    pub async fn build_trees(
+        &self,
+        repo: &dyn Repo,
+        source: &AbsorbSource,
+    ) -> BackendResult<HashMap<CommitId, MergedTreeBuilder>> {
+        let mut target_commits: HashMap<CommitId, MergedTreeBuilder> = HashMap::new();
+        for file in &self.files {
+            let hunks_by_commit = file
+                .hunks
+                .iter()
+                .filter_map(|hunk| {
+                    let range = (hunk.left_range.clone(), hunk.right_range.clone());
+                    Some((hunk.destination.as_ref()?, range))
+                })
+                .into_group_map();
+            // Build trees containing parent (= left) contents + selected hunks
+            for (commit_id, ranges) in hunks_by_commit {
+                let tree_builder = target_commits
+                    .entry(commit_id.clone())
+                    .or_insert_with(|| MergedTreeBuilder::new(source.parent_tree.id().clone()));
+                let new_text = combine_texts(&file.left_text, &file.right_text, &ranges);
+                let id = repo
+                    .store()
+                    .write_file(&file.path, &mut new_text.as_slice())
+                    .await?;
+                tree_builder.set_or_remove(
+                    file.path.clone(),
+                    Merge::normal(TreeValue::File {
+                        id,
+                        executable: file.executable,
+                    }),
+                );
+            }
+        }
+        Ok(target_commits)
+    }
+}
+
 /// Builds trees to be merged into destination commits by splitting source
 /// changes based on file annotation.
 pub async fn split_hunks_to_trees(
@@ -94,7 +207,25 @@ pub async fn split_hunks_to_trees(
     destinations: &Rc<ResolvedRevsetExpression>,
     matcher: &dyn Matcher,
 ) -> Result<SelectedTrees, AbsorbError> {
-    let mut selected_trees = SelectedTrees::default();
+    let plan = plan_absorb(repo, source, destinations, matcher).await?;
+    let target_commits = plan.build_trees(repo, source).await?;
+    Ok(SelectedTrees {
+        target_commits,
+        skipped_paths: plan.skipped_paths,
+    })
+}
+
+/// Maps source hunks to destination commits based on file annotation.
+///
+/// Hunks which can't be mapped unambiguously are included in the plan with no
+/// destination.
+pub async fn plan_absorb(
+    repo: &dyn Repo,
+    source: &AbsorbSource,
+    destinations: &Rc<ResolvedRevsetExpression>,
+    matcher: &dyn Matcher,
+) -> Result<AbsorbPlan, AbsorbError> {
+    let mut plan = AbsorbPlan::default();
 
     let left_tree = &source.parent_tree;
     let right_tree = source.commit.tree()?;
@@ -111,9 +242,7 @@ pub async fn split_hunks_to_trees(
             // New file should have no destinations
             Ok(None) => continue,
             Err(reason) => {
-                selected_trees
-                    .skipped_paths
-                    .push((left_path.to_owned(), reason));
+                plan.skipped_paths.push((left_path.to_owned(), reason));
                 continue;
             }
         };
@@ -123,52 +252,88 @@ pub async fn split_hunks_to_trees(
             // handling to propagate deletion of the tree entry
             Ok(None) => {
                 let reason = "Deleted file".to_owned();
-                selected_trees
-                    .skipped_paths
-                    .push((right_path.to_owned(), reason));
+                plan.skipped_paths.push((right_path.to_owned(), reason));
                 continue;
             }
             Err(reason) => {
-                selected_trees
-                    .skipped_paths
-                    .push((right_path.to_owned(), reason));
+                plan.skipped_paths.push((right_path.to_owned(), reason));
                 continue;
             }
         };
 
-        // Compute annotation of parent (= left) content to map right hunks
+        // Compute annotation of parent (= left) content to map right hunks.
+        // Copies aren't followed since the hunks are squashed into the same
+        // path.
         let annotation = get_annotation_with_file_content(
             repo,
             source.commit.id(),
             destinations,
             left_path,
+            &AnnotationOptions::default(),
             left_text.clone(),
         )?;
         let annotation_ranges = annotation
             .compact_line_ranges()
             .filter_map(|(commit_id, range)| Some((commit_id.ok()?, range)))
             .collect_vec();
-        let diff = Diff::by_line([&left_text, &right_text]);
-        let selected_ranges = split_file_hunks(&annotation_ranges, &diff);
-        // Build trees containing parent (= left) contents + selected hunks
-        for (&commit_id, ranges) in &selected_ranges {
-            let tree_builder = selected_trees
-                .target_commits
-                .entry(commit_id.clone())
-                .or_insert_with(|| MergedTreeBuilder::new(left_tree.id().clone()));
-            let new_text = combine_texts(&left_text, &right_text, ranges);
-            let id = repo
-                .store()
-                .write_file(left_path, &mut new_text.as_slice())
-                .await?;
-            tree_builder.set_or_remove(
-                left_path.to_owned(),
-                Merge::normal(TreeValue::File { id, executable }),
-            );
-        }
+        let hunks = map_file_hunks(
+            &annotation_ranges,
+            &Diff::by_line_with(
+                [&left_text, &right_text],
+                CompareBytesExactly,
+                repo.store().diff_algorithm(),
+            ),
+        );
+        plan.files.push(AbsorbFile {
+            path: left_path.to_owned(),
+            executable,
+            left_text: left_text.into(),
+            right_text: right_text.into(),
+            hunks,
+        });
     }
 
-    Ok(selected_trees)
+    Ok(plan)
+}
+
+/// Maps `diff` hunks to commits based on the left `annotation_ranges`. Unlike
+/// [`split_file_hunks()`], this includes hunks that can't be mapped.
+fn map_file_hunks(annotation_ranges: &[(&CommitId, Range<usize>)], diff: &Diff) -> Vec<AbsorbHunk> {
+    let selected_ranges = split_file_hunks(annotation_ranges, diff);
+    let mut hunks = selected_ranges
+        .into_iter()
+        .flat_map(|(commit_id, ranges)| {
+            ranges
+                .into_iter()
+                .map(|(left_range, right_range)| AbsorbHunk {
+                    left_range,
+                    right_range,
+                    destination: Some(commit_id.clone()),
+                })
+        })
+        .collect_vec();
+    let num_selected = hunks.len();
+    for hunk in diff
+        .hunk_ranges()
+        .filter(|hunk| hunk.kind == DiffHunkKind::Different)
+    {
+        let [left_range, right_range]: &[_; 2] = hunk.ranges[..].try_into().unwrap();
+        // Pure deletion may be split into multiple selected ranges
+        let selected = hunks[..num_selected].iter().any(|selected| {
+            selected.right_range == *right_range
+                && left_range.start <= selected.left_range.start
+                && selected.left_range.end <= left_range.end
+        });
+        if !selected {
+            hunks.push(AbsorbHunk {
+                left_range: left_range.clone(),
+                right_range: right_range.clone(),
+                destination: None,
+            });
+        }
+    }
+    hunks.sort_unstable_by_key(|hunk| (hunk.left_range.start, hunk.left_range.end));
+    hunks
 }
 
 type SelectedRange = (Range<usize>, Range<usize>);
@@ -1087,6 +1252,68 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_map_file_hunks() {
+        let commit_id1 = &CommitId::from_hex("111111");
+        let commit_id2 = &CommitId::from_hex("222222");
+        let hunk =
+            |left_range: Range<usize>, right_range, commit_id: Option<&CommitId>| AbsorbHunk {
+                left_range,
+                right_range,
+                destination: commit_id.cloned(),
+            };
+
+        // insert between ranges is ambiguous, but the others are mapped
+        assert_eq!(
+            map_file_hunks(
+                &[(commit_id1, 0..6), (commit_id2, 6..12)],
+                &Diff::by_line(["1a\n1b\n2a\n2b\n", "1A\n1b\nX\n2a\n2B\n"])
+            ),
+            vec![
+                hunk(0..3, 0..3, Some(commit_id1)),
+                hunk(6..6, 6..8, None),
+                hunk(9..12, 11..14, Some(commit_id2)),
+            ]
+        );
+        // delete over ranges is split
+        assert_eq!(
+            map_file_hunks(
+                &[(commit_id1, 0..6), (commit_id2, 6..12)],
+                &Diff::by_line(["1a\n1b\n2a\n2b\n", "1a\n2b\n"])
+            ),
+            vec![
+                hunk(3..6, 3..3, Some(commit_id1)),
+                hunk(6..9, 3..3, Some(commit_id2)),
+            ]
+        );
+        // unannotated lines can't be mapped
+        assert_eq!(
+            map_file_hunks(
+                &[(commit_id1, 0..3)],
+                &Diff::by_line(["1a\n2a\n", "1A\n2A\n"])
+            ),
+            vec![hunk(0..6, 0..6, None)]
+        );
+    }
+
+    #[test]
+    fn test_hunk_line_ranges() {
+        let file = AbsorbFile {
+            path: RepoPathBuf::from_internal_string("file"),
+            executable: false,
+            left_text: "a\nb\nc".into(),
+            right_text: "a\nB\nx\nC".into(),
+            hunks: vec![],
+        };
+        let hunk = AbsorbHunk {
+            left_range: 2..5,
+            right_range: 2..7,
+            destination: None,
+        };
+        assert_eq!(file.hunk_line_ranges(&hunk), [1..3, 1..4]);
+        assert_eq!(file.hunk_contents(&hunk), [&b"b\nc"[..], b"B\nx\nC"]);
+    }
+
     #[test]
     fn test_combine_texts() {
         assert_eq!(combine_texts(b"", b"", &[]), "");
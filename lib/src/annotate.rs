@@ -19,24 +19,32 @@
 //! Like commit metadata and more.
 
 use std::collections::hash_map;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::iter;
 use std::ops::Range;
 use std::rc::Rc;
 
 use bstr::BStr;
 use bstr::BString;
+use futures::TryStreamExt as _;
 use itertools::Itertools as _;
 use pollster::FutureExt as _;
 
 use crate::backend::BackendError;
 use crate::backend::CommitId;
+use crate::backend::CopyRecord;
 use crate::commit::Commit;
 use crate::conflicts::materialize_merge_result_to_bytes;
 use crate::conflicts::materialize_tree_value;
 use crate::conflicts::ConflictMarkerStyle;
 use crate::conflicts::MaterializedTreeValue;
+use crate::diff::CompareBytesExactly;
+use crate::diff::CompareBytesIgnoreAllWhitespace;
+use crate::diff::CompareBytesIgnoreWhitespaceAmount;
 use crate::diff::Diff;
+use crate::diff::DiffAlgorithm;
 use crate::diff::DiffHunkKind;
 use crate::fileset::FilesetExpression;
 use crate::graph::GraphEdge;
@@ -44,6 +52,7 @@ use crate::graph::GraphEdgeType;
 use crate::merged_tree::MergedTree;
 use crate::repo::Repo;
 use crate::repo_path::RepoPath;
+use crate::repo_path::RepoPathBuf;
 use crate::revset::ResolvedRevsetExpression;
 use crate::revset::RevsetEvaluationError;
 use crate::revset::RevsetExpression;
@@ -53,7 +62,8 @@ use crate::store::Store;
 /// Annotation results for a specific file
 #[derive(Clone, Debug)]
 pub struct FileAnnotation {
-    line_map: OriginalLineMap,
+    line_map: Vec<Result<CommitId, CommitId>>,
+    paths: Vec<RepoPathBuf>,
     text: BString,
 }
 
@@ -113,12 +123,47 @@ impl FileAnnotation {
         })
     }
 
+    /// Returns iterator over the paths where the lines originated, in the same
+    /// order as [`Self::lines()`].
+    ///
+    /// The path differs from the annotated path if the line was copied or
+    /// renamed from another file.
+    pub fn original_paths(&self) -> impl Iterator<Item = &RepoPath> {
+        self.paths.iter().map(|path| path.as_ref())
+    }
+
     /// File content at the starting commit.
     pub fn text(&self) -> &BStr {
         self.text.as_ref()
     }
 }
 
+/// Options for computing annotations.
+#[derive(Clone, Debug, Default)]
+pub struct AnnotationOptions {
+    /// Whether to follow copies and renames, as reported by
+    /// [`Store::get_copy_records()`], to the source file.
+    pub follow_copies: bool,
+    /// Commits whose changes are ignored. Lines changed by these commits are
+    /// attributed to the commits that introduced the corresponding lines in
+    /// the first parent.
+    pub ignored_commits: HashSet<CommitId>,
+    /// How lines are compared.
+    pub line_comparison: LineComparison,
+}
+
+/// How lines are compared when looking for the origin of a line.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum LineComparison {
+    /// Compares lines literally.
+    #[default]
+    Exact,
+    /// Compares lines ignoring any whitespace occurrences.
+    IgnoreAllSpace,
+    /// Compares lines ignoring changes in whitespace amount.
+    IgnoreSpaceChange,
+}
+
 /// A map from commits to file line mappings and contents.
 type CommitSourceMap = HashMap<CommitId, Source>;
 
@@ -152,9 +197,22 @@ impl Source {
     }
 }
 
-/// List of commit IDs that originated lines, indexed by line numbers in the
+/// Commit IDs and paths that originated lines, indexed by line numbers in the
 /// original file.
-type OriginalLineMap = Vec<Result<CommitId, CommitId>>;
+#[derive(Clone, Debug)]
+struct OriginalLineMap {
+    commit_ids: Vec<Result<CommitId, CommitId>>,
+    paths: Vec<RepoPathBuf>,
+}
+
+impl OriginalLineMap {
+    fn set(&mut self, line_number: usize, commit_id: Result<CommitId, CommitId>, path: &RepoPath) {
+        self.commit_ids[line_number] = commit_id;
+        if *self.paths[line_number] != *path {
+            self.paths[line_number] = path.to_owned();
+        }
+    }
+}
 
 /// Get line by line annotations for a specific file path in the repo.
 ///
@@ -168,9 +226,17 @@ pub fn get_annotation_for_file(
     starting_commit: &Commit,
     domain: &Rc<ResolvedRevsetExpression>,
     file_path: &RepoPath,
+    options: &AnnotationOptions,
 ) -> Result<FileAnnotation, RevsetEvaluationError> {
     let source = Source::load(starting_commit, file_path)?;
-    compute_file_annotation(repo, starting_commit.id(), domain, file_path, source)
+    compute_file_annotation(
+        repo,
+        starting_commit.id(),
+        domain,
+        file_path,
+        options,
+        source,
+    )
 }
 
 /// Get line by line annotations for a specific file path starting with the
@@ -185,10 +251,11 @@ pub fn get_annotation_with_file_content(
     starting_commit_id: &CommitId,
     domain: &Rc<ResolvedRevsetExpression>,
     file_path: &RepoPath,
+    options: &AnnotationOptions,
     starting_text: impl Into<Vec<u8>>,
 ) -> Result<FileAnnotation, RevsetEvaluationError> {
     let source = Source::new(BString::new(starting_text.into()));
-    compute_file_annotation(repo, starting_commit_id, domain, file_path, source)
+    compute_file_annotation(repo, starting_commit_id, domain, file_path, options, source)
 }
 
 fn compute_file_annotation(
@@ -196,12 +263,18 @@ fn compute_file_annotation(
     starting_commit_id: &CommitId,
     domain: &Rc<ResolvedRevsetExpression>,
     file_path: &RepoPath,
+    options: &AnnotationOptions,
     mut source: Source,
 ) -> Result<FileAnnotation, RevsetEvaluationError> {
     source.fill_line_map();
     let text = source.text.clone();
-    let line_map = process_commits(repo, starting_commit_id, source, domain, file_path)?;
-    Ok(FileAnnotation { line_map, text })
+    let OriginalLineMap { commit_ids, paths } =
+        process_commits(repo, starting_commit_id, source, domain, file_path, options)?;
+    Ok(FileAnnotation {
+        line_map: commit_ids,
+        paths,
+        text,
+    })
 }
 
 /// Starting at the starting commit, compute changes at that commit relative to
@@ -212,36 +285,53 @@ fn process_commits(
     starting_commit_id: &CommitId,
     starting_source: Source,
     domain: &Rc<ResolvedRevsetExpression>,
-    file_name: &RepoPath,
+    file_path: &RepoPath,
+    options: &AnnotationOptions,
 ) -> Result<OriginalLineMap, RevsetEvaluationError> {
-    let predicate = RevsetFilterPredicate::File(FilesetExpression::file_path(file_name.to_owned()));
-    // TODO: If the domain isn't a contiguous range, changes masked out by it
-    // might not be caught by the closest ancestor revision. For example,
-    // domain=merges() would pick up almost nothing because merge revisions
-    // are usually empty. Perhaps, we want to query `files(file_path,
-    // within_sub_graph=domain)`, not `domain & files(file_path)`.
-    let ancestors = RevsetExpression::commit(starting_commit_id.clone()).ancestors();
-    let revset = RevsetExpression::commit(starting_commit_id.clone())
-        .union(&domain.intersection(&ancestors).filtered(predicate))
-        .evaluate(repo)?;
-
-    let mut original_line_map =
-        vec![Err(starting_commit_id.clone()); starting_source.line_map.len()];
-    let mut commit_source_map = HashMap::from([(starting_commit_id.clone(), starting_source)]);
-
-    for node in revset.iter_graph() {
-        let (commit_id, edge_list) = node?;
-        process_commit(
-            repo,
-            file_name,
-            &mut original_line_map,
-            &mut commit_source_map,
-            &commit_id,
-            &edge_list,
-        )?;
-        if commit_source_map.is_empty() {
-            // No more lines to propagate to ancestors.
-            break;
+    let num_lines = starting_source.line_map.len();
+    let mut original_line_map = OriginalLineMap {
+        commit_ids: vec![Err(starting_commit_id.clone()); num_lines],
+        paths: vec![file_path.to_owned(); num_lines],
+    };
+    // Sources to be processed, grouped by path. When following a copy, the
+    // lines are moved to the source path in the parent commit, and the
+    // ancestors of the parent are searched for changes to the source path.
+    let mut pending_sources = BTreeMap::from([(
+        file_path.to_owned(),
+        HashMap::from([(starting_commit_id.clone(), starting_source)]),
+    )]);
+    while let Some((path, mut commit_source_map)) = pending_sources.pop_first() {
+        let heads = commit_source_map.keys().cloned().collect_vec();
+        // The file predicate consults the changed-path filters of the index
+        // if they are recorded, so commits which can't touch the path are
+        // skipped without diffing their trees.
+        let predicate = RevsetFilterPredicate::File(FilesetExpression::file_path(path.clone()));
+        // TODO: If the domain isn't a contiguous range, changes masked out by it
+        // might not be caught by the closest ancestor revision. For example,
+        // domain=merges() would pick up almost nothing because merge revisions
+        // are usually empty. Perhaps, we want to query `files(file_path,
+        // within_sub_graph=domain)`, not `domain & files(file_path)`.
+        let ancestors = RevsetExpression::commits(heads.clone()).ancestors();
+        let revset = RevsetExpression::commits(heads)
+            .union(&domain.intersection(&ancestors).filtered(predicate))
+            .evaluate(repo)?;
+
+        for node in revset.iter_graph() {
+            let (commit_id, edge_list) = node?;
+            process_commit(
+                repo,
+                &path,
+                options,
+                &mut original_line_map,
+                &mut commit_source_map,
+                &mut pending_sources,
+                &commit_id,
+                &edge_list,
+            )?;
+            if commit_source_map.is_empty() {
+                // No more lines to propagate to ancestors.
+                break;
+            }
         }
     }
     Ok(original_line_map)
@@ -249,66 +339,50 @@ fn process_commits(
 
 /// For a given commit, for each parent, we compare the version in the parent
 /// tree with the current version, updating the mappings for any lines in
-/// common. If the parent doesn't have the file, we skip it.
+/// common. If the parent doesn't have the file, we skip it, or look for the
+/// file it was copied from if copies are followed.
+#[expect(clippy::too_many_arguments)]
 fn process_commit(
     repo: &dyn Repo,
-    file_name: &RepoPath,
+    file_path: &RepoPath,
+    options: &AnnotationOptions,
     original_line_map: &mut OriginalLineMap,
     commit_source_map: &mut CommitSourceMap,
+    pending_sources: &mut BTreeMap<RepoPathBuf, CommitSourceMap>,
     current_commit_id: &CommitId,
     edges: &[GraphEdge<CommitId>],
 ) -> Result<(), BackendError> {
     let Some(mut current_source) = commit_source_map.remove(current_commit_id) else {
         return Ok(());
     };
+    let ignored = options.ignored_commits.contains(current_commit_id);
 
-    for parent_edge in edges {
+    for (i, parent_edge) in edges.iter().enumerate() {
         let parent_commit_id = &parent_edge.target;
         let parent_source = match commit_source_map.entry(parent_commit_id.clone()) {
             hash_map::Entry::Occupied(entry) => entry.into_mut(),
             hash_map::Entry::Vacant(entry) => {
                 let commit = repo.store().get_commit(entry.key())?;
-                entry.insert(Source::load(&commit, file_name)?)
+                entry.insert(Source::load(&commit, file_path)?)
             }
         };
 
-        // For two versions of the same file, for all the lines in common,
-        // overwrite the new mapping in the results for the new commit. Let's
-        // say I have a file in commit A and commit B. We know that according to
-        // local line_map, in commit A, line 3 corresponds to line 7 of the
-        // original file. Now, line 3 in Commit A corresponds to line 6 in
-        // commit B. Then, we update local line_map to say that "Commit B line 6
-        // goes to line 7 of the original file". We repeat this for all lines in
-        // common in the two commits.
-        let mut current_lines = current_source.line_map.iter().copied().peekable();
-        let mut new_current_line_map = Vec::new();
-        let mut new_parent_line_map = Vec::new();
-        copy_same_lines_with(
-            &current_source.text,
-            &parent_source.text,
-            |current_start, parent_start, count| {
-                new_current_line_map
-                    .extend(current_lines.peeking_take_while(|&(cur, _)| cur < current_start));
-                while let Some((current, original)) =
-                    current_lines.next_if(|&(cur, _)| cur < current_start + count)
-                {
-                    let parent = parent_start + (current - current_start);
-                    new_parent_line_map.push((parent, original));
-                }
-            },
+        // Changes made by an ignored commit are attributed to the nearby lines
+        // in the first parent.
+        let map_changed_lines = ignored && i == 0;
+        move_same_lines(
+            &mut current_source,
+            parent_source,
+            options.line_comparison,
+            repo.store().diff_algorithm(),
+            map_changed_lines,
         );
-        new_current_line_map.extend(current_lines);
-        current_source.line_map = new_current_line_map;
-        parent_source.line_map = if parent_source.line_map.is_empty() {
-            new_parent_line_map
-        } else {
-            itertools::merge(parent_source.line_map.iter().copied(), new_parent_line_map).collect()
-        };
         // If an omitted parent had the file, leave these lines unresolved. The
         // origin of the unresolved lines is represented as Err(root_commit_id).
         if parent_edge.edge_type == GraphEdgeType::Missing {
             for (_, original_line_number) in parent_source.line_map.drain(..) {
-                original_line_map[original_line_number] = Err(current_commit_id.clone());
+                let commit_id = Err(current_commit_id.clone());
+                original_line_map.set(original_line_number, commit_id, file_path);
             }
         }
         if parent_source.line_map.is_empty() {
@@ -316,24 +390,154 @@ fn process_commit(
         }
     }
 
+    if options.follow_copies && !current_source.line_map.is_empty() {
+        move_copied_lines(
+            repo,
+            file_path,
+            options,
+            &mut current_source,
+            pending_sources,
+            current_commit_id,
+        )?;
+    }
+
     // Once we've looked at all parents of a commit, any leftover lines must be
     // original to the current commit, so we save this information in
     // original_line_map.
     for (_, original_line_number) in current_source.line_map {
-        original_line_map[original_line_number] = Ok(current_commit_id.clone());
+        let commit_id = Ok(current_commit_id.clone());
+        original_line_map.set(original_line_number, commit_id, file_path);
     }
 
     Ok(())
 }
 
+/// If the file doesn't exist in a parent of the current commit, but was copied
+/// or renamed from another file in the parent, moves the lines in common to
+/// the source file in the parent.
+fn move_copied_lines(
+    repo: &dyn Repo,
+    file_path: &RepoPath,
+    options: &AnnotationOptions,
+    current_source: &mut Source,
+    pending_sources: &mut BTreeMap<RepoPathBuf, CommitSourceMap>,
+    current_commit_id: &CommitId,
+) -> Result<(), BackendError> {
+    let store = repo.store();
+    let commit = store.get_commit(current_commit_id)?;
+    let target_paths = [file_path.to_owned()];
+    for parent_commit_id in commit.parent_ids() {
+        let parent_commit = store.get_commit(parent_commit_id)?;
+        if !parent_commit.tree()?.path_value(file_path)?.is_absent() {
+            continue;
+        }
+        let copy_records: Vec<CopyRecord> = store
+            .get_copy_records(Some(&target_paths), parent_commit_id, current_commit_id)?
+            .try_collect()
+            .block_on()?;
+        let Some(record) = copy_records
+            .into_iter()
+            .find(|record| *record.target == *file_path)
+        else {
+            continue;
+        };
+        let commit_source_map = pending_sources.entry(record.source.clone()).or_default();
+        let parent_source = match commit_source_map.entry(parent_commit_id.clone()) {
+            hash_map::Entry::Occupied(entry) => entry.into_mut(),
+            hash_map::Entry::Vacant(entry) => {
+                entry.insert(Source::load(&parent_commit, &record.source)?)
+            }
+        };
+        move_same_lines(
+            current_source,
+            parent_source,
+            options.line_comparison,
+            store.diff_algorithm(),
+            false,
+        );
+        if parent_source.line_map.is_empty() {
+            commit_source_map.remove(parent_commit_id);
+            if commit_source_map.is_empty() {
+                pending_sources.remove(&record.source);
+            }
+        }
+        if current_source.line_map.is_empty() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// For two versions of the same file, for all the lines in common, overwrite
+/// the new mapping in the results for the new commit. Let's say I have a file
+/// in commit A and commit B. We know that according to local line_map, in
+/// commit A, line 3 corresponds to line 7 of the original file. Now, line 3 in
+/// Commit A corresponds to line 6 in commit B. Then, we update local line_map
+/// to say that "Commit B line 6 goes to line 7 of the original file". We
+/// repeat this for all lines in common in the two commits.
+///
+/// If `map_changed_lines` is true, changed lines are also moved to the parent
+/// if the corresponding hunk in the parent isn't empty.
+fn move_same_lines(
+    current_source: &mut Source,
+    parent_source: &mut Source,
+    line_comparison: LineComparison,
+    algorithm: DiffAlgorithm,
+    map_changed_lines: bool,
+) {
+    let mut current_lines = current_source.line_map.iter().copied().peekable();
+    let mut new_current_line_map = Vec::new();
+    let mut new_parent_line_map = Vec::new();
+    copy_same_lines_with(
+        &current_source.text,
+        &parent_source.text,
+        line_comparison,
+        algorithm,
+        map_changed_lines,
+        |current_start, parent_start, count| {
+            new_current_line_map
+                .extend(current_lines.peeking_take_while(|&(cur, _)| cur < current_start));
+            while let Some((current, original)) =
+                current_lines.next_if(|&(cur, _)| cur < current_start + count)
+            {
+                let parent = parent_start + (current - current_start);
+                new_parent_line_map.push((parent, original));
+            }
+        },
+    );
+    new_current_line_map.extend(current_lines);
+    current_source.line_map = new_current_line_map;
+    parent_source.line_map = if parent_source.line_map.is_empty() {
+        new_parent_line_map
+    } else {
+        itertools::merge(parent_source.line_map.iter().copied(), new_parent_line_map).collect()
+    };
+}
+
 /// For two files, calls `copy(current_start, parent_start, count)` for each
 /// range of contiguous lines in common (e.g. line 8-10 maps to line 9-11.)
+///
+/// If `map_changed_lines` is true, each changed line is also mapped to the
+/// line at the same offset in the parent hunk, or to the last line of the
+/// parent hunk if it's shorter.
 fn copy_same_lines_with(
     current_contents: &[u8],
     parent_contents: &[u8],
+    line_comparison: LineComparison,
+    algorithm: DiffAlgorithm,
+    map_changed_lines: bool,
     mut copy: impl FnMut(usize, usize, usize),
 ) {
-    let diff = Diff::by_line([current_contents, parent_contents]);
+    let inputs = [current_contents, parent_contents];
+    let diff = match line_comparison {
+        LineComparison::Exact => Diff::by_line_with(inputs, CompareBytesExactly, algorithm),
+        LineComparison::IgnoreAllSpace => {
+            Diff::by_line_with(inputs, CompareBytesIgnoreAllWhitespace, algorithm)
+        }
+        LineComparison::IgnoreSpaceChange => {
+            Diff::by_line_with(inputs, CompareBytesIgnoreWhitespaceAmount, algorithm)
+        }
+    };
     let mut current_line_counter: usize = 0;
     let mut parent_line_counter: usize = 0;
     for hunk in diff.hunks() {
@@ -347,8 +551,16 @@ fn copy_same_lines_with(
             DiffHunkKind::Different => {
                 let current_output = hunk.contents[0];
                 let parent_output = hunk.contents[1];
-                current_line_counter += current_output.split_inclusive(|b| *b == b'\n').count();
-                parent_line_counter += parent_output.split_inclusive(|b| *b == b'\n').count();
+                let current_count = current_output.split_inclusive(|b| *b == b'\n').count();
+                let parent_count = parent_output.split_inclusive(|b| *b == b'\n').count();
+                if map_changed_lines && parent_count > 0 {
+                    for i in 0..current_count {
+                        let parent_line = parent_line_counter + i.min(parent_count - 1);
+                        copy(current_line_counter + i, parent_line, 1);
+                    }
+                }
+                current_line_counter += current_count;
+                parent_line_counter += parent_count;
             }
         }
     }
@@ -363,9 +575,13 @@ fn get_file_contents(
     let effective_file_value = materialize_tree_value(store, path, file_value).block_on()?;
     match effective_file_value {
         MaterializedTreeValue::File(mut file) => Ok(file.read_all(path)?.into()),
-        MaterializedTreeValue::FileConflict { contents, .. } => Ok(
-            materialize_merge_result_to_bytes(&contents, ConflictMarkerStyle::default()),
-        ),
+        MaterializedTreeValue::FileConflict { contents, .. } => {
+            Ok(materialize_merge_result_to_bytes(
+                &contents,
+                ConflictMarkerStyle::default(),
+                store.diff_algorithm(),
+            ))
+        }
         _ => Ok(BString::default()),
     }
 }
@@ -378,6 +594,7 @@ mod tests {
     fn test_lines_iterator_empty() {
         let annotation = FileAnnotation {
             line_map: vec![],
+            paths: vec![],
             text: "".into(),
         };
         assert_eq!(annotation.lines().collect_vec(), vec![]);
@@ -396,6 +613,7 @@ mod tests {
                 Ok(commit_id2.clone()),
                 Ok(commit_id3.clone()),
             ],
+            paths: vec![RepoPathBuf::from_internal_string("file"); 3],
             text: "foo\n\nbar\n".into(),
         };
         assert_eq!(
@@ -439,6 +657,7 @@ mod tests {
                 Ok(commit_id3.clone()),
                 Ok(commit_id3.clone()),
             ],
+            paths: vec![RepoPathBuf::from_internal_string("file"); 7],
             text: "\n".repeat(7).into(),
         };
         assert_eq!(
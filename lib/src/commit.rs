@@ -163,6 +163,12 @@ impl Commit {
         maybe_entries.is_none_or(|entries| !entries.contains(&self.id))
     }
 
+    /// A commit is divergent if its change id is shared by other visible
+    /// commits.
+    pub fn is_divergent(&self, repo: &dyn Repo) -> bool {
+        repo.is_divergent_change(self.change_id())
+    }
+
     /// A commit is discardable if it has no change from its parent, and an
     /// empty description.
     pub fn is_discardable(&self, repo: &dyn Repo) -> BackendResult<bool> {
@@ -28,6 +28,7 @@ use crate::commit::is_backend_commit_empty;
 use crate::commit::Commit;
 use crate::repo::MutableRepo;
 use crate::repo::Repo;
+use crate::rerere;
 use crate::settings::JJRng;
 use crate::settings::SignSettings;
 use crate::settings::UserSettings;
@@ -354,6 +355,23 @@ impl DetachedCommitBuilder {
             if rewrite_source.change_id() == commit.change_id() {
                 mut_repo.set_rewritten_commit(rewrite_source.id().clone(), commit.id().clone());
             }
+            if let Some(resolution_store) = mut_repo.base_repo().loader().resolution_store() {
+                if resolution_store.is_enabled() && rewrite_source.tree_id() != commit.tree_id() {
+                    // The recorded resolutions are just a cache, so a failure
+                    // shouldn't prevent the commit from being written.
+                    if let Err(err) = rerere::record_rewrite_resolutions(
+                        resolution_store,
+                        &rewrite_source,
+                        &commit,
+                    ) {
+                        tracing::warn!(
+                            ?err,
+                            commit_id = ?commit.id(),
+                            "failed to record conflict resolutions"
+                        );
+                    }
+                }
+            }
         }
         Ok(commit)
     }
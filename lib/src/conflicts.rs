@@ -38,7 +38,9 @@ use crate::backend::TreeId;
 use crate::backend::TreeValue;
 use crate::copies::CopiesTreeDiffEntry;
 use crate::copies::CopiesTreeDiffEntryPath;
+use crate::diff::CompareBytesExactly;
 use crate::diff::Diff;
+use crate::diff::DiffAlgorithm;
 use crate::diff::DiffHunk;
 use crate::diff::DiffHunkKind;
 use crate::files;
@@ -256,6 +258,10 @@ pub enum ConflictMarkerStyle {
     Snapshot,
     /// Style which replicates Git's "diff3" style to support external tools.
     Git,
+    /// Like [`ConflictMarkerStyle::Git`], but lines common to all sides at the
+    /// start and the end of a conflict are moved out of the conflict markers,
+    /// like Git's "zdiff3" style.
+    Zdiff3,
 }
 
 /// Characters which can be repeated to form a conflict marker line when
@@ -360,14 +366,21 @@ pub fn choose_materialized_conflict_marker_len<T: AsRef<[u8]>>(single_hunk: &Mer
 pub fn materialize_merge_result<T: AsRef<[u8]>>(
     single_hunk: &Merge<T>,
     conflict_marker_style: ConflictMarkerStyle,
+    algorithm: DiffAlgorithm,
     output: &mut dyn Write,
 ) -> io::Result<()> {
-    let merge_result = files::merge(single_hunk);
+    let merge_result = files::merge(single_hunk, algorithm);
     match &merge_result {
         MergeResult::Resolved(content) => output.write_all(content),
         MergeResult::Conflict(hunks) => {
             let conflict_marker_len = choose_materialized_conflict_marker_len(single_hunk);
-            materialize_conflict_hunks(hunks, conflict_marker_style, conflict_marker_len, output)
+            materialize_conflict_hunks(
+                hunks,
+                conflict_marker_style,
+                conflict_marker_len,
+                algorithm,
+                output,
+            )
         }
     }
 }
@@ -376,22 +389,28 @@ pub fn materialize_merge_result_with_marker_len<T: AsRef<[u8]>>(
     single_hunk: &Merge<T>,
     conflict_marker_style: ConflictMarkerStyle,
     conflict_marker_len: usize,
+    algorithm: DiffAlgorithm,
     output: &mut dyn Write,
 ) -> io::Result<()> {
-    let merge_result = files::merge(single_hunk);
+    let merge_result = files::merge(single_hunk, algorithm);
     match &merge_result {
         MergeResult::Resolved(content) => output.write_all(content),
-        MergeResult::Conflict(hunks) => {
-            materialize_conflict_hunks(hunks, conflict_marker_style, conflict_marker_len, output)
-        }
+        MergeResult::Conflict(hunks) => materialize_conflict_hunks(
+            hunks,
+            conflict_marker_style,
+            conflict_marker_len,
+            algorithm,
+            output,
+        ),
     }
 }
 
 pub fn materialize_merge_result_to_bytes<T: AsRef<[u8]>>(
     single_hunk: &Merge<T>,
     conflict_marker_style: ConflictMarkerStyle,
+    algorithm: DiffAlgorithm,
 ) -> BString {
-    let merge_result = files::merge(single_hunk);
+    let merge_result = files::merge(single_hunk, algorithm);
     match merge_result {
         MergeResult::Resolved(content) => content,
         MergeResult::Conflict(hunks) => {
@@ -401,6 +420,7 @@ pub fn materialize_merge_result_to_bytes<T: AsRef<[u8]>>(
                 &hunks,
                 conflict_marker_style,
                 conflict_marker_len,
+                algorithm,
                 &mut output,
             )
             .expect("writing to an in-memory buffer should never fail");
@@ -413,8 +433,9 @@ pub fn materialize_merge_result_to_bytes_with_marker_len<T: AsRef<[u8]>>(
     single_hunk: &Merge<T>,
     conflict_marker_style: ConflictMarkerStyle,
     conflict_marker_len: usize,
+    algorithm: DiffAlgorithm,
 ) -> BString {
-    let merge_result = files::merge(single_hunk);
+    let merge_result = files::merge(single_hunk, algorithm);
     match merge_result {
         MergeResult::Resolved(content) => content,
         MergeResult::Conflict(hunks) => {
@@ -423,6 +444,7 @@ pub fn materialize_merge_result_to_bytes_with_marker_len<T: AsRef<[u8]>>(
                 &hunks,
                 conflict_marker_style,
                 conflict_marker_len,
+                algorithm,
                 &mut output,
             )
             .expect("writing to an in-memory buffer should never fail");
@@ -435,8 +457,16 @@ fn materialize_conflict_hunks(
     hunks: &[Merge<BString>],
     conflict_marker_style: ConflictMarkerStyle,
     conflict_marker_len: usize,
+    algorithm: DiffAlgorithm,
     output: &mut dyn Write,
 ) -> io::Result<()> {
+    let minimized_hunks;
+    let hunks = if conflict_marker_style == ConflictMarkerStyle::Zdiff3 {
+        minimized_hunks = minimize_conflict_hunks(hunks);
+        &minimized_hunks
+    } else {
+        hunks
+    };
     let num_conflicts = hunks
         .iter()
         .filter(|hunk| hunk.as_resolved().is_none())
@@ -451,7 +481,7 @@ fn materialize_conflict_hunks(
 
             match (conflict_marker_style, hunk.as_slice()) {
                 // 2-sided conflicts can use Git-style conflict markers
-                (ConflictMarkerStyle::Git, [left, base, right]) => {
+                (ConflictMarkerStyle::Git | ConflictMarkerStyle::Zdiff3, [left, base, right]) => {
                     materialize_git_style_conflict(
                         left,
                         base,
@@ -467,6 +497,7 @@ fn materialize_conflict_hunks(
                         &conflict_info,
                         conflict_marker_style,
                         conflict_marker_len,
+                        algorithm,
                         output,
                     )?;
                 }
@@ -476,6 +507,56 @@ fn materialize_conflict_hunks(
     Ok(())
 }
 
+/// Moves the lines common to all sides at the start and the end of each
+/// conflicting hunk out of the hunk.
+///
+/// The bases are left as is. When the result is parsed back, the moved lines
+/// become part of the bases too, which doesn't change the sides or how the
+/// conflict is materialized.
+fn minimize_conflict_hunks(hunks: &[Merge<BString>]) -> Vec<Merge<BString>> {
+    let mut minimized = Vec::with_capacity(hunks.len());
+    for hunk in hunks {
+        if hunk.is_resolved() {
+            minimized.push(hunk.clone());
+            continue;
+        }
+        let sides = hunk
+            .adds()
+            .map(|side| side.lines_with_terminator().collect_vec())
+            .collect_vec();
+        let min_len = sides.iter().map(|lines| lines.len()).min().unwrap();
+        let prefix_len = (0..min_len)
+            .take_while(|&i| sides.iter().map(|lines| lines[i]).all_equal())
+            .count();
+        let suffix_len = (1..=min_len - prefix_len)
+            .take_while(|&i| sides.iter().map(|lines| lines[lines.len() - i]).all_equal())
+            .count();
+        let prefix: BString = sides[0][..prefix_len].concat().into();
+        let suffix: BString = sides[0][sides[0].len() - suffix_len..].concat().into();
+        let trimmed_sides = hunk
+            .adds()
+            .map(|side| BString::from(&side[prefix.len()..side.len() - suffix.len()]))
+            .collect_vec();
+        // The sides can't be the same, or the hunk would have been resolved,
+        // but check just in case.
+        if (prefix.is_empty() && suffix.is_empty()) || trimmed_sides.iter().all_equal() {
+            minimized.push(hunk.clone());
+            continue;
+        }
+        if !prefix.is_empty() {
+            minimized.push(Merge::resolved(prefix));
+        }
+        minimized.push(Merge::from_removes_adds(
+            hunk.removes().cloned(),
+            trimmed_sides,
+        ));
+        if !suffix.is_empty() {
+            minimized.push(Merge::resolved(suffix));
+        }
+    }
+    minimized
+}
+
 fn materialize_git_style_conflict(
     left: &[u8],
     base: &[u8],
@@ -524,6 +605,7 @@ fn materialize_jj_style_conflict(
     conflict_info: &str,
     conflict_marker_style: ConflictMarkerStyle,
     conflict_marker_len: usize,
+    algorithm: DiffAlgorithm,
     output: &mut dyn Write,
 ) -> io::Result<()> {
     // Write a positive snapshot (side) of a conflict
@@ -610,12 +692,16 @@ fn materialize_jj_style_conflict(
             continue;
         }
 
-        let diff1 = Diff::by_line([&left, &right1]).hunks().collect_vec();
+        let diff1 = Diff::by_line_with([&left, &right1], CompareBytesExactly, algorithm)
+            .hunks()
+            .collect_vec();
         // Check if the diff against the next positive term is better. Since we want to
         // preserve the order of the terms, we don't match against any later positive
         // terms.
         if let Some(right2) = hunk.get_add(add_index + 1) {
-            let diff2 = Diff::by_line([&left, &right2]).hunks().collect_vec();
+            let diff2 = Diff::by_line_with([&left, &right2], CompareBytesExactly, algorithm)
+                .hunks()
+                .collect_vec();
             if diff_size(&diff2) < diff_size(&diff1) {
                 // If the next positive term is a better match, emit the current positive term
                 // as a snapshot and the next positive term as a diff.
@@ -934,6 +1020,7 @@ pub async fn update_from_content(
         &merge_hunk,
         conflict_marker_style,
         conflict_marker_len,
+        DiffAlgorithm::Default,
         &mut old_content,
     )
     .unwrap();
@@ -14,21 +14,39 @@
 
 //! Code for working with copies and renames.
 
+use std::cmp::Reverse;
 use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::io::Read as _;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::ready;
 use std::task::Context;
 use std::task::Poll;
 
 use futures::Stream;
+use futures::StreamExt as _;
+use itertools::Itertools as _;
+use pollster::FutureExt as _;
 
+use crate::backend::BackendError;
 use crate::backend::BackendResult;
+use crate::backend::CommitId;
 use crate::backend::CopyRecord;
+use crate::backend::FileId;
+use crate::backend::TreeValue;
+use crate::config::ConfigGetError;
+use crate::matchers::EverythingMatcher;
+use crate::matchers::Matcher;
+use crate::matchers::Visit;
 use crate::merge::MergedTreeValue;
 use crate::merged_tree::MergedTree;
+use crate::merged_tree::TreeDiffEntry;
 use crate::merged_tree::TreeDiffStream;
 use crate::repo_path::RepoPath;
 use crate::repo_path::RepoPathBuf;
+use crate::settings::UserSettings;
+use crate::store::Store;
 
 /// A collection of CopyRecords.
 #[derive(Default, Debug)]
@@ -214,3 +232,298 @@ impl Stream for CopiesTreeDiffStream<'_> {
         Poll::Ready(None)
     }
 }
+
+/// How copies and renames are detected.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CopyDetectionMethod {
+    /// Uses the copy information provided by the backend.
+    #[default]
+    Backend,
+    /// Compares the contents of added and removed files, regardless of the
+    /// backend.
+    Content,
+}
+
+/// Options for detecting copies and renames.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CopyDetectionOptions {
+    /// How copies and renames are detected.
+    pub method: CopyDetectionMethod,
+    /// Minimum similarity, in percent, for a file to be considered a copy of
+    /// another.
+    pub similarity_threshold: u32,
+    /// Inexact matching is skipped if the number of source and target pairs
+    /// exceeds the square of this.
+    pub max_files: usize,
+    /// Whether modified files are considered as copy sources. If false, only
+    /// renames are detected.
+    pub detect_copies: bool,
+}
+
+impl Default for CopyDetectionOptions {
+    fn default() -> Self {
+        CopyDetectionOptions {
+            method: CopyDetectionMethod::default(),
+            similarity_threshold: 50,
+            max_files: 1000,
+            detect_copies: true,
+        }
+    }
+}
+
+impl CopyDetectionOptions {
+    /// Loads the `copy-tracking` config.
+    pub fn from_settings(settings: &UserSettings) -> Result<Self, ConfigGetError> {
+        let similarity_threshold = settings.get("copy-tracking.similarity-threshold")?;
+        if similarity_threshold > 100 {
+            return Err(ConfigGetError::Type {
+                name: "copy-tracking.similarity-threshold".to_owned(),
+                error: "Must be a percentage between 0 and 100".into(),
+                source_path: None,
+            });
+        }
+        Ok(CopyDetectionOptions {
+            method: settings.get("copy-tracking.method")?,
+            similarity_threshold,
+            max_files: settings.get("copy-tracking.max-files")?,
+            detect_copies: settings.get_bool("copy-tracking.detect-copies")?,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct CopyCandidate {
+    path: RepoPathBuf,
+    id: FileId,
+    deleted: bool,
+}
+
+impl CopyCandidate {
+    /// Reads the file content. Returns `None` if the file isn't accessible.
+    fn read_content(&self, store: &Store) -> BackendResult<Option<Vec<u8>>> {
+        let mut reader = match store.read_file(&self.path, &self.id) {
+            Ok(reader) => reader,
+            Err(BackendError::ReadAccessDenied { .. }) => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        let mut content = vec![];
+        reader
+            .read_to_end(&mut content)
+            .map_err(|err| BackendError::ReadFile {
+                path: self.path.clone(),
+                id: self.id.clone(),
+                source: err.into(),
+            })?;
+        Ok(Some(content))
+    }
+}
+
+/// Detects copies and renames between the `root` and `head` commits by
+/// comparing file contents.
+///
+/// Files added in `head` are matched against files deleted in `head` and, if
+/// copies are enabled, files modified in `head`. Files with identical contents
+/// are paired first, and the remaining files are paired by line similarity
+/// unless there are too many of them. Each source is used at most once, and
+/// deleted files are preferred over modified files. Empty and inaccessible
+/// files are never paired.
+pub fn detect_copies(
+    store: &Arc<Store>,
+    options: &CopyDetectionOptions,
+    matcher: &dyn Matcher,
+    root: &CommitId,
+    head: &CommitId,
+) -> BackendResult<Vec<CopyRecord>> {
+    let root_tree = store.get_commit(root)?.tree()?;
+    let head_tree = store.get_commit(head)?.tree()?;
+    let diff_entries = |matcher: &dyn Matcher| -> BackendResult<Vec<_>> {
+        let entries: Vec<TreeDiffEntry> = root_tree
+            .diff_stream(&head_tree, matcher)
+            .collect()
+            .block_on();
+        entries
+            .into_iter()
+            .map(|TreeDiffEntry { path, values }| {
+                let (before, after) = values?;
+                Ok((path, before, after))
+            })
+            .collect()
+    };
+    let matched_entries = diff_entries(matcher)?;
+    let mut targets = vec![];
+    for (path, before, after) in &matched_entries {
+        if let (None, Some(id)) = (to_file_id(before), to_file_id(after)) {
+            if before.is_absent() {
+                targets.push(CopyCandidate {
+                    path: path.clone(),
+                    id,
+                    deleted: false,
+                });
+            }
+        }
+    }
+    if targets.is_empty() {
+        return Ok(vec![]);
+    }
+    // Sources may live outside of the matched paths, so the whole trees have
+    // to be compared unless the matcher already covered them.
+    let source_entries = if matcher.visit(RepoPath::root()) == Visit::AllRecursively {
+        matched_entries
+    } else {
+        diff_entries(&EverythingMatcher)?
+    };
+    let mut sources = vec![];
+    for (path, before, after) in source_entries {
+        match (to_file_id(&before), to_file_id(&after)) {
+            (Some(id), None) if after.is_absent() => {
+                sources.push(CopyCandidate {
+                    path,
+                    id,
+                    deleted: true,
+                });
+            }
+            (Some(id), Some(_)) if options.detect_copies => {
+                sources.push(CopyCandidate {
+                    path,
+                    id,
+                    deleted: false,
+                });
+            }
+            _ => {}
+        }
+    }
+    // Prefer renames over copies.
+    sources.sort_by_key(|source| !source.deleted);
+
+    let mut records = vec![];
+    let mut used_sources = vec![false; sources.len()];
+    let mut make_record = |target: &CopyCandidate, source: &CopyCandidate| {
+        records.push(CopyRecord {
+            target: target.path.clone(),
+            target_commit: head.clone(),
+            source: source.path.clone(),
+            source_file: source.id.clone(),
+            source_commit: root.clone(),
+        });
+    };
+
+    let mut sources_by_id: HashMap<&FileId, VecDeque<usize>> = HashMap::new();
+    for (i, source) in sources.iter().enumerate() {
+        sources_by_id.entry(&source.id).or_default().push_back(i);
+    }
+    let mut unmatched_targets = vec![];
+    for target in targets {
+        let exact_match = sources_by_id
+            .get_mut(&target.id)
+            .and_then(|indices| indices.front().copied());
+        match exact_match {
+            Some(i) if target.read_content(store)?.is_some_and(|c| !c.is_empty()) => {
+                sources_by_id.get_mut(&target.id).unwrap().pop_front();
+                make_record(&target, &sources[i]);
+                used_sources[i] = true;
+            }
+            _ => unmatched_targets.push(target),
+        }
+    }
+    let unmatched_sources = sources
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| !used_sources[i])
+        .map(|(_, source)| source)
+        .collect_vec();
+    let num_pairs = unmatched_targets.len() * unmatched_sources.len();
+    if num_pairs > 0 && num_pairs <= options.max_files.saturating_mul(options.max_files) {
+        let read_lines = |candidates: &[&CopyCandidate]| -> BackendResult<Vec<_>> {
+            candidates
+                .iter()
+                .map(|candidate| {
+                    let content = candidate.read_content(store)?.unwrap_or_default();
+                    Ok(LineCounts::new(content))
+                })
+                .collect()
+        };
+        let target_lines = read_lines(&unmatched_targets.iter().collect_vec())?;
+        let source_lines = read_lines(&unmatched_sources)?;
+        let mut pairs = vec![];
+        for (target_index, target) in target_lines.iter().enumerate() {
+            for (source_index, source) in source_lines.iter().enumerate() {
+                if let Some(score) = target.similarity(source, options.similarity_threshold) {
+                    pairs.push((score, target_index, source_index));
+                }
+            }
+        }
+        // Pairs are ordered by score, then by deleted sources first (which
+        // `sources` is sorted by), then by paths.
+        pairs.sort_by_key(|&(score, target_index, source_index)| {
+            (Reverse(score), source_index, target_index)
+        });
+        let mut used_targets = vec![false; unmatched_targets.len()];
+        let mut used_sources = vec![false; unmatched_sources.len()];
+        for (_, target_index, source_index) in pairs {
+            if used_targets[target_index] || used_sources[source_index] {
+                continue;
+            }
+            used_targets[target_index] = true;
+            used_sources[source_index] = true;
+            make_record(
+                &unmatched_targets[target_index],
+                unmatched_sources[source_index],
+            );
+        }
+    }
+    records.sort_by(|a, b| a.target.cmp(&b.target));
+    Ok(records)
+}
+
+fn to_file_id(value: &MergedTreeValue) -> Option<FileId> {
+    match value.as_resolved() {
+        Some(Some(TreeValue::File { id, .. })) => Some(id.clone()),
+        _ => None,
+    }
+}
+
+/// Multiset of lines in a file, used to estimate the similarity of files.
+struct LineCounts {
+    len: usize,
+    lines: HashMap<Vec<u8>, usize>,
+}
+
+impl LineCounts {
+    fn new(content: Vec<u8>) -> Self {
+        let mut lines = HashMap::new();
+        for line in content.split_inclusive(|&b| b == b'\n') {
+            *lines.entry(line.to_vec()).or_default() += 1;
+        }
+        LineCounts {
+            len: content.len(),
+            lines,
+        }
+    }
+
+    /// Returns the similarity score in basis points if the files are at least
+    /// `threshold` percent similar. The similarity is the size of the lines
+    /// common to both files relative to the size of the larger file.
+    fn similarity(&self, other: &Self, threshold: u32) -> Option<u64> {
+        let min_len = self.len.min(other.len) as u64;
+        let max_len = self.len.max(other.len) as u64;
+        let threshold = u64::from(threshold);
+        // Files of very different sizes can't be similar enough.
+        if min_len == 0 || min_len * 100 < threshold * max_len {
+            return None;
+        }
+        let (smaller, larger) = if self.lines.len() <= other.lines.len() {
+            (&self.lines, &other.lines)
+        } else {
+            (&other.lines, &self.lines)
+        };
+        let common: u64 = smaller
+            .iter()
+            .filter_map(|(line, &count)| {
+                let other_count = *larger.get(line)?;
+                Some((line.len() * count.min(other_count)) as u64)
+            })
+            .sum();
+        (common * 100 >= threshold * max_len).then(|| common * 10000 / max_len)
+    }
+}
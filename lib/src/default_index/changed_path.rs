@@ -0,0 +1,271 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Bloom filters of paths changed by commits.
+//!
+//! This is similar to the changed-path Bloom filters of Git's commit-graph.
+//! A filter contains the changed file paths and their ancestor directories,
+//! so a directory prefix can be tested without listing its files.
+
+#![allow(missing_docs)]
+
+use std::collections::HashSet;
+use std::iter;
+
+use futures::StreamExt as _;
+use itertools::Itertools as _;
+use pollster::FutureExt as _;
+
+use crate::backend::BackendResult;
+use crate::commit::Commit;
+use crate::fileset::FilePattern;
+use crate::fileset::FilesetExpression;
+use crate::index::Index;
+use crate::matchers::EverythingMatcher;
+use crate::repo_path::RepoPath;
+use crate::repo_path::RepoPathBuf;
+use crate::rewrite;
+
+/// Filter is disabled if the commit changes more paths than this.
+const MAX_CHANGED_PATHS: usize = 512;
+const BITS_PER_PATH: usize = 10;
+const NUM_HASHES: u64 = 7;
+
+/// The encoded data is followed by the Bloom filter bits.
+const FILTER_KIND_BLOOM: u8 = 0;
+/// Any path may be changed. Used if there are too many changed paths, or if
+/// the changed paths couldn't be calculated.
+const FILTER_KIND_ALL: u8 = 1;
+
+/// Encoded filter which matches any path.
+pub(super) fn all_paths_filter() -> Box<[u8]> {
+    Box::new([FILTER_KIND_ALL])
+}
+
+/// Builds encoded Bloom filter from the changed file `paths`.
+pub(super) fn build_changed_path_filter<'a>(
+    paths: impl IntoIterator<Item = &'a RepoPath>,
+) -> Box<[u8]> {
+    let mut keys: HashSet<&RepoPath> = HashSet::new();
+    for path in paths {
+        // The root path is implicitly contained if the filter isn't empty.
+        keys.extend(
+            iter::successors(Some(path), |path| path.parent()).filter(|path| !path.is_root()),
+        );
+        if keys.len() > MAX_CHANGED_PATHS {
+            return all_paths_filter();
+        }
+    }
+    let num_bytes = (keys.len() * BITS_PER_PATH).div_ceil(8);
+    let mut data = vec![0; 1 + num_bytes];
+    data[0] = FILTER_KIND_BLOOM;
+    let bits = &mut data[1..];
+    let num_bits = (num_bytes * 8) as u64;
+    for key in keys {
+        for bit in bit_positions(key, num_bits) {
+            bits[bit / 8] |= 1 << (bit % 8);
+        }
+    }
+    data.into()
+}
+
+/// Calculates paths changed by the `commit` compared to the merge of its
+/// parents. The result may include paths whose content would be identical
+/// after resolving conflicts.
+pub(super) fn changed_paths_of(
+    index: &dyn Index,
+    commit: &Commit,
+) -> BackendResult<Vec<RepoPathBuf>> {
+    let store = commit.store();
+    let parents: Vec<_> = commit.parents().try_collect()?;
+    if let [parent] = parents.as_slice() {
+        if parent.tree_id() == commit.tree_id() {
+            return Ok(vec![]);
+        }
+    }
+    let from_tree = rewrite::merge_commit_trees_no_resolve_without_repo(store, index, &parents)?;
+    let to_tree = commit.tree()?;
+    let mut tree_diff = from_tree.diff_stream(&to_tree, &EverythingMatcher);
+    async {
+        let mut paths = vec![];
+        while let Some(entry) = tree_diff.next().await {
+            entry.values?;
+            paths.push(entry.path);
+        }
+        Ok(paths)
+    }
+    .block_on()
+}
+
+/// Returns bit positions for the given `key` by double hashing.
+fn bit_positions(key: &RepoPath, num_bits: u64) -> impl Iterator<Item = usize> {
+    let hash = fnv1a_64(key.as_internal_file_string().as_bytes());
+    let h1 = hash & 0xffff_ffff;
+    let h2 = (hash >> 32) | 1;
+    (0..NUM_HASHES).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % num_bits) as usize)
+}
+
+// The hash function must be stable since the filter bits are persisted.
+fn fnv1a_64(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// Reference to encoded changed-path filter of a commit.
+#[derive(Clone, Copy, Debug)]
+pub struct ChangedPathFilter<'a>(&'a [u8]);
+
+impl<'a> ChangedPathFilter<'a> {
+    pub(super) fn new(data: &'a [u8]) -> Self {
+        ChangedPathFilter(data)
+    }
+
+    /// Returns `false` if the commit certainly doesn't change the `path` nor
+    /// files under the `path` directory.
+    pub fn might_contain(&self, path: &RepoPath) -> bool {
+        match self.0.split_first() {
+            Some((&FILTER_KIND_BLOOM, bits)) => {
+                if bits.is_empty() {
+                    false
+                } else if path.is_root() {
+                    true
+                } else {
+                    let num_bits = (bits.len() * 8) as u64;
+                    bit_positions(path, num_bits).all(|bit| bits[bit / 8] & (1 << (bit % 8)) != 0)
+                }
+            }
+            _ => true,
+        }
+    }
+
+    /// Returns `false` if the commit certainly doesn't change any of the
+    /// `paths`.
+    pub fn might_contain_any<'b>(&self, paths: impl IntoIterator<Item = &'b RepoPath>) -> bool {
+        paths.into_iter().any(|path| self.might_contain(path))
+    }
+}
+
+/// Returns paths one of which should be a prefix of any file matched by the
+/// `expression`. Returns `None` if there's no such bound.
+pub(super) fn fileset_path_prefixes(expression: &FilesetExpression) -> Option<Vec<&RepoPath>> {
+    match expression {
+        FilesetExpression::None => Some(vec![]),
+        FilesetExpression::All => None,
+        FilesetExpression::Pattern(pattern) => match pattern {
+            FilePattern::FilePath(path) | FilePattern::PrefixPath(path) => Some(vec![path]),
+            FilePattern::FileGlob { dir, .. } => Some(vec![dir]),
+        },
+        FilesetExpression::UnionAll(expressions) => {
+            let mut prefixes = vec![];
+            for expression in expressions {
+                prefixes.extend(fileset_path_prefixes(expression)?);
+            }
+            Some(prefixes)
+        }
+        FilesetExpression::Intersection(expression1, expression2) => {
+            fileset_path_prefixes(expression1).or_else(|| fileset_path_prefixes(expression2))
+        }
+        FilesetExpression::Difference(expression1, _) => fileset_path_prefixes(expression1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repo_path(value: &str) -> &RepoPath {
+        RepoPath::from_internal_string(value)
+    }
+
+    #[test]
+    fn test_changed_path_filter_empty() {
+        let data = build_changed_path_filter([]);
+        let filter = ChangedPathFilter::new(&data);
+        assert!(!filter.might_contain(RepoPath::root()));
+        assert!(!filter.might_contain(repo_path("foo")));
+    }
+
+    #[test]
+    fn test_changed_path_filter_contains_ancestors() {
+        let data = build_changed_path_filter([repo_path("dir/sub/file"), repo_path("top")]);
+        let filter = ChangedPathFilter::new(&data);
+        assert!(filter.might_contain(RepoPath::root()));
+        assert!(filter.might_contain(repo_path("dir")));
+        assert!(filter.might_contain(repo_path("dir/sub")));
+        assert!(filter.might_contain(repo_path("dir/sub/file")));
+        assert!(filter.might_contain(repo_path("top")));
+        assert!(filter.might_contain_any([repo_path("unknown"), repo_path("top")]));
+    }
+
+    #[test]
+    fn test_changed_path_filter_false_positive_rate() {
+        let paths = (0..100)
+            .map(|i| RepoPathBuf::from_internal_string(format!("file{i}")))
+            .collect_vec();
+        let data = build_changed_path_filter(paths.iter().map(|path| path.as_ref()));
+        let filter = ChangedPathFilter::new(&data);
+        assert!(paths.iter().all(|path| filter.might_contain(path)));
+        let num_false_positives = (0..1000)
+            .map(|i| RepoPathBuf::from_internal_string(format!("other{i}")))
+            .filter(|path| filter.might_contain(path))
+            .count();
+        // Expected rate is about 1% with 10 bits per path and 7 hashes.
+        assert!(num_false_positives < 50, "{num_false_positives}");
+    }
+
+    #[test]
+    fn test_changed_path_filter_too_many_paths() {
+        let paths = (0..=MAX_CHANGED_PATHS)
+            .map(|i| RepoPathBuf::from_internal_string(format!("file{i}")))
+            .collect_vec();
+        let data = build_changed_path_filter(paths.iter().map(|path| path.as_ref()));
+        assert_eq!(data, all_paths_filter());
+        let filter = ChangedPathFilter::new(&data);
+        assert!(filter.might_contain(repo_path("unknown")));
+    }
+
+    #[test]
+    fn test_fileset_path_prefixes() {
+        let file = |value: &str| FilesetExpression::file_path(repo_path(value).to_owned());
+        let prefix = |value: &str| FilesetExpression::prefix_path(repo_path(value).to_owned());
+        assert_eq!(
+            fileset_path_prefixes(&FilesetExpression::none()),
+            Some(vec![])
+        );
+        assert_eq!(fileset_path_prefixes(&FilesetExpression::all()), None);
+        assert_eq!(
+            fileset_path_prefixes(&FilesetExpression::union_all(vec![
+                file("a/b"),
+                prefix("c")
+            ])),
+            Some(vec![repo_path("a/b"), repo_path("c")])
+        );
+        assert_eq!(
+            fileset_path_prefixes(&FilesetExpression::all().intersection(prefix("c"))),
+            Some(vec![repo_path("c")])
+        );
+        assert_eq!(
+            fileset_path_prefixes(&FilesetExpression::all().difference(prefix("c"))),
+            None
+        );
+        assert_eq!(
+            fileset_path_prefixes(&FilesetExpression::union_all(vec![
+                file("a"),
+                FilesetExpression::all()
+            ])),
+            None
+        );
+    }
+}
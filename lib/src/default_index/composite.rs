@@ -19,6 +19,7 @@ use std::cmp::min;
 use std::cmp::Ordering;
 use std::collections::BTreeSet;
 use std::collections::BinaryHeap;
+use std::collections::HashMap;
 use std::collections::HashSet;
 use std::iter;
 use std::sync::Arc;
@@ -91,6 +92,19 @@ pub(super) trait IndexSegment: Send + Sync {
     fn num_parents(&self, local_pos: LocalPosition) -> u32;
 
     fn parent_positions(&self, local_pos: LocalPosition) -> SmallIndexPositionsVec;
+
+    /// Returns the encoded changed-path filter if recorded.
+    fn changed_path_filter(&self, local_pos: LocalPosition) -> Option<&[u8]>;
+
+    /// Returns sorted positions of the heads of the visible commits.
+    fn visible_heads(&self) -> Vec<IndexPosition>;
+
+    /// Returns the number of visible commits including the parent segments.
+    fn num_visible_commits(&self) -> u32;
+
+    /// Returns true if the visibility of the commit at `pos` differs from the
+    /// parent segment.
+    fn is_visibility_changed(&self, pos: IndexPosition) -> bool;
 }
 
 pub(super) type DynIndexSegment = dyn IndexSegment;
@@ -153,16 +167,28 @@ impl CompositeIndex {
         let num_commits = self.num_commits();
         let mut num_merges = 0;
         let mut max_generation_number = 0;
+        let mut num_changed_path_filters = 0;
         let mut change_ids = HashSet::new();
+        let mut visible_change_counts: HashMap<ChangeId, u32> = HashMap::new();
         for pos in 0..num_commits {
             let entry = self.entry_by_pos(IndexPosition(pos));
+            if self.is_visible_pos(entry.position()) {
+                *visible_change_counts.entry(entry.change_id()).or_default() += 1;
+            }
             max_generation_number = max(max_generation_number, entry.generation_number());
             if entry.num_parents() > 1 {
                 num_merges += 1;
             }
+            if entry.changed_path_filter().is_some() {
+                num_changed_path_filters += 1;
+            }
             change_ids.insert(entry.change_id());
         }
         let num_heads = u32::try_from(self.all_heads_pos().count()).unwrap();
+        let num_divergent_changes = visible_change_counts
+            .values()
+            .filter(|&&count| count > 1)
+            .count();
 
         let mut levels = self
             .ancestor_index_segments()
@@ -179,10 +205,24 @@ impl CompositeIndex {
             max_generation_number,
             num_heads,
             num_changes: change_ids.len().try_into().unwrap(),
+            num_changed_path_filters,
+            num_visible_heads: self.0.visible_heads().len().try_into().unwrap(),
+            num_visible_commits: self.0.num_visible_commits(),
+            num_divergent_changes: num_divergent_changes.try_into().unwrap(),
             levels,
         }
     }
 
+    /// Returns true if the commit at `pos` is an ancestor of the visible
+    /// heads recorded in the index.
+    pub(super) fn is_visible_pos(&self, pos: IndexPosition) -> bool {
+        self.ancestor_index_segments()
+            .filter(|segment| segment.is_visibility_changed(pos))
+            .count()
+            % 2
+            == 1
+    }
+
     pub fn entry_by_pos(&self, pos: IndexPosition) -> IndexEntry<'_> {
         self.ancestor_index_segments()
             .find_map(|segment| {
@@ -514,19 +554,56 @@ impl Index for &CompositeIndex {
 
 pub(super) struct ChangeIdIndexImpl<I> {
     index: I,
-    reachable_set: Mutex<AncestorsBitSet>,
+    /// Commits reachable from the heads, or `None` if the heads are the
+    /// visible heads recorded in the index.
+    reachable_set: Option<Mutex<AncestorsBitSet>>,
 }
 
 impl<I: AsCompositeIndex> ChangeIdIndexImpl<I> {
     pub fn new(index: I, heads: &mut dyn Iterator<Item = &CommitId>) -> ChangeIdIndexImpl<I> {
         let composite = index.as_composite();
+        let head_positions = heads
+            .map(|id| composite.commit_id_to_pos(id).unwrap())
+            .sorted_unstable()
+            .dedup()
+            .collect_vec();
+        if head_positions == composite.0.visible_heads() {
+            return ChangeIdIndexImpl {
+                index,
+                reachable_set: None,
+            };
+        }
         let mut reachable_set = AncestorsBitSet::with_capacity(composite.num_commits());
-        for id in heads {
-            reachable_set.add_head(composite.commit_id_to_pos(id).unwrap());
+        for pos in head_positions {
+            reachable_set.add_head(pos);
         }
         ChangeIdIndexImpl {
             index,
-            reachable_set: Mutex::new(reachable_set),
+            reachable_set: Some(Mutex::new(reachable_set)),
+        }
+    }
+}
+
+impl<I: AsCompositeIndex> ChangeIdIndexImpl<I> {
+    /// Filters the sorted `positions` down to the commits reachable from the
+    /// heads.
+    fn reachable_positions(&self, positions: &[IndexPosition]) -> Vec<IndexPosition> {
+        debug_assert!(positions.iter().tuple_windows().all(|(a, b)| a < b));
+        let index = self.index.as_composite();
+        if let Some(reachable_set) = &self.reachable_set {
+            let mut reachable_set = reachable_set.lock().unwrap();
+            reachable_set.visit_until(index, *positions.first().unwrap());
+            positions
+                .iter()
+                .copied()
+                .filter(|&pos| reachable_set.contains(pos))
+                .collect()
+        } else {
+            positions
+                .iter()
+                .copied()
+                .filter(|&pos| index.is_visible_pos(pos))
+                .collect()
         }
     }
 }
@@ -543,13 +620,10 @@ impl<I: AsCompositeIndex + Send + Sync> ChangeIdIndex for ChangeIdIndexImpl<I> {
         match index.resolve_change_id_prefix(prefix) {
             PrefixResolution::NoMatch => PrefixResolution::NoMatch,
             PrefixResolution::SingleMatch((_change_id, positions)) => {
-                debug_assert!(positions.iter().tuple_windows().all(|(a, b)| a < b));
-                let mut reachable_set = self.reachable_set.lock().unwrap();
-                reachable_set.visit_until(index, *positions.first().unwrap());
-                let reachable_commit_ids = positions
-                    .iter()
-                    .filter(|&&pos| reachable_set.contains(pos))
-                    .map(|&pos| index.entry_by_pos(pos).commit_id())
+                let reachable_commit_ids = self
+                    .reachable_positions(&positions)
+                    .into_iter()
+                    .map(|pos| index.entry_by_pos(pos).commit_id())
                     .collect_vec();
                 if reachable_commit_ids.is_empty() {
                     PrefixResolution::NoMatch
@@ -561,6 +635,20 @@ impl<I: AsCompositeIndex + Send + Sync> ChangeIdIndex for ChangeIdIndexImpl<I> {
         }
     }
 
+    // Unlike resolve_prefix(), this doesn't look up the commit ids of the
+    // entries. If the heads are the visible heads recorded in the index, the
+    // persisted visibility is used without walking the commit graph.
+    fn is_divergent(&self, change_id: &ChangeId) -> bool {
+        let index = self.index.as_composite();
+        let prefix = HexPrefix::from_bytes(change_id.as_bytes());
+        match index.resolve_change_id_prefix(&prefix) {
+            PrefixResolution::SingleMatch((_change_id, positions)) => {
+                positions.len() > 1 && self.reachable_positions(&positions).len() > 1
+            }
+            PrefixResolution::NoMatch | PrefixResolution::AmbiguousMatch => false,
+        }
+    }
+
     // Calculates the shortest prefix length of the given `change_id` among all
     // IDs, including hidden entries.
     //
@@ -585,6 +673,12 @@ pub struct IndexStats {
     pub max_generation_number: u32,
     pub num_heads: u32,
     pub num_changes: u32,
+    /// Number of commits which have changed-path filters.
+    pub num_changed_path_filters: u32,
+    pub num_visible_heads: u32,
+    pub num_visible_commits: u32,
+    /// Number of change ids associated with more than one visible commit.
+    pub num_divergent_changes: u32,
     pub levels: Vec<IndexLevelStats>,
 }
 
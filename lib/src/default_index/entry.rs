@@ -21,6 +21,7 @@ use std::hash::Hasher;
 
 use smallvec::SmallVec;
 
+use super::changed_path::ChangedPathFilter;
 use super::composite::CompositeIndex;
 use super::composite::DynIndexSegment;
 use crate::backend::ChangeId;
@@ -114,6 +115,13 @@ impl<'a> IndexEntry<'a> {
         self.source.parent_positions(self.local_pos)
     }
 
+    /// Returns the filter of paths changed by this commit if recorded.
+    pub fn changed_path_filter(&self) -> Option<ChangedPathFilter<'a>> {
+        self.source
+            .changed_path_filter(self.local_pos)
+            .map(ChangedPathFilter::new)
+    }
+
     pub fn parents(&self) -> impl ExactSizeIterator<Item = IndexEntry<'a>> + use<'a> {
         let composite = CompositeIndex::new(self.source);
         self.parent_positions()
@@ -21,6 +21,7 @@
 
 #![allow(missing_docs)]
 
+mod changed_path;
 mod composite;
 mod entry;
 mod mutable;
@@ -30,6 +31,7 @@ pub mod revset_engine;
 mod revset_graph_iterator;
 mod store;
 
+pub use self::changed_path::ChangedPathFilter;
 pub use self::composite::AsCompositeIndex;
 pub use self::composite::CompositeIndex;
 pub use self::composite::IndexLevelStats;
@@ -31,6 +31,7 @@ use smallvec::smallvec;
 use smallvec::SmallVec;
 use tempfile::NamedTempFile;
 
+use super::changed_path;
 use super::composite::AsCompositeIndex;
 use super::composite::ChangeIdIndexImpl;
 use super::composite::CompositeIndex;
@@ -44,6 +45,7 @@ use super::readonly::DefaultReadonlyIndex;
 use super::readonly::ReadonlyIndexSegment;
 use super::readonly::INDEX_SEGMENT_FILE_FORMAT_VERSION;
 use super::readonly::OVERFLOW_FLAG;
+use super::rev_walk::RevWalkBuilder;
 use crate::backend::ChangeId;
 use crate::backend::CommitId;
 use crate::commit::Commit;
@@ -68,6 +70,7 @@ struct MutableGraphEntry {
     change_id: ChangeId,
     generation_number: u32,
     parent_positions: SmallIndexPositionsVec,
+    changed_path_filter: Option<Box<[u8]>>,
 }
 
 pub(super) struct MutableIndexSegment {
@@ -78,6 +81,13 @@ pub(super) struct MutableIndexSegment {
     graph: Vec<MutableGraphEntry>,
     commit_lookup: BTreeMap<CommitId, LocalPosition>,
     change_lookup: BTreeMap<ChangeId, SmallLocalPositionsVec>,
+    /// Whether to calculate changed-path filters of new commits.
+    track_changed_paths: bool,
+    visible_heads: Vec<IndexPosition>,
+    num_visible_commits: u32,
+    /// Sorted positions of commits whose visibility differs from the parent
+    /// segment.
+    visibility_changes: Vec<IndexPosition>,
 }
 
 impl MutableIndexSegment {
@@ -90,6 +100,10 @@ impl MutableIndexSegment {
             graph: vec![],
             commit_lookup: BTreeMap::new(),
             change_lookup: BTreeMap::new(),
+            track_changed_paths: false,
+            visible_heads: vec![],
+            num_visible_commits: 0,
+            visibility_changes: vec![],
         }
     }
 
@@ -97,6 +111,11 @@ impl MutableIndexSegment {
         let num_parent_commits = parent_file.as_composite().num_commits();
         let commit_id_length = parent_file.commit_id_length();
         let change_id_length = parent_file.change_id_length();
+        // Keep tracking changed paths if the parent does.
+        let track_changed_paths = parent_file.has_changed_path_filters();
+        // Nothing changes visibility until new heads are set.
+        let visible_heads = parent_file.visible_heads();
+        let num_visible_commits = parent_file.num_visible_commits();
         Self {
             parent_file: Some(parent_file),
             num_parent_commits,
@@ -105,26 +124,99 @@ impl MutableIndexSegment {
             graph: vec![],
             commit_lookup: BTreeMap::new(),
             change_lookup: BTreeMap::new(),
+            track_changed_paths,
+            visible_heads,
+            num_visible_commits,
+            visibility_changes: vec![],
         }
     }
 
+    pub(super) fn set_track_changed_paths(&mut self, enabled: bool) {
+        self.track_changed_paths = enabled;
+    }
+
     pub(super) fn as_composite(&self) -> &CompositeIndex {
         CompositeIndex::new(self)
     }
 
+    /// Records the ancestors of the `heads` as visible commits.
+    ///
+    /// The changes are calculated relative to the visible heads of the parent
+    /// segment, so the cost is proportional to the number of commits whose
+    /// visibility changed.
+    pub(super) fn set_visible_heads<'a>(&mut self, heads: impl IntoIterator<Item = &'a CommitId>) {
+        let composite = self.as_composite();
+        let new_heads = heads
+            .into_iter()
+            // Unindexed heads can't be recorded, but the view shouldn't
+            // contain such commits in practice.
+            .filter_map(|id| composite.commit_id_to_pos(id))
+            .sorted_unstable()
+            .dedup()
+            .collect_vec();
+        let (old_heads, old_num_visible_commits) = match &self.parent_file {
+            Some(parent_file) => (
+                parent_file.visible_heads(),
+                parent_file.num_visible_commits(),
+            ),
+            None => (vec![], 0),
+        };
+        let added = RevWalkBuilder::new(composite)
+            .wanted_heads(new_heads.clone())
+            .unwanted_roots(old_heads.clone())
+            .ancestors()
+            .collect_vec();
+        let removed = RevWalkBuilder::new(composite)
+            .wanted_heads(old_heads)
+            .unwanted_roots(new_heads.clone())
+            .ancestors()
+            .collect_vec();
+        let num_visible_commits = old_num_visible_commits + u32::try_from(added.len()).unwrap()
+            - u32::try_from(removed.len()).unwrap();
+        let mut visibility_changes = itertools::chain(added, removed).collect_vec();
+        visibility_changes.sort_unstable();
+        self.visible_heads = new_heads;
+        self.num_visible_commits = num_visible_commits;
+        self.visibility_changes = visibility_changes;
+    }
+
     pub(super) fn add_commit(&mut self, commit: &Commit) {
-        self.add_commit_data(
+        if self.as_composite().has_id(commit.id()) {
+            return;
+        }
+        let changed_path_filter = self.track_changed_paths.then(|| {
+            match changed_path::changed_paths_of(&self.as_composite(), commit) {
+                Ok(paths) => changed_path::build_changed_path_filter(paths.iter().map(AsRef::as_ref)),
+                Err(err) => {
+                    tracing::warn!(?err, commit_id = ?commit.id(), "failed to calculate changed paths");
+                    changed_path::all_paths_filter()
+                }
+            }
+        });
+        self.add_entry(
             commit.id().clone(),
             commit.change_id().clone(),
             commit.parent_ids(),
+            changed_path_filter,
         );
     }
 
+    #[cfg(test)]
     pub(super) fn add_commit_data(
         &mut self,
         commit_id: CommitId,
         change_id: ChangeId,
         parent_ids: &[CommitId],
+    ) {
+        self.add_entry(commit_id, change_id, parent_ids, None);
+    }
+
+    fn add_entry(
+        &mut self,
+        commit_id: CommitId,
+        change_id: ChangeId,
+        parent_ids: &[CommitId],
+        changed_path_filter: Option<Box<[u8]>>,
     ) {
         if self.as_composite().has_id(&commit_id) {
             return;
@@ -134,6 +226,7 @@ impl MutableIndexSegment {
             change_id,
             generation_number: 0,
             parent_positions: SmallVec::new(),
+            changed_path_filter,
         };
         for parent_id in parent_ids {
             let parent_entry = self
@@ -162,7 +255,15 @@ impl MutableIndexSegment {
         for pos in other_segment.num_parent_commits()..other.num_commits() {
             let entry = other.entry_by_pos(IndexPosition(pos));
             let parent_ids = entry.parents().map(|entry| entry.commit_id()).collect_vec();
-            self.add_commit_data(entry.commit_id(), entry.change_id(), &parent_ids);
+            let changed_path_filter = other_segment
+                .changed_path_filter(LocalPosition(pos - other_segment.num_parent_commits()))
+                .map(Box::from);
+            self.add_entry(
+                entry.commit_id(),
+                entry.change_id(),
+                &parent_ids,
+                changed_path_filter,
+            );
         }
     }
 
@@ -228,6 +329,23 @@ impl MutableIndexSegment {
         buf.extend(0_u32.to_le_bytes());
         let change_overflow_offset = buf.len();
         buf.extend(0_u32.to_le_bytes());
+        let has_changed_path_filters = self
+            .graph
+            .iter()
+            .any(|entry| entry.changed_path_filter.is_some());
+        let num_changed_path_filters = if has_changed_path_filters {
+            num_commits
+        } else {
+            0
+        };
+        buf.extend(num_changed_path_filters.to_le_bytes());
+        let changed_path_data_size_offset = buf.len();
+        buf.extend(0_u32.to_le_bytes());
+        let num_visible_heads = u32::try_from(self.visible_heads.len()).unwrap();
+        buf.extend(num_visible_heads.to_le_bytes());
+        buf.extend(self.num_visible_commits.to_le_bytes());
+        let num_visibility_changes = u32::try_from(self.visibility_changes.len()).unwrap();
+        buf.extend(num_visibility_changes.to_le_bytes());
 
         // Positions of change ids in the sorted table
         let change_id_pos_map: HashMap<&ChangeId, u32> = self
@@ -312,6 +430,40 @@ impl MutableIndexSegment {
         for LocalPosition(pos) in change_overflow {
             buf.extend(pos.to_le_bytes());
         }
+
+        for IndexPosition(pos) in &self.visible_heads {
+            buf.extend(pos.to_le_bytes());
+        }
+        for IndexPosition(pos) in &self.visibility_changes {
+            buf.extend(pos.to_le_bytes());
+        }
+
+        if has_changed_path_filters {
+            // Commits imported from segments without changed paths may be
+            // mixed in. Those can't be filtered out.
+            let all_paths_filter = changed_path::all_paths_filter();
+            let filters = self
+                .graph
+                .iter()
+                .map(|entry| {
+                    entry
+                        .changed_path_filter
+                        .as_ref()
+                        .unwrap_or(&all_paths_filter)
+                })
+                .collect_vec();
+            let mut end: u32 = 0;
+            for filter in &filters {
+                end = end
+                    .checked_add(u32::try_from(filter.len()).unwrap())
+                    .unwrap();
+                buf.extend(end.to_le_bytes());
+            }
+            buf[changed_path_data_size_offset..][..4].copy_from_slice(&end.to_le_bytes());
+            for filter in filters {
+                buf.extend_from_slice(filter);
+            }
+        }
     }
 
     /// If the MutableIndex has more than half the commits of its parent
@@ -341,16 +493,34 @@ impl MutableIndexSegment {
         } else {
             MutableIndexSegment::full(self.commit_id_length, self.change_id_length)
         };
+        squashed.set_track_changed_paths(self.track_changed_paths);
         for parent_file in files_to_squash.iter().rev() {
             squashed.add_commits_from(parent_file.as_ref());
         }
         squashed.add_commits_from(&self);
+        // Changes cancel out if the visibility is flipped an even number of
+        // times.
+        squashed.visibility_changes = files_to_squash
+            .iter()
+            .map(|parent_file| parent_file.visibility_changes())
+            .fold(self.visibility_changes, |acc, changes| {
+                acc.into_iter()
+                    .merge(changes)
+                    .dedup_with_count()
+                    .filter(|(count, _)| count % 2 == 1)
+                    .map(|(_, pos)| pos)
+                    .collect()
+            });
+        squashed.visible_heads = self.visible_heads;
+        squashed.num_visible_commits = self.num_visible_commits;
         squashed
     }
 
     pub(super) fn save_in(self, dir: &Path) -> io::Result<Arc<ReadonlyIndexSegment>> {
-        if self.num_local_commits() == 0 && self.parent_file.is_some() {
-            return Ok(self.parent_file.unwrap());
+        if let Some(parent_file) = &self.parent_file {
+            if self.num_local_commits() == 0 && self.visibility_changes.is_empty() {
+                return Ok(parent_file.clone());
+            }
         }
 
         let mut buf = Vec::new();
@@ -453,6 +623,24 @@ impl IndexSegment for MutableIndexSegment {
     fn parent_positions(&self, local_pos: LocalPosition) -> SmallIndexPositionsVec {
         self.graph[local_pos.0 as usize].parent_positions.clone()
     }
+
+    fn changed_path_filter(&self, local_pos: LocalPosition) -> Option<&[u8]> {
+        self.graph[local_pos.0 as usize]
+            .changed_path_filter
+            .as_deref()
+    }
+
+    fn visible_heads(&self) -> Vec<IndexPosition> {
+        self.visible_heads.clone()
+    }
+
+    fn num_visible_commits(&self) -> u32 {
+        self.num_visible_commits
+    }
+
+    fn is_visibility_changed(&self, pos: IndexPosition) -> bool {
+        self.visibility_changes.binary_search(&pos).is_ok()
+    }
 }
 
 /// In-memory mutable records for the on-disk commit index backend.
@@ -469,6 +657,12 @@ impl DefaultMutableIndex {
         DefaultMutableIndex(mutable_segment)
     }
 
+    /// Enables or disables calculation of changed-path filters for commits
+    /// to be added.
+    pub(super) fn set_track_changed_paths(&mut self, enabled: bool) {
+        self.0.set_track_changed_paths(enabled);
+    }
+
     #[cfg(test)]
     pub(crate) fn add_commit_data(
         &mut self,
@@ -479,7 +673,14 @@ impl DefaultMutableIndex {
         self.0.add_commit_data(commit_id, change_id, parent_ids);
     }
 
-    pub(super) fn squash_and_save_in(self, dir: &Path) -> io::Result<Arc<ReadonlyIndexSegment>> {
+    /// Records the ancestors of the `visible_heads` as visible, squashes
+    /// segments if needed, and saves the result in `dir`.
+    pub(super) fn squash_and_save_in<'a>(
+        mut self,
+        dir: &Path,
+        visible_heads: impl IntoIterator<Item = &'a CommitId>,
+    ) -> io::Result<Arc<ReadonlyIndexSegment>> {
+        self.0.set_visible_heads(visible_heads);
         self.0.maybe_squash_with_ancestors().save_in(dir)
     }
 }
@@ -104,7 +104,7 @@ impl ReadonlyIndexLoadError {
 }
 
 /// Current format version of the index segment file.
-pub(crate) const INDEX_SEGMENT_FILE_FORMAT_VERSION: u32 = 6;
+pub(crate) const INDEX_SEGMENT_FILE_FORMAT_VERSION: u32 = 8;
 
 /// If set, the value is stored in the overflow table.
 pub(crate) const OVERFLOW_FLAG: u32 = 0x8000_0000;
@@ -186,6 +186,11 @@ impl CommitGraphEntry<'_> {
 /// u32: number of local change ids
 /// u32: number of overflow parent entries
 /// u32: number of overflow change id positions
+/// u32: number of changed-path filters (0 or number of local commit entries)
+/// u32: size of changed-path filter data in bytes
+/// u32: number of visible heads
+/// u32: number of visible commits (including parent segments)
+/// u32: number of visibility changes
 /// for each entry, in some topological order with parents first:
 ///   u32: generation number
 ///   if number of parents <= 2:
@@ -211,8 +216,19 @@ impl CommitGraphEntry<'_> {
 ///   u32: global index position
 /// for each overflow change id entry:
 ///   u32: local position in the graph entries table
+/// for each visible head, sorted:
+///   u32: global index position
+/// for each commit whose visibility differs from the parent segment, sorted:
+///   u32: global index position
+/// for each changed-path filter, in the graph entries order:
+///   u32: end offset in the changed-path filter data
+/// <size of changed-path filter data number of bytes>: changed-path filters
 /// ```
 ///
+/// The visible commits are the ancestors of the visible heads, which are the
+/// view heads of the operation the segment was written for. Visibility of a
+/// commit is flipped by each segment which records the position.
+///
 /// Note that u32 fields are 4-byte aligned so long as the parent file name
 /// (which is hexadecimal hash) and commit/change ids aren't of exotic length.
 // TODO: replace the table by a trie so we don't have to repeat the full commit
@@ -228,12 +244,19 @@ pub(super) struct ReadonlyIndexSegment {
     num_local_commits: u32,
     num_local_change_ids: u32,
     num_change_overflow_entries: u32,
+    num_changed_path_filters: u32,
+    num_visible_commits: u32,
+    num_visibility_changes: u32,
     // Base data offsets in bytes:
     commit_lookup_base: usize,
     change_id_table_base: usize,
     change_pos_table_base: usize,
     parent_overflow_base: usize,
     change_overflow_base: usize,
+    visible_heads_base: usize,
+    visibility_changes_base: usize,
+    changed_path_lookup_base: usize,
+    changed_path_data_base: usize,
     data: Vec<u8>,
 }
 
@@ -329,6 +352,17 @@ impl ReadonlyIndexSegment {
         let num_local_change_ids = read_u32(file)?;
         let num_parent_overflow_entries = read_u32(file)?;
         let num_change_overflow_entries = read_u32(file)?;
+        let num_changed_path_filters = read_u32(file)?;
+        let changed_path_data_size = read_u32(file)?;
+        let num_visible_heads = read_u32(file)?;
+        let num_visible_commits = read_u32(file)?;
+        let num_visibility_changes = read_u32(file)?;
+        if num_changed_path_filters != 0 && num_changed_path_filters != num_local_commits {
+            return Err(ReadonlyIndexLoadError::invalid_data(
+                name,
+                "unexpected number of changed-path filters",
+            ));
+        }
         let mut data = vec![];
         file.read_to_end(&mut data).map_err(from_io_err)?;
 
@@ -339,6 +373,10 @@ impl ReadonlyIndexSegment {
         let change_pos_table_size = (num_local_change_ids as usize) * 4;
         let parent_overflow_size = (num_parent_overflow_entries as usize) * 4;
         let change_overflow_size = (num_change_overflow_entries as usize) * 4;
+        let visible_heads_size = (num_visible_heads as usize) * 4;
+        let visibility_changes_size = (num_visibility_changes as usize) * 4;
+        let changed_path_lookup_size = (num_changed_path_filters as usize) * 4;
+        let changed_path_data_size = changed_path_data_size as usize;
 
         let graph_base = 0;
         let commit_lookup_base = graph_base + graph_size;
@@ -346,7 +384,11 @@ impl ReadonlyIndexSegment {
         let change_pos_table_base = change_id_table_base + change_id_table_size;
         let parent_overflow_base = change_pos_table_base + change_pos_table_size;
         let change_overflow_base = parent_overflow_base + parent_overflow_size;
-        let expected_size = change_overflow_base + change_overflow_size;
+        let visible_heads_base = change_overflow_base + change_overflow_size;
+        let visibility_changes_base = visible_heads_base + visible_heads_size;
+        let changed_path_lookup_base = visibility_changes_base + visibility_changes_size;
+        let changed_path_data_base = changed_path_lookup_base + changed_path_lookup_size;
+        let expected_size = changed_path_data_base + changed_path_data_size;
 
         if data.len() != expected_size {
             return Err(ReadonlyIndexLoadError::invalid_data(
@@ -364,11 +406,18 @@ impl ReadonlyIndexSegment {
             num_local_commits,
             num_local_change_ids,
             num_change_overflow_entries,
+            num_changed_path_filters,
+            num_visible_commits,
+            num_visibility_changes,
             commit_lookup_base,
             change_id_table_base,
             change_pos_table_base,
             parent_overflow_base,
             change_overflow_base,
+            visible_heads_base,
+            visibility_changes_base,
+            changed_path_lookup_base,
+            changed_path_data_base,
             data,
         }))
     }
@@ -389,6 +438,12 @@ impl ReadonlyIndexSegment {
         self.change_id_length
     }
 
+    /// Returns true if this segment records changed-path filters of the local
+    /// commits.
+    pub(super) fn has_changed_path_filters(&self) -> bool {
+        self.num_changed_path_filters > 0
+    }
+
     fn graph_entry(&self, local_pos: LocalPosition) -> CommitGraphEntry {
         let table = &self.data[..self.commit_lookup_base];
         let entry_size = CommitGraphEntry::size(self.commit_id_length);
@@ -421,6 +476,26 @@ impl ReadonlyIndexSegment {
         ChangeLocalPosition(u32::from_le_bytes(table[offset..][..4].try_into().unwrap()))
     }
 
+    /// Returns sorted positions of commits whose visibility differs from the
+    /// parent segment.
+    pub(super) fn visibility_changes(&self) -> Vec<IndexPosition> {
+        (0..self.num_visibility_changes)
+            .map(|change_pos| self.visibility_change_pos(change_pos))
+            .collect()
+    }
+
+    fn visibility_change_pos(&self, change_pos: u32) -> IndexPosition {
+        let table = &self.data[self.visibility_changes_base..self.changed_path_lookup_base];
+        let offset = (change_pos as usize) * 4;
+        IndexPosition(u32::from_le_bytes(table[offset..][..4].try_into().unwrap()))
+    }
+
+    fn changed_path_filter_end(&self, local_pos: LocalPosition) -> usize {
+        let table = &self.data[self.changed_path_lookup_base..self.changed_path_data_base];
+        let offset = (local_pos.0 as usize) * 4;
+        u32::from_le_bytes(table[offset..][..4].try_into().unwrap()) as usize
+    }
+
     fn overflow_parents(&self, overflow_pos: u32, num_parents: u32) -> SmallIndexPositionsVec {
         let table = &self.data[self.parent_overflow_base..self.change_overflow_base];
         let offset = (overflow_pos as usize) * 4;
@@ -436,7 +511,7 @@ impl ReadonlyIndexSegment {
         &self,
         overflow_pos: u32,
     ) -> impl Iterator<Item = LocalPosition> + use<'_> {
-        let table = &self.data[self.change_overflow_base..];
+        let table = &self.data[self.change_overflow_base..self.visible_heads_base];
         let offset = (overflow_pos as usize) * 4;
         table[offset..]
             .chunks_exact(4)
@@ -587,6 +662,37 @@ impl IndexSegment for ReadonlyIndexSegment {
             self.overflow_parents(overflow_pos, num_parents)
         }
     }
+
+    fn visible_heads(&self) -> Vec<IndexPosition> {
+        let table = &self.data[self.visible_heads_base..self.visibility_changes_base];
+        table
+            .chunks_exact(4)
+            .map(|chunk| IndexPosition(u32::from_le_bytes(chunk.try_into().unwrap())))
+            .collect()
+    }
+
+    fn num_visible_commits(&self) -> u32 {
+        self.num_visible_commits
+    }
+
+    fn is_visibility_changed(&self, pos: IndexPosition) -> bool {
+        binary_search_pos_by(self.num_visibility_changes, |change_pos| {
+            self.visibility_change_pos(change_pos).cmp(&pos)
+        })
+        .ok()
+        .is_some()
+    }
+
+    fn changed_path_filter(&self, local_pos: LocalPosition) -> Option<&[u8]> {
+        if !self.has_changed_path_filters() {
+            return None;
+        }
+        let start = local_pos.0.checked_sub(1).map_or(0, |prev_pos| {
+            self.changed_path_filter_end(LocalPosition(prev_pos))
+        });
+        let end = self.changed_path_filter_end(local_pos);
+        Some(&self.data[self.changed_path_data_base..][start..end])
+    }
 }
 
 /// Commit index backend which stores data on local disk.
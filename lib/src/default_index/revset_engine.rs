@@ -31,6 +31,7 @@ use futures::StreamExt as _;
 use itertools::Itertools as _;
 use pollster::FutureExt as _;
 
+use super::changed_path;
 use super::rev_walk::EagerRevWalk;
 use super::rev_walk::PeekableRevWalk;
 use super::rev_walk::RevWalk;
@@ -47,13 +48,17 @@ use crate::conflicts::ConflictMarkerStyle;
 use crate::conflicts::MaterializedTreeValue;
 use crate::default_index::AsCompositeIndex;
 use crate::default_index::CompositeIndex;
+use crate::default_index::IndexEntry;
 use crate::default_index::IndexPosition;
+use crate::diff::DiffAlgorithm;
+use crate::fileset::FilesetExpression;
 use crate::graph::GraphNode;
 use crate::matchers::Matcher;
 use crate::matchers::Visit;
 use crate::merged_tree::resolve_file_values;
 use crate::object_id::ObjectId as _;
 use crate::repo_path::RepoPath;
+use crate::repo_path::RepoPathBuf;
 use crate::revset::ResolvedExpression;
 use crate::revset::ResolvedPredicateExpression;
 use crate::revset::Revset;
@@ -1222,8 +1227,12 @@ fn build_predicate_fn(
         }
         RevsetFilterPredicate::File(expr) => {
             let matcher: Rc<dyn Matcher> = expr.to_matcher().into();
+            let path_prefixes = owned_fileset_path_prefixes(expr);
             box_pure_predicate_fn(move |index, pos| {
                 let entry = index.entry_by_pos(pos);
+                if !might_change_paths(&entry, path_prefixes.as_deref()) {
+                    return Ok(false);
+                }
                 let commit = store.get_commit(&entry.commit_id())?;
                 Ok(has_diff_from_parent(&store, index, &commit, &*matcher)?)
             })
@@ -1231,8 +1240,12 @@ fn build_predicate_fn(
         RevsetFilterPredicate::DiffContains { text, files } => {
             let text_pattern = text.clone();
             let files_matcher: Rc<dyn Matcher> = files.to_matcher().into();
+            let path_prefixes = owned_fileset_path_prefixes(files);
             box_pure_predicate_fn(move |index, pos| {
                 let entry = index.entry_by_pos(pos);
+                if !might_change_paths(&entry, path_prefixes.as_deref()) {
+                    return Ok(false);
+                }
                 let commit = store.get_commit(&entry.commit_id())?;
                 Ok(matches_diff_from_parent(
                     &store,
@@ -1259,6 +1272,22 @@ fn build_predicate_fn(
     }
 }
 
+fn owned_fileset_path_prefixes(expression: &FilesetExpression) -> Option<Vec<RepoPathBuf>> {
+    let prefixes = changed_path::fileset_path_prefixes(expression)?;
+    Some(prefixes.into_iter().map(ToOwned::to_owned).collect())
+}
+
+/// Returns `false` if the changed-path filter of the `entry` proves that none
+/// of the `path_prefixes` is changed. `None` means any path may match.
+fn might_change_paths(entry: &IndexEntry, path_prefixes: Option<&[RepoPathBuf]>) -> bool {
+    match (entry.changed_path_filter(), path_prefixes) {
+        (Some(filter), Some(prefixes)) => {
+            filter.might_contain_any(prefixes.iter().map(AsRef::as_ref))
+        }
+        _ => true,
+    }
+}
+
 fn has_diff_from_parent(
     store: &Arc<Store>,
     index: &CompositeIndex,
@@ -1358,7 +1387,12 @@ fn to_file_content(path: &RepoPath, value: MaterializedTreeValue) -> BackendResu
         MaterializedTreeValue::Symlink { id: _, target } => Ok(target.into_bytes()),
         MaterializedTreeValue::GitSubmodule(_) => Ok(vec![]),
         MaterializedTreeValue::FileConflict { contents, .. } => {
-            Ok(materialize_merge_result_to_bytes(&contents, ConflictMarkerStyle::default()).into())
+            Ok(materialize_merge_result_to_bytes(
+                &contents,
+                ConflictMarkerStyle::default(),
+                DiffAlgorithm::Default,
+            )
+            .into())
         }
         MaterializedTreeValue::OtherConflict { .. } => Ok(vec![]),
         MaterializedTreeValue::Tree(id) => {
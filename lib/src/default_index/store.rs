@@ -91,6 +91,7 @@ pub enum DefaultIndexStoreError {
 #[derive(Debug)]
 pub struct DefaultIndexStore {
     dir: PathBuf,
+    track_changed_paths: bool,
 }
 
 impl DefaultIndexStore {
@@ -101,6 +102,7 @@ impl DefaultIndexStore {
     pub fn init(dir: &Path) -> Result<Self, DefaultIndexStoreInitError> {
         let store = DefaultIndexStore {
             dir: dir.to_owned(),
+            track_changed_paths: false,
         };
         store.ensure_base_dirs()?;
         Ok(store)
@@ -109,9 +111,20 @@ impl DefaultIndexStore {
     pub fn load(dir: &Path) -> DefaultIndexStore {
         DefaultIndexStore {
             dir: dir.to_owned(),
+            track_changed_paths: false,
         }
     }
 
+    /// Enables recording of changed-path filters when the index is built
+    /// from scratch.
+    ///
+    /// Existing index segments aren't affected. Use `reinit()` to rebuild
+    /// the index with (or without) changed-path filters.
+    pub fn with_changed_paths(mut self, enabled: bool) -> Self {
+        self.track_changed_paths = enabled;
+        self
+    }
+
     pub fn reinit(&self) -> Result<(), DefaultIndexStoreInitError> {
         // Create base directories in case the store was initialized by old jj.
         self.ensure_base_dirs()?;
@@ -222,6 +235,7 @@ impl DefaultIndexStore {
             None => {
                 maybe_parent_file = None;
                 mutable_index = DefaultMutableIndex::full(commit_id_length, change_id_length);
+                mutable_index.set_track_changed_paths(self.track_changed_paths);
             }
             Some(parent_op_id) => {
                 let parent_file = self.load_index_segments_at_operation(
@@ -273,7 +287,7 @@ impl DefaultIndexStore {
             mutable_index.add_commit(commit);
         }
 
-        let index_file = self.save_mutable_index(mutable_index, operation.id())?;
+        let index_file = self.save_mutable_index(mutable_index, operation)?;
         tracing::info!(
             ?index_file,
             commits_count = commits.len(),
@@ -286,10 +300,12 @@ impl DefaultIndexStore {
     fn save_mutable_index(
         &self,
         mutable_index: DefaultMutableIndex,
-        op_id: &OperationId,
+        operation: &Operation,
     ) -> Result<Arc<ReadonlyIndexSegment>, DefaultIndexStoreError> {
+        let op_id = operation.id();
+        let view = operation.view()?;
         let index_segment = mutable_index
-            .squash_and_save_in(&self.segments_dir())
+            .squash_and_save_in(&self.segments_dir(), view.heads())
             .map_err(DefaultIndexStoreError::SaveIndex)?;
         self.associate_file_with_operation(&index_segment, op_id)
             .map_err(|source| DefaultIndexStoreError::AssociateIndex {
@@ -374,7 +390,7 @@ impl IndexStore for DefaultIndexStore {
             .downcast::<DefaultMutableIndex>()
             .expect("index to merge in must be a DefaultMutableIndex");
         let index_segment = self
-            .save_mutable_index(*index, op.id())
+            .save_mutable_index(*index, op)
             .map_err(|err| IndexWriteError(err.into()))?;
         Ok(Box::new(DefaultReadonlyIndex::from_segment(index_segment)))
     }
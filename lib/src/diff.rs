@@ -14,6 +14,7 @@
 
 #![allow(missing_docs)]
 
+use std::cmp;
 use std::collections::BTreeMap;
 use std::hash::BuildHasher;
 use std::hash::Hash;
@@ -22,6 +23,7 @@ use std::hash::RandomState;
 use std::iter;
 use std::ops::Range;
 use std::slice;
+use std::str::FromStr;
 
 use bstr::BStr;
 use hashbrown::HashTable;
@@ -183,6 +185,46 @@ impl CompareBytes for CompareBytesIgnoreWhitespaceAmount {
     }
 }
 
+/// Algorithm to find unchanged regions between inputs.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DiffAlgorithm {
+    /// Recursively matches the least frequent words which occur the same
+    /// number of times in both inputs.
+    #[default]
+    Default,
+    /// Matches the longest common run of words seeded by the least frequent
+    /// words, as Git's histogram algorithm does. Inserted or deleted lines are
+    /// shifted to match the indentation structure.
+    Histogram,
+    /// Matches words which are unique in both inputs. Inserted or deleted
+    /// lines are shifted to match the indentation structure.
+    Patience,
+}
+
+impl DiffAlgorithm {
+    /// Whether line diffs should be adjusted by the indent heuristic.
+    fn uses_indent_heuristic(self) -> bool {
+        match self {
+            DiffAlgorithm::Default => false,
+            DiffAlgorithm::Histogram | DiffAlgorithm::Patience => true,
+        }
+    }
+}
+
+impl FromStr for DiffAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "default" => Ok(DiffAlgorithm::Default),
+            "histogram" => Ok(DiffAlgorithm::Histogram),
+            "patience" => Ok(DiffAlgorithm::Patience),
+            _ => Err(format!("Invalid diff algorithm: {s}")),
+        }
+    }
+}
+
 // Not implementing Eq because the text should be compared by WordComparator.
 #[derive(Clone, Copy, Debug)]
 struct HashedWord<'input> {
@@ -548,6 +590,224 @@ fn collect_unchanged_words_lcs<C: CompareBytes, S: BuildHasher>(
     );
 }
 
+/// Finds unchanged word positions by the specified `algorithm`.
+fn collect_unchanged_words_with<C: CompareBytes, S: BuildHasher>(
+    found_positions: &mut Vec<(WordPosition, WordPosition)>,
+    left: &LocalDiffSource,
+    right: &LocalDiffSource,
+    comp: &WordComparator<C, S>,
+    algorithm: DiffAlgorithm,
+) {
+    match algorithm {
+        DiffAlgorithm::Default => collect_unchanged_words(found_positions, left, right, comp),
+        DiffAlgorithm::Histogram => {
+            collect_unchanged_words_histogram(found_positions, left, right, comp);
+        }
+        DiffAlgorithm::Patience => {
+            collect_unchanged_words_patience(found_positions, left, right, comp);
+        }
+    }
+}
+
+/// Finds the longest common run of words which contains the least frequent
+/// words, and recurses into the ranges before and after the run.
+fn collect_unchanged_words_histogram<C: CompareBytes, S: BuildHasher>(
+    found_positions: &mut Vec<(WordPosition, WordPosition)>,
+    left: &LocalDiffSource,
+    right: &LocalDiffSource,
+    comp: &WordComparator<C, S>,
+) {
+    if left.ranges.is_empty() || right.ranges.is_empty() {
+        return;
+    }
+
+    let max_occurrences = 100;
+    let left_words = left.hashed_words().collect_vec();
+    let right_words = right.hashed_words().collect_vec();
+    let left_histogram = Histogram::calculate(left, comp, max_occurrences);
+    let left_counts = left_words
+        .iter()
+        .map(|&word| left_histogram.positions_by_word(word, comp).unwrap().len())
+        .collect_vec();
+
+    // (left start, right start, length) of the best run so far
+    let mut best_run: Option<(usize, usize, usize)> = None;
+    let mut best_count = max_occurrences;
+    let mut right_pos = 0;
+    while right_pos < right_words.len() {
+        let mut next_right_pos = right_pos + 1;
+        let left_positions = left_histogram
+            .positions_by_word(right_words[right_pos], comp)
+            .unwrap_or_default();
+        for &LocalWordPosition(left_pos) in left_positions {
+            if left_counts[left_pos] > best_count {
+                continue;
+            }
+            let mut count = left_counts[left_pos];
+            let (mut left_start, mut right_start) = (left_pos, right_pos);
+            while left_start > 0
+                && right_start > 0
+                && comp.eq_hashed(left_words[left_start - 1], right_words[right_start - 1])
+            {
+                left_start -= 1;
+                right_start -= 1;
+                count = cmp::min(count, left_counts[left_start]);
+            }
+            let (mut left_end, mut right_end) = (left_pos + 1, right_pos + 1);
+            while left_end < left_words.len()
+                && right_end < right_words.len()
+                && comp.eq_hashed(left_words[left_end], right_words[right_end])
+            {
+                count = cmp::min(count, left_counts[left_end]);
+                left_end += 1;
+                right_end += 1;
+            }
+            next_right_pos = cmp::max(next_right_pos, right_end);
+            let len = left_end - left_start;
+            let is_longer = best_run.is_none_or(|(_, _, best_len)| len > best_len);
+            if is_longer || count < best_count {
+                best_run = Some((left_start, right_start, len));
+                best_count = count;
+            }
+        }
+        right_pos = next_right_pos;
+    }
+
+    let Some((left_start, right_start, len)) = best_run else {
+        // All common words are too frequent
+        collect_unchanged_words(found_positions, left, right, comp);
+        return;
+    };
+    collect_unchanged_words_histogram(
+        found_positions,
+        &left.narrowed(LocalWordPosition(0)..LocalWordPosition(left_start)),
+        &right.narrowed(LocalWordPosition(0)..LocalWordPosition(right_start)),
+        comp,
+    );
+    found_positions.extend((0..len).map(|i| {
+        (
+            left.map_to_global(LocalWordPosition(left_start + i)),
+            right.map_to_global(LocalWordPosition(right_start + i)),
+        )
+    }));
+    collect_unchanged_words_histogram(
+        found_positions,
+        &left.narrowed(LocalWordPosition(left_start + len)..LocalWordPosition(left.ranges.len())),
+        &right
+            .narrowed(LocalWordPosition(right_start + len)..LocalWordPosition(right.ranges.len())),
+        comp,
+    );
+}
+
+/// Trims common leading and trailing words, and then matches words which are
+/// unique in both inputs.
+fn collect_unchanged_words_patience<C: CompareBytes, S: BuildHasher>(
+    found_positions: &mut Vec<(WordPosition, WordPosition)>,
+    left: &LocalDiffSource,
+    right: &LocalDiffSource,
+    comp: &WordComparator<C, S>,
+) {
+    if left.ranges.is_empty() || right.ranges.is_empty() {
+        return;
+    }
+
+    let common_leading_len = iter::zip(left.hashed_words(), right.hashed_words())
+        .take_while(|&(l, r)| comp.eq_hashed(l, r))
+        .count();
+    let common_trailing_len = iter::zip(
+        left.hashed_words().skip(common_leading_len).rev(),
+        right.hashed_words().skip(common_leading_len).rev(),
+    )
+    .take_while(|&(l, r)| comp.eq_hashed(l, r))
+    .count();
+    found_positions.extend((0..common_leading_len).map(|i| {
+        (
+            left.map_to_global(LocalWordPosition(i)),
+            right.map_to_global(LocalWordPosition(i)),
+        )
+    }));
+    let left_middle = left.narrowed(
+        LocalWordPosition(common_leading_len)
+            ..LocalWordPosition(left.ranges.len() - common_trailing_len),
+    );
+    let right_middle = right.narrowed(
+        LocalWordPosition(common_leading_len)
+            ..LocalWordPosition(right.ranges.len() - common_trailing_len),
+    );
+    collect_unique_unchanged_words(found_positions, &left_middle, &right_middle, comp);
+    found_positions.extend((1..=common_trailing_len).rev().map(|i| {
+        (
+            left.map_to_global(LocalWordPosition(left.ranges.len() - i)),
+            right.map_to_global(LocalWordPosition(right.ranges.len() - i)),
+        )
+    }));
+}
+
+fn collect_unique_unchanged_words<C: CompareBytes, S: BuildHasher>(
+    found_positions: &mut Vec<(WordPosition, WordPosition)>,
+    left: &LocalDiffSource,
+    right: &LocalDiffSource,
+    comp: &WordComparator<C, S>,
+) {
+    if left.ranges.is_empty() || right.ranges.is_empty() {
+        return;
+    }
+
+    let left_histogram = Histogram::calculate(left, comp, 1);
+    let right_histogram = Histogram::calculate(right, comp, 1);
+    // [(left position, right position)] of the unique words sorted by right
+    let mut unique_positions = left_histogram
+        .word_to_positions
+        .iter()
+        .filter_map(|(word, left_positions)| {
+            let right_positions = right_histogram.positions_by_word(*word, comp)?;
+            match (&left_positions[..], right_positions) {
+                (&[left_pos], &[right_pos]) => Some((left_pos, right_pos)),
+                _ => None,
+            }
+        })
+        .collect_vec();
+    if unique_positions.is_empty() {
+        collect_unchanged_words(found_positions, left, right, comp);
+        return;
+    }
+    unique_positions.sort_unstable_by_key(|&(_, right_pos)| right_pos);
+    let left_index_by_right_index = {
+        let mut left_order = (0..unique_positions.len()).collect_vec();
+        left_order.sort_unstable_by_key(|&i| unique_positions[i].0);
+        let mut left_index_map = vec![0; unique_positions.len()];
+        for (left_index, &right_index) in left_order.iter().enumerate() {
+            left_index_map[right_index] = left_index;
+        }
+        left_index_map
+    };
+    let lcs = find_lcs(&left_index_by_right_index);
+
+    let mut previous_left_position = LocalWordPosition(0);
+    let mut previous_right_position = LocalWordPosition(0);
+    for (_, right_index) in lcs {
+        let (left_position, right_position) = unique_positions[right_index];
+        collect_unchanged_words_patience(
+            found_positions,
+            &left.narrowed(previous_left_position..left_position),
+            &right.narrowed(previous_right_position..right_position),
+            comp,
+        );
+        found_positions.push((
+            left.map_to_global(left_position),
+            right.map_to_global(right_position),
+        ));
+        previous_left_position = LocalWordPosition(left_position.0 + 1);
+        previous_right_position = LocalWordPosition(right_position.0 + 1);
+    }
+    collect_unchanged_words_patience(
+        found_positions,
+        &left.narrowed(previous_left_position..LocalWordPosition(left.ranges.len())),
+        &right.narrowed(previous_right_position..LocalWordPosition(right.ranges.len())),
+        comp,
+    );
+}
+
 /// Intersects two sorted sequences of `(base, other)` word positions by
 /// `base`. `base` positions should refer to the same source text.
 fn intersect_unchanged_words(
@@ -595,6 +855,149 @@ impl UnchangedRange {
     }
 }
 
+const MAX_INDENT: i32 = 200;
+const MAX_BLANKS: i32 = 20;
+const INDENT_HEURISTIC_MAX_SLIDING: usize = 100;
+
+const START_OF_FILE_PENALTY: i32 = 1;
+const END_OF_FILE_PENALTY: i32 = 21;
+const TOTAL_BLANK_WEIGHT: i32 = -30;
+const POST_BLANK_WEIGHT: i32 = 6;
+const RELATIVE_INDENT_PENALTY: i32 = -4;
+const RELATIVE_INDENT_WITH_BLANK_PENALTY: i32 = 10;
+const RELATIVE_OUTDENT_PENALTY: i32 = 24;
+const RELATIVE_OUTDENT_WITH_BLANK_PENALTY: i32 = 17;
+const RELATIVE_DEDENT_PENALTY: i32 = 23;
+const RELATIVE_DEDENT_WITH_BLANK_PENALTY: i32 = 17;
+const INDENT_WEIGHT: i32 = 60;
+
+/// Returns the indentation width of the line, or `None` if the line is blank.
+fn get_indent(line: &[u8]) -> Option<i32> {
+    let mut indent = 0;
+    for &b in line {
+        match b {
+            b' ' => indent += 1,
+            b'\t' => indent += 8 - indent % 8,
+            b'\n' | b'\r' | b'\x0b' | b'\x0c' => {}
+            _ => return Some(indent),
+        }
+        if indent >= MAX_INDENT {
+            return Some(MAX_INDENT);
+        }
+    }
+    None
+}
+
+/// Characteristics of the lines around a split position.
+#[derive(Debug)]
+struct SplitMeasurement {
+    end_of_file: bool,
+    /// Indentation of the line after the split, or `None` if blank.
+    indent: Option<i32>,
+    /// Number of blank lines before the split.
+    pre_blank: i32,
+    /// Indentation of the first non-blank line before the split.
+    pre_indent: Option<i32>,
+    /// Number of blank lines after the line after the split.
+    post_blank: i32,
+    /// Indentation of the first non-blank line after the line after the split.
+    post_indent: Option<i32>,
+}
+
+fn measure_split(text: &[u8], lines: &[Range<usize>], split: usize) -> SplitMeasurement {
+    let indent_at = |index: usize| get_indent(&text[lines[index].clone()]);
+    let end_of_file = split >= lines.len();
+    let indent = if end_of_file { None } else { indent_at(split) };
+    let mut pre_blank = 0;
+    let mut pre_indent = None;
+    for index in (0..split).rev() {
+        pre_indent = indent_at(index);
+        if pre_indent.is_some() {
+            break;
+        }
+        pre_blank += 1;
+        if pre_blank == MAX_BLANKS {
+            pre_indent = Some(0);
+            break;
+        }
+    }
+    let mut post_blank = 0;
+    let mut post_indent = None;
+    for index in (split + 1)..lines.len() {
+        post_indent = indent_at(index);
+        if post_indent.is_some() {
+            break;
+        }
+        post_blank += 1;
+        if post_blank == MAX_BLANKS {
+            post_indent = Some(0);
+            break;
+        }
+    }
+    SplitMeasurement {
+        end_of_file,
+        indent,
+        pre_blank,
+        pre_indent,
+        post_blank,
+        post_indent,
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct SplitScore {
+    effective_indent: i32,
+    penalty: i32,
+}
+
+impl SplitScore {
+    fn add_split(&mut self, m: &SplitMeasurement) {
+        if m.pre_indent.is_none() && m.pre_blank == 0 {
+            self.penalty += START_OF_FILE_PENALTY;
+        }
+        if m.end_of_file {
+            self.penalty += END_OF_FILE_PENALTY;
+        }
+        let post_blank = if m.indent.is_none() {
+            1 + m.post_blank
+        } else {
+            0
+        };
+        let total_blank = m.pre_blank + post_blank;
+        self.penalty += TOTAL_BLANK_WEIGHT * total_blank;
+        self.penalty += POST_BLANK_WEIGHT * post_blank;
+        let indent = m.indent.or(m.post_indent);
+        let any_blanks = total_blank != 0;
+        self.effective_indent += indent.unwrap_or(-1);
+        let (Some(indent), Some(pre_indent)) = (indent, m.pre_indent) else {
+            return;
+        };
+        if indent > pre_indent {
+            self.penalty += if any_blanks {
+                RELATIVE_INDENT_WITH_BLANK_PENALTY
+            } else {
+                RELATIVE_INDENT_PENALTY
+            };
+        } else if indent < pre_indent {
+            let is_outdent = m
+                .post_indent
+                .is_some_and(|post_indent| post_indent > indent);
+            self.penalty += match (is_outdent, any_blanks) {
+                (true, true) => RELATIVE_OUTDENT_WITH_BLANK_PENALTY,
+                (true, false) => RELATIVE_OUTDENT_PENALTY,
+                (false, true) => RELATIVE_DEDENT_WITH_BLANK_PENALTY,
+                (false, false) => RELATIVE_DEDENT_PENALTY,
+            };
+        }
+    }
+
+    /// Compares scores. The lesser is better.
+    fn compare(&self, other: &Self) -> cmp::Ordering {
+        let cmp_indents = self.effective_indent.cmp(&other.effective_indent) as i32;
+        (INDENT_WEIGHT * cmp_indents + (self.penalty - other.penalty)).cmp(&0)
+    }
+}
+
 /// Takes any number of inputs and finds regions that are them same between all
 /// of them.
 #[derive(Clone, Debug)]
@@ -613,6 +1016,16 @@ impl<'input> Diff<'input> {
         inputs: impl IntoIterator<Item = &'input T>,
         tokenizer: impl Fn(&[u8]) -> Vec<Range<usize>>,
         compare: impl CompareBytes,
+    ) -> Self {
+        Self::for_tokenizer_with_algorithm(inputs, tokenizer, compare, DiffAlgorithm::Default)
+    }
+
+    /// Compares `inputs` token by token using the specified `algorithm`.
+    pub fn for_tokenizer_with_algorithm<T: AsRef<[u8]> + ?Sized + 'input>(
+        inputs: impl IntoIterator<Item = &'input T>,
+        tokenizer: impl Fn(&[u8]) -> Vec<Range<usize>>,
+        compare: impl CompareBytes,
+        algorithm: DiffAlgorithm,
     ) -> Self {
         let mut inputs = inputs.into_iter().map(BStr::new);
         let base_input = inputs.next().expect("inputs must not be empty");
@@ -640,6 +1053,7 @@ impl<'input> Diff<'input> {
             &base_token_ranges,
             &other_token_ranges,
             compare,
+            algorithm,
         )
     }
 
@@ -649,6 +1063,7 @@ impl<'input> Diff<'input> {
         base_token_ranges: &[Range<usize>],
         other_token_ranges: &[Vec<Range<usize>>],
         compare: impl CompareBytes,
+        algorithm: DiffAlgorithm,
     ) -> Self {
         assert_eq!(other_inputs.len(), other_token_ranges.len());
         let comp = WordComparator::new(compare);
@@ -676,11 +1091,12 @@ impl<'input> Diff<'input> {
                     others: smallvec![0..0; other_inputs.len()],
                 });
                 let mut first_positions = Vec::new();
-                collect_unchanged_words(
+                collect_unchanged_words_with(
                     &mut first_positions,
                     &base_source.local(),
                     &first_other_source.local(),
                     &comp,
+                    algorithm,
                 );
                 if tail_other_sources.is_empty() {
                     unchanged_regions.extend(first_positions.iter().map(
@@ -702,11 +1118,12 @@ impl<'input> Diff<'input> {
                         first_positions,
                         |current_positions, other_source| {
                             let mut new_positions = Vec::new();
-                            collect_unchanged_words(
+                            collect_unchanged_words_with(
                                 &mut new_positions,
                                 &base_source.local(),
                                 &other_source.local(),
                                 &comp,
+                                algorithm,
                             );
                             intersect_unchanged_words(current_positions, &new_positions)
                         },
@@ -753,7 +1170,22 @@ impl<'input> Diff<'input> {
     pub fn by_line<T: AsRef<[u8]> + ?Sized + 'input>(
         inputs: impl IntoIterator<Item = &'input T>,
     ) -> Self {
-        Diff::for_tokenizer(inputs, find_line_ranges, CompareBytesExactly)
+        Diff::by_line_with(inputs, CompareBytesExactly, DiffAlgorithm::Default)
+    }
+
+    /// Compares `inputs` line by line using the specified `compare` function
+    /// and `algorithm`.
+    pub fn by_line_with<T: AsRef<[u8]> + ?Sized + 'input>(
+        inputs: impl IntoIterator<Item = &'input T>,
+        compare: impl CompareBytes,
+        algorithm: DiffAlgorithm,
+    ) -> Self {
+        let mut diff =
+            Diff::for_tokenizer_with_algorithm(inputs, find_line_ranges, compare, algorithm);
+        if algorithm.uses_indent_heuristic() {
+            diff.shift_hunks_by_indent();
+        }
+        diff
     }
 
     /// Compares `inputs` word by word.
@@ -836,6 +1268,107 @@ impl<'input> Diff<'input> {
         self.compact_unchanged_regions();
     }
 
+    /// Shifts inserted or deleted lines to the positions that better match the
+    /// indentation structure, as Git's indent heuristic does. Only two-way
+    /// line diffs are adjusted.
+    fn shift_hunks_by_indent(&mut self) {
+        let [other_input] = self.other_inputs[..] else {
+            return;
+        };
+        let texts = [self.base_input, other_input];
+        let line_ranges = texts.map(|text| find_line_ranges(text));
+        let num_regions = self.unchanged_regions.len();
+        for i in 1..num_regions {
+            let (head, tail) = self.unchanged_regions.split_at_mut(i);
+            let (previous, current) = (&mut head[i - 1], &mut tail[0]);
+            let [previous_base, previous_other] = [&mut previous.base, &mut previous.others[0]];
+            let [current_base, current_other] = [&mut current.base, &mut current.others[0]];
+            // The side containing the inserted or deleted lines
+            let (side, [previous_side, previous_other], [current_side, current_other]) = match (
+                previous_base.end == current_base.start,
+                previous_other.end == current_other.start,
+            ) {
+                (false, true) => (
+                    0,
+                    [previous_base, previous_other],
+                    [current_base, current_other],
+                ),
+                (true, false) => (
+                    1,
+                    [previous_other, previous_base],
+                    [current_other, current_base],
+                ),
+                _ => continue,
+            };
+            let text = texts[side];
+            let other_text = texts[1 - side];
+            let lines = &line_ranges[side];
+            let line_at = |offset: usize| lines.partition_point(|range| range.start < offset);
+            let line_start = |index: usize| lines.get(index).map_or(text.len(), |r| r.start);
+            // Unchanged regions should be kept non-empty except at the ends
+            let min_start = line_at(previous_side.start) + usize::from(i > 1);
+            let max_end = line_at(current_side.end) - usize::from(i + 1 < num_regions);
+            let min_other_pos = previous_other.start + usize::from(i > 1);
+            let max_other_pos = current_other.end - usize::from(i + 1 < num_regions);
+
+            // (start line, end line, position in the other text)
+            let mut group = (
+                line_at(previous_side.end),
+                line_at(current_side.start),
+                previous_other.end,
+            );
+            let slide_up = |(start, end, other_pos): (usize, usize, usize)| {
+                let line = &text[lines[start.checked_sub(1)?].clone()];
+                let fits = start > min_start
+                    && other_pos >= min_other_pos + line.len()
+                    && *line == text[lines[end - 1].clone()]
+                    && *line == other_text[other_pos - line.len()..other_pos];
+                fits.then(|| (start - 1, end - 1, other_pos - line.len()))
+            };
+            let slide_down = |(start, end, other_pos): (usize, usize, usize)| {
+                let line = &text[lines.get(end)?.clone()];
+                let fits = end < max_end
+                    && other_pos + line.len() <= max_other_pos
+                    && *line == text[lines[start].clone()]
+                    && *line == other_text[other_pos..other_pos + line.len()];
+                fits.then(|| (start + 1, end + 1, other_pos + line.len()))
+            };
+            while let Some(new_group) = slide_up(group) {
+                group = new_group;
+            }
+            let earliest_end = group.1;
+            let mut candidates = vec![group];
+            while let Some(new_group) = slide_down(group) {
+                group = new_group;
+                candidates.push(group);
+            }
+            if candidates.len() == 1 {
+                continue;
+            }
+            let group_size = group.1 - group.0;
+            let latest_end = group.1;
+            let min_end = earliest_end
+                .max((latest_end).saturating_sub(group_size + 1))
+                .max(latest_end.saturating_sub(INDENT_HEURISTIC_MAX_SLIDING));
+            // Scores aren't totally ordered. Pick the best one in the same way as
+            // Git, which prefers the later position.
+            let mut best: Option<((usize, usize, usize), SplitScore)> = None;
+            for group in candidates.into_iter().filter(|&(_, end, _)| end >= min_end) {
+                let mut score = SplitScore::default();
+                score.add_split(&measure_split(text, lines, group.1));
+                score.add_split(&measure_split(text, lines, group.0));
+                if best.is_none_or(|(_, best_score)| score.compare(&best_score).is_le()) {
+                    best = Some((group, score));
+                }
+            }
+            let ((start, end, other_pos), _) = best.unwrap();
+            previous_side.end = line_start(start);
+            current_side.start = line_start(end);
+            previous_other.end = other_pos;
+            current_other.start = other_pos;
+        }
+    }
+
     fn compact_unchanged_regions(&mut self) {
         let mut compacted = vec![];
         let mut maybe_previous: Option<UnchangedRange> = None;
@@ -1428,6 +1961,103 @@ mod tests {
         );
     }
 
+    fn diff_lines_with<'a>(inputs: [&'a str; 2], algorithm: DiffAlgorithm) -> Vec<DiffHunk<'a>> {
+        Diff::by_line_with(inputs, CompareBytesExactly, algorithm)
+            .hunks()
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_algorithm_from_str() {
+        assert_eq!("default".parse(), Ok(DiffAlgorithm::Default));
+        assert_eq!("histogram".parse(), Ok(DiffAlgorithm::Histogram));
+        assert_eq!("patience".parse(), Ok(DiffAlgorithm::Patience));
+        assert!("myers".parse::<DiffAlgorithm>().is_err());
+    }
+
+    #[test]
+    fn test_diff_histogram_moved_line() {
+        // The default algorithm anchors on the longest common subsequence of
+        // unique lines, whereas histogram starts from the lowest-occurrence
+        // run found in the left input.
+        let inputs = ["a\nb\nc\nd\ne\n", "a\nc\nb\nd\ne\n"];
+        assert_eq!(
+            diff_lines_with(inputs, DiffAlgorithm::Default),
+            vec![
+                DiffHunk::matching(["a\n"].repeat(2)),
+                DiffHunk::different(["", "c\n"]),
+                DiffHunk::matching(["b\n"].repeat(2)),
+                DiffHunk::different(["c\n", ""]),
+                DiffHunk::matching(["d\ne\n"].repeat(2)),
+            ]
+        );
+        assert_eq!(
+            diff_lines_with(inputs, DiffAlgorithm::Histogram),
+            vec![
+                DiffHunk::matching(["a\n"].repeat(2)),
+                DiffHunk::different(["b\n", ""]),
+                DiffHunk::matching(["c\n"].repeat(2)),
+                DiffHunk::different(["", "b\n"]),
+                DiffHunk::matching(["d\ne\n"].repeat(2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_patience_unique_lines() {
+        let inputs = [
+            "int a() {\n    return 1;\n}\nint c() {\n    return 1;\n}\n",
+            "int a() {\n    return 1;\n}\nint b() {\n    return 1;\n}\nint c() {\n    return 1;\n}\n",
+        ];
+        assert_eq!(
+            diff_lines_with(inputs, DiffAlgorithm::Patience),
+            vec![
+                DiffHunk::matching(["int a() {\n    return 1;\n}\n"].repeat(2)),
+                DiffHunk::different(["", "int b() {\n    return 1;\n}\n"]),
+                DiffHunk::matching(["int c() {\n    return 1;\n}\n"].repeat(2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_indent_heuristic() {
+        // Same example as git's t4061-diff-indent. The inserted lines can
+        // slide, and the heuristic prefers splitting at the blank lines.
+        let inputs = ["1\n2\na\n\nb\n3\n4\n", "1\n2\na\n\nb\na\n\nb\n3\n4\n"];
+        assert_eq!(
+            diff_lines_with(inputs, DiffAlgorithm::Default),
+            vec![
+                DiffHunk::matching(["1\n2\na\n\nb\n"].repeat(2)),
+                DiffHunk::different(["", "a\n\nb\n"]),
+                DiffHunk::matching(["3\n4\n"].repeat(2)),
+            ]
+        );
+        for algorithm in [DiffAlgorithm::Histogram, DiffAlgorithm::Patience] {
+            assert_eq!(
+                diff_lines_with(inputs, algorithm),
+                vec![
+                    DiffHunk::matching(["1\n2\na\n\n"].repeat(2)),
+                    DiffHunk::different(["", "b\na\n\n"]),
+                    DiffHunk::matching(["b\n3\n4\n"].repeat(2)),
+                ],
+                "{algorithm:?}"
+            );
+        }
+
+        let inputs = [
+            "if a:\n    foo()\n\nif b:\n    bar()\n",
+            "if a:\n    foo()\n\nif c:\n    baz()\n\nif b:\n    bar()\n",
+        ];
+        assert_eq!(
+            diff_lines_with(inputs, DiffAlgorithm::Histogram),
+            vec![
+                DiffHunk::matching(["if a:\n    foo()\n\n"].repeat(2)),
+                DiffHunk::different(["", "if c:\n    baz()\n\n"]),
+                DiffHunk::matching(["if b:\n    bar()\n"].repeat(2)),
+            ]
+        );
+    }
+
     #[test]
     fn test_diff_nothing_in_common() {
         assert_eq!(
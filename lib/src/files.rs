@@ -23,7 +23,9 @@ use bstr::BStr;
 use bstr::BString;
 use itertools::Itertools as _;
 
+use crate::diff::CompareBytesExactly;
 use crate::diff::Diff;
+use crate::diff::DiffAlgorithm;
 use crate::diff::DiffHunk;
 use crate::diff::DiffHunkKind;
 use crate::merge::Merge;
@@ -193,13 +195,14 @@ pub enum MergeResult {
     Conflict(Vec<Merge<BString>>),
 }
 
-pub fn merge<T: AsRef<[u8]>>(slices: &Merge<T>) -> MergeResult {
+pub fn merge<T: AsRef<[u8]>>(slices: &Merge<T>, algorithm: DiffAlgorithm) -> MergeResult {
     // TODO: Using the first remove as base (first in the inputs) is how it's
     // usually done for 3-way conflicts. Are there better heuristics when there are
     // more than 3 parts?
     let num_diffs = slices.removes().len();
     let diff_inputs = slices.removes().chain(slices.adds());
-    merge_hunks(&Diff::by_line(diff_inputs), num_diffs)
+    let diff = Diff::by_line_with(diff_inputs, CompareBytesExactly, algorithm);
+    merge_hunks(&diff, num_diffs)
 }
 
 fn merge_hunks(diff: &Diff, num_diffs: usize) -> MergeResult {
@@ -248,7 +251,10 @@ mod tests {
     }
 
     fn merge(removes: &[&[u8]], adds: &[&[u8]]) -> MergeResult {
-        super::merge(&Merge::from_removes_adds(removes, adds))
+        super::merge(
+            &Merge::from_removes_adds(removes, adds),
+            DiffAlgorithm::default(),
+        )
     }
 
     #[test]
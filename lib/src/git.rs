@@ -142,7 +142,7 @@ impl<'a: 'b, 'b> Borrow<RemoteRefSymbol<'b>> for RemoteRefKey<'a> {
 /// It is often the case that we need only parts of the refspec,
 /// Passing strings around and repeatedly parsing them is sub-optimal, confusing
 /// and error prone
-#[derive(Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub(crate) struct RefSpec {
     forced: bool,
     source: Option<String>,
@@ -150,7 +150,7 @@ pub(crate) struct RefSpec {
 }
 
 impl RefSpec {
-    fn forced(source: impl Into<String>, destination: impl Into<String>) -> Self {
+    pub(crate) fn forced(source: impl Into<String>, destination: impl Into<String>) -> Self {
         RefSpec {
             forced: true,
             source: Some(source.into()),
@@ -2214,7 +2214,7 @@ fn expand_fetch_refspecs(
         .collect()
 }
 
-enum GitFetchImpl<'a> {
+pub(crate) enum GitFetchImpl<'a> {
     #[cfg(feature = "git2")]
     Git2 { git_repo: git2::Repository },
     Subprocess {
@@ -2224,7 +2224,10 @@ enum GitFetchImpl<'a> {
 }
 
 impl<'a> GitFetchImpl<'a> {
-    fn new(store: &Store, git_settings: &'a GitSettings) -> Result<Self, GitFetchPrepareError> {
+    pub(crate) fn new(
+        store: &Store,
+        git_settings: &'a GitSettings,
+    ) -> Result<Self, GitFetchPrepareError> {
         let git_backend = get_git_backend(store)?;
         #[cfg(feature = "git2")]
         if !git_settings.subprocess {
@@ -2260,6 +2263,25 @@ impl<'a> GitFetchImpl<'a> {
         }
     }
 
+    /// Fetches the specified refspecs. Unlike [`Self::fetch()`], the refspecs
+    /// aren't limited to branches.
+    pub(crate) fn fetch_refspecs(
+        &self,
+        remote_name: &RemoteName,
+        refspecs: &[RefSpec],
+        callbacks: RemoteCallbacks<'_>,
+    ) -> Result<(), GitFetchError> {
+        match self {
+            #[cfg(feature = "git2")]
+            GitFetchImpl::Git2 { git_repo } => {
+                git2_fetch_refspecs(git_repo, remote_name, refspecs, callbacks)
+            }
+            GitFetchImpl::Subprocess { git_repo, git_ctx } => {
+                subprocess_fetch_refspecs(git_repo, git_ctx, remote_name, refspecs, callbacks)
+            }
+        }
+    }
+
     fn get_default_branch(
         &self,
         remote_name: &RemoteName,
@@ -2320,6 +2342,35 @@ fn git2_fetch(
     Ok(())
 }
 
+#[cfg(feature = "git2")]
+fn git2_fetch_refspecs(
+    git_repo: &git2::Repository,
+    remote_name: &RemoteName,
+    refspecs: &[RefSpec],
+    callbacks: RemoteCallbacks<'_>,
+) -> Result<(), GitFetchError> {
+    let mut remote = git_repo.find_remote(remote_name.as_str()).map_err(|err| {
+        if is_remote_not_found_err(&err) {
+            GitFetchError::NoSuchRemote(remote_name.to_owned())
+        } else {
+            GitFetchError::InternalGitError(err)
+        }
+    })?;
+    let refspecs: Vec<String> = refspecs.iter().map(RefSpec::to_git_format).collect();
+    tracing::debug!("remote.download");
+    remote.download(&refspecs, Some(&mut git2_fetch_options(callbacks, None)))?;
+    tracing::debug!("remote.update_tips");
+    remote.update_tips(
+        None,
+        git2::RemoteUpdateFlags::empty(),
+        git2::AutotagOption::Unspecified,
+        None,
+    )?;
+    tracing::debug!("remote.disconnect");
+    remote.disconnect()?;
+    Ok(())
+}
+
 #[cfg(feature = "git2")]
 fn git2_get_default_branch(
     git_repo: &git2::Repository,
@@ -2409,6 +2460,27 @@ fn subprocess_fetch(
     Ok(())
 }
 
+fn subprocess_fetch_refspecs(
+    git_repo: &gix::Repository,
+    git_ctx: &GitSubprocessContext,
+    remote_name: &RemoteName,
+    refspecs: &[RefSpec],
+    mut callbacks: RemoteCallbacks<'_>,
+) -> Result<(), GitFetchError> {
+    if git_repo.try_find_remote(remote_name.as_str()).is_none() {
+        return Err(GitFetchError::NoSuchRemote(remote_name.to_owned()));
+    }
+    let mut remaining_refspecs = refspecs.to_vec();
+    // Retry without the refspecs of missing refs, as in subprocess_fetch()
+    while let Some(failing_refspec) =
+        git_ctx.spawn_fetch(remote_name, &remaining_refspecs, &mut callbacks, None)?
+    {
+        tracing::debug!(failing_refspec, "failed to fetch ref");
+        remaining_refspecs.retain(|r| r.source.as_ref() != Some(&failing_refspec));
+    }
+    Ok(())
+}
+
 fn subprocess_get_default_branch(
     git_repo: &gix::Repository,
     git_ctx: &GitSubprocessContext,
@@ -147,6 +147,16 @@ pub enum GitGcError {
     GcCommandErrorStatus(ExitStatus),
 }
 
+/// Result of [`GitBackend::import_commit_metadata()`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CommitMetadataImportStats {
+    /// Commits whose metadata was recorded.
+    pub changed_ids: Vec<CommitId>,
+    /// Commits which already had different metadata. The existing metadata
+    /// was kept.
+    pub conflicting_ids: Vec<CommitId>,
+}
+
 pub struct GitBackend {
     // While gix::Repository can be created from gix::ThreadSafeRepository, it's
     // cheaper to cache the thread-local instance behind a mutex than creating
@@ -395,6 +405,56 @@ impl GitBackend {
         self.save_extra_metadata_table(mut_table, &table_lock)
     }
 
+    /// Returns true if the commit object exists in the backing Git repo.
+    pub fn has_commit(&self, id: &CommitId) -> BackendResult<bool> {
+        let git_commit_id = validate_git_object_id(id)?;
+        Ok(self.lock_git_repo().has_object(git_commit_id))
+    }
+
+    /// Records the jj-specific metadata (such as change ids and predecessors)
+    /// of commits that have been transferred to the backing Git repo without
+    /// it.
+    ///
+    /// Existing entries are only replaced if they were synthesized when the
+    /// commits were read. Commits which already have different metadata keep
+    /// it, and are reported as conflicting. The caller must ensure that the
+    /// commit objects exist.
+    #[tracing::instrument(skip_all)]
+    pub fn import_commit_metadata<'a>(
+        &self,
+        commits: impl IntoIterator<Item = (&'a CommitId, &'a Commit)>,
+    ) -> BackendResult<CommitMetadataImportStats> {
+        let (table, table_lock) = self.read_extra_metadata_table_locked()?;
+        let mut mut_table = table.start_mutation();
+        let mut stats = CommitMetadataImportStats::default();
+        let locked_repo = self.lock_git_repo();
+        for (id, contents) in commits {
+            if *id == self.root_commit_id {
+                continue;
+            }
+            let extras = serialize_extras(contents);
+            match table.get_value(id.as_bytes()) {
+                None => {}
+                Some(existing) if existing == extras.as_slice() => continue,
+                Some(existing) if existing == synthesized_extras(&locked_repo, id)? => {}
+                Some(_) => {
+                    stats.conflicting_ids.push(id.clone());
+                    continue;
+                }
+            }
+            mut_table.add_entry(id.to_bytes(), extras);
+            stats.changed_ids.push(id.clone());
+        }
+        if stats.changed_ids.is_empty() {
+            return Ok(stats);
+        }
+        locked_repo
+            .edit_references(stats.changed_ids.iter().map(to_no_gc_ref_update))
+            .map_err(|err| BackendError::Other(Box::new(err)))?;
+        self.save_extra_metadata_table(mut_table, &table_lock)?;
+        Ok(stats)
+    }
+
     fn read_file_sync(&self, id: &FileId) -> BackendResult<Box<dyn Read>> {
         let git_blob_id = validate_git_object_id(id)?;
         let locked_repo = self.lock_git_repo();
@@ -683,6 +743,16 @@ fn deserialize_extras(commit: &mut Commit, bytes: &[u8]) {
     }
 }
 
+/// Returns the metadata that would be synthesized for the commit if it had no
+/// entry in the extra metadata table.
+fn synthesized_extras(git_repo: &gix::Repository, id: &CommitId) -> BackendResult<Vec<u8>> {
+    let git_object = git_repo
+        .find_object(validate_git_object_id(id)?)
+        .map_err(|err| map_not_found_err(err, id))?;
+    let commit = commit_from_git_without_root_parent(id, &git_object, true, false)?;
+    Ok(serialize_extras(&commit))
+}
+
 /// Returns `RefEdit` that will create a ref in `refs/jj/keep` if not exist.
 /// Used for preventing GC of commits we create.
 fn to_no_gc_ref_update(id: &CommitId) -> gix::refs::transaction::RefEdit {
@@ -0,0 +1,370 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Synchronization of the operation log through Git remotes.
+//!
+//! Operations are exported as [bundles](crate::op_bundle) without tree
+//! objects, and stored in "carrier" commits. A carrier commit has the commits
+//! referred to by the bundle as parents, so Git transfers them along with the
+//! bundle. The carrier commits are chained to the previously pushed carrier,
+//! and each bundle only contains operations and commits that aren't in the
+//! previous bundles.
+//!
+//! The latest carrier commit is pushed to [`REMOTE_OP_LOG_REF`], and fetched
+//! to a remote-specific ref under `refs/jj/remotes/`.
+
+#![allow(missing_docs)]
+
+use std::collections::HashSet;
+use std::error::Error;
+use std::slice;
+
+use itertools::Itertools as _;
+use thiserror::Error;
+
+use crate::backend::BackendError;
+use crate::backend::CommitId;
+use crate::git;
+use crate::git::GitFetchError;
+use crate::git::GitFetchImpl;
+use crate::git::GitFetchPrepareError;
+use crate::git::GitPushError;
+use crate::git::GitRefUpdate;
+use crate::git::RefSpec;
+use crate::git::RemoteCallbacks;
+use crate::git::UnexpectedGitBackendError;
+use crate::object_id::ObjectId as _;
+use crate::op_bundle;
+use crate::op_bundle::OpBundleError;
+use crate::op_bundle::OpBundleExportOptions;
+use crate::op_bundle::OpBundleStats;
+use crate::op_store::OpStoreError;
+use crate::op_store::OperationId;
+use crate::op_walk;
+use crate::ref_name::RemoteName;
+use crate::ref_name::RemoteNameBuf;
+use crate::repo::ReadonlyRepo;
+use crate::repo::Repo as _;
+use crate::repo::RepoLoader;
+use crate::settings::GitSettings;
+
+/// Ref on the remote which points to the latest carrier commit.
+pub const REMOTE_OP_LOG_REF: &str = "refs/jj/op-log/head";
+
+const CARRIER_BUNDLE_FILE: &str = "bundle";
+const CARRIER_HEADS_FILE: &str = "heads";
+const CARRIER_PREVIOUS_FILE: &str = "previous";
+
+/// Error that may occur while pushing or fetching the operation log.
+#[derive(Debug, Error)]
+pub enum GitOpLogError {
+    #[error(transparent)]
+    UnexpectedBackend(#[from] UnexpectedGitBackendError),
+    #[error(transparent)]
+    FetchPrepare(#[from] GitFetchPrepareError),
+    #[error(transparent)]
+    Fetch(#[from] GitFetchError),
+    #[error(transparent)]
+    Push(#[from] GitPushError),
+    #[error(transparent)]
+    Bundle(#[from] OpBundleError),
+    #[error(transparent)]
+    Backend(#[from] BackendError),
+    #[error(transparent)]
+    OpStore(#[from] OpStoreError),
+    #[error("The operation log on remote {} has changed since it was last fetched", .0.as_symbol())]
+    Rejected(RemoteNameBuf),
+    #[error("Invalid operation log commit {0}")]
+    InvalidCarrier(String),
+    #[error("Unexpected Git error")]
+    Git(#[source] Box<dyn Error + Send + Sync>),
+}
+
+/// Returns the local ref which tracks the operation log on the remote.
+pub fn remote_op_log_tracking_ref(remote_name: &RemoteName) -> String {
+    format!("refs/jj/remotes/{}/op-log/head", remote_name.as_str())
+}
+
+/// Pushes the operations up to `repo.operation()` to the remote.
+///
+/// The operations which were fetched from or pushed to the remote before are
+/// left out. Returns `None` if there are no new operations to push. If the
+/// operation log on the remote has been updated since it was last fetched,
+/// the push is rejected.
+pub fn push_op_log(
+    repo: &ReadonlyRepo,
+    git_settings: &GitSettings,
+    remote_name: &RemoteName,
+    callbacks: RemoteCallbacks<'_>,
+) -> Result<Option<OpBundleStats>, GitOpLogError> {
+    let git_backend = git::get_git_backend(repo.store())?;
+    let git_repo = git_backend.git_repo();
+    let tracking_ref = remote_op_log_tracking_ref(remote_name);
+    let previous_carrier_id = find_ref_target(&git_repo, &tracking_ref)?;
+
+    let mut known_ops = vec![];
+    if let Some(id) = previous_carrier_id {
+        let carrier = read_carrier(&git_repo, id)?;
+        for id in &carrier.head_ids {
+            if has_operation(repo.loader(), id)? {
+                known_ops.push(repo.loader().load_operation(id)?);
+            }
+        }
+    }
+    let head_ops = slice::from_ref(repo.operation());
+    if op_walk::walk_ancestors_range(head_ops, &known_ops)
+        .next()
+        .transpose()?
+        .is_none()
+    {
+        return Ok(None);
+    }
+
+    let mut bundle = vec![];
+    let options = OpBundleExportOptions {
+        known_ops,
+        exclude_tree_objects: true,
+    };
+    let stats =
+        op_bundle::export_operations_with_options(repo.loader(), head_ops, &options, &mut bundle)?;
+
+    // Parents of the exported commits were either exported or pushed before.
+    let mut parent_ids = HashSet::new();
+    for id in &stats.commit_ids {
+        let commit = repo.store().get_commit(id)?;
+        parent_ids.extend(commit.parent_ids().iter().cloned());
+    }
+    let head_ids = stats
+        .commit_ids
+        .iter()
+        .filter(|id| !parent_ids.contains(*id))
+        .map(|id| gix::ObjectId::from_bytes_or_panic(id.as_bytes()));
+    let carrier_id = write_carrier(
+        &git_repo,
+        &stats.head_ids,
+        previous_carrier_id,
+        head_ids,
+        &bundle,
+    )?;
+
+    let to_commit_id = |id: gix::ObjectId| CommitId::from_bytes(id.as_bytes());
+    let update = GitRefUpdate {
+        qualified_name: REMOTE_OP_LOG_REF.to_owned(),
+        expected_current_target: previous_carrier_id.map(to_commit_id),
+        new_target: Some(to_commit_id(carrier_id)),
+    };
+    let push_stats = git::push_updates(repo, git_settings, remote_name, &[update], callbacks)?;
+    if !push_stats.all_ok() {
+        return Err(GitOpLogError::Rejected(remote_name.to_owned()));
+    }
+    git_repo
+        .reference(
+            tracking_ref,
+            carrier_id,
+            gix::refs::transaction::PreviousValue::Any,
+            "push operation log",
+        )
+        .map_err(|err| GitOpLogError::Git(err.into()))?;
+    Ok(Some(stats))
+}
+
+/// Fetches the operation log from the remote, and imports the operations and
+/// commit metadata which don't exist in the local repo.
+///
+/// The imported operations aren't reachable from the current op heads. The
+/// caller should merge the returned `head_ids`, which are the operation heads
+/// on the remote. If the remote has no operation log, `head_ids` is empty.
+pub fn fetch_op_log(
+    repo_loader: &RepoLoader,
+    git_settings: &GitSettings,
+    remote_name: &RemoteName,
+    callbacks: RemoteCallbacks<'_>,
+) -> Result<OpBundleStats, GitOpLogError> {
+    let git_backend = git::get_git_backend(repo_loader.store())?;
+    let tracking_ref = remote_op_log_tracking_ref(remote_name);
+    let (remote_ref_dir, _) = REMOTE_OP_LOG_REF.rsplit_once('/').unwrap();
+    let (tracking_ref_dir, _) = tracking_ref.rsplit_once('/').unwrap();
+    // A glob refspec doesn't fail if the remote has no operation log.
+    let refspec = RefSpec::forced(
+        format!("{remote_ref_dir}/*"),
+        format!("{tracking_ref_dir}/*"),
+    );
+    GitFetchImpl::new(repo_loader.store(), git_settings)?.fetch_refspecs(
+        remote_name,
+        &[refspec],
+        callbacks,
+    )?;
+
+    let mut stats = OpBundleStats {
+        head_ids: vec![],
+        num_operations: 0,
+        commit_ids: vec![],
+        conflicting_commit_ids: vec![],
+    };
+    let git_repo = git_backend.git_repo();
+    let Some(tip_id) = find_ref_target(&git_repo, &tracking_ref)? else {
+        return Ok(stats);
+    };
+    // Collect carriers up to the last one that was imported or pushed.
+    let mut carriers = vec![];
+    let mut next_id = Some(tip_id);
+    while let Some(id) = next_id {
+        let carrier = read_carrier(&git_repo, id)?;
+        if carrier
+            .head_ids
+            .iter()
+            .map(|id| has_operation(repo_loader, id))
+            .process_results(|mut found| found.all(|found| found))?
+        {
+            break;
+        }
+        next_id = carrier.previous_id;
+        carriers.push(carrier);
+    }
+    for carrier in carriers.iter().rev() {
+        let bundle = read_blob(&git_repo, carrier.bundle_id)?;
+        let bundle_stats = op_bundle::import_operations(repo_loader, &mut bundle.as_slice())?;
+        stats.num_operations += bundle_stats.num_operations;
+        stats.commit_ids.extend(bundle_stats.commit_ids);
+        stats
+            .conflicting_commit_ids
+            .extend(bundle_stats.conflicting_commit_ids);
+    }
+    stats.head_ids = read_carrier(&git_repo, tip_id)?.head_ids;
+    Ok(stats)
+}
+
+#[derive(Debug)]
+struct Carrier {
+    head_ids: Vec<OperationId>,
+    previous_id: Option<gix::ObjectId>,
+    bundle_id: gix::ObjectId,
+}
+
+fn write_carrier(
+    git_repo: &gix::Repository,
+    head_ids: &[OperationId],
+    previous_id: Option<gix::ObjectId>,
+    commit_ids: impl IntoIterator<Item = gix::ObjectId>,
+    bundle: &[u8],
+) -> Result<gix::ObjectId, GitOpLogError> {
+    let to_git_err = |err: gix::object::write::Error| GitOpLogError::Git(err.into());
+    let write_file = |name: &str, data: &[u8]| -> Result<_, GitOpLogError> {
+        let oid = git_repo.write_blob(data).map_err(to_git_err)?.detach();
+        Ok(gix::objs::tree::Entry {
+            mode: gix::object::tree::EntryKind::Blob.into(),
+            filename: name.into(),
+            oid,
+        })
+    };
+    let heads = head_ids.iter().map(|id| format!("{}\n", id.hex())).join("");
+    // Entries are sorted by name.
+    let mut entries = vec![
+        write_file(CARRIER_BUNDLE_FILE, bundle)?,
+        write_file(CARRIER_HEADS_FILE, heads.as_bytes())?,
+    ];
+    if let Some(id) = previous_id {
+        entries.push(write_file(
+            CARRIER_PREVIOUS_FILE,
+            id.to_string().as_bytes(),
+        )?);
+    }
+    let tree_id = git_repo
+        .write_object(gix::objs::Tree { entries })
+        .map_err(to_git_err)?
+        .detach();
+    let signature = gix::actor::Signature {
+        name: "jj".into(),
+        email: "jj@localhost".into(),
+        time: gix::date::Time::now_utc(),
+    };
+    let commit = gix::objs::Commit {
+        message: "jj operation log\n".into(),
+        tree: tree_id,
+        author: signature.clone(),
+        committer: signature,
+        encoding: None,
+        parents: previous_id.into_iter().chain(commit_ids).collect(),
+        extra_headers: vec![],
+    };
+    let id = git_repo.write_object(&commit).map_err(to_git_err)?.detach();
+    Ok(id)
+}
+
+fn read_carrier(git_repo: &gix::Repository, id: gix::ObjectId) -> Result<Carrier, GitOpLogError> {
+    let invalid = || GitOpLogError::InvalidCarrier(id.to_string());
+    let commit = git_repo
+        .find_commit(id)
+        .map_err(|err| GitOpLogError::Git(err.into()))?;
+    let tree = commit
+        .tree()
+        .map_err(|err| GitOpLogError::Git(err.into()))?;
+    let find_file = |name: &str| {
+        tree.find_entry(name)
+            .filter(|entry| entry.mode().is_blob())
+            .map(|entry| entry.object_id())
+    };
+    let parse_hex_lines = |data: &[u8]| -> Result<Vec<Vec<u8>>, GitOpLogError> {
+        let text = std::str::from_utf8(data).map_err(|_| invalid())?;
+        text.lines()
+            .map(|line| hex::decode(line).map_err(|_| invalid()))
+            .try_collect()
+    };
+
+    let bundle_id = find_file(CARRIER_BUNDLE_FILE).ok_or_else(invalid)?;
+    let heads_id = find_file(CARRIER_HEADS_FILE).ok_or_else(invalid)?;
+    let head_ids = parse_hex_lines(&read_blob(git_repo, heads_id)?)?
+        .into_iter()
+        .map(OperationId::new)
+        .collect();
+    let previous_id = match find_file(CARRIER_PREVIOUS_FILE) {
+        Some(blob_id) => {
+            let [id] = parse_hex_lines(&read_blob(git_repo, blob_id)?)?
+                .try_into()
+                .map_err(|_| invalid())?;
+            Some(gix::ObjectId::try_from(id.as_slice()).map_err(|_| invalid())?)
+        }
+        None => None,
+    };
+    Ok(Carrier {
+        head_ids,
+        previous_id,
+        bundle_id,
+    })
+}
+
+fn read_blob(git_repo: &gix::Repository, id: gix::ObjectId) -> Result<Vec<u8>, GitOpLogError> {
+    let mut blob = git_repo
+        .find_blob(id)
+        .map_err(|err| GitOpLogError::Git(err.into()))?;
+    Ok(blob.take_data())
+}
+
+fn find_ref_target(
+    git_repo: &gix::Repository,
+    name: &str,
+) -> Result<Option<gix::ObjectId>, GitOpLogError> {
+    let reference = git_repo
+        .try_find_reference(name)
+        .map_err(|err| GitOpLogError::Git(err.into()))?;
+    Ok(reference.and_then(|reference| reference.target().try_id().map(|id| id.to_owned())))
+}
+
+fn has_operation(repo_loader: &RepoLoader, id: &OperationId) -> Result<bool, OpStoreError> {
+    match repo_loader.op_store().read_operation(id) {
+        Ok(_) => Ok(true),
+        Err(OpStoreError::ObjectNotFound { .. }) => Ok(false),
+        Err(err) => Err(err),
+    }
+}
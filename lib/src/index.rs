@@ -174,6 +174,10 @@ pub trait ChangeIdIndex: Send + Sync {
     /// The order of the returned commit IDs is unspecified.
     fn resolve_prefix(&self, prefix: &HexPrefix) -> PrefixResolution<Vec<CommitId>>;
 
+    /// Returns true if more than one commit reachable from the heads has the
+    /// `change_id`.
+    fn is_divergent(&self, change_id: &ChangeId) -> bool;
+
     /// This function returns the shortest length of a prefix of `key` that
     /// disambiguates it from every other key in the index.
     ///
@@ -68,6 +68,8 @@ pub mod git {
 #[cfg(feature = "git")]
 pub mod git_backend;
 #[cfg(feature = "git")]
+pub mod git_op_log;
+#[cfg(feature = "git")]
 mod git_subprocess;
 pub mod gitignore;
 pub mod gpg_signing;
@@ -79,8 +81,10 @@ pub mod local_working_copy;
 pub mod lock;
 pub mod matchers;
 pub mod merge;
+pub mod merge_driver;
 pub mod merged_tree;
 pub mod object_id;
+pub mod op_bundle;
 pub mod op_heads_store;
 pub mod op_store;
 pub mod op_walk;
@@ -91,6 +95,7 @@ pub mod ref_name;
 pub mod refs;
 pub mod repo;
 pub mod repo_path;
+pub mod rerere;
 pub mod revset;
 mod revset_parser;
 pub mod rewrite;
@@ -103,11 +108,13 @@ pub mod signing;
 // "examples/simple-backend/").
 pub mod simple_backend;
 pub mod simple_op_heads_store;
+mod simple_op_pack;
 pub mod simple_op_store;
 pub mod ssh_signing;
 pub mod stacked_table;
 pub mod store;
 pub mod str_util;
+pub mod structured_merge;
 pub mod submodule_store;
 #[cfg(feature = "testing")]
 pub mod test_signing_backend;
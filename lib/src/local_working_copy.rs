@@ -72,6 +72,7 @@ use crate::conflicts::materialize_tree_value;
 use crate::conflicts::ConflictMarkerStyle;
 use crate::conflicts::MaterializedTreeValue;
 use crate::conflicts::MIN_CONFLICT_MARKER_LEN;
+use crate::diff::DiffAlgorithm;
 use crate::file_util::check_symlink_support;
 use crate::file_util::try_symlink;
 #[cfg(feature = "watchman")]
@@ -1857,6 +1858,7 @@ impl TreeState {
                         &contents,
                         conflict_marker_style,
                         conflict_marker_len,
+                        DiffAlgorithm::Default,
                     )
                     .into();
                     let materialized_conflict_data = MaterializedConflictData {
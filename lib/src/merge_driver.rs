@@ -0,0 +1,520 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Per-path merge strategies ("merge drivers").
+//!
+//! The merge driver of a file is selected by the `merge` attribute in
+//! `.gitattributes` files, or by the `merge-drivers.<name>.patterns` config.
+//! The config takes precedence over `.gitattributes`.
+
+#![allow(missing_docs)]
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::io::Read as _;
+use std::process::Command;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use bstr::BString;
+use thiserror::Error;
+
+use crate::backend::BackendError;
+use crate::backend::BackendResult;
+use crate::backend::TreeValue;
+use crate::config::ConfigGetError;
+use crate::config::ConfigGetResultExt as _;
+use crate::diff::DiffAlgorithm;
+use crate::files;
+use crate::files::MergeResult;
+use crate::merge::Merge;
+use crate::repo_path::RepoPath;
+use crate::repo_path::RepoPathBuf;
+use crate::repo_path::RepoPathComponent;
+use crate::settings::UserSettings;
+use crate::tree::Tree;
+
+const GITATTRIBUTES_FILE_NAME: &str = ".gitattributes";
+
+/// Strategy to merge the contents of a file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MergeDriver {
+    /// The built-in line-based merge (`merge=text`, the default).
+    Text,
+    /// Leaves the conflict unresolved (`-merge` or `merge=binary`).
+    Binary,
+    /// Takes the lines from all sides of conflicting hunks (`merge=union`).
+    Union,
+    /// Takes the contents of the first side (`merge=ours`).
+    Ours,
+    /// Merges JSON, TOML, and YAML files key by key, falling back to the
+    /// line-based merge (`merge=structured`).
+    Structured,
+    /// Runs a command configured in `merge-drivers.<name>.command`.
+    External(Arc<ExternalMergeDriver>),
+}
+
+impl MergeDriver {
+    fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "text" => Some(MergeDriver::Text),
+            "binary" => Some(MergeDriver::Binary),
+            "union" => Some(MergeDriver::Union),
+            "ours" => Some(MergeDriver::Ours),
+            "structured" => Some(MergeDriver::Structured),
+            _ => None,
+        }
+    }
+}
+
+/// Merge driver which runs an external command.
+///
+/// The command arguments may contain the variables `$base`, `$left`, `$right`,
+/// `$output`, and `$path`. The `$output` file initially contains the contents
+/// of the left side, and the command should replace it with the merged
+/// contents. The conflict is resolved if the command exits successfully.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExternalMergeDriver {
+    pub name: String,
+    pub command: Vec<String>,
+}
+
+/// Error that may occur when running an external merge driver.
+#[derive(Debug, Error)]
+#[error("Failed to run merge driver '{name}'")]
+pub struct ExternalMergeDriverError {
+    pub name: String,
+    #[source]
+    pub source: io::Error,
+}
+
+/// State of the `merge` attribute.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum MergeAttribute {
+    Set,
+    Unset,
+    Unspecified,
+    Value(String),
+}
+
+/// Line of a `.gitattributes` file (or a config pattern) specifying the
+/// `merge` attribute.
+#[derive(Clone, Debug)]
+struct AttributeRule {
+    dir: RepoPathBuf,
+    pattern: glob::Pattern,
+    // Whether the pattern is matched against the path relative to `dir`
+    // rather than the file name.
+    anchored: bool,
+    merge: MergeAttribute,
+}
+
+impl AttributeRule {
+    fn new(dir: &RepoPath, pattern: &str, merge: MergeAttribute) -> Option<Self> {
+        // Patterns matching directories don't apply to files.
+        if pattern.is_empty() || pattern.ends_with('/') {
+            return None;
+        }
+        let (pattern, anchored) = match pattern.strip_prefix('/') {
+            Some(pattern) => (pattern, true),
+            None => (pattern, pattern.contains('/')),
+        };
+        Some(AttributeRule {
+            dir: dir.to_owned(),
+            pattern: glob::Pattern::new(pattern).ok()?,
+            anchored,
+            merge,
+        })
+    }
+
+    fn matches(&self, path: &RepoPath) -> bool {
+        let Some(relative_path) = path.strip_prefix(&self.dir) else {
+            return false;
+        };
+        let options = glob::MatchOptions {
+            case_sensitive: true,
+            require_literal_separator: true,
+            require_literal_leading_dot: false,
+        };
+        if self.anchored {
+            self.pattern
+                .matches_with(relative_path.as_internal_file_string(), options)
+        } else {
+            let (_, basename) = relative_path.split().unwrap();
+            self.pattern
+                .matches_with(basename.as_internal_str(), options)
+        }
+    }
+}
+
+/// Parses the rules specifying the `merge` attribute in the contents of a
+/// `.gitattributes` file in `dir`.
+fn parse_gitattributes(dir: &RepoPath, content: &[u8]) -> Vec<AttributeRule> {
+    let mut rules = vec![];
+    for line in String::from_utf8_lossy(content).lines() {
+        let line = line.trim();
+        // Quoted patterns and macro definitions aren't supported.
+        if line.is_empty() || line.starts_with(['#', '"', '[']) {
+            continue;
+        }
+        let mut words = line.split_whitespace();
+        let pattern = words.next().unwrap();
+        // Later attributes on the same line override the earlier ones.
+        let merge = words
+            .filter_map(|word| match word {
+                "merge" => Some(MergeAttribute::Set),
+                "-merge" | "binary" => Some(MergeAttribute::Unset),
+                "!merge" => Some(MergeAttribute::Unspecified),
+                _ => word
+                    .strip_prefix("merge=")
+                    .map(|name| MergeAttribute::Value(name.to_owned())),
+            })
+            .next_back();
+        if let Some(rule) = merge.and_then(|merge| AttributeRule::new(dir, pattern, merge)) {
+            rules.push(rule);
+        }
+    }
+    rules
+}
+
+/// Merge drivers and patterns loaded from the config.
+#[derive(Clone, Debug, Default)]
+pub struct MergeDrivers {
+    drivers: HashMap<String, Arc<ExternalMergeDriver>>,
+    rules: Vec<AttributeRule>,
+}
+
+impl MergeDrivers {
+    /// Loads the `merge-drivers` config.
+    pub fn from_settings(settings: &UserSettings) -> Result<Self, ConfigGetError> {
+        let mut drivers = HashMap::new();
+        let mut rules = vec![];
+        for name in settings.table_keys("merge-drivers") {
+            let command = settings
+                .get::<Vec<String>>(["merge-drivers", name, "command"])
+                .optional()?;
+            if let Some(command) = command {
+                if MergeDriver::builtin(name).is_some() {
+                    return Err(ConfigGetError::Type {
+                        name: format!("merge-drivers.{name}.command"),
+                        error: format!("The built-in merge driver '{name}' can't be redefined")
+                            .into(),
+                        source_path: None,
+                    });
+                }
+                let driver = ExternalMergeDriver {
+                    name: name.to_owned(),
+                    command,
+                };
+                drivers.insert(name.to_owned(), Arc::new(driver));
+            }
+            let patterns = settings
+                .get::<Vec<String>>(["merge-drivers", name, "patterns"])
+                .optional()?
+                .unwrap_or_default();
+            for pattern in patterns {
+                let merge = MergeAttribute::Value(name.to_owned());
+                let rule =
+                    AttributeRule::new(RepoPath::root(), &pattern, merge).ok_or_else(|| {
+                        ConfigGetError::Type {
+                            name: format!("merge-drivers.{name}.patterns"),
+                            error: format!("Invalid file pattern '{pattern}'").into(),
+                            source_path: None,
+                        }
+                    })?;
+                rules.push(rule);
+            }
+        }
+        Ok(MergeDrivers { drivers, rules })
+    }
+
+    fn driver_for_attribute(&self, attribute: &MergeAttribute) -> MergeDriver {
+        match attribute {
+            MergeAttribute::Set | MergeAttribute::Unspecified => MergeDriver::Text,
+            MergeAttribute::Unset => MergeDriver::Binary,
+            MergeAttribute::Value(name) => MergeDriver::builtin(name)
+                .or_else(|| self.drivers.get(name).cloned().map(MergeDriver::External))
+                // Like Git, fall back to the default merge if the driver isn't
+                // defined.
+                .unwrap_or(MergeDriver::Text),
+        }
+    }
+}
+
+/// Looks up the merge driver of paths in a tree merge.
+pub struct MergeDriverResolver<'a> {
+    drivers: &'a MergeDrivers,
+    // Root tree to read `.gitattributes` files from.
+    attributes_tree: Option<Tree>,
+    attributes_cache: Mutex<HashMap<RepoPathBuf, Arc<Vec<AttributeRule>>>>,
+}
+
+impl<'a> MergeDriverResolver<'a> {
+    /// Creates a resolver which reads `.gitattributes` files from the
+    /// `attributes_tree` if given.
+    pub fn new(drivers: &'a MergeDrivers, attributes_tree: Option<Tree>) -> Self {
+        MergeDriverResolver {
+            drivers,
+            attributes_tree,
+            attributes_cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the merge driver to use for the file at `path`.
+    pub fn driver_for(&self, path: &RepoPath) -> BackendResult<MergeDriver> {
+        if let Some(rule) = self
+            .drivers
+            .rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matches(path))
+        {
+            return Ok(self.drivers.driver_for_attribute(&rule.merge));
+        }
+        if let Some(tree) = &self.attributes_tree {
+            // Attributes in deeper directories take precedence.
+            let mut dir = path.parent();
+            while let Some(current_dir) = dir {
+                let rules = self.load_gitattributes(tree, current_dir)?;
+                if let Some(rule) = rules.iter().rev().find(|rule| rule.matches(path)) {
+                    return Ok(self.drivers.driver_for_attribute(&rule.merge));
+                }
+                dir = current_dir.parent();
+            }
+        }
+        Ok(MergeDriver::Text)
+    }
+
+    fn load_gitattributes(
+        &self,
+        root_tree: &Tree,
+        dir: &RepoPath,
+    ) -> BackendResult<Arc<Vec<AttributeRule>>> {
+        if let Some(rules) = self.attributes_cache.lock().unwrap().get(dir) {
+            return Ok(rules.clone());
+        }
+        let file_name = RepoPathComponent::new(GITATTRIBUTES_FILE_NAME);
+        let value = root_tree
+            .sub_tree_recursive(dir)?
+            .and_then(|tree| tree.value(file_name).cloned());
+        let rules = if let Some(TreeValue::File { id, .. }) = value {
+            let path = dir.join(file_name);
+            let mut content = vec![];
+            root_tree
+                .store()
+                .read_file(&path, &id)?
+                .read_to_end(&mut content)
+                .map_err(|err| BackendError::ReadFile {
+                    path,
+                    id,
+                    source: err.into(),
+                })?;
+            parse_gitattributes(dir, &content)
+        } else {
+            vec![]
+        };
+        let rules = Arc::new(rules);
+        self.attributes_cache
+            .lock()
+            .unwrap()
+            .insert(dir.to_owned(), rules.clone());
+        Ok(rules)
+    }
+}
+
+/// Merges the file `contents` by taking the lines from all sides of the
+/// conflicting hunks.
+pub fn merge_union(contents: &Merge<Vec<u8>>) -> BString {
+    match files::merge(contents, DiffAlgorithm::Default) {
+        MergeResult::Resolved(content) => content,
+        MergeResult::Conflict(hunks) => {
+            let mut merged = BString::default();
+            for hunk in hunks {
+                if let Some(content) = hunk.as_resolved() {
+                    merged.extend_from_slice(content);
+                } else {
+                    for content in hunk.adds() {
+                        merged.extend_from_slice(content);
+                    }
+                }
+            }
+            merged
+        }
+    }
+}
+
+/// Merges the file `contents` by running the external `driver`. Returns `None`
+/// if the driver failed to merge the contents.
+///
+/// Only 2-sided conflicts are supported.
+pub fn run_external_merge_driver(
+    driver: &ExternalMergeDriver,
+    path: &RepoPath,
+    contents: &Merge<Vec<u8>>,
+) -> Result<Option<Vec<u8>>, ExternalMergeDriverError> {
+    let (Some(base), Some(left), Some(right)) = (
+        contents.get_remove(0),
+        contents.get_add(0),
+        contents.get_add(1),
+    ) else {
+        return Ok(None);
+    };
+    if contents.num_sides() != 2 {
+        return Ok(None);
+    }
+    let to_error = |source| ExternalMergeDriverError {
+        name: driver.name.clone(),
+        source,
+    };
+    let temp_dir = tempfile::Builder::new()
+        .prefix("jj-merge-")
+        .tempdir()
+        .map_err(to_error)?;
+    let mut variables = vec![];
+    for (name, content) in [
+        ("base", base),
+        ("left", left),
+        ("right", right),
+        ("output", left),
+    ] {
+        let file_path = temp_dir.path().join(name);
+        fs::write(&file_path, content).map_err(to_error)?;
+        variables.push((name, file_path.to_string_lossy().into_owned()));
+    }
+    variables.push(("path", path.as_internal_file_string().to_owned()));
+    let args = driver.command.iter().map(|arg| {
+        variables.iter().fold(arg.clone(), |arg, (name, value)| {
+            arg.replace(&format!("${name}"), value)
+        })
+    });
+    let mut args = args.collect::<Vec<_>>().into_iter();
+    let Some(program) = args.next() else {
+        return Err(to_error(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "empty command",
+        )));
+    };
+    let status = Command::new(program)
+        .args(args)
+        .current_dir(temp_dir.path())
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .status()
+        .map_err(to_error)?;
+    if !status.success() {
+        return Ok(None);
+    }
+    let merged = fs::read(temp_dir.path().join("output")).map_err(to_error)?;
+    Ok(Some(merged))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repo_path(value: &str) -> &RepoPath {
+        RepoPath::from_internal_string(value)
+    }
+
+    fn merge_attribute(rules: &[AttributeRule], path: &str) -> Option<MergeAttribute> {
+        let path = repo_path(path);
+        rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matches(path))
+            .map(|rule| rule.merge.clone())
+    }
+
+    #[test]
+    fn test_parse_gitattributes() {
+        let rules = parse_gitattributes(
+            RepoPath::root(),
+            b"# comment\n\
+              *.lock merge=ours\n\
+              CHANGELOG.md merge=union text\n\
+              /generated/** -merge\n\
+              docs/*.md merge=foo\n\
+              *.png binary\n\
+              keep.lock !merge\n\
+              *.txt diff\n\
+              dir/ merge=union\n",
+        );
+        assert_eq!(
+            merge_attribute(&rules, "Cargo.lock"),
+            Some(MergeAttribute::Value("ours".to_owned()))
+        );
+        assert_eq!(
+            merge_attribute(&rules, "sub/dir/Cargo.lock"),
+            Some(MergeAttribute::Value("ours".to_owned()))
+        );
+        assert_eq!(
+            merge_attribute(&rules, "sub/CHANGELOG.md"),
+            Some(MergeAttribute::Value("union".to_owned()))
+        );
+        assert_eq!(
+            merge_attribute(&rules, "generated/a/b.rs"),
+            Some(MergeAttribute::Unset)
+        );
+        assert_eq!(merge_attribute(&rules, "sub/generated/b.rs"), None);
+        assert_eq!(
+            merge_attribute(&rules, "docs/index.md"),
+            Some(MergeAttribute::Value("foo".to_owned()))
+        );
+        assert_eq!(merge_attribute(&rules, "docs/sub/index.md"), None);
+        assert_eq!(
+            merge_attribute(&rules, "image.png"),
+            Some(MergeAttribute::Unset)
+        );
+        assert_eq!(
+            merge_attribute(&rules, "keep.lock"),
+            Some(MergeAttribute::Unspecified)
+        );
+        assert_eq!(merge_attribute(&rules, "a.txt"), None);
+        assert_eq!(merge_attribute(&rules, "dir/file"), None);
+    }
+
+    #[test]
+    fn test_parse_gitattributes_in_subdir() {
+        let rules = parse_gitattributes(repo_path("sub"), b"*.lock merge=ours\n/a.txt -merge\n");
+        assert_eq!(merge_attribute(&rules, "Cargo.lock"), None);
+        assert_eq!(
+            merge_attribute(&rules, "sub/x/Cargo.lock"),
+            Some(MergeAttribute::Value("ours".to_owned()))
+        );
+        assert_eq!(
+            merge_attribute(&rules, "sub/a.txt"),
+            Some(MergeAttribute::Unset)
+        );
+        assert_eq!(merge_attribute(&rules, "sub/x/a.txt"), None);
+    }
+
+    #[test]
+    fn test_merge_union() {
+        let contents = Merge::from_vec(vec![
+            b"a\nleft\nc\n".to_vec(),
+            b"a\nb\nc\n".to_vec(),
+            b"a\nright\nc\n".to_vec(),
+        ]);
+        assert_eq!(merge_union(&contents), "a\nleft\nright\nc\n");
+
+        let contents = Merge::from_vec(vec![
+            b"a\nleft\nc\n".to_vec(),
+            b"a\nb\nc\n".to_vec(),
+            b"a\nb\nright\n".to_vec(),
+        ]);
+        assert_eq!(merge_union(&contents), "a\nleft\nc\nb\nright\n");
+    }
+}
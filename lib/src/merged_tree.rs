@@ -51,6 +51,7 @@ use crate::merge::Merge;
 use crate::merge::MergeBuilder;
 use crate::merge::MergedTreeVal;
 use crate::merge::MergedTreeValue;
+use crate::merge_driver::MergeDriverResolver;
 use crate::repo_path::RepoPath;
 use crate::repo_path::RepoPathBuf;
 use crate::repo_path::RepoPathComponent;
@@ -160,8 +161,20 @@ impl MergedTree {
 
     /// Tries to resolve any conflicts, resolving any conflicts that can be
     /// automatically resolved and leaving the rest unresolved.
+    ///
+    /// File conflicts are merged line by line. Merge drivers aren't consulted.
     pub fn resolve(&self) -> BackendResult<MergedTree> {
-        let merged = merge_trees(&self.trees)?;
+        self.resolve_impl(None)
+    }
+
+    /// Like `resolve()`, but file conflicts are merged by the drivers selected
+    /// by the `resolver`.
+    pub fn resolve_with(&self, resolver: &MergeDriverResolver) -> BackendResult<MergedTree> {
+        self.resolve_impl(Some(resolver))
+    }
+
+    fn resolve_impl(&self, resolver: Option<&MergeDriverResolver>) -> BackendResult<MergedTree> {
+        let merged = merge_trees(&self.trees, resolver)?;
         // If the result can be resolved, then `merge_trees()` above would have returned
         // a resolved merge. However, that function will always preserve the arity of
         // conflicts it cannot resolve. So we simplify the conflict again
@@ -171,7 +184,7 @@ impl MergedTree {
         // particular,  that this last simplification doesn't enable further automatic
         // resolutions
         if cfg!(debug_assertions) {
-            let re_merged = merge_trees(&simplified).unwrap();
+            let re_merged = merge_trees(&simplified, resolver).unwrap();
             debug_assert_eq!(re_merged, simplified);
         }
         Ok(MergedTree { trees: simplified })
@@ -440,7 +453,10 @@ fn trees_value<'a>(trees: &'a Merge<Tree>, basename: &RepoPathComponent) -> Merg
 
 /// The returned conflict will either be resolved or have the same number of
 /// sides as the input.
-fn merge_trees(merge: &Merge<Tree>) -> BackendResult<Merge<Tree>> {
+fn merge_trees(
+    merge: &Merge<Tree>,
+    resolver: Option<&MergeDriverResolver>,
+) -> BackendResult<Merge<Tree>> {
     if let Some(tree) = merge.resolve_trivial() {
         return Ok(Merge::resolved(tree.clone()));
     }
@@ -456,7 +472,7 @@ fn merge_trees(merge: &Merge<Tree>) -> BackendResult<Merge<Tree>> {
     // TODO: Merge values concurrently
     for (basename, path_merge) in all_merged_tree_entries(merge) {
         let path = dir.join(basename);
-        let path_merge = merge_tree_values(store, &path, &path_merge).block_on()?;
+        let path_merge = merge_tree_values(store, &path, &path_merge, resolver).block_on()?;
         match path_merge.into_resolved() {
             Ok(value) => {
                 new_tree.set_or_remove(basename, value);
@@ -494,6 +510,7 @@ async fn merge_tree_values(
     store: &Arc<Store>,
     path: &RepoPath,
     values: &MergedTreeVal<'_>,
+    resolver: Option<&MergeDriverResolver<'_>>,
 ) -> BackendResult<MergedTreeValue> {
     if let Some(resolved) = values.resolve_trivial() {
         return Ok(Merge::resolved(resolved.cloned()));
@@ -503,11 +520,11 @@ async fn merge_tree_values(
         // If all sides are trees or missing, merge the trees recursively, treating
         // missing trees as empty.
         let empty_tree_id = store.empty_tree_id();
-        let merged_tree = merge_trees(&trees)?;
+        let merged_tree = merge_trees(&trees, resolver)?;
         Ok(merged_tree
             .map(|tree| (tree.id() != empty_tree_id).then(|| TreeValue::Tree(tree.id().clone()))))
     } else {
-        let maybe_resolved = try_resolve_file_values(store, path, values).await?;
+        let maybe_resolved = try_resolve_file_values(store, path, values, resolver).await?;
         Ok(maybe_resolved.unwrap_or_else(|| values.cloned()))
     }
 }
@@ -524,7 +541,7 @@ pub async fn resolve_file_values(
         return Ok(Merge::resolved(resolved.clone()));
     }
 
-    let maybe_resolved = try_resolve_file_values(store, path, &values).await?;
+    let maybe_resolved = try_resolve_file_values(store, path, &values, None).await?;
     Ok(maybe_resolved.unwrap_or(values))
 }
 
@@ -532,6 +549,7 @@ async fn try_resolve_file_values<T: Borrow<TreeValue>>(
     store: &Arc<Store>,
     path: &RepoPath,
     values: &Merge<Option<T>>,
+    resolver: Option<&MergeDriverResolver<'_>>,
 ) -> BackendResult<Option<MergedTreeValue>> {
     // The values may contain trees canceling each other (notably padded absent
     // trees), so we need to simplify them first.
@@ -540,7 +558,7 @@ async fn try_resolve_file_values<T: Borrow<TreeValue>>(
         .simplify();
     // No fast path for simplified.is_resolved(). If it could be resolved, it would
     // have been caught by values.resolve_trivial() above.
-    if let Some(resolved) = try_resolve_file_conflict(store, path, &simplified).await? {
+    if let Some(resolved) = try_resolve_file_conflict(store, path, &simplified, resolver).await? {
         Ok(Some(Merge::normal(resolved)))
     } else {
         // Failed to merge the files, or the paths are not files
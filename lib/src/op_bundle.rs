@@ -0,0 +1,570 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Portable bundles of operations.
+//!
+//! A bundle contains operations, their views, and all commits, trees, files,
+//! and symlinks reachable from the views. It can be imported to another repo
+//! backed by the same kind of commit backend, where the imported operations
+//! can be merged with the local operations.
+//!
+//! The bundle is encoded as a zlib-compressed protobuf message prefixed by
+//! magic bytes. Objects are serialized in the formats of the simple backend
+//! and the simple op store.
+
+#![allow(missing_docs)]
+
+use std::collections::HashSet;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::sync::Arc;
+
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use itertools::Itertools as _;
+use pollster::FutureExt as _;
+use prost::Message as _;
+use thiserror::Error;
+
+use crate::backend::BackendError;
+use crate::backend::CommitId;
+use crate::backend::ConflictId;
+use crate::backend::FileId;
+use crate::backend::MergedTreeId;
+use crate::backend::SymlinkId;
+use crate::backend::TreeId;
+use crate::backend::TreeValue;
+use crate::commit::Commit;
+use crate::dag_walk;
+#[cfg(feature = "git")]
+use crate::git_backend::GitBackend;
+use crate::index::Index;
+use crate::index::IndexReadError;
+use crate::index::ReadonlyIndex;
+use crate::merge::Merge;
+use crate::object_id::ObjectId;
+use crate::op_store::OpStoreError;
+use crate::op_store::OperationId;
+use crate::op_store::ViewId;
+use crate::op_walk;
+use crate::operation::Operation;
+use crate::protos::op_bundle as proto;
+use crate::repo::RepoLoader;
+use crate::repo_path::RepoPath;
+use crate::repo_path::RepoPathBuf;
+use crate::revset::ResolvedExpression;
+use crate::revset::Revset;
+use crate::revset::RevsetEvaluationError;
+use crate::revset::GENERATION_RANGE_FULL;
+use crate::simple_backend;
+use crate::simple_op_store;
+use crate::store::Store;
+
+const BUNDLE_MAGIC: &[u8; 8] = b"JJOPBNDL";
+const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+/// Error that may occur during bundle export or import.
+#[derive(Debug, Error)]
+pub enum OpBundleError {
+    #[error(transparent)]
+    Backend(#[from] BackendError),
+    #[error(transparent)]
+    OpStore(#[from] OpStoreError),
+    #[error(transparent)]
+    IndexRead(#[from] IndexReadError),
+    #[error(transparent)]
+    RevsetEvaluation(#[from] RevsetEvaluationError),
+    #[error("I/O error while reading or writing bundle")]
+    Io(#[from] io::Error),
+    #[error("Not an operation bundle, or unsupported bundle format")]
+    UnsupportedFormat,
+    #[error("Corrupt operation bundle")]
+    Corrupt(#[source] Box<dyn std::error::Error + Send + Sync>),
+    #[error("Bundle was created by an incompatible commit backend")]
+    IncompatibleBackend,
+    #[error("Cannot export {object_type} at {path:?}: not supported by operation bundles")]
+    UnsupportedObject {
+        object_type: &'static str,
+        path: RepoPathBuf,
+    },
+    #[error("Imported {object_type} {actual} does not match the expected id {expected}")]
+    IdMismatch {
+        object_type: &'static str,
+        expected: String,
+        actual: String,
+    },
+}
+
+/// Summary of an exported or imported bundle.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OpBundleStats {
+    /// Operation heads contained in the bundle.
+    pub head_ids: Vec<OperationId>,
+    /// Number of operations in the bundle.
+    pub num_operations: usize,
+    /// Commits in the bundle. On import, commits that already existed in the
+    /// destination repo aren't included.
+    pub commit_ids: Vec<CommitId>,
+    /// Imported commits whose metadata differed from the metadata in the
+    /// destination repo. The destination metadata was kept.
+    pub conflicting_commit_ids: Vec<CommitId>,
+}
+
+/// Options to select the contents of an exported bundle.
+#[derive(Clone, Debug, Default)]
+pub struct OpBundleExportOptions {
+    /// Operations known to the destination repo. These operations, their
+    /// ancestors, and commits reachable from their views are left out.
+    pub known_ops: Vec<Operation>,
+    /// Leave out trees, files, symlinks, and conflicts. Use this if the commit
+    /// contents are transferred by other means, e.g. by Git.
+    pub exclude_tree_objects: bool,
+}
+
+/// Writes the `head_ops` and their ancestors to `output` as a bundle.
+pub fn export_operations(
+    repo_loader: &RepoLoader,
+    head_ops: &[Operation],
+    output: &mut dyn Write,
+) -> Result<OpBundleStats, OpBundleError> {
+    export_operations_with_options(
+        repo_loader,
+        head_ops,
+        &OpBundleExportOptions::default(),
+        output,
+    )
+}
+
+/// Writes the `head_ops` and their ancestors to `output` as a bundle,
+/// excluding the objects specified by the `options`.
+pub fn export_operations_with_options(
+    repo_loader: &RepoLoader,
+    head_ops: &[Operation],
+    options: &OpBundleExportOptions,
+    output: &mut dyn Write,
+) -> Result<OpBundleStats, OpBundleError> {
+    let store = repo_loader.store();
+    let root_op_id = repo_loader.op_store().root_operation_id();
+    // walk_ancestors_range() emits children first
+    let mut ops: Vec<Operation> = op_walk::walk_ancestors_range(head_ops, &options.known_ops)
+        .filter_ok(|op| op.id() != root_op_id)
+        .try_collect()?;
+    ops.reverse();
+
+    // Commits reachable from the views of the known operations exist in the
+    // destination. They are looked up in the indexes of the heads, which
+    // include the known operations if they are ancestors.
+    let mut known_view_ids: HashSet<ViewId> = HashSet::new();
+    let mut known_head_ids: Vec<CommitId> = vec![];
+    for op in &options.known_ops {
+        if known_view_ids.insert(op.view_id().clone()) {
+            known_head_ids.extend(op.view()?.all_referenced_commit_ids().cloned());
+        }
+    }
+    let indexes: Vec<Box<dyn ReadonlyIndex>> = if known_head_ids.is_empty() {
+        vec![]
+    } else {
+        head_ops
+            .iter()
+            .map(|op| repo_loader.index_store().get_index_at_op(op, store))
+            .try_collect()?
+    };
+    let known_revsets: Vec<(&dyn Index, Box<dyn Revset>)> = indexes
+        .iter()
+        .map(|index| {
+            let index = index.as_index();
+            let heads = known_head_ids
+                .iter()
+                .filter(|id| index.has_id(id))
+                .cloned()
+                .collect();
+            let expression = ResolvedExpression::Ancestors {
+                heads: Box::new(ResolvedExpression::Commits(heads)),
+                generation: GENERATION_RANGE_FULL,
+            };
+            Ok::<_, OpBundleError>((index, index.evaluate_revset(&expression, store)?))
+        })
+        .try_collect()?;
+    let known_fns = known_revsets
+        .iter()
+        .map(|(index, revset)| (*index, revset.containing_fn()))
+        .collect_vec();
+    let is_known = |id: &CommitId| -> Result<bool, OpBundleError> {
+        for (index, contains) in &known_fns {
+            if index.has_id(id) && contains(id)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    };
+
+    let mut bundle = proto::Bundle {
+        root_commit_id: store.root_commit_id().to_bytes(),
+        head_ids: head_ops.iter().map(|op| op.id().to_bytes()).collect(),
+        ..Default::default()
+    };
+    let mut seen_view_ids: HashSet<ViewId> = HashSet::new();
+    let mut referenced_commit_ids: Vec<CommitId> = vec![];
+    for op in &ops {
+        if seen_view_ids.insert(op.view_id().clone()) {
+            let view = op.view()?;
+            referenced_commit_ids.extend(view.all_referenced_commit_ids().cloned());
+            bundle.views.push(proto::bundle::View {
+                id: op.view_id().to_bytes(),
+                view: Some(simple_op_store::view_to_proto(view.store_view())),
+            });
+        }
+        bundle.operations.push(proto::bundle::Operation {
+            id: op.id().to_bytes(),
+            operation: Some(simple_op_store::operation_to_proto(op.store_operation())),
+        });
+    }
+
+    let commits = collect_commits(store, referenced_commit_ids, &is_known)?;
+    let mut collector = TreeObjectCollector::new(store);
+    for commit in &commits {
+        if !options.exclude_tree_objects {
+            let tree_ids = match &commit.store_commit().root_tree {
+                MergedTreeId::Legacy(id) => vec![id.clone()],
+                MergedTreeId::Merge(ids) => ids.iter().cloned().collect(),
+            };
+            for id in &tree_ids {
+                collector.collect_tree(&mut bundle, RepoPathBuf::root(), id)?;
+            }
+        }
+        let mut commit_proto = simple_backend::commit_to_proto(commit.store_commit());
+        commit_proto.secure_sig = commit
+            .store_commit()
+            .secure_sig
+            .as_ref()
+            .map(|sig| sig.sig.clone());
+        bundle.commits.push(proto::bundle::Commit {
+            id: commit.id().to_bytes(),
+            commit: Some(commit_proto),
+        });
+    }
+
+    let stats = OpBundleStats {
+        head_ids: head_ops.iter().map(|op| op.id().clone()).collect(),
+        num_operations: bundle.operations.len(),
+        commit_ids: commits.iter().map(|commit| commit.id().clone()).collect(),
+        conflicting_commit_ids: vec![],
+    };
+    output.write_all(BUNDLE_MAGIC)?;
+    output.write_all(&BUNDLE_FORMAT_VERSION.to_le_bytes())?;
+    let mut encoder = ZlibEncoder::new(output, Compression::default());
+    encoder.write_all(&bundle.encode_to_vec())?;
+    encoder.finish()?;
+    Ok(stats)
+}
+
+/// Reads a bundle from `input`, and writes the contained objects to the repo.
+///
+/// The imported operations aren't reachable from the current op heads. The
+/// caller should merge the returned `head_ids` into a new operation.
+pub fn import_operations(
+    repo_loader: &RepoLoader,
+    input: &mut dyn Read,
+) -> Result<OpBundleStats, OpBundleError> {
+    let mut header = [0; BUNDLE_MAGIC.len() + 4];
+    input
+        .read_exact(&mut header)
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::UnexpectedEof => OpBundleError::UnsupportedFormat,
+            _ => OpBundleError::Io(err),
+        })?;
+    let (magic, version) = header.split_at(BUNDLE_MAGIC.len());
+    if magic != BUNDLE_MAGIC || version != BUNDLE_FORMAT_VERSION.to_le_bytes() {
+        return Err(OpBundleError::UnsupportedFormat);
+    }
+    let mut buf = vec![];
+    ZlibDecoder::new(input)
+        .read_to_end(&mut buf)
+        .map_err(|err| OpBundleError::Corrupt(err.into()))?;
+    let bundle =
+        proto::Bundle::decode(buf.as_slice()).map_err(|err| OpBundleError::Corrupt(err.into()))?;
+
+    let store = repo_loader.store();
+    let op_store = repo_loader.op_store();
+    if bundle.root_commit_id != store.root_commit_id().as_bytes() {
+        return Err(OpBundleError::IncompatibleBackend);
+    }
+
+    for file in bundle.files {
+        let id = store
+            .write_file(RepoPath::root(), &mut file.content.as_slice())
+            .block_on()?;
+        check_id("file", &FileId::new(file.id), &id)?;
+    }
+    for symlink in bundle.symlinks {
+        let id = store
+            .write_symlink(RepoPath::root(), &symlink.target)
+            .block_on()?;
+        check_id("symlink", &SymlinkId::new(symlink.id), &id)?;
+    }
+    for conflict in bundle.conflicts {
+        let contents =
+            simple_backend::conflict_from_proto(conflict.conflict.ok_or_else(missing_field)?);
+        let id = store.write_conflict(RepoPath::root(), &Merge::from_backend_conflict(contents))?;
+        check_id("conflict", &ConflictId::new(conflict.id), &id)?;
+    }
+    for tree in bundle.trees {
+        let contents = simple_backend::tree_from_proto(tree.tree.ok_or_else(missing_field)?);
+        let written = store.write_tree(RepoPath::root(), contents).block_on()?;
+        check_id("tree", &TreeId::new(tree.id), written.id())?;
+    }
+    let mut commit_ids = vec![];
+    #[cfg_attr(not(feature = "git"), expect(unused_mut))]
+    let mut conflicting_commit_ids = vec![];
+    #[cfg(feature = "git")]
+    let mut transferred_commits = vec![];
+    for commit in bundle.commits {
+        let expected_id = CommitId::new(commit.id);
+        let mut contents =
+            simple_backend::commit_from_proto(commit.commit.ok_or_else(missing_field)?);
+        #[cfg(feature = "git")]
+        if let Some(git_backend) = store.backend_impl().downcast_ref::<GitBackend>() {
+            // The commit objects may have been transferred by Git, in which case
+            // only the jj-specific metadata needs to be recorded.
+            if git_backend.has_commit(&expected_id)? {
+                transferred_commits.push((expected_id, contents));
+                continue;
+            }
+        }
+        if store.get_commit(&expected_id).is_ok() {
+            continue;
+        }
+        // The signature is reused as is so the commit id is preserved.
+        let written = if let Some(secure_sig) = contents.secure_sig.take() {
+            let mut sign_with = |_: &[u8]| Ok(secure_sig.sig.clone());
+            store
+                .write_commit(contents, Some(&mut sign_with))
+                .block_on()?
+        } else {
+            store.write_commit(contents, None).block_on()?
+        };
+        check_id("commit", &expected_id, written.id())?;
+        commit_ids.push(expected_id);
+    }
+    #[cfg(feature = "git")]
+    if let Some(git_backend) = store.backend_impl().downcast_ref::<GitBackend>() {
+        let metadata_stats = git_backend
+            .import_commit_metadata(transferred_commits.iter().map(|(id, commit)| (id, commit)))?;
+        commit_ids.extend(metadata_stats.changed_ids);
+        conflicting_commit_ids.extend(metadata_stats.conflicting_ids);
+    }
+    for view in bundle.views {
+        let contents = simple_op_store::view_from_proto(view.view.ok_or_else(missing_field)?);
+        let id = op_store.write_view(&contents)?;
+        check_id("view", &ViewId::new(view.id), &id)?;
+    }
+    let num_operations = bundle.operations.len();
+    for operation in bundle.operations {
+        let contents =
+            simple_op_store::operation_from_proto(operation.operation.ok_or_else(missing_field)?)
+                .map_err(|err| OpBundleError::Corrupt(err.into()))?;
+        let id = op_store.write_operation(&contents)?;
+        check_id("operation", &OperationId::new(operation.id), &id)?;
+    }
+
+    Ok(OpBundleStats {
+        head_ids: bundle.head_ids.into_iter().map(OperationId::new).collect(),
+        num_operations,
+        commit_ids,
+        conflicting_commit_ids,
+    })
+}
+
+/// Loads commits reachable from the `ids` through parents and predecessors,
+/// sorted so that parents and predecessors come first. The traversal stops at
+/// commits for which `is_known` returns true.
+fn collect_commits(
+    store: &Arc<Store>,
+    ids: impl IntoIterator<Item = CommitId>,
+    is_known: &dyn Fn(&CommitId) -> Result<bool, OpBundleError>,
+) -> Result<Vec<Commit>, OpBundleError> {
+    let root_commit_id = store.root_commit_id();
+    let is_new = |id: &CommitId| Ok::<_, OpBundleError>(id != root_commit_id && !is_known(id)?);
+    let mut start: Vec<Commit> = vec![];
+    for id in ids.into_iter().unique() {
+        if is_new(&id)? {
+            start.push(store.get_commit(&id)?);
+        }
+    }
+    let commits = dag_walk::topo_order_forward_ok(
+        start.into_iter().map(Ok),
+        |commit: &Commit| commit.id().clone(),
+        |commit: &Commit| {
+            let mut neighbors = vec![];
+            for id in commit.parent_ids() {
+                match is_new(id) {
+                    Ok(true) => neighbors.push(store.get_commit(id).map_err(OpBundleError::from)),
+                    Ok(false) => {}
+                    Err(err) => neighbors.push(Err(err)),
+                }
+            }
+            // Predecessors may have been garbage collected.
+            for id in commit.predecessor_ids() {
+                match is_new(id) {
+                    Ok(true) => neighbors.extend(store.get_commit(id).ok().map(Ok)),
+                    Ok(false) => {}
+                    Err(err) => neighbors.push(Err(err)),
+                }
+            }
+            neighbors
+        },
+    )?;
+    Ok(commits)
+}
+
+/// Adds trees and the objects they reference to the bundle, children first.
+struct TreeObjectCollector<'a> {
+    store: &'a Arc<Store>,
+    tree_ids: HashSet<TreeId>,
+    file_ids: HashSet<FileId>,
+    symlink_ids: HashSet<SymlinkId>,
+    conflict_ids: HashSet<ConflictId>,
+}
+
+impl<'a> TreeObjectCollector<'a> {
+    fn new(store: &'a Arc<Store>) -> Self {
+        TreeObjectCollector {
+            store,
+            tree_ids: HashSet::new(),
+            file_ids: HashSet::new(),
+            symlink_ids: HashSet::new(),
+            conflict_ids: HashSet::new(),
+        }
+    }
+
+    fn collect_tree(
+        &mut self,
+        bundle: &mut proto::Bundle,
+        dir: RepoPathBuf,
+        id: &TreeId,
+    ) -> Result<(), OpBundleError> {
+        if self.tree_ids.contains(id) {
+            return Ok(());
+        }
+        let tree = self.store.get_tree(dir, id)?;
+        for entry in tree.data().entries() {
+            let path = tree.dir().join(entry.name());
+            match entry.value() {
+                TreeValue::Tree(id) => self.collect_tree(bundle, path, id)?,
+                TreeValue::Conflict(id) => self.collect_conflict(bundle, &path, id)?,
+                value => self.collect_leaf(bundle, &path, value)?,
+            }
+        }
+        self.tree_ids.insert(id.clone());
+        bundle.trees.push(proto::bundle::Tree {
+            id: id.to_bytes(),
+            tree: Some(simple_backend::tree_to_proto(tree.data())),
+        });
+        Ok(())
+    }
+
+    fn collect_conflict(
+        &mut self,
+        bundle: &mut proto::Bundle,
+        path: &RepoPath,
+        id: &ConflictId,
+    ) -> Result<(), OpBundleError> {
+        if self.conflict_ids.contains(id) {
+            return Ok(());
+        }
+        let contents = self.store.read_conflict(path, id)?;
+        for value in contents.iter().flatten() {
+            self.collect_leaf(bundle, path, value)?;
+        }
+        self.conflict_ids.insert(id.clone());
+        bundle.conflicts.push(proto::bundle::Conflict {
+            id: id.to_bytes(),
+            conflict: Some(simple_backend::conflict_to_proto(
+                &contents.into_backend_conflict(),
+            )),
+        });
+        Ok(())
+    }
+
+    fn collect_leaf(
+        &mut self,
+        bundle: &mut proto::Bundle,
+        path: &RepoPath,
+        value: &TreeValue,
+    ) -> Result<(), OpBundleError> {
+        match value {
+            TreeValue::File { id, .. } => {
+                if self.file_ids.insert(id.clone()) {
+                    let mut content = vec![];
+                    self.store
+                        .read_file(path, id)?
+                        .read_to_end(&mut content)
+                        .map_err(|err| BackendError::ReadFile {
+                            path: path.to_owned(),
+                            id: id.clone(),
+                            source: err.into(),
+                        })?;
+                    bundle.files.push(proto::bundle::File {
+                        id: id.to_bytes(),
+                        content,
+                    });
+                }
+            }
+            TreeValue::Symlink(id) => {
+                if self.symlink_ids.insert(id.clone()) {
+                    let target = self.store.read_symlink(path, id)?;
+                    bundle.symlinks.push(proto::bundle::Symlink {
+                        id: id.to_bytes(),
+                        target,
+                    });
+                }
+            }
+            TreeValue::GitSubmodule(_) => {
+                return Err(OpBundleError::UnsupportedObject {
+                    object_type: "submodule",
+                    path: path.to_owned(),
+                });
+            }
+            TreeValue::Tree(_) | TreeValue::Conflict(_) => {
+                return Err(OpBundleError::UnsupportedObject {
+                    object_type: "nested conflict",
+                    path: path.to_owned(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+fn check_id<T: ObjectId + PartialEq>(
+    object_type: &'static str,
+    expected: &T,
+    actual: &T,
+) -> Result<(), OpBundleError> {
+    if expected == actual {
+        Ok(())
+    } else {
+        Err(OpBundleError::IdMismatch {
+            object_type,
+            expected: expected.hex(),
+            actual: actual.hex(),
+        })
+    }
+}
+
+fn missing_field() -> OpBundleError {
+    OpBundleError::Corrupt("Missing object data".into())
+}
@@ -15,8 +15,10 @@
 //! Utility for operation id resolution and traversal.
 
 use std::cmp::Ordering;
+use std::collections::BinaryHeap;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::iter;
 use std::slice;
 use std::sync::Arc;
 
@@ -262,6 +264,66 @@ pub fn walk_ancestors(
     .map_ok(|OperationByEndTime(op)| op)
 }
 
+/// Walks ancestors of `head_ops` that aren't ancestors of `root_ops` in
+/// reverse topological order.
+///
+/// Unlike filtering the output of [`walk_ancestors()`], the walk stops once
+/// the remaining operations are all ancestors of `root_ops`, so the ancestors
+/// of `root_ops` aren't loaded in full.
+pub fn walk_ancestors_range(
+    head_ops: &[Operation],
+    root_ops: &[Operation],
+) -> impl Iterator<Item = OpStoreResult<Operation>> + use<> {
+    // Entries are (op, is_unwanted). An unwanted entry sorts after the wanted
+    // entry of the same operation, so it's popped first.
+    let mut queue: BinaryHeap<(OperationByEndTime, bool)> = itertools::chain(
+        head_ops
+            .iter()
+            .map(|op| (OperationByEndTime(op.clone()), false)),
+        root_ops
+            .iter()
+            .map(|op| (OperationByEndTime(op.clone()), true)),
+    )
+    .collect();
+    let mut wanted_count = head_ops.len();
+    let mut visited_ids = HashSet::new();
+    iter::from_fn(move || {
+        while wanted_count > 0 {
+            let (OperationByEndTime(op), mut unwanted) = queue.pop().unwrap();
+            if !unwanted {
+                wanted_count -= 1;
+            }
+            while queue
+                .peek()
+                .is_some_and(|(OperationByEndTime(next), _)| *next == op)
+            {
+                let (_, next_unwanted) = queue.pop().unwrap();
+                if !next_unwanted {
+                    wanted_count -= 1;
+                }
+                unwanted |= next_unwanted;
+            }
+            if !visited_ids.insert(op.id().clone()) {
+                continue;
+            }
+            let parents: Vec<_> = match op.parents().try_collect() {
+                Ok(parents) => parents,
+                Err(err) => return Some(Err(err)),
+            };
+            for parent in parents {
+                if !unwanted {
+                    wanted_count += 1;
+                }
+                queue.push((OperationByEndTime(parent), unwanted));
+            }
+            if !unwanted {
+                return Some(Ok(op));
+            }
+        }
+        None
+    })
+}
+
 /// Stats about `reparent_range()`.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct ReparentStats {
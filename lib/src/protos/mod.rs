@@ -2,6 +2,9 @@
 pub mod git_store {
     include!("git_store.rs");
 }
+pub mod op_bundle {
+    include!("op_bundle.rs");
+}
 pub mod op_store {
     include!("op_store.rs");
 }
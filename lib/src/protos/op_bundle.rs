@@ -0,0 +1,86 @@
+// This file is @generated by prost-build.
+/// Self-contained set of operations and the objects they refer to. Objects are
+/// listed in dependency order, so they can be written one by one.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Bundle {
+    /// Root commit id of the source backend. Commit ids are only meaningful if
+    /// the destination backend has the same root commit id.
+    #[prost(bytes = "vec", tag = "1")]
+    pub root_commit_id: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", repeated, tag = "2")]
+    pub head_ids: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+    #[prost(message, repeated, tag = "3")]
+    pub files: ::prost::alloc::vec::Vec<bundle::File>,
+    #[prost(message, repeated, tag = "4")]
+    pub symlinks: ::prost::alloc::vec::Vec<bundle::Symlink>,
+    #[prost(message, repeated, tag = "5")]
+    pub conflicts: ::prost::alloc::vec::Vec<bundle::Conflict>,
+    #[prost(message, repeated, tag = "6")]
+    pub trees: ::prost::alloc::vec::Vec<bundle::Tree>,
+    #[prost(message, repeated, tag = "7")]
+    pub commits: ::prost::alloc::vec::Vec<bundle::Commit>,
+    #[prost(message, repeated, tag = "8")]
+    pub views: ::prost::alloc::vec::Vec<bundle::View>,
+    #[prost(message, repeated, tag = "9")]
+    pub operations: ::prost::alloc::vec::Vec<bundle::Operation>,
+}
+/// Nested message and enum types in `Bundle`.
+pub mod bundle {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct File {
+        #[prost(bytes = "vec", tag = "1")]
+        pub id: ::prost::alloc::vec::Vec<u8>,
+        #[prost(bytes = "vec", tag = "2")]
+        pub content: ::prost::alloc::vec::Vec<u8>,
+    }
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct Symlink {
+        #[prost(bytes = "vec", tag = "1")]
+        pub id: ::prost::alloc::vec::Vec<u8>,
+        #[prost(string, tag = "2")]
+        pub target: ::prost::alloc::string::String,
+    }
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct Conflict {
+        #[prost(bytes = "vec", tag = "1")]
+        pub id: ::prost::alloc::vec::Vec<u8>,
+        #[prost(message, optional, tag = "2")]
+        pub conflict: ::core::option::Option<super::super::simple_store::Conflict>,
+    }
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct Tree {
+        #[prost(bytes = "vec", tag = "1")]
+        pub id: ::prost::alloc::vec::Vec<u8>,
+        #[prost(message, optional, tag = "2")]
+        pub tree: ::core::option::Option<super::super::simple_store::Tree>,
+    }
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct Commit {
+        #[prost(bytes = "vec", tag = "1")]
+        pub id: ::prost::alloc::vec::Vec<u8>,
+        #[prost(message, optional, tag = "2")]
+        pub commit: ::core::option::Option<super::super::simple_store::Commit>,
+    }
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct View {
+        #[prost(bytes = "vec", tag = "1")]
+        pub id: ::prost::alloc::vec::Vec<u8>,
+        #[prost(message, optional, tag = "2")]
+        pub view: ::core::option::Option<super::super::op_store::View>,
+    }
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct Operation {
+        #[prost(bytes = "vec", tag = "1")]
+        pub id: ::prost::alloc::vec::Vec<u8>,
+        #[prost(message, optional, tag = "2")]
+        pub operation: ::core::option::Option<super::super::op_store::Operation>,
+    }
+}
@@ -42,6 +42,7 @@ use crate::commit::Commit;
 use crate::commit::CommitByCommitterTimestamp;
 use crate::commit_builder::CommitBuilder;
 use crate::commit_builder::DetachedCommitBuilder;
+use crate::copies::CopyDetectionOptions;
 use crate::dag_walk;
 use crate::default_index::DefaultIndexStore;
 use crate::default_index::DefaultMutableIndex;
@@ -55,6 +56,7 @@ use crate::index::IndexStore;
 use crate::index::MutableIndex;
 use crate::index::ReadonlyIndex;
 use crate::merge::MergeBuilder;
+use crate::merge_driver::MergeDrivers;
 use crate::object_id::HexPrefix;
 use crate::object_id::ObjectId as _;
 use crate::object_id::PrefixResolution;
@@ -80,6 +82,7 @@ use crate::refs::diff_named_ref_targets;
 use crate::refs::diff_named_remote_refs;
 use crate::refs::merge_ref_targets;
 use crate::refs::merge_remote_refs;
+use crate::rerere::ResolutionStore;
 use crate::revset;
 use crate::revset::RevsetExpression;
 use crate::revset::RevsetIteratorExt as _;
@@ -128,6 +131,9 @@ pub trait Repo {
 
     fn resolve_change_id_prefix(&self, prefix: &HexPrefix) -> PrefixResolution<Vec<CommitId>>;
 
+    /// Returns true if more than one visible commit has the `change_id`.
+    fn is_divergent_change(&self, change_id: &ChangeId) -> bool;
+
     fn shortest_unique_change_id_prefix_len(&self, target_id_bytes: &ChangeId) -> usize;
 }
 
@@ -170,7 +176,13 @@ impl ReadonlyRepo {
     }
 
     pub fn default_index_store_initializer() -> &'static IndexStoreInitializer<'static> {
-        &|_settings, store_path| Ok(Box::new(DefaultIndexStore::init(store_path)?))
+        &|settings, store_path| {
+            let changed_paths = settings
+                .get_bool("index.changed-paths")
+                .map_err(|err| BackendInitError(err.into()))?;
+            let store = DefaultIndexStore::init(store_path)?.with_changed_paths(changed_paths);
+            Ok(Box::new(store))
+        }
     }
 
     pub fn default_submodule_store_initializer() -> &'static SubmoduleStoreInitializer<'static> {
@@ -195,7 +207,18 @@ impl ReadonlyRepo {
         let backend = backend_initializer(settings, &store_path)?;
         let backend_path = store_path.join("type");
         fs::write(&backend_path, backend.name()).context(&backend_path)?;
-        let store = Store::new(backend, signer);
+        let merge_drivers =
+            MergeDrivers::from_settings(settings).map_err(|err| BackendInitError(err.into()))?;
+        let copy_detection = CopyDetectionOptions::from_settings(settings)
+            .map_err(|err| BackendInitError(err.into()))?;
+        let diff_algorithm = settings
+            .get("diff.algorithm")
+            .map_err(|err| BackendInitError(err.into()))?;
+        let store = Store::builder(backend, signer)
+            .merge_drivers(merge_drivers)
+            .copy_detection(copy_detection)
+            .diff_algorithm(diff_algorithm)
+            .build();
 
         let op_store_path = repo_path.join("op_store");
         fs::create_dir(&op_store_path).context(&op_store_path)?;
@@ -230,6 +253,9 @@ impl ReadonlyRepo {
             .context(&submodule_store_type_path)?;
         let submodule_store = Arc::from(submodule_store);
 
+        let resolution_store = ResolutionStore::load(settings, &repo_path.join("rerere"))
+            .map_err(|err| BackendInitError(err.into()))?;
+
         let loader = RepoLoader {
             settings: settings.clone(),
             store,
@@ -237,6 +263,7 @@ impl ReadonlyRepo {
             op_heads_store,
             index_store,
             submodule_store,
+            resolution_store: Some(Arc::new(resolution_store)),
         };
 
         let root_operation = loader.root_operation();
@@ -342,6 +369,10 @@ impl Repo for ReadonlyRepo {
         self.change_id_index().resolve_prefix(prefix)
     }
 
+    fn is_divergent_change(&self, change_id: &ChangeId) -> bool {
+        self.change_id_index().is_divergent(change_id)
+    }
+
     fn shortest_unique_change_id_prefix_len(&self, target_id: &ChangeId) -> usize {
         self.change_id_index().shortest_unique_prefix_len(target_id)
     }
@@ -438,7 +469,13 @@ impl Default for StoreFactories {
         // Index
         factories.add_index_store(
             DefaultIndexStore::name(),
-            Box::new(|_settings, store_path| Ok(Box::new(DefaultIndexStore::load(store_path)))),
+            Box::new(|settings, store_path| {
+                let changed_paths = settings
+                    .get_bool("index.changed-paths")
+                    .map_err(|err| BackendLoadError(err.into()))?;
+                let store = DefaultIndexStore::load(store_path).with_changed_paths(changed_paths);
+                Ok(Box::new(store))
+            }),
         );
 
         // SubmoduleStores
@@ -636,6 +673,7 @@ pub struct RepoLoader {
     op_heads_store: Arc<dyn OpHeadsStore>,
     index_store: Arc<dyn IndexStore>,
     submodule_store: Arc<dyn SubmoduleStore>,
+    resolution_store: Option<Arc<ResolutionStore>>,
 }
 
 impl RepoLoader {
@@ -654,9 +692,16 @@ impl RepoLoader {
             op_heads_store,
             index_store,
             submodule_store,
+            resolution_store: None,
         }
     }
 
+    /// Sets the store of recorded conflict resolutions.
+    pub fn with_resolution_store(mut self, resolution_store: Arc<ResolutionStore>) -> Self {
+        self.resolution_store = Some(resolution_store);
+        self
+    }
+
     /// Creates a `RepoLoader` for the repo at `repo_path` by reading the
     /// various `.jj/repo/<backend>/type` files and loading the right
     /// backends from `store_factories`.
@@ -665,10 +710,23 @@ impl RepoLoader {
         repo_path: &Path,
         store_factories: &StoreFactories,
     ) -> Result<Self, StoreLoadError> {
-        let store = Store::new(
+        let store = Store::builder(
             store_factories.load_backend(settings, &repo_path.join("store"))?,
             Signer::from_settings(settings)?,
-        );
+        )
+        .merge_drivers(
+            MergeDrivers::from_settings(settings).map_err(|err| BackendLoadError(err.into()))?,
+        )
+        .copy_detection(
+            CopyDetectionOptions::from_settings(settings)
+                .map_err(|err| BackendLoadError(err.into()))?,
+        )
+        .diff_algorithm(
+            settings
+                .get("diff.algorithm")
+                .map_err(|err| BackendLoadError(err.into()))?,
+        )
+        .build();
         let root_op_data = RootOperationData {
             root_commit_id: store.root_commit_id().clone(),
         };
@@ -684,6 +742,8 @@ impl RepoLoader {
         let submodule_store = Arc::from(
             store_factories.load_submodule_store(settings, &repo_path.join("submodule_store"))?,
         );
+        let resolution_store = ResolutionStore::load(settings, &repo_path.join("rerere"))
+            .map_err(|err| BackendLoadError(err.into()))?;
         Ok(Self {
             settings: settings.clone(),
             store,
@@ -691,6 +751,7 @@ impl RepoLoader {
             op_heads_store,
             index_store,
             submodule_store,
+            resolution_store: Some(Arc::new(resolution_store)),
         })
     }
 
@@ -718,6 +779,11 @@ impl RepoLoader {
         &self.submodule_store
     }
 
+    /// Returns the store of recorded conflict resolutions, if any.
+    pub fn resolution_store(&self) -> Option<&Arc<ResolutionStore>> {
+        self.resolution_store.as_ref()
+    }
+
     pub fn load_at_head(&self) -> Result<Arc<ReadonlyRepo>, RepoLoaderError> {
         let op = op_heads_store::resolve_op_heads(
             self.op_heads_store.as_ref(),
@@ -1845,6 +1911,11 @@ impl Repo for MutableRepo {
         change_id_index.resolve_prefix(prefix)
     }
 
+    fn is_divergent_change(&self, change_id: &ChangeId) -> bool {
+        let change_id_index = self.index.change_id_index(&mut self.view().heads().iter());
+        change_id_index.is_divergent(change_id)
+    }
+
     fn shortest_unique_change_id_prefix_len(&self, target_id: &ChangeId) -> usize {
         let change_id_index = self.index.change_id_index(&mut self.view().heads().iter());
         change_id_index.shortest_unique_prefix_len(target_id)
@@ -0,0 +1,331 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Recorded resolutions of conflicts ("rerere", reuse recorded resolution).
+//!
+//! When a conflicted commit is rewritten into a commit in which a conflicted
+//! file is resolved, the resolution of each conflicting hunk is recorded. The
+//! resolutions are keyed by the contents of the bases and sides of the hunk,
+//! ignoring their order. When a rebase produces the same conflict again, the
+//! recorded resolutions are applied.
+
+#![allow(missing_docs)]
+
+use std::fs;
+use std::io;
+use std::io::Write as _;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use blake2::Blake2b512;
+use blake2::Digest as _;
+use bstr::BString;
+use itertools::Itertools as _;
+use pollster::FutureExt as _;
+use tempfile::NamedTempFile;
+
+use crate::backend::BackendError;
+use crate::backend::BackendResult;
+use crate::backend::TreeValue;
+use crate::commit::Commit;
+use crate::config::ConfigGetError;
+use crate::conflicts::extract_as_single_hunk;
+use crate::diff::DiffAlgorithm;
+use crate::file_util::IoResultExt as _;
+use crate::file_util::PathError;
+use crate::files;
+use crate::files::MergeResult;
+use crate::matchers::Matcher;
+use crate::merge::Merge;
+use crate::merge::MergedTreeValue;
+use crate::merged_tree::MergedTree;
+use crate::merged_tree::MergedTreeBuilder;
+use crate::repo_path::RepoPath;
+use crate::settings::UserSettings;
+use crate::store::Store;
+
+/// Stores recorded conflict resolutions in a directory, one file per
+/// conflicting hunk.
+#[derive(Debug)]
+pub struct ResolutionStore {
+    dir: PathBuf,
+    enabled: bool,
+}
+
+impl ResolutionStore {
+    /// Creates a store at `dir`, which will be created when the first
+    /// resolution is recorded. Resolutions are only recorded and applied if
+    /// `rerere.enabled` is set.
+    pub fn load(settings: &UserSettings, dir: &Path) -> Result<Self, ConfigGetError> {
+        Ok(ResolutionStore {
+            dir: dir.to_owned(),
+            enabled: settings.get_bool("rerere.enabled")?,
+        })
+    }
+
+    /// Whether resolutions should be recorded and applied.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Looks up the resolution of a conflicting hunk.
+    pub fn get(&self, hunk: &Merge<BString>) -> Result<Option<BString>, PathError> {
+        let path = self.entry_path(hunk);
+        match fs::read(&path) {
+            Ok(content) => Ok(Some(content.into())),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err).context(path),
+        }
+    }
+
+    /// Records the resolution of a conflicting hunk, replacing the existing
+    /// one if any.
+    pub fn set(&self, hunk: &Merge<BString>, resolution: &[u8]) -> Result<(), PathError> {
+        fs::create_dir_all(&self.dir).context(&self.dir)?;
+        let mut temp_file = NamedTempFile::new_in(&self.dir).context(&self.dir)?;
+        temp_file.write_all(resolution).context(temp_file.path())?;
+        let path = self.entry_path(hunk);
+        temp_file
+            .persist(&path)
+            .map_err(|err| err.error)
+            .context(path)?;
+        Ok(())
+    }
+
+    /// Removes the resolution of a conflicting hunk. Returns true if there was
+    /// a recorded resolution.
+    pub fn remove(&self, hunk: &Merge<BString>) -> Result<bool, PathError> {
+        let path = self.entry_path(hunk);
+        match fs::remove_file(&path) {
+            Ok(()) => Ok(true),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(err) => Err(err).context(path),
+        }
+    }
+
+    fn entry_path(&self, hunk: &Merge<BString>) -> PathBuf {
+        // The order of the sides (and bases) doesn't affect the resolution.
+        let removes = hunk.removes().sorted().collect_vec();
+        let adds = hunk.adds().sorted().collect_vec();
+        let mut hasher = Blake2b512::new();
+        for terms in [removes, adds] {
+            hasher.update((terms.len() as u64).to_le_bytes());
+            for term in terms {
+                hasher.update((term.len() as u64).to_le_bytes());
+                hasher.update(term);
+            }
+        }
+        self.dir.join(hex::encode(&hasher.finalize()[..32]))
+    }
+}
+
+/// Records resolutions of the conflicts in `old_commit` which have been
+/// resolved in `new_commit`.
+///
+/// Nothing is recorded if the parents differ, since the conflicts might have
+/// been resolved by rebasing rather than by the user. Returns the number of
+/// recorded hunks.
+pub fn record_rewrite_resolutions(
+    resolution_store: &ResolutionStore,
+    old_commit: &Commit,
+    new_commit: &Commit,
+) -> BackendResult<usize> {
+    if old_commit.parent_ids() != new_commit.parent_ids() || !old_commit.has_conflict()? {
+        return Ok(0);
+    }
+    let store = new_commit.store();
+    let new_tree = new_commit.tree()?;
+    let mut num_recorded = 0;
+    for (path, value) in old_commit.tree()?.conflicts() {
+        let Some(file_merge) = value?.to_file_merge() else {
+            continue;
+        };
+        let Some(TreeValue::File { id, .. }) =
+            new_tree.path_value(&path)?.into_resolved().ok().flatten()
+        else {
+            continue;
+        };
+        let mut resolved = vec![];
+        store
+            .read_file(&path, &id)?
+            .read_to_end(&mut resolved)
+            .map_err(|err| BackendError::ReadFile {
+                path: path.clone(),
+                id: id.clone(),
+                source: err.into(),
+            })?;
+        let content = extract_as_single_hunk(&file_merge, store, &path).block_on()?;
+        let MergeResult::Conflict(hunks) = files::merge(&content, DiffAlgorithm::Default) else {
+            continue;
+        };
+        let Some(resolutions) = split_resolutions(&hunks, &resolved) else {
+            continue;
+        };
+        for (hunk, resolution) in hunks
+            .iter()
+            .filter(|hunk| !hunk.is_resolved())
+            .zip(resolutions)
+        {
+            resolution_store
+                .set(hunk, resolution)
+                .map_err(|err| BackendError::Other(err.into()))?;
+            num_recorded += 1;
+        }
+    }
+    Ok(num_recorded)
+}
+
+/// Resolves conflicted files in the `tree` by using the recorded resolutions.
+///
+/// A file is only resolved if resolutions have been recorded for all its
+/// conflicting hunks.
+pub fn apply_resolutions(
+    resolution_store: &ResolutionStore,
+    tree: MergedTree,
+) -> BackendResult<MergedTree> {
+    if !tree.has_conflict() {
+        return Ok(tree);
+    }
+    let store = tree.store().clone();
+    let mut tree_builder = MergedTreeBuilder::new(tree.id());
+    let mut num_resolved = 0;
+    for (path, value) in tree.conflicts() {
+        if let Some(value) = resolve_file(resolution_store, &store, &path, &value?)? {
+            tree_builder.set_or_remove(path, Merge::normal(value));
+            num_resolved += 1;
+        }
+    }
+    if num_resolved == 0 {
+        return Ok(tree);
+    }
+    let tree_id = tree_builder.write_tree(&store)?;
+    store.get_root_tree(&tree_id)
+}
+
+/// Forgets the recorded resolutions of the conflicts in the `tree` at the
+/// paths matching the `matcher`. Returns the number of forgotten hunks.
+pub fn forget_resolutions(
+    resolution_store: &ResolutionStore,
+    tree: &MergedTree,
+    matcher: &dyn Matcher,
+) -> BackendResult<usize> {
+    let mut num_forgotten = 0;
+    for (path, value) in tree.conflicts() {
+        if !matcher.matches(&path) {
+            continue;
+        }
+        let Some(file_merge) = value?.to_file_merge() else {
+            continue;
+        };
+        let content = extract_as_single_hunk(&file_merge, tree.store(), &path).block_on()?;
+        let MergeResult::Conflict(hunks) = files::merge(&content, DiffAlgorithm::Default) else {
+            continue;
+        };
+        for hunk in hunks.iter().filter(|hunk| !hunk.is_resolved()) {
+            if resolution_store
+                .remove(hunk)
+                .map_err(|err| BackendError::Other(err.into()))?
+            {
+                num_forgotten += 1;
+            }
+        }
+    }
+    Ok(num_forgotten)
+}
+
+fn resolve_file(
+    resolution_store: &ResolutionStore,
+    store: &Arc<Store>,
+    path: &RepoPath,
+    value: &MergedTreeValue,
+) -> BackendResult<Option<TreeValue>> {
+    let (Some(file_merge), Some(executable_merge)) =
+        (value.to_file_merge(), value.to_executable_merge())
+    else {
+        return Ok(None);
+    };
+    let Some(&executable) = executable_merge.resolve_trivial() else {
+        return Ok(None);
+    };
+    let content = extract_as_single_hunk(&file_merge, store, path).block_on()?;
+    let MergeResult::Conflict(hunks) = files::merge(&content, DiffAlgorithm::Default) else {
+        return Ok(None);
+    };
+    let mut resolved = BString::default();
+    for hunk in &hunks {
+        if let Some(context) = hunk.as_resolved() {
+            resolved.extend_from_slice(context);
+        } else if let Some(resolution) = resolution_store
+            .get(hunk)
+            .map_err(|err| BackendError::Other(err.into()))?
+        {
+            resolved.extend_from_slice(&resolution);
+        } else {
+            return Ok(None);
+        }
+    }
+    let id = store
+        .write_file(path, &mut resolved.as_slice())
+        .block_on()?;
+    Ok(Some(TreeValue::File { id, executable }))
+}
+
+/// Splits the `resolved` content into the resolutions of the conflicting
+/// `hunks` by locating the resolved hunks around them.
+///
+/// Returns `None` if the resolved hunks can't be found in order.
+fn split_resolutions<'a>(hunks: &[Merge<BString>], resolved: &'a [u8]) -> Option<Vec<&'a [u8]>> {
+    let mut resolutions = vec![];
+    let mut pos = 0;
+    let mut conflict_start = None;
+    for (i, hunk) in hunks.iter().enumerate() {
+        let Some(context) = hunk.as_resolved() else {
+            if conflict_start.is_some() {
+                return None;
+            }
+            conflict_start = Some(pos);
+            continue;
+        };
+        let remainder = &resolved[pos..];
+        let context_start = if conflict_start.is_none() {
+            remainder.starts_with(context).then_some(pos)?
+        } else if i == hunks.len() - 1 {
+            remainder
+                .ends_with(context)
+                .then(|| resolved.len() - context.len())?
+        } else {
+            pos + find_subslice(remainder, context)?
+        };
+        if let Some(start) = conflict_start.take() {
+            resolutions.push(&resolved[start..context_start]);
+        }
+        pos = context_start + context.len();
+    }
+    if let Some(start) = conflict_start {
+        resolutions.push(&resolved[start..]);
+    } else if pos != resolved.len() {
+        return None;
+    }
+    Some(resolutions)
+}
+
+fn find_subslice(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    if needle.is_empty() {
+        return Some(0);
+    }
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
@@ -37,12 +37,14 @@ use crate::index::Index;
 use crate::index::IndexError;
 use crate::matchers::Matcher;
 use crate::matchers::Visit;
+use crate::merge_driver::MergeDriverResolver;
 use crate::merged_tree::MergedTree;
 use crate::merged_tree::MergedTreeBuilder;
 use crate::merged_tree::TreeDiffEntry;
 use crate::repo::MutableRepo;
 use crate::repo::Repo;
 use crate::repo_path::RepoPath;
+use crate::rerere;
 use crate::revset::RevsetExpression;
 use crate::revset::RevsetIteratorExt as _;
 use crate::store::Store;
@@ -57,6 +59,28 @@ pub fn merge_commit_trees(repo: &dyn Repo, commits: &[Commit]) -> BackendResult<
     }
 }
 
+/// Merges `commits` like `merge_commit_trees()`, but file conflicts are
+/// resolved by the configured merge drivers.
+pub fn merge_commit_trees_with_drivers(
+    repo: &dyn Repo,
+    commits: &[Commit],
+) -> BackendResult<MergedTree> {
+    if let [commit] = commits {
+        commit.tree()
+    } else {
+        let tree = merge_commit_trees_no_resolve_without_repo(repo.store(), repo.index(), commits)?;
+        resolve_with_merge_drivers(&tree)
+    }
+}
+
+/// Resolves conflicts in the `tree` by the merge drivers configured for the
+/// store. The drivers are selected by the `.gitattributes` of the first side.
+pub fn resolve_with_merge_drivers(tree: &MergedTree) -> BackendResult<MergedTree> {
+    let first_tree = tree.as_merge().first();
+    let resolver = MergeDriverResolver::new(tree.store().merge_drivers(), Some(first_tree.clone()));
+    tree.resolve_with(&resolver)
+}
+
 /// Merges `commits` without attempting to resolve file conflicts.
 #[instrument(skip(index))]
 pub fn merge_commit_trees_no_resolve_without_repo(
@@ -249,12 +273,20 @@ impl<'repo> CommitRewriter<'repo> {
                 self.old_commit.tree_id().clone(),
             )
         } else {
-            let old_base_tree = merge_commit_trees(self.mut_repo, &old_parents)?;
-            let new_base_tree = merge_commit_trees(self.mut_repo, &new_parents)?;
+            let old_base_tree = merge_commit_trees_with_drivers(self.mut_repo, &old_parents)?;
+            let new_base_tree = merge_commit_trees_with_drivers(self.mut_repo, &new_parents)?;
             let old_tree = self.old_commit.tree()?;
+            let mut new_tree = resolve_with_merge_drivers(
+                &new_base_tree.merge_no_resolve(&old_base_tree, &old_tree),
+            )?;
+            if let Some(resolution_store) = self.mut_repo.base_repo().loader().resolution_store() {
+                if resolution_store.is_enabled() {
+                    new_tree = rerere::apply_resolutions(resolution_store, new_tree)?;
+                }
+            }
             (
                 old_base_tree.id() == *self.old_commit.tree_id(),
-                new_base_tree.merge(&old_base_tree, &old_tree)?.id(),
+                new_tree.id(),
             )
         };
         // Ensure we don't abandon commits with multiple parents (merge commits), even
@@ -0,0 +1,538 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Pack files of the simple operation store.
+//!
+//! A pack file consolidates many operation and view objects into a single
+//! file. The file starts with a header, followed by two sorted index tables
+//! (one for operations, one for views), followed by the zlib-compressed
+//! object data. The index tables are kept in memory, and the object data is
+//! read on demand.
+//!
+//! ```text
+//! magic: b"jjoppack"
+//! version: u32
+//! num_operations: u32
+//! num_views: u32
+//! operation entries: [entry; num_operations]
+//! view entries: [entry; num_views]
+//! data: [u8]
+//!
+//! entry:
+//!   id: [u8; ID_LENGTH]
+//!   offset: u64 (relative to the start of the data)
+//!   size: u32
+//!   mtime: i64 (milliseconds since the Unix epoch)
+//! ```
+//!
+//! All integers are little-endian.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::Read as _;
+use std::io::Seek as _;
+use std::io::SeekFrom;
+use std::io::Write as _;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use blake2::Blake2b512;
+use blake2::Digest as _;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use tempfile::NamedTempFile;
+
+use crate::file_util::persist_content_addressed_temp_file;
+use crate::object_id::HexPrefix;
+use crate::object_id::PrefixResolution;
+use crate::op_store::OperationId;
+
+const PACK_MAGIC: &[u8; 8] = b"jjoppack";
+const PACK_FORMAT_VERSION: u32 = 1;
+const PACK_FILE_EXTENSION: &str = "pack";
+
+// Both operation ids and view ids are BLAKE2b-512 hashes.
+const ID_LENGTH: usize = 64;
+const HEADER_SIZE: usize = 8 + 4 + 4 + 4;
+const ENTRY_SIZE: usize = ID_LENGTH + 8 + 4 + 8;
+
+/// Kind of objects stored in a pack.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum PackedObjectKind {
+    Operation,
+    View,
+}
+
+/// Loaded pack file. Only the index part is kept in memory.
+#[derive(Debug)]
+pub(crate) struct OpPack {
+    path: PathBuf,
+    num_operations: usize,
+    num_views: usize,
+    index: Vec<u8>,
+    data_offset: u64,
+}
+
+impl OpPack {
+    /// Loads the index of the pack file at `path`.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let mut header = [0; HEADER_SIZE];
+        file.read_exact(&mut header)?;
+        if &header[0..8] != PACK_MAGIC {
+            return Err(invalid_data("bad pack file magic"));
+        }
+        let version = u32::from_le_bytes(header[8..12].try_into().unwrap());
+        if version != PACK_FORMAT_VERSION {
+            return Err(invalid_data(format!(
+                "unsupported pack file version {version}"
+            )));
+        }
+        let num_operations = u32::from_le_bytes(header[12..16].try_into().unwrap()) as usize;
+        let num_views = u32::from_le_bytes(header[16..20].try_into().unwrap()) as usize;
+        // Don't trust the counts until they're checked against the file size.
+        // A corrupt header could otherwise make us allocate a huge buffer.
+        let index_size = (num_operations as u64 + num_views as u64) * ENTRY_SIZE as u64;
+        if HEADER_SIZE as u64 + index_size > file.metadata()?.len() {
+            return Err(invalid_data("pack index exceeds file size"));
+        }
+        let mut index = vec![0; index_size as usize];
+        file.read_exact(&mut index)?;
+        Ok(OpPack {
+            path: path.to_owned(),
+            num_operations,
+            num_views,
+            data_offset: (HEADER_SIZE + index.len()) as u64,
+            index,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn num_entries(&self, kind: PackedObjectKind) -> usize {
+        match kind {
+            PackedObjectKind::Operation => self.num_operations,
+            PackedObjectKind::View => self.num_views,
+        }
+    }
+
+    fn entries(&self, kind: PackedObjectKind) -> &[u8] {
+        let (ops, views) = self.index.split_at(self.num_operations * ENTRY_SIZE);
+        match kind {
+            PackedObjectKind::Operation => ops,
+            PackedObjectKind::View => views,
+        }
+    }
+
+    fn entry(&self, kind: PackedObjectKind, pos: usize) -> PackEntry<'_> {
+        let entries = self.entries(kind);
+        PackEntry::parse(&entries[pos * ENTRY_SIZE..][..ENTRY_SIZE])
+    }
+
+    /// Returns the position of the first entry whose id is not less than
+    /// `key`.
+    fn lower_bound(&self, kind: PackedObjectKind, key: &[u8]) -> usize {
+        let num_entries = self.num_entries(kind);
+        let (mut low, mut high) = (0, num_entries);
+        while low < high {
+            let mid = (low + high) / 2;
+            if self.entry(kind, mid).id < key {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        low
+    }
+
+    fn find_entry(&self, kind: PackedObjectKind, id: &[u8]) -> Option<PackEntry<'_>> {
+        let pos = self.lower_bound(kind, id);
+        (pos < self.num_entries(kind))
+            .then(|| self.entry(kind, pos))
+            .filter(|entry| entry.id == id)
+    }
+
+    /// Iterates over the entries of the given `kind` in id order.
+    pub fn iter_entries(&self, kind: PackedObjectKind) -> impl Iterator<Item = PackEntry<'_>> {
+        self.entries(kind)
+            .chunks_exact(ENTRY_SIZE)
+            .map(PackEntry::parse)
+    }
+
+    pub fn contains(&self, kind: PackedObjectKind, id: &[u8]) -> bool {
+        self.find_entry(kind, id).is_some()
+    }
+
+    /// Reads and decompresses the object content.
+    pub fn read_object(&self, kind: PackedObjectKind, id: &[u8]) -> io::Result<Option<Vec<u8>>> {
+        let Some(entry) = self.find_entry(kind, id) else {
+            return Ok(None);
+        };
+        let compressed = self.read_compressed(&entry)?;
+        let mut buf = Vec::new();
+        ZlibDecoder::new(&*compressed).read_to_end(&mut buf)?;
+        Ok(Some(buf))
+    }
+
+    /// Reads the compressed object content as it is stored in the pack.
+    fn read_compressed(&self, entry: &PackEntry) -> io::Result<Vec<u8>> {
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(self.data_offset + entry.offset))?;
+        let mut buf = vec![0; entry.size as usize];
+        file.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// Reads the whole data part of the pack. Use this to copy many entries
+    /// without opening the file for each entry.
+    pub fn read_data(&self) -> io::Result<Vec<u8>> {
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(self.data_offset))?;
+        let mut buf = Vec::new();
+        file.read_to_end(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// Looks up operation ids matching the given `prefix` by binary search.
+    pub fn resolve_operation_id_prefix(&self, prefix: &HexPrefix) -> PrefixResolution<OperationId> {
+        let kind = PackedObjectKind::Operation;
+        let pos = self.lower_bound(kind, prefix.min_prefix_bytes());
+        let mut matches = (pos..self.num_entries(kind))
+            .map(|pos| OperationId::from_bytes(self.entry(kind, pos).id))
+            .take_while(|id| prefix.matches(id));
+        match (matches.next(), matches.next()) {
+            (None, _) => PrefixResolution::NoMatch,
+            (Some(id), None) => PrefixResolution::SingleMatch(id),
+            (Some(_), Some(_)) => PrefixResolution::AmbiguousMatch,
+        }
+    }
+}
+
+/// Index entry of a pack.
+#[derive(Clone, Debug)]
+pub(crate) struct PackEntry<'a> {
+    pub id: &'a [u8],
+    offset: u64,
+    size: u32,
+    mtime_millis: i64,
+}
+
+impl<'a> PackEntry<'a> {
+    fn parse(data: &'a [u8]) -> Self {
+        let (id, rest) = data.split_at(ID_LENGTH);
+        let (offset, rest) = rest.split_at(8);
+        let (size, mtime) = rest.split_at(4);
+        PackEntry {
+            id,
+            offset: u64::from_le_bytes(offset.try_into().unwrap()),
+            size: u32::from_le_bytes(size.try_into().unwrap()),
+            mtime_millis: i64::from_le_bytes(mtime.try_into().unwrap()),
+        }
+    }
+
+    /// Returns the compressed content of this entry in the pack `data`.
+    pub fn compressed_in<'b>(&self, data: &'b [u8]) -> io::Result<&'b [u8]> {
+        usize::try_from(self.offset)
+            .ok()
+            .and_then(|start| data.get(start..)?.get(..self.size as usize))
+            .ok_or_else(|| invalid_data("pack entry out of range"))
+    }
+
+    /// Modification time of the loose object file this entry was packed from.
+    pub fn mtime(&self) -> SystemTime {
+        millis_to_system_time(self.mtime_millis)
+    }
+}
+
+/// Collects objects to be written to a new pack file.
+#[derive(Debug, Default)]
+pub(crate) struct OpPackBuilder {
+    operations: BTreeMap<Vec<u8>, (Vec<u8>, i64)>,
+    views: BTreeMap<Vec<u8>, (Vec<u8>, i64)>,
+}
+
+impl OpPackBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty() && self.views.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.operations.len() + self.views.len()
+    }
+
+    fn objects_mut(&mut self, kind: PackedObjectKind) -> &mut BTreeMap<Vec<u8>, (Vec<u8>, i64)> {
+        match kind {
+            PackedObjectKind::Operation => &mut self.operations,
+            PackedObjectKind::View => &mut self.views,
+        }
+    }
+
+    /// Compresses and adds the object `content`.
+    pub fn add_object(
+        &mut self,
+        kind: PackedObjectKind,
+        id: &[u8],
+        content: &[u8],
+        mtime: SystemTime,
+    ) -> io::Result<()> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(content)?;
+        let compressed = encoder.finish()?;
+        self.add_compressed(kind, id, compressed, mtime);
+        Ok(())
+    }
+
+    /// Adds the object data which has already been compressed.
+    pub fn add_compressed(
+        &mut self,
+        kind: PackedObjectKind,
+        id: &[u8],
+        compressed: Vec<u8>,
+        mtime: SystemTime,
+    ) {
+        assert_eq!(id.len(), ID_LENGTH);
+        let mtime_millis = system_time_to_millis(mtime);
+        self.objects_mut(kind)
+            .insert(id.to_vec(), (compressed, mtime_millis));
+    }
+
+    /// Writes a new pack file to the `dir`, and returns its path.
+    pub fn write(self, dir: &Path) -> io::Result<PathBuf> {
+        let num_operations = u32::try_from(self.operations.len())
+            .map_err(|_| invalid_data("too many operations to pack"))?;
+        let num_views =
+            u32::try_from(self.views.len()).map_err(|_| invalid_data("too many views to pack"))?;
+        let mut buf = Vec::new();
+        buf.extend_from_slice(PACK_MAGIC);
+        buf.extend_from_slice(&PACK_FORMAT_VERSION.to_le_bytes());
+        buf.extend_from_slice(&num_operations.to_le_bytes());
+        buf.extend_from_slice(&num_views.to_le_bytes());
+        let mut offset: u64 = 0;
+        for (id, (compressed, mtime_millis)) in self.operations.iter().chain(&self.views) {
+            let size = u32::try_from(compressed.len())
+                .map_err(|_| invalid_data("object too large to pack"))?;
+            buf.extend_from_slice(id);
+            buf.extend_from_slice(&offset.to_le_bytes());
+            buf.extend_from_slice(&size.to_le_bytes());
+            buf.extend_from_slice(&mtime_millis.to_le_bytes());
+            offset += u64::from(size);
+        }
+        for (compressed, _) in self.operations.values().chain(self.views.values()) {
+            buf.extend_from_slice(compressed);
+        }
+
+        let name = format!(
+            "{}.{PACK_FILE_EXTENSION}",
+            hex::encode(Blake2b512::digest(&buf))
+        );
+        let path = dir.join(name);
+        let mut temp_file = NamedTempFile::new_in(dir)?;
+        temp_file.write_all(&buf)?;
+        persist_content_addressed_temp_file(temp_file, &path)?;
+        Ok(path)
+    }
+}
+
+/// Lists pack files in the `dir`. Returns an empty list if the directory
+/// doesn't exist.
+pub(crate) fn list_pack_paths(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let entries = match dir.read_dir() {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(err),
+    };
+    let mut paths = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path
+            .extension()
+            .is_some_and(|ext| ext == PACK_FILE_EXTENSION)
+        {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Removes the pack file, ignoring error if it has already been removed.
+pub(crate) fn remove_pack(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err),
+    }
+}
+
+fn system_time_to_millis(time: SystemTime) -> i64 {
+    match time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(duration) => duration.as_millis().try_into().unwrap_or(i64::MAX),
+        Err(err) => -i64::try_from(err.duration().as_millis()).unwrap_or(i64::MAX),
+    }
+}
+
+fn millis_to_system_time(millis: i64) -> SystemTime {
+    let duration = Duration::from_millis(millis.unsigned_abs());
+    if millis >= 0 {
+        SystemTime::UNIX_EPOCH + duration
+    } else {
+        SystemTime::UNIX_EPOCH - duration
+    }
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::new_temp_dir;
+
+    fn id(hex: &str) -> Vec<u8> {
+        let mut bytes = hex::decode(hex).unwrap();
+        bytes.resize(ID_LENGTH, 0);
+        bytes
+    }
+
+    #[test]
+    fn test_write_and_read_pack() {
+        let temp_dir = new_temp_dir();
+        let mtime = SystemTime::UNIX_EPOCH + Duration::from_millis(123456);
+        let mut builder = OpPackBuilder::new();
+        assert!(builder.is_empty());
+        builder
+            .add_object(PackedObjectKind::Operation, &id("bb"), b"op bb", mtime)
+            .unwrap();
+        builder
+            .add_object(PackedObjectKind::Operation, &id("aa"), b"op aa", mtime)
+            .unwrap();
+        builder
+            .add_object(PackedObjectKind::View, &id("aa"), b"view aa", mtime)
+            .unwrap();
+        assert_eq!(builder.len(), 3);
+        let path = builder.write(temp_dir.path()).unwrap();
+        assert_eq!(
+            list_pack_paths(temp_dir.path()).unwrap(),
+            vec![path.clone()]
+        );
+
+        let pack = OpPack::load(&path).unwrap();
+        assert_eq!(pack.num_entries(PackedObjectKind::Operation), 2);
+        assert_eq!(pack.num_entries(PackedObjectKind::View), 1);
+        assert_eq!(
+            pack.read_object(PackedObjectKind::Operation, &id("aa"))
+                .unwrap()
+                .as_deref(),
+            Some(b"op aa".as_slice())
+        );
+        assert_eq!(
+            pack.read_object(PackedObjectKind::Operation, &id("bb"))
+                .unwrap()
+                .as_deref(),
+            Some(b"op bb".as_slice())
+        );
+        assert_eq!(
+            pack.read_object(PackedObjectKind::View, &id("aa"))
+                .unwrap()
+                .as_deref(),
+            Some(b"view aa".as_slice())
+        );
+        assert_eq!(
+            pack.read_object(PackedObjectKind::View, &id("bb")).unwrap(),
+            None
+        );
+        assert!(pack
+            .iter_entries(PackedObjectKind::Operation)
+            .all(|entry| entry.mtime() == mtime));
+
+        // Copy entries to new pack without decompression
+        let data = pack.read_data().unwrap();
+        let mut builder = OpPackBuilder::new();
+        for entry in pack.iter_entries(PackedObjectKind::Operation) {
+            let compressed = entry.compressed_in(&data).unwrap().to_vec();
+            builder.add_compressed(PackedObjectKind::Operation, entry.id, compressed, mtime);
+        }
+        let pack = OpPack::load(&builder.write(temp_dir.path()).unwrap()).unwrap();
+        assert_eq!(pack.num_entries(PackedObjectKind::Operation), 2);
+        assert_eq!(pack.num_entries(PackedObjectKind::View), 0);
+        assert_eq!(
+            pack.read_object(PackedObjectKind::Operation, &id("bb"))
+                .unwrap()
+                .as_deref(),
+            Some(b"op bb".as_slice())
+        );
+    }
+
+    #[test]
+    fn test_resolve_operation_id_prefix() {
+        let temp_dir = new_temp_dir();
+        let mut builder = OpPackBuilder::new();
+        for hex in ["0a11", "0a22", "0b11"] {
+            builder
+                .add_object(
+                    PackedObjectKind::Operation,
+                    &id(hex),
+                    b"",
+                    SystemTime::UNIX_EPOCH,
+                )
+                .unwrap();
+        }
+        let pack = OpPack::load(&builder.write(temp_dir.path()).unwrap()).unwrap();
+        let resolve =
+            |prefix: &str| pack.resolve_operation_id_prefix(&HexPrefix::new(prefix).unwrap());
+        assert_eq!(resolve("0"), PrefixResolution::AmbiguousMatch);
+        assert_eq!(resolve("0a"), PrefixResolution::AmbiguousMatch);
+        assert_eq!(
+            resolve("0a1"),
+            PrefixResolution::SingleMatch(OperationId::new(id("0a11")))
+        );
+        assert_eq!(
+            resolve("0b"),
+            PrefixResolution::SingleMatch(OperationId::new(id("0b11")))
+        );
+        assert_eq!(resolve("0c"), PrefixResolution::NoMatch);
+        assert_eq!(resolve("1"), PrefixResolution::NoMatch);
+    }
+
+    #[test]
+    fn test_load_corrupt_pack() {
+        let temp_dir = new_temp_dir();
+        let path = temp_dir.path().join("bad.pack");
+        fs::write(&path, b"not a pack file at all").unwrap();
+        let err = OpPack::load(&path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // Entry counts larger than the file
+        let mut data = PACK_MAGIC.to_vec();
+        data.extend(PACK_FORMAT_VERSION.to_le_bytes());
+        data.extend(u32::MAX.to_le_bytes());
+        data.extend(u32::MAX.to_le_bytes());
+        fs::write(&path, data).unwrap();
+        let err = OpPack::load(&path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "pack index exceeds file size");
+    }
+}
@@ -20,11 +20,12 @@ use std::collections::HashMap;
 use std::collections::HashSet;
 use std::fmt::Debug;
 use std::fs;
-use std::io;
 use std::io::ErrorKind;
 use std::io::Write as _;
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::RwLock;
 use std::time::SystemTime;
 
 use itertools::Itertools as _;
@@ -62,6 +63,10 @@ use crate::op_store::ViewId;
 use crate::op_store::WorkspaceId;
 use crate::ref_name::RefNameBuf;
 use crate::ref_name::RemoteNameBuf;
+use crate::simple_op_pack;
+use crate::simple_op_pack::OpPack;
+use crate::simple_op_pack::OpPackBuilder;
+use crate::simple_op_pack::PackedObjectKind;
 
 // BLAKE2b-512 hash length in bytes
 const OPERATION_ID_LENGTH: usize = 64;
@@ -84,6 +89,8 @@ pub struct SimpleOpStore {
     root_data: RootOperationData,
     root_operation_id: OperationId,
     root_view_id: ViewId,
+    /// Loaded pack files. `None` until the packs directory is scanned.
+    packs: RwLock<Option<Vec<Arc<OpPack>>>>,
 }
 
 impl SimpleOpStore {
@@ -112,6 +119,7 @@ impl SimpleOpStore {
             root_data,
             root_operation_id: OperationId::from_bytes(&[0; OPERATION_ID_LENGTH]),
             root_view_id: ViewId::from_bytes(&[0; VIEW_ID_LENGTH]),
+            packs: RwLock::new(None),
         }
     }
 
@@ -129,6 +137,187 @@ impl SimpleOpStore {
     fn operations_dir(&self) -> PathBuf {
         self.path.join("operations")
     }
+
+    fn packs_dir(&self) -> PathBuf {
+        self.path.join("packs")
+    }
+
+    fn loaded_packs(&self) -> Result<Vec<Arc<OpPack>>, PathError> {
+        if let Some(packs) = self.packs.read().unwrap().as_ref() {
+            return Ok(packs.clone());
+        }
+        self.reload_packs()
+    }
+
+    /// Rescans the packs directory. Packs that have already been loaded are
+    /// reused.
+    fn reload_packs(&self) -> Result<Vec<Arc<OpPack>>, PathError> {
+        let packs_dir = self.packs_dir();
+        let paths = simple_op_pack::list_pack_paths(&packs_dir).context(&packs_dir)?;
+        let old_packs = self.packs.read().unwrap().clone().unwrap_or_default();
+        let mut packs = Vec::with_capacity(paths.len());
+        for path in paths {
+            if let Some(pack) = old_packs.iter().find(|pack| pack.path() == path) {
+                packs.push(pack.clone());
+                continue;
+            }
+            match OpPack::load(&path) {
+                Ok(pack) => packs.push(Arc::new(pack)),
+                // The pack may have been removed by concurrent gc.
+                Err(err) if err.kind() == ErrorKind::NotFound => {}
+                Err(err) => return Err(err).context(&path),
+            }
+        }
+        *self.packs.write().unwrap() = Some(packs.clone());
+        Ok(packs)
+    }
+
+    /// Looks up the object in pack files. If not found, the pack list is
+    /// reloaded and looked up again since the object may have been moved to
+    /// a new pack by concurrent gc.
+    fn read_packed_object(
+        &self,
+        kind: PackedObjectKind,
+        id: &[u8],
+    ) -> Result<Option<Vec<u8>>, PathError> {
+        for reload in [false, true] {
+            let packs = if reload {
+                self.reload_packs()?
+            } else {
+                self.loaded_packs()?
+            };
+            for pack in &packs {
+                match pack.read_object(kind, id) {
+                    Ok(Some(buf)) => return Ok(Some(buf)),
+                    Ok(None) => {}
+                    Err(err) if err.kind() == ErrorKind::NotFound => {}
+                    Err(err) => return Err(err).context(pack.path()),
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Reads the encoded object from the loose file, or from pack files.
+    fn read_object_bytes(
+        &self,
+        kind: PackedObjectKind,
+        dir: &Path,
+        id: &impl ObjectId,
+    ) -> OpStoreResult<Vec<u8>> {
+        let not_found_err = match fs::read(dir.join(id.hex())) {
+            Ok(buf) => return Ok(buf),
+            Err(err) if err.kind() == ErrorKind::NotFound => err,
+            Err(err) => return Err(io_to_read_error(err, id)),
+        };
+        self.read_packed_object(kind, id.as_bytes())
+            .map_err(|err| to_read_error(err.into(), id))?
+            .ok_or_else(|| io_to_read_error(not_found_err, id))
+    }
+
+    /// Consolidates old loose objects and existing pack files into a single
+    /// pack file. Unreachable packed objects older than `keep_newer` are
+    /// dropped.
+    fn repack(
+        &self,
+        reachable_ops: &HashMap<OperationId, Operation>,
+        reachable_views: &HashSet<&ViewId>,
+        keep_newer: SystemTime,
+    ) -> Result<(), PathError> {
+        let packs = self.reload_packs()?;
+        let mut builder = OpPackBuilder::new();
+        let mut num_pruned = 0;
+        for pack in &packs {
+            let data = pack.read_data().context(pack.path())?;
+            for kind in [PackedObjectKind::Operation, PackedObjectKind::View] {
+                for entry in pack.iter_entries(kind) {
+                    let is_reachable = match kind {
+                        PackedObjectKind::Operation => {
+                            reachable_ops.contains_key(&OperationId::from_bytes(entry.id))
+                        }
+                        PackedObjectKind::View => {
+                            reachable_views.contains(&ViewId::from_bytes(entry.id))
+                        }
+                    };
+                    if !is_reachable && entry.mtime() <= keep_newer {
+                        tracing::trace!(?kind, id = hex::encode(entry.id), "pruning packed");
+                        num_pruned += 1;
+                        continue;
+                    }
+                    let compressed = entry.compressed_in(&data).context(pack.path())?;
+                    builder.add_compressed(kind, entry.id, compressed.to_vec(), entry.mtime());
+                }
+            }
+        }
+
+        // Unreachable old loose objects have been removed, so the remaining
+        // old objects are all reachable.
+        let mut loose_paths = Vec::new();
+        for (kind, dir, id_length) in [
+            (
+                PackedObjectKind::Operation,
+                self.operations_dir(),
+                OPERATION_ID_LENGTH,
+            ),
+            (PackedObjectKind::View, self.views_dir(), VIEW_ID_LENGTH),
+        ] {
+            for entry in dir.read_dir().context(&dir)? {
+                let entry = entry.context(&dir)?;
+                let path = entry.path();
+                let Some(id) = entry
+                    .file_name()
+                    .to_str()
+                    .and_then(|name| hex::decode(name).ok())
+                    .filter(|id| id.len() == id_length)
+                else {
+                    continue;
+                };
+                let mtime = entry
+                    .metadata()
+                    .context(&path)?
+                    .modified()
+                    .expect("unsupported platform?");
+                if mtime > keep_newer {
+                    continue;
+                }
+                let content = fs::read(&path).context(&path)?;
+                builder
+                    .add_object(kind, &id, &content, mtime)
+                    .context(&path)?;
+                loose_paths.push(path);
+            }
+        }
+
+        if num_pruned == 0 && loose_paths.is_empty() && packs.len() <= 1 {
+            tracing::info!("no objects to repack");
+            return Ok(());
+        }
+        tracing::info!(
+            num_pruned,
+            num_packed_loose = loose_paths.len(),
+            num_old_packs = packs.len(),
+            num_objects = builder.len(),
+            "repacking objects"
+        );
+        let new_pack_path = if builder.is_empty() {
+            None
+        } else {
+            let packs_dir = self.packs_dir();
+            fs::create_dir_all(&packs_dir).context(&packs_dir)?;
+            Some(builder.write(&packs_dir).context(&packs_dir)?)
+        };
+        // Remove old files only after the new pack gets persisted.
+        for path in &loose_paths {
+            fs::remove_file(path).context(path)?;
+        }
+        for pack in &packs {
+            if Some(pack.path()) != new_pack_path.as_deref() {
+                simple_op_pack::remove_pack(pack.path()).context(pack.path())?;
+            }
+        }
+        self.reload_packs()?;
+        Ok(())
+    }
 }
 
 impl OpStore for SimpleOpStore {
@@ -149,8 +338,7 @@ impl OpStore for SimpleOpStore {
             return Ok(View::make_root(self.root_data.root_commit_id.clone()));
         }
 
-        let path = self.views_dir().join(id.hex());
-        let buf = fs::read(path).map_err(|err| io_to_read_error(err, id))?;
+        let buf = self.read_object_bytes(PackedObjectKind::View, &self.views_dir(), id)?;
 
         let proto = crate::protos::op_store::View::decode(&*buf)
             .map_err(|err| to_read_error(err.into(), id))?;
@@ -180,8 +368,8 @@ impl OpStore for SimpleOpStore {
             return Ok(Operation::make_root(self.root_view_id.clone()));
         }
 
-        let path = self.operations_dir().join(id.hex());
-        let buf = fs::read(path).map_err(|err| io_to_read_error(err, id))?;
+        let buf =
+            self.read_object_bytes(PackedObjectKind::Operation, &self.operations_dir(), id)?;
 
         let proto = crate::protos::op_store::Operation::decode(&*buf)
             .map_err(|err| to_read_error(err.into(), id))?;
@@ -219,13 +407,21 @@ impl OpStore for SimpleOpStore {
         prefix: &HexPrefix,
     ) -> OpStoreResult<PrefixResolution<OperationId>> {
         let op_dir = self.operations_dir();
-        let find = || -> io::Result<_> {
+        let find = || -> Result<_, PathError> {
             let matches_root = prefix.matches(&self.root_operation_id);
             let hex_prefix = prefix.hex();
             if hex_prefix.len() == OPERATION_ID_LENGTH * 2 {
                 // Fast path for full-length ID
-                if matches_root || op_dir.join(hex_prefix).try_exists()? {
-                    let id = OperationId::from_bytes(prefix.as_full_bytes().unwrap());
+                let id_bytes = prefix.as_full_bytes().unwrap();
+                let path = op_dir.join(hex_prefix);
+                if matches_root
+                    || path.try_exists().context(&path)?
+                    || self
+                        .reload_packs()?
+                        .iter()
+                        .any(|pack| pack.contains(PackedObjectKind::Operation, id_bytes))
+                {
+                    let id = OperationId::from_bytes(id_bytes);
                     return Ok(PrefixResolution::SingleMatch(id));
                 } else {
                     return Ok(PrefixResolution::NoMatch);
@@ -233,8 +429,8 @@ impl OpStore for SimpleOpStore {
             }
 
             let mut matched = matches_root.then(|| self.root_operation_id.clone());
-            for entry in op_dir.read_dir()? {
-                let Ok(name) = entry?.file_name().into_string() else {
+            for entry in op_dir.read_dir().context(&op_dir)? {
+                let Ok(name) = entry.context(&op_dir)?.file_name().into_string() else {
                     continue; // Skip invalid UTF-8
                 };
                 if !name.starts_with(&hex_prefix) {
@@ -248,15 +444,29 @@ impl OpStore for SimpleOpStore {
                 }
                 matched = Some(id);
             }
+            // Loose objects are scanned first so an object moved to a new
+            // pack by concurrent gc can still be found.
+            for pack in self.reload_packs()? {
+                match pack.resolve_operation_id_prefix(prefix) {
+                    PrefixResolution::NoMatch => {}
+                    PrefixResolution::SingleMatch(id) => match &matched {
+                        // The same object may exist both in loose and packed forms.
+                        Some(matched_id) if *matched_id == id => {}
+                        Some(_) => return Ok(PrefixResolution::AmbiguousMatch),
+                        None => matched = Some(id),
+                    },
+                    PrefixResolution::AmbiguousMatch => {
+                        return Ok(PrefixResolution::AmbiguousMatch);
+                    }
+                }
+            }
             if let Some(id) = matched {
                 Ok(PrefixResolution::SingleMatch(id))
             } else {
                 Ok(PrefixResolution::NoMatch)
             }
         };
-        find()
-            .context(&op_dir)
-            .map_err(|err| OpStoreError::Other(err.into()))
+        find().map_err(|err| OpStoreError::Other(err.into()))
     }
 
     #[tracing::instrument(skip(self))]
@@ -339,6 +549,9 @@ impl OpStore for SimpleOpStore {
         };
         prune_views().map_err(|err| OpStoreError::Other(err.into()))?;
 
+        self.repack(&reachable_ops, &reachable_views, keep_newer)
+            .map_err(|err| OpStoreError::Other(err.into()))?;
+
         Ok(())
     }
 }
@@ -374,7 +587,7 @@ fn io_to_write_error(err: std::io::Error, object_type: &'static str) -> OpStoreE
 }
 
 #[derive(Debug, Error)]
-enum PostDecodeError {
+pub(crate) enum PostDecodeError {
     #[error("Invalid hash length (expected {expected} bytes, got {actual} bytes)")]
     InvalidHashLength { expected: usize, actual: usize },
 }
@@ -445,7 +658,7 @@ fn operation_metadata_from_proto(
     }
 }
 
-fn operation_to_proto(operation: &Operation) -> crate::protos::op_store::Operation {
+pub(crate) fn operation_to_proto(operation: &Operation) -> crate::protos::op_store::Operation {
     let mut proto = crate::protos::op_store::Operation {
         view_id: operation.view_id.as_bytes().to_vec(),
         metadata: Some(operation_metadata_to_proto(&operation.metadata)),
@@ -457,7 +670,7 @@ fn operation_to_proto(operation: &Operation) -> crate::protos::op_store::Operati
     proto
 }
 
-fn operation_from_proto(
+pub(crate) fn operation_from_proto(
     proto: crate::protos::op_store::Operation,
 ) -> Result<Operation, PostDecodeError> {
     let parents = proto
@@ -474,7 +687,7 @@ fn operation_from_proto(
     })
 }
 
-fn view_to_proto(view: &View) -> crate::protos::op_store::View {
+pub(crate) fn view_to_proto(view: &View) -> crate::protos::op_store::View {
     let mut proto = crate::protos::op_store::View {
         ..Default::default()
     };
@@ -509,7 +722,7 @@ fn view_to_proto(view: &View) -> crate::protos::op_store::View {
     proto
 }
 
-fn view_from_proto(proto: crate::protos::op_store::View) -> View {
+pub(crate) fn view_from_proto(proto: crate::protos::op_store::View) -> View {
     // TODO: validate commit id length?
     let mut view = View::empty();
     // For compatibility with old repos before we had support for multiple working
@@ -726,6 +939,9 @@ fn remote_ref_state_from_proto(proto_value: Option<i32>) -> Option<RemoteRefStat
 
 #[cfg(test)]
 mod tests {
+    use std::slice;
+
+    use assert_matches::assert_matches;
     use insta::assert_snapshot;
     use itertools::Itertools as _;
     use maplit::btreemap;
@@ -862,6 +1078,88 @@ mod tests {
         assert_eq!(read_operation, operation);
     }
 
+    #[test]
+    fn test_gc_packs_objects() {
+        let temp_dir = new_temp_dir();
+        let root_data = RootOperationData {
+            root_commit_id: CommitId::from_hex("000000"),
+        };
+        let store = SimpleOpStore::init(temp_dir.path(), root_data.clone()).unwrap();
+        let view = create_view();
+        let view_id = store.write_view(&view).unwrap();
+        let make_op = |parent: &OperationId, description: &str| Operation {
+            view_id: view_id.clone(),
+            parents: vec![parent.clone()],
+            metadata: OperationMetadata {
+                description: description.to_owned(),
+                ..create_operation().metadata
+            },
+        };
+        let op1 = make_op(store.root_operation_id(), "op1");
+        let op1_id = store.write_operation(&op1).unwrap();
+        let op2 = make_op(&op1_id, "op2");
+        let op2_id = store.write_operation(&op2).unwrap();
+        let op3 = make_op(store.root_operation_id(), "op3");
+        let op3_id = store.write_operation(&op3).unwrap();
+        let count_files = |dir: PathBuf| dir.read_dir().unwrap().count();
+
+        // Reachable objects are moved to a pack, and unreachable op3 is
+        // removed.
+        store
+            .gc(slice::from_ref(&op2_id), SystemTime::now())
+            .unwrap();
+        assert_eq!(count_files(store.operations_dir()), 0);
+        assert_eq!(count_files(store.views_dir()), 0);
+        assert_eq!(count_files(store.packs_dir()), 1);
+        assert_eq!(store.read_operation(&op1_id).unwrap(), op1);
+        assert_eq!(store.read_operation(&op2_id).unwrap(), op2);
+        assert_eq!(store.read_view(&view_id).unwrap(), view);
+        assert_matches!(
+            store.read_operation(&op3_id),
+            Err(OpStoreError::ObjectNotFound { .. })
+        );
+
+        // Packed objects can be read by newly loaded store
+        let store = SimpleOpStore::load(temp_dir.path(), root_data);
+        assert_eq!(store.read_operation(&op2_id).unwrap(), op2);
+        let resolve = |id: &OperationId, len: usize| {
+            let prefix = HexPrefix::new(&id.hex()[..len]).unwrap();
+            store.resolve_operation_id_prefix(&prefix).unwrap()
+        };
+        assert_eq!(
+            resolve(&op1_id, OPERATION_ID_LENGTH * 2),
+            PrefixResolution::SingleMatch(op1_id.clone())
+        );
+        assert_eq!(
+            resolve(&op2_id, 8),
+            PrefixResolution::SingleMatch(op2_id.clone())
+        );
+        assert_eq!(
+            resolve(&op3_id, OPERATION_ID_LENGTH * 2),
+            PrefixResolution::NoMatch
+        );
+
+        // The same object can exist in both loose and packed forms
+        assert_eq!(store.write_operation(&op2).unwrap(), op2_id);
+        assert_eq!(
+            resolve(&op2_id, 8),
+            PrefixResolution::SingleMatch(op2_id.clone())
+        );
+
+        // Packed objects which are no longer reachable are pruned
+        store
+            .gc(slice::from_ref(&op1_id), SystemTime::now())
+            .unwrap();
+        assert_eq!(count_files(store.operations_dir()), 0);
+        assert_eq!(count_files(store.packs_dir()), 1);
+        assert_eq!(store.read_operation(&op1_id).unwrap(), op1);
+        assert_matches!(
+            store.read_operation(&op2_id),
+            Err(OpStoreError::ObjectNotFound { .. })
+        );
+        assert_eq!(resolve(&op2_id, 8), PrefixResolution::NoMatch);
+    }
+
     #[test]
     fn test_bookmark_views_legacy_roundtrip() {
         let new_remote_ref = |target: &RefTarget| RemoteRef {
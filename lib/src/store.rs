@@ -23,6 +23,7 @@ use std::sync::Mutex;
 use std::time::SystemTime;
 
 use clru::CLruCache;
+use futures::stream;
 use futures::stream::BoxStream;
 use pollster::FutureExt as _;
 
@@ -39,9 +40,17 @@ use crate::backend::SigningFn;
 use crate::backend::SymlinkId;
 use crate::backend::TreeId;
 use crate::commit::Commit;
+use crate::copies;
+use crate::copies::CopyDetectionMethod;
+use crate::copies::CopyDetectionOptions;
+use crate::diff::DiffAlgorithm;
 use crate::index::Index;
+use crate::matchers::EverythingMatcher;
+use crate::matchers::FilesMatcher;
+use crate::matchers::Matcher;
 use crate::merge::Merge;
 use crate::merge::MergedTreeValue;
+use crate::merge_driver::MergeDrivers;
 use crate::merged_tree::MergedTree;
 use crate::repo_path::RepoPath;
 use crate::repo_path::RepoPathBuf;
@@ -59,10 +68,54 @@ const TREE_CACHE_CAPACITY: usize = 1000;
 pub struct Store {
     backend: Box<dyn Backend>,
     signer: Signer,
+    merge_drivers: MergeDrivers,
+    copy_detection: CopyDetectionOptions,
+    diff_algorithm: DiffAlgorithm,
     commit_cache: Mutex<CLruCache<CommitId, Arc<backend::Commit>>>,
     tree_cache: Mutex<CLruCache<(RepoPathBuf, TreeId), Arc<backend::Tree>>>,
 }
 
+/// Builder of `Store` with optional settings.
+pub struct StoreBuilder {
+    backend: Box<dyn Backend>,
+    signer: Signer,
+    merge_drivers: MergeDrivers,
+    copy_detection: CopyDetectionOptions,
+    diff_algorithm: DiffAlgorithm,
+}
+
+impl StoreBuilder {
+    /// Sets the merge drivers used when rebasing and creating merge commits.
+    pub fn merge_drivers(mut self, merge_drivers: MergeDrivers) -> Self {
+        self.merge_drivers = merge_drivers;
+        self
+    }
+
+    /// Sets how copies and renames are detected.
+    pub fn copy_detection(mut self, copy_detection: CopyDetectionOptions) -> Self {
+        self.copy_detection = copy_detection;
+        self
+    }
+
+    /// Sets the line diff algorithm.
+    pub fn diff_algorithm(mut self, diff_algorithm: DiffAlgorithm) -> Self {
+        self.diff_algorithm = diff_algorithm;
+        self
+    }
+
+    pub fn build(self) -> Arc<Store> {
+        Arc::new(Store {
+            backend: self.backend,
+            signer: self.signer,
+            merge_drivers: self.merge_drivers,
+            copy_detection: self.copy_detection,
+            diff_algorithm: self.diff_algorithm,
+            commit_cache: Mutex::new(CLruCache::new(COMMIT_CACHE_CAPACITY.try_into().unwrap())),
+            tree_cache: Mutex::new(CLruCache::new(TREE_CACHE_CAPACITY.try_into().unwrap())),
+        })
+    }
+}
+
 impl Debug for Store {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
         f.debug_struct("Store")
@@ -72,13 +125,21 @@ impl Debug for Store {
 }
 
 impl Store {
+    /// Creates a store with the default merge drivers, copy detection, and
+    /// diff algorithm.
     pub fn new(backend: Box<dyn Backend>, signer: Signer) -> Arc<Self> {
-        Arc::new(Store {
+        Self::builder(backend, signer).build()
+    }
+
+    /// Starts building a store with non-default options.
+    pub fn builder(backend: Box<dyn Backend>, signer: Signer) -> StoreBuilder {
+        StoreBuilder {
             backend,
             signer,
-            commit_cache: Mutex::new(CLruCache::new(COMMIT_CACHE_CAPACITY.try_into().unwrap())),
-            tree_cache: Mutex::new(CLruCache::new(TREE_CACHE_CAPACITY.try_into().unwrap())),
-        })
+            merge_drivers: MergeDrivers::default(),
+            copy_detection: CopyDetectionOptions::default(),
+            diff_algorithm: DiffAlgorithm::default(),
+        }
     }
 
     pub fn backend_impl(&self) -> &dyn Any {
@@ -89,13 +150,39 @@ impl Store {
         &self.signer
     }
 
+    pub fn merge_drivers(&self) -> &MergeDrivers {
+        &self.merge_drivers
+    }
+
+    pub fn copy_detection(&self) -> &CopyDetectionOptions {
+        &self.copy_detection
+    }
+
+    /// Returns the line diff algorithm configured by `diff.algorithm`.
+    pub fn diff_algorithm(&self) -> DiffAlgorithm {
+        self.diff_algorithm
+    }
+
+    /// Returns the copies and renames from `root` to `head`, detected as
+    /// configured by `copy-tracking.method`.
     pub fn get_copy_records(
-        &self,
+        self: &Arc<Self>,
         paths: Option<&[RepoPathBuf]>,
         root: &CommitId,
         head: &CommitId,
-    ) -> BackendResult<BoxStream<BackendResult<CopyRecord>>> {
-        self.backend.get_copy_records(paths, root, head)
+    ) -> BackendResult<BoxStream<'_, BackendResult<CopyRecord>>> {
+        match self.copy_detection.method {
+            CopyDetectionMethod::Backend => self.backend.get_copy_records(paths, root, head),
+            CopyDetectionMethod::Content => {
+                let matcher: Box<dyn Matcher> = match paths {
+                    Some(paths) => Box::new(FilesMatcher::new(paths)),
+                    None => Box::new(EverythingMatcher),
+                };
+                let records =
+                    copies::detect_copies(self, &self.copy_detection, &*matcher, root, head)?;
+                Ok(Box::pin(stream::iter(records.into_iter().map(Ok))))
+            }
+        }
     }
 
     pub fn commit_id_length(&self) -> usize {
@@ -208,6 +208,26 @@ impl StringPattern {
         }
     }
 
+    /// Converts this pattern to a regular expression that finds matching
+    /// substrings.
+    ///
+    /// The regular expression should match the same strings as
+    /// [`Self::matches()`]. Case‐insensitive patterns fold only ASCII case
+    /// differences. Returns an error if the compiled expression would exceed
+    /// the size limit.
+    pub fn to_regex(&self) -> Result<regex::Regex, regex::Error> {
+        let source = match self {
+            StringPattern::Exact(literal) => format!("^{}$", literal_to_regex(literal, false)),
+            StringPattern::ExactI(literal) => format!("^{}$", literal_to_regex(literal, true)),
+            StringPattern::Substring(needle) => literal_to_regex(needle, false),
+            StringPattern::SubstringI(needle) => literal_to_regex(needle, true),
+            StringPattern::Glob(pattern) => glob_to_regex(pattern.as_str(), false),
+            StringPattern::GlobI(pattern) => glob_to_regex(pattern.as_str(), true),
+            StringPattern::Regex(pattern) => return Ok(pattern.clone()),
+        };
+        regex::Regex::new(&source)
+    }
+
     /// Returns true if this pattern matches the `haystack`.
     ///
     /// When matching against a case‐insensitive pattern, only ASCII case
@@ -299,6 +319,152 @@ impl StringPattern {
     }
 }
 
+/// Pushes class items matching `c`, folding ASCII case if `case_insensitive`.
+fn push_class_char(out: &mut String, c: char, case_insensitive: bool) {
+    out.push_str(&regex::escape(c.encode_utf8(&mut [0; 4])));
+    if case_insensitive && c.is_ascii_alphabetic() {
+        let other = if c.is_ascii_lowercase() {
+            c.to_ascii_uppercase()
+        } else {
+            c.to_ascii_lowercase()
+        };
+        out.push(other);
+    }
+}
+
+/// Pushes an expression matching `c`, folding ASCII case if
+/// `case_insensitive`.
+fn push_literal_char(out: &mut String, c: char, case_insensitive: bool) {
+    if case_insensitive && c.is_ascii_alphabetic() {
+        out.push('[');
+        push_class_char(out, c, case_insensitive);
+        out.push(']');
+    } else {
+        out.push_str(&regex::escape(c.encode_utf8(&mut [0; 4])));
+    }
+}
+
+/// Translates a literal string to a regular expression in the same way as
+/// [`StringPattern::matches()`] compares strings.
+fn literal_to_regex(src: &str, case_insensitive: bool) -> String {
+    let mut out = String::new();
+    for c in src.chars() {
+        push_literal_char(&mut out, c, case_insensitive);
+    }
+    out
+}
+
+/// Translates a (valid) Unix‐style shell wildcard pattern to an anchored
+/// regular expression.
+///
+/// This follows the tokenization and matching rules of [`glob::Pattern`] with
+/// the default [`glob::MatchOptions`].
+fn glob_to_regex(src: &str, case_insensitive: bool) -> String {
+    let push_char = |out: &mut String, c: char| {
+        if cfg!(windows) && std::path::is_separator(c) {
+            out.push_str(r"[/\\]");
+        } else {
+            push_literal_char(out, c, case_insensitive);
+        }
+    };
+    let chars: Vec<char> = src.chars().collect();
+    let mut out = String::from("(?s)^");
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '?' => {
+                out.push('.');
+                i += 1;
+            }
+            '*' => {
+                let count = chars[i..].iter().take_while(|&&c| c == '*').count();
+                i += count;
+                if count == 2 && chars.get(i).copied().is_some_and(std::path::is_separator) {
+                    // "**/" matches zero or more path components.
+                    i += 1;
+                    if i < chars.len() {
+                        out.push_str("(?:.*");
+                        push_char(&mut out, chars[i - 1]);
+                        out.push_str(")?");
+                        continue;
+                    }
+                }
+                out.push_str(".*");
+            }
+            '[' => {
+                let (negated, start) = if chars.get(i + 1) == Some(&'!') {
+                    (true, i + 2)
+                } else {
+                    (false, i + 1)
+                };
+                // The first character is always a member of the class.
+                let Some(end) = chars
+                    .get(start + 1..)
+                    .and_then(|rest| rest.iter().position(|&c| c == ']'))
+                    .map(|pos| start + 1 + pos)
+                else {
+                    // Not a class, which glob::Pattern would reject.
+                    push_char(&mut out, '[');
+                    i += 1;
+                    continue;
+                };
+                out.push_str(&glob_class_to_regex(
+                    &chars[start..end],
+                    negated,
+                    case_insensitive,
+                ));
+                i = end + 1;
+            }
+            c => {
+                push_char(&mut out, c);
+                i += 1;
+            }
+        }
+    }
+    out.push('$');
+    out
+}
+
+/// Translates the members of a glob character class to a regular expression.
+fn glob_class_to_regex(members: &[char], negated: bool, case_insensitive: bool) -> String {
+    let mut items = String::new();
+    let mut i = 0;
+    while i < members.len() {
+        if let [start, '-', end, ..] = members[i..] {
+            if start <= end {
+                push_class_char(&mut items, start, false);
+                items.push('-');
+                push_class_char(&mut items, end, false);
+            }
+            // Ranges between ASCII letters also match the other case.
+            let (start, end) = (start.to_ascii_lowercase(), end.to_ascii_lowercase());
+            if case_insensitive
+                && start.is_ascii_lowercase()
+                && end.is_ascii_lowercase()
+                && start <= end
+            {
+                items.push_str(&format!("{start}-{end}"));
+                let (start, end) = (start.to_ascii_uppercase(), end.to_ascii_uppercase());
+                items.push_str(&format!("{start}-{end}"));
+            }
+            i += 3;
+        } else {
+            let c = members[i];
+            push_class_char(&mut items, c, case_insensitive);
+            if cfg!(windows) && std::path::is_separator(c) {
+                items.push_str(r"/\\");
+            }
+            i += 1;
+        }
+    }
+    match (items.is_empty(), negated) {
+        (true, false) => r"[^\x00-\x{10FFFF}]".to_owned(),
+        (true, true) => ".".to_owned(),
+        (false, false) => format!("[{items}]"),
+        (false, true) => format!("[^{items}]"),
+    }
+}
+
 impl fmt::Display for StringPattern {
     /// Shows the original string of this pattern.
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -331,6 +497,97 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_string_pattern_to_regex() {
+        let find = |pattern: StringPattern, haystack: &str| {
+            pattern
+                .to_regex()
+                .unwrap()
+                .find(haystack)
+                .map(|m| m.as_str().to_owned())
+        };
+        assert_eq!(find(StringPattern::exact("a.b"), "a.b"), Some("a.b".into()));
+        assert_eq!(find(StringPattern::exact("a.b"), "xa.b"), None);
+        assert_eq!(
+            find(StringPattern::exact_i("a.b"), "A.B"),
+            Some("A.B".into())
+        );
+        assert_eq!(
+            find(StringPattern::substring("a.b"), "xa.by"),
+            Some("a.b".into())
+        );
+        assert_eq!(find(StringPattern::substring("a.b"), "axb"), None);
+        assert_eq!(
+            find(StringPattern::substring_i("a.b"), "xA.By"),
+            Some("A.B".into())
+        );
+        assert_eq!(
+            find(StringPattern::glob("a*.[!x-z]").unwrap(), "ab/c.d"),
+            Some("ab/c.d".into())
+        );
+        assert_eq!(
+            find(StringPattern::glob("a*.[!x-z]").unwrap(), "ab.z"),
+            None
+        );
+        assert_eq!(
+            find(StringPattern::glob("?[]-]").unwrap(), "a-"),
+            Some("a-".into())
+        );
+        assert_eq!(find(StringPattern::glob("a?").unwrap(), "xa?"), None);
+        assert_eq!(
+            find(StringPattern::glob_i("A?").unwrap(), "ab"),
+            Some("ab".into())
+        );
+        assert_eq!(
+            find(StringPattern::regex("b+").unwrap(), "abbc"),
+            Some("bb".into())
+        );
+    }
+
+    #[test]
+    fn test_string_pattern_to_regex_agrees_with_matches() {
+        let patterns = [
+            StringPattern::exact("a.B"),
+            StringPattern::exact_i("a.B"),
+            StringPattern::exact_i("é"),
+            StringPattern::substring("a.B"),
+            StringPattern::substring_i("a.B"),
+            StringPattern::substring_i("ǅ"),
+            StringPattern::glob("*.[ch]").unwrap(),
+            StringPattern::glob("?[]-a]").unwrap(),
+            StringPattern::glob("[!]-a]").unwrap(),
+            StringPattern::glob("[]]*[!!]").unwrap(),
+            StringPattern::glob("[z-a]").unwrap(),
+            StringPattern::glob("[!z-a]").unwrap(),
+            StringPattern::glob("[a-]x").unwrap(),
+            StringPattern::glob("a/**/b").unwrap(),
+            StringPattern::glob("**/b").unwrap(),
+            StringPattern::glob("a/**").unwrap(),
+            StringPattern::glob_i("A?").unwrap(),
+            StringPattern::glob_i("[b-D]").unwrap(),
+            StringPattern::glob_i("[B-d]").unwrap(),
+            StringPattern::glob_i("[!a-c]x").unwrap(),
+            StringPattern::glob_i("[[-b]").unwrap(),
+            StringPattern::glob_i("é*").unwrap(),
+        ];
+        let haystacks = [
+            "", "a", "A", "b", "B", "c", "C", "d", "D", "x", "X", "-", "]", "^", "_", "`", "!",
+            "[", "ax", "Ax", "bx", "dx", "a.b", "a.B", "A.B", "xa.By", "axb", "é", "É", "éx", "Éx",
+            "ǆ", "ǅ", "foo.c", "foo.H", "a-", "a]", "a^", "ab", "Ab", "aB", "a/b", "a/x/b", "a/xb",
+            "x/b", "xb", "a/", "a/x", "]x!", "]!",
+        ];
+        for pattern in &patterns {
+            let regex = pattern.to_regex().unwrap();
+            for haystack in haystacks {
+                assert_eq!(
+                    regex.is_match(haystack),
+                    pattern.matches(haystack),
+                    "pattern {pattern:?} (regex {regex}) against {haystack:?}",
+                );
+            }
+        }
+    }
+
     #[test]
     fn test_parse() {
         // Parse specific pattern kinds.
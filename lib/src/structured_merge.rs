@@ -0,0 +1,516 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Key-level merge of structured files (JSON, TOML, and YAML).
+//!
+//! The sides are parsed into trees of tables, which are merged key by key.
+//! Arrays are merged element by element like lines of text, and other values
+//! are merged as a whole. The merged tree is then serialized in the format of
+//! the file, keeping the order of the keys of the first side.
+
+#![allow(missing_docs)]
+
+use std::collections::HashMap;
+
+use bstr::BString;
+use bstr::ByteSlice as _;
+use indexmap::IndexMap;
+use itertools::Itertools as _;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::diff::DiffAlgorithm;
+use crate::files;
+use crate::files::MergeResult;
+use crate::merge::Merge;
+use crate::repo_path::RepoPath;
+
+/// Key used by `toml_edit` to represent datetime values, which can't be
+/// round-tripped through [`Node`].
+const TOML_DATETIME_KEY: &str = "$__toml_private_datetime";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StructuredFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl StructuredFormat {
+    /// Determines the format of the file from its extension.
+    pub fn from_path(path: &RepoPath) -> Option<Self> {
+        let (_, extension) = path.as_internal_file_string().rsplit_once('.')?;
+        match extension.to_ascii_lowercase().as_str() {
+            "json" => Some(StructuredFormat::Json),
+            "toml" => Some(StructuredFormat::Toml),
+            "yaml" | "yml" => Some(StructuredFormat::Yaml),
+            _ => None,
+        }
+    }
+
+    fn parse(self, content: &[u8]) -> Option<Node> {
+        let node: Node = match self {
+            StructuredFormat::Json => serde_json::from_slice(content).ok()?,
+            StructuredFormat::Toml => toml_edit::de::from_slice(content).ok()?,
+            StructuredFormat::Yaml => serde_yaml::from_slice(content).ok()?,
+        };
+        (!node.contains_key(TOML_DATETIME_KEY)).then_some(node)
+    }
+
+    fn serialize(self, node: &Node, template: &[u8]) -> Option<BString> {
+        let mut content = match self {
+            StructuredFormat::Json => {
+                let indent = detect_indent(template).unwrap_or(b"  ");
+                let formatter = serde_json::ser::PrettyFormatter::with_indent(indent);
+                let mut content = vec![];
+                let mut serializer =
+                    serde_json::Serializer::with_formatter(&mut content, formatter);
+                node.serialize(&mut serializer).ok()?;
+                content
+            }
+            StructuredFormat::Toml => {
+                // Update the document of the first side in place to preserve
+                // the formatting and comments of the unchanged items.
+                let mut document: toml_edit::DocumentMut = template.to_str().ok()?.parse().ok()?;
+                let (Node::Table(old_table), Node::Table(new_table)) =
+                    (self.parse(template)?, node)
+                else {
+                    return None;
+                };
+                update_toml_table(document.as_table_mut(), &old_table, new_table)?;
+                document.to_string().into_bytes()
+            }
+            StructuredFormat::Yaml => serde_yaml::to_string(node).ok()?.into_bytes(),
+        };
+        // Keep the trailing newline (or the lack of it) of the first side
+        let has_newline = template.ends_with(b"\n");
+        while content.ends_with(b"\n") {
+            content.pop();
+        }
+        if has_newline {
+            content.push(b'\n');
+        }
+        Some(content.into())
+    }
+}
+
+/// Parsed value of a structured file.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+enum Node {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    Unsigned(u64),
+    Float(f64),
+    String(String),
+    Array(Vec<Node>),
+    Table(IndexMap<String, Node>),
+}
+
+impl Node {
+    fn contains_key(&self, key: &str) -> bool {
+        match self {
+            Node::Array(items) => items.iter().any(|item| item.contains_key(key)),
+            Node::Table(table) => {
+                table.contains_key(key) || table.values().any(|value| value.contains_key(key))
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Merges the structured `contents` of the file at `path` key by key.
+///
+/// Returns `None` if the format isn't supported, any of the contents can't be
+/// parsed, or the same key is changed differently by the sides.
+pub fn merge_structured(path: &RepoPath, contents: &Merge<Vec<u8>>) -> Option<BString> {
+    let format = StructuredFormat::from_path(path)?;
+    let nodes = contents
+        .try_map(|content| format.parse(content).ok_or(()))
+        .ok()?;
+    let merged = merge_nodes(nodes.map(Some))??;
+    format.serialize(&merged, contents.first())
+}
+
+/// Merges the nodes, each of which may be absent. Returns `None` if there's a
+/// conflict, or `Some(None)` if the merged node is absent.
+fn merge_nodes(nodes: Merge<Option<&Node>>) -> Option<Option<Node>> {
+    // Nodes aren't hashable, so resolve the indices of the first equal nodes
+    // instead.
+    let indices = Merge::from_vec(
+        nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| nodes.iter().position(|other| other == node).unwrap_or(i))
+            .collect_vec(),
+    );
+    if let Some(&index) = indices.resolve_trivial() {
+        return Some(nodes.as_slice()[index].cloned());
+    }
+    if let Some(tables) = nodes.maybe_map(|node| match node {
+        Some(Node::Table(table)) => Some(table),
+        _ => None,
+    }) {
+        return merge_tables(tables).map(|table| Some(Node::Table(table)));
+    }
+    if let Some(arrays) = nodes.maybe_map(|node| match node {
+        Some(Node::Array(items)) => Some(items.as_slice()),
+        _ => None,
+    }) {
+        return merge_arrays(arrays).map(|items| Some(Node::Array(items)));
+    }
+    None
+}
+
+fn merge_tables(tables: Merge<&IndexMap<String, Node>>) -> Option<IndexMap<String, Node>> {
+    // Keys are ordered as in the first side, followed by the keys added by the
+    // other sides.
+    let keys = tables
+        .adds()
+        .chain(tables.removes())
+        .flat_map(|table| table.keys())
+        .unique();
+    let mut merged = IndexMap::new();
+    for key in keys {
+        if let Some(node) = merge_nodes(tables.map(|table| table.get(key)))? {
+            merged.insert(key.clone(), node);
+        }
+    }
+    Some(merged)
+}
+
+/// Merges the arrays like lines of text, one item per line.
+fn merge_arrays(arrays: Merge<&[Node]>) -> Option<Vec<Node>> {
+    let mut items_by_line = HashMap::new();
+    let lines = arrays
+        .try_map(|&items| {
+            let mut lines = vec![];
+            for item in items {
+                // JSON strings don't contain newlines, so each item is
+                // serialized to a single line.
+                let line = serde_json::to_string(item).map_err(|_| ())?;
+                lines.extend_from_slice(line.as_bytes());
+                lines.push(b'\n');
+                // Distinct items must be serialized differently (e.g. NaN is
+                // serialized as null)
+                match items_by_line.insert(line, item) {
+                    Some(other) if other != item => return Err(()),
+                    _ => {}
+                }
+            }
+            Ok(lines)
+        })
+        .ok()?;
+    let MergeResult::Resolved(merged) = files::merge(&lines, DiffAlgorithm::default()) else {
+        return None;
+    };
+    merged
+        .lines()
+        .map(|line| {
+            let line = line.to_str().ok()?;
+            items_by_line.get(line).map(|&item| item.clone())
+        })
+        .collect()
+}
+
+/// Updates the TOML `table` from the `old` contents to the `new` contents,
+/// leaving the unchanged items alone.
+fn update_toml_table(
+    table: &mut dyn toml_edit::TableLike,
+    old: &IndexMap<String, Node>,
+    new: &IndexMap<String, Node>,
+) -> Option<()> {
+    for key in old.keys() {
+        if !new.contains_key(key) {
+            table.remove(key);
+        }
+    }
+    for (key, new_node) in new {
+        let old_node = old.get(key);
+        if old_node == Some(new_node) {
+            continue;
+        }
+        match (old_node, new_node, table.get_mut(key)) {
+            (Some(Node::Table(old_table)), Node::Table(new_table), Some(item))
+                if item.is_table_like() =>
+            {
+                update_toml_table(item.as_table_like_mut()?, old_table, new_table)?;
+            }
+            (Some(Node::Array(old_items)), Node::Array(new_items), Some(item))
+                if item.is_array_of_tables() =>
+            {
+                // Keep the unchanged tables as is
+                let old_tables = item.as_array_of_tables()?;
+                let mut new_tables = toml_edit::ArrayOfTables::new();
+                for new_item in new_items {
+                    let table = match old_items.iter().position(|old_item| old_item == new_item) {
+                        Some(i) => old_tables.get(i)?.clone(),
+                        None => to_toml_value(new_item)?
+                            .as_inline_table()?
+                            .clone()
+                            .into_table(),
+                    };
+                    new_tables.push(table);
+                }
+                *item = toml_edit::Item::ArrayOfTables(new_tables);
+            }
+            (_, _, Some(toml_edit::Item::Value(value))) => {
+                let decor = value.decor().clone();
+                *value = to_toml_value(new_node)?;
+                *value.decor_mut() = decor;
+            }
+            (_, _, Some(item)) => {
+                *item = toml_edit::Item::Value(to_toml_value(new_node)?);
+            }
+            (_, _, None) => {
+                let value = to_toml_value(new_node)?;
+                let item = match value {
+                    toml_edit::Value::InlineTable(table) if table.iter().next().is_some() => {
+                        toml_edit::Item::Table(table.into_table())
+                    }
+                    value => toml_edit::Item::Value(value),
+                };
+                table.insert(key, item);
+            }
+        }
+    }
+    Some(())
+}
+
+fn to_toml_value(node: &Node) -> Option<toml_edit::Value> {
+    node.serialize(toml_edit::ser::ValueSerializer::new()).ok()
+}
+
+/// Returns the indentation of the first indented line.
+fn detect_indent(content: &[u8]) -> Option<&[u8]> {
+    content.lines().find_map(|line| {
+        let len = line
+            .iter()
+            .take_while(|&&b| b == b' ' || b == b'\t')
+            .count();
+        (len > 0).then(|| &line[..len])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+
+    fn merge(path: &str, base: &str, left: &str, right: &str) -> Option<String> {
+        let contents = Merge::from_vec(vec![
+            left.as_bytes().to_vec(),
+            base.as_bytes().to_vec(),
+            right.as_bytes().to_vec(),
+        ]);
+        let merged = merge_structured(RepoPath::from_internal_string(path), &contents)?;
+        Some(merged.to_str().unwrap().to_owned())
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        let format = |path| StructuredFormat::from_path(RepoPath::from_internal_string(path));
+        assert_eq!(format("package.json"), Some(StructuredFormat::Json));
+        assert_eq!(format("dir/Cargo.toml"), Some(StructuredFormat::Toml));
+        assert_eq!(format("config.YML"), Some(StructuredFormat::Yaml));
+        assert_eq!(format("config.yaml"), Some(StructuredFormat::Yaml));
+        assert_eq!(format("file.txt"), None);
+        assert_eq!(format("json"), None);
+    }
+
+    #[test]
+    fn test_merge_json() {
+        let base = indoc! {r#"
+            {
+                "name": "foo",
+                "version": "1.0.0",
+                "dependencies": {
+                    "a": "1",
+                    "b": "1"
+                }
+            }
+        "#};
+        let left = indoc! {r#"
+            {
+                "name": "foo",
+                "version": "1.1.0",
+                "dependencies": {
+                    "a": "2",
+                    "b": "1"
+                }
+            }
+        "#};
+        let right = indoc! {r#"
+            {
+                "name": "foo",
+                "version": "1.0.0",
+                "dependencies": {
+                    "a": "1",
+                    "b": "1",
+                    "c": "1"
+                }
+            }
+        "#};
+        insta::assert_snapshot!(merge("package.json", base, left, right).unwrap(), @r#"
+        {
+            "name": "foo",
+            "version": "1.1.0",
+            "dependencies": {
+                "a": "2",
+                "b": "1",
+                "c": "1"
+            }
+        }
+        "#);
+
+        // Conflicting changes to the same key
+        let right = base.replace(r#""a": "1""#, r#""a": "3""#);
+        assert_eq!(merge("package.json", base, left, &right), None);
+
+        // Invalid contents
+        assert_eq!(merge("package.json", base, left, "{"), None);
+    }
+
+    #[test]
+    fn test_merge_json_removed_keys() {
+        let base = r#"{"a": 1, "b": 2, "c": 3}"#;
+        let left = r#"{"b": 2, "c": 3}"#;
+        let right = r#"{"a": 1, "b": 2, "c": 4, "d": 5}"#;
+        insta::assert_snapshot!(merge("file.json", base, left, right).unwrap(), @r#"
+        {
+          "b": 2,
+          "c": 4,
+          "d": 5
+        }
+        "#);
+
+        // Removed on one side, modified on the other
+        let right = r#"{"a": 0, "b": 2, "c": 3}"#;
+        assert_eq!(merge("file.json", base, left, right), None);
+    }
+
+    #[test]
+    fn test_merge_json_arrays() {
+        let base = r#"{"items": [1, 2, 3, 4, 5]}"#;
+        let left = r#"{"items": [0, 1, 2, 3, 4, 5]}"#;
+        let right = r#"{"items": [1, 2, 3, 4, 5, 6]}"#;
+        insta::assert_snapshot!(merge("file.json", base, left, right).unwrap(), @r#"
+        {
+          "items": [
+            0,
+            1,
+            2,
+            3,
+            4,
+            5,
+            6
+          ]
+        }
+        "#);
+
+        // Type changes can't be merged
+        let right = r#"{"items": {"a": 1}}"#;
+        assert_eq!(merge("file.json", base, left, right), None);
+    }
+
+    #[test]
+    fn test_merge_toml() {
+        let base = indoc! {r#"
+            [package]
+            name = "foo"
+            version = "1.0.0"
+
+            [dependencies]
+            a = "1" # comment
+            b = { version = "1", features = ["x"] }
+        "#};
+        let left = indoc! {r#"
+            [package]
+            name = "foo"
+            version = "1.1.0"
+
+            [dependencies]
+            a = "1" # comment
+            b = { version = "1", features = ["x"] }
+        "#};
+        let right = indoc! {r#"
+            [package]
+            name = "foo"
+            version = "1.0.0"
+
+            [dependencies]
+            a = "1" # comment
+            b = { version = "1", features = ["x", "y"] }
+            c = "1"
+        "#};
+        insta::assert_snapshot!(merge("Cargo.toml", base, left, right).unwrap(), @r#"
+        [package]
+        name = "foo"
+        version = "1.1.0"
+
+        [dependencies]
+        a = "1" # comment
+        b = { version = "1", features = ["x", "y"] }
+        c = "1"
+        "#);
+
+        // Datetime values aren't supported
+        let base = "a = 1979-05-27T07:32:00Z\nb = 1\n";
+        let left = "a = 1979-05-27T07:32:00Z\nb = 2\n";
+        let right = "a = 1979-05-27T07:32:00Z\nb = 1\nc = 3\n";
+        assert_eq!(merge("file.toml", base, left, right), None);
+    }
+
+    #[test]
+    fn test_merge_yaml() {
+        let base = indoc! {"
+            name: foo
+            steps:
+            - build
+            - test
+            env:
+              A: 1
+        "};
+        let left = indoc! {"
+            name: foo
+            steps:
+            - build
+            - test
+            - deploy
+            env:
+              A: 1
+        "};
+        let right = indoc! {"
+            name: bar
+            steps:
+            - build
+            - test
+            env:
+              A: 1
+              B: 2
+        "};
+        insta::assert_snapshot!(merge("ci.yaml", base, left, right).unwrap(), @r"
+        name: bar
+        steps:
+        - build
+        - test
+        - deploy
+        env:
+          A: 1
+          B: 2
+        ");
+    }
+}
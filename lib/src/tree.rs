@@ -34,17 +34,23 @@ use crate::backend::TreeEntriesNonRecursiveIterator;
 use crate::backend::TreeEntry;
 use crate::backend::TreeId;
 use crate::backend::TreeValue;
+use crate::diff::DiffAlgorithm;
 use crate::files;
 use crate::files::MergeResult;
 use crate::matchers::EverythingMatcher;
 use crate::matchers::Matcher;
 use crate::merge::Merge;
 use crate::merge::MergedTreeVal;
+use crate::merge_driver::merge_union;
+use crate::merge_driver::run_external_merge_driver;
+use crate::merge_driver::MergeDriver;
+use crate::merge_driver::MergeDriverResolver;
 use crate::object_id::ObjectId as _;
 use crate::repo_path::RepoPath;
 use crate::repo_path::RepoPathBuf;
 use crate::repo_path::RepoPathComponent;
 use crate::store::Store;
+use crate::structured_merge::merge_structured;
 
 #[derive(Clone)]
 pub struct Tree {
@@ -266,7 +272,8 @@ impl Iterator for TreeEntriesIterator<'_> {
     }
 }
 
-/// Resolves file-level conflict by merging content hunks.
+/// Resolves file-level conflict by merging content hunks with the merge driver
+/// selected by the `resolver`, or as text if no `resolver` is given.
 ///
 /// The input `conflict` is supposed to be simplified. It shouldn't contain
 /// non-file values that cancel each other.
@@ -274,6 +281,7 @@ pub async fn try_resolve_file_conflict(
     store: &Store,
     filename: &RepoPath,
     conflict: &MergedTreeVal<'_>,
+    resolver: Option<&MergeDriverResolver<'_>>,
 ) -> BackendResult<Option<TreeValue>> {
     // If there are any non-file or any missing parts in the conflict, we can't
     // merge it. We check early so we don't waste time reading file contents if
@@ -312,6 +320,24 @@ pub async fn try_resolve_file_conflict(
     //    cannot
     let file_id_conflict = file_id_conflict.simplify();
 
+    let driver = match resolver {
+        Some(resolver) => resolver.driver_for(filename)?,
+        None => MergeDriver::Text,
+    };
+    match &driver {
+        MergeDriver::Binary => return Ok(None),
+        MergeDriver::Ours => {
+            return Ok(Some(TreeValue::File {
+                id: (*file_id_conflict.first()).clone(),
+                executable,
+            }));
+        }
+        MergeDriver::Text
+        | MergeDriver::Union
+        | MergeDriver::Structured
+        | MergeDriver::External(_) => {}
+    }
+
     let content_futures = file_id_conflict.into_iter().map(|file_id| async {
         let mut content = vec![];
         let mut reader = store.read_file_async(filename, file_id).await?;
@@ -325,14 +351,33 @@ pub async fn try_resolve_file_conflict(
         BackendResult::Ok(content)
     });
     let contents = Merge::from_vec(try_join_all(content_futures).await?);
-    let merge_result = files::merge(&contents);
-    match merge_result {
-        MergeResult::Resolved(merged_content) => {
-            let id = store
-                .write_file(filename, &mut merged_content.as_slice())
-                .await?;
-            Ok(Some(TreeValue::File { id, executable }))
+    let merged_content = match &driver {
+        MergeDriver::Text => match files::merge(&contents, DiffAlgorithm::Default) {
+            MergeResult::Resolved(merged_content) => merged_content.into(),
+            MergeResult::Conflict(_) => return Ok(None),
+        },
+        MergeDriver::Union => merge_union(&contents).into(),
+        // Structured merge is only attempted if the line-based merge conflicts,
+        // so that cleanly merged files keep their formatting.
+        MergeDriver::Structured => match files::merge(&contents, DiffAlgorithm::Default) {
+            MergeResult::Resolved(merged_content) => merged_content.into(),
+            MergeResult::Conflict(_) => match merge_structured(filename, &contents) {
+                Some(merged_content) => merged_content.into(),
+                None => return Ok(None),
+            },
+        },
+        MergeDriver::External(external_driver) => {
+            match run_external_merge_driver(external_driver, filename, &contents)
+                .map_err(|err| BackendError::Other(err.into()))?
+            {
+                Some(merged_content) => merged_content,
+                None => return Ok(None),
+            }
         }
-        MergeResult::Conflict(_) => Ok(None),
-    }
+        MergeDriver::Binary | MergeDriver::Ours => unreachable!(),
+    };
+    let id = store
+        .write_file(filename, &mut merged_content.as_slice())
+        .await?;
+    Ok(Some(TreeValue::File { id, executable }))
 }
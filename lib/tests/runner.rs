@@ -11,6 +11,7 @@ mod test_bad_locking;
 mod test_commit_builder;
 mod test_commit_concurrent;
 mod test_conflicts;
+mod test_copies;
 mod test_default_revset_graph_iterator;
 mod test_fix;
 mod test_git;
@@ -23,11 +24,14 @@ mod test_load_repo;
 mod test_local_working_copy;
 mod test_local_working_copy_concurrent;
 mod test_local_working_copy_sparse;
+mod test_merge_driver;
 mod test_merge_trees;
 mod test_merged_tree;
 mod test_mut_repo;
+mod test_op_bundle;
 mod test_operations;
 mod test_refs;
+mod test_rerere;
 mod test_revset;
 mod test_rewrite;
 mod test_rewrite_duplicate;
@@ -12,12 +12,15 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashSet;
 use std::fmt::Write as _;
 use std::rc::Rc;
 
 use jj_lib::annotate::get_annotation_for_file;
 use jj_lib::annotate::get_annotation_with_file_content;
+use jj_lib::annotate::AnnotationOptions;
 use jj_lib::annotate::FileAnnotation;
+use jj_lib::annotate::LineComparison;
 use jj_lib::backend::CommitId;
 use jj_lib::backend::MergedTreeId;
 use jj_lib::backend::MillisSinceEpoch;
@@ -25,11 +28,16 @@ use jj_lib::backend::Signature;
 use jj_lib::backend::Timestamp;
 use jj_lib::backend::TreeValue;
 use jj_lib::commit::Commit;
+use jj_lib::config::ConfigLayer;
+use jj_lib::config::ConfigSource;
+use jj_lib::default_index::AsCompositeIndex as _;
+use jj_lib::default_index::DefaultMutableIndex;
 use jj_lib::repo::MutableRepo;
 use jj_lib::repo::Repo;
 use jj_lib::repo_path::RepoPath;
 use jj_lib::revset::ResolvedRevsetExpression;
 use jj_lib::revset::RevsetExpression;
+use jj_lib::settings::UserSettings;
 use testutils::create_tree;
 use testutils::TestRepo;
 
@@ -68,10 +76,29 @@ fn annotate_within(
     domain: &Rc<ResolvedRevsetExpression>,
     file_path: &RepoPath,
 ) -> String {
-    let annotation = get_annotation_for_file(repo, commit, domain, file_path).unwrap();
+    let options = AnnotationOptions::default();
+    let annotation = get_annotation_for_file(repo, commit, domain, file_path, &options).unwrap();
     format_annotation(repo, &annotation)
 }
 
+fn annotate_with_options(
+    repo: &dyn Repo,
+    commit: &Commit,
+    file_path: &RepoPath,
+    options: &AnnotationOptions,
+) -> String {
+    let domain = RevsetExpression::all();
+    let annotation = get_annotation_for_file(repo, commit, &domain, file_path, options).unwrap();
+    let mut output = String::new();
+    for ((commit_id, line), path) in annotation.lines().zip(annotation.original_paths()) {
+        let commit = repo.store().get_commit(commit_id.unwrap()).unwrap();
+        let desc = commit.description().trim_end();
+        let path = path.as_internal_file_string();
+        write!(output, "{desc} {path}: {line}").unwrap();
+    }
+    output
+}
+
 fn annotate_parent_tree(repo: &dyn Repo, commit: &Commit, file_path: &RepoPath) -> String {
     let tree = commit.parent_tree(repo).unwrap();
     let text = match tree.path_value(file_path).unwrap().into_resolved().unwrap() {
@@ -84,8 +111,10 @@ fn annotate_parent_tree(repo: &dyn Repo, commit: &Commit, file_path: &RepoPath)
         value => panic!("unexpected path value: {value:?}"),
     };
     let domain = RevsetExpression::all();
+    let options = AnnotationOptions::default();
     let annotation =
-        get_annotation_with_file_content(repo, commit.id(), &domain, file_path, text).unwrap();
+        get_annotation_with_file_content(repo, commit.id(), &domain, file_path, &options, text)
+            .unwrap();
     format_annotation(repo, &annotation)
 }
 
@@ -138,6 +167,54 @@ fn test_annotate_linear() {
     ");
 }
 
+#[test]
+fn test_annotate_with_changed_path_filters() {
+    let mut config = testutils::base_user_config();
+    config.add_layer(ConfigLayer::parse(ConfigSource::User, "index.changed-paths = true").unwrap());
+    let settings = UserSettings::from_config(config).unwrap();
+    let test_repo = TestRepo::init_with_settings(&settings);
+    let repo = &test_repo.repo;
+
+    let root_commit_id = repo.store().root_commit_id();
+    let file_path = RepoPath::from_internal_string("dir/file");
+    let sibling_path = RepoPath::from_internal_string("dir/sibling");
+    let other_path = RepoPath::from_internal_string("other");
+
+    let mut tx = repo.start_transaction();
+    let mut create_commit = create_commit_fn(tx.repo_mut());
+    let tree1 = create_tree(repo, &[(file_path, "1\n")]);
+    let tree2 = create_tree(repo, &[(file_path, "1\n"), (other_path, "2\n")]);
+    let tree3 = create_tree(repo, &[(file_path, "1\n3\n"), (other_path, "2\n")]);
+    let tree4 = create_tree(
+        repo,
+        &[
+            (file_path, "1\n3\n"),
+            (sibling_path, "4\n"),
+            (other_path, "2\n"),
+        ],
+    );
+    let commit1 = create_commit("commit1", &[root_commit_id], tree1.id());
+    let commit2 = create_commit("commit2", &[commit1.id()], tree2.id());
+    let commit3 = create_commit("commit3", &[commit2.id()], tree3.id());
+    let commit4 = create_commit("commit4", &[commit3.id()], tree4.id());
+    drop(create_commit);
+
+    // The ancestors are searched by files(), which skips commits by the
+    // changed-path filters
+    let index = tx
+        .repo()
+        .mutable_index()
+        .as_any()
+        .downcast_ref::<DefaultMutableIndex>()
+        .unwrap()
+        .as_composite();
+    assert_eq!(index.stats().num_changed_path_filters, index.num_commits());
+    insta::assert_snapshot!(annotate(tx.repo(), &commit4, file_path), @r"
+    commit1 : 1
+    commit3 : 3
+    ");
+}
+
 #[test]
 fn test_annotate_merge_simple() {
     let test_repo = TestRepo::init();
@@ -375,3 +452,126 @@ fn test_annotate_file_directory_transition() {
 
     insta::assert_snapshot!(annotate(tx.repo(), &commit2, file_path2), @"commit2 : 2");
 }
+
+#[test]
+fn test_annotate_follow_copies() {
+    let mut config = testutils::base_user_config();
+    config.add_layer(
+        ConfigLayer::parse(ConfigSource::User, "copy-tracking.method = 'content'").unwrap(),
+    );
+    let settings = UserSettings::from_config(config).unwrap();
+    let test_repo = TestRepo::init_with_settings(&settings);
+    let repo = &test_repo.repo;
+
+    let root_commit_id = repo.store().root_commit_id();
+    let old_path = RepoPath::from_internal_string("old");
+    let new_path = RepoPath::from_internal_string("new");
+
+    // 5    new: "0 1 x 2 3"
+    // |\
+    // | 4  old: "0 1 2"
+    // | |
+    // 3 |  new: "1 x 2 3"
+    // | |
+    // 2 |  new: "1 2 3" (renamed)
+    // |/
+    // 1    old: "1 2"
+    let mut tx = repo.start_transaction();
+    let mut create_commit = create_commit_fn(tx.repo_mut());
+    let tree1 = create_tree(repo, &[(old_path, "1\n2\n")]);
+    let tree2 = create_tree(repo, &[(new_path, "1\n2\n3\n")]);
+    let tree3 = create_tree(repo, &[(new_path, "1\nx\n2\n3\n")]);
+    let tree4 = create_tree(repo, &[(old_path, "0\n1\n2\n")]);
+    let tree5 = create_tree(repo, &[(new_path, "0\n1\nx\n2\n3\n")]);
+    let commit1 = create_commit("commit1", &[root_commit_id], tree1.id());
+    let commit2 = create_commit("commit2", &[commit1.id()], tree2.id());
+    let commit3 = create_commit("commit3", &[commit2.id()], tree3.id());
+    let commit4 = create_commit("commit4", &[commit1.id()], tree4.id());
+    let commit5 = create_commit("commit5", &[commit3.id(), commit4.id()], tree5.id());
+    drop(create_commit);
+
+    insta::assert_snapshot!(annotate(tx.repo(), &commit3, new_path), @r"
+    commit2 : 1
+    commit3 : x
+    commit2 : 2
+    commit2 : 3
+    ");
+    insta::assert_snapshot!(annotate(tx.repo(), &commit5, new_path), @r"
+    commit5 : 0
+    commit2 : 1
+    commit3 : x
+    commit2 : 2
+    commit2 : 3
+    ");
+
+    let options = AnnotationOptions {
+        follow_copies: true,
+        ..Default::default()
+    };
+    insta::assert_snapshot!(annotate_with_options(tx.repo(), &commit3, new_path, &options), @r"
+    commit1 old: 1
+    commit3 new: x
+    commit1 old: 2
+    commit2 new: 3
+    ");
+    insta::assert_snapshot!(annotate_with_options(tx.repo(), &commit5, new_path, &options), @r"
+    commit4 old: 0
+    commit1 old: 1
+    commit3 new: x
+    commit1 old: 2
+    commit2 new: 3
+    ");
+}
+
+#[test]
+fn test_annotate_ignored_changes() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let root_commit_id = repo.store().root_commit_id();
+    let file_path = RepoPath::from_internal_string("file");
+
+    let mut tx = repo.start_transaction();
+    let mut create_commit = create_commit_fn(tx.repo_mut());
+    let tree1 = create_tree(repo, &[(file_path, "a\nb\nc\n")]);
+    let tree2 = create_tree(repo, &[(file_path, "a\n  b\nc\n")]);
+    let tree3 = create_tree(repo, &[(file_path, "A\n  b\nc\nd\ne\n")]);
+    let tree4 = create_tree(repo, &[(file_path, "A\n  b\nC\nd\n")]);
+    let commit1 = create_commit("commit1", &[root_commit_id], tree1.id());
+    let commit2 = create_commit("commit2", &[commit1.id()], tree2.id());
+    let commit3 = create_commit("commit3", &[commit2.id()], tree3.id());
+    let commit4 = create_commit("commit4", &[commit3.id()], tree4.id());
+    drop(create_commit);
+
+    insta::assert_snapshot!(annotate(tx.repo(), &commit4, file_path), @r"
+    commit3 : A
+    commit2 :   b
+    commit4 : C
+    commit3 : d
+    ");
+
+    // Whitespace changes
+    let options = AnnotationOptions {
+        line_comparison: LineComparison::IgnoreAllSpace,
+        ..Default::default()
+    };
+    insta::assert_snapshot!(annotate_with_options(tx.repo(), &commit4, file_path, &options), @r"
+    commit3 file: A
+    commit1 file:   b
+    commit4 file: C
+    commit3 file: d
+    ");
+
+    // Changed lines are attributed to the nearby lines in the parent, but
+    // added lines aren't
+    let options = AnnotationOptions {
+        ignored_commits: HashSet::from([commit3.id().clone()]),
+        ..Default::default()
+    };
+    insta::assert_snapshot!(annotate_with_options(tx.repo(), &commit4, file_path, &options), @r"
+    commit1 file: A
+    commit2 file:   b
+    commit4 file: C
+    commit3 file: d
+    ");
+}
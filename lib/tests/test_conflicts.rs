@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::io::Read as _;
+
 use indoc::indoc;
 use itertools::Itertools as _;
 use jj_lib::backend::FileId;
@@ -583,6 +585,7 @@ fn test_materialize_parse_roundtrip_different_markers() {
         ConflictMarkerStyle::Diff,
         ConflictMarkerStyle::Snapshot,
         ConflictMarkerStyle::Git,
+        ConflictMarkerStyle::Zdiff3,
     ];
 
     // For every pair of conflict marker styles, materialize the conflict using the
@@ -610,6 +613,123 @@ fn test_materialize_parse_roundtrip_different_markers() {
     }
 }
 
+#[test]
+fn test_materialize_conflict_zdiff3() {
+    let test_repo = TestRepo::init();
+    let store = test_repo.repo.store();
+
+    let path = RepoPath::from_internal_string("file");
+    let base_id = testutils::write_file(store, path, "line 1\nline 4\nline 5\n");
+    let left_id = testutils::write_file(
+        store,
+        path,
+        indoc! {"
+            line 1
+            fn foo() {
+                left
+            }
+            line 4
+            line 5 left
+        "},
+    );
+    let right_id = testutils::write_file(
+        store,
+        path,
+        indoc! {"
+            line 1
+            fn foo() {
+                right
+            }
+            line 4
+            line 5 right
+        "},
+    );
+    let conflict = Merge::from_removes_adds(
+        vec![Some(base_id.clone())],
+        vec![Some(left_id.clone()), Some(right_id.clone())],
+    );
+
+    // Lines added by both sides are moved out of the conflict markers
+    let materialized =
+        materialize_conflict_string(store, path, &conflict, ConflictMarkerStyle::Zdiff3);
+    insta::assert_snapshot!(materialized, @r"
+    line 1
+    fn foo() {
+    <<<<<<< Side #1 (Conflict 1 of 2)
+        left
+    ||||||| Base
+    =======
+        right
+    >>>>>>> Side #2 (Conflict 1 of 2 ends)
+    }
+    line 4
+    <<<<<<< Side #1 (Conflict 2 of 2)
+    line 5 left
+    ||||||| Base
+    line 5
+    =======
+    line 5 right
+    >>>>>>> Side #2 (Conflict 2 of 2 ends)
+    ");
+
+    // Unchanged content round-trips
+    let parse = |conflict: &Merge<Option<FileId>>, content: &str| {
+        update_from_content(
+            conflict,
+            store,
+            path,
+            content.as_bytes(),
+            ConflictMarkerStyle::Zdiff3,
+            MIN_CONFLICT_MARKER_LEN,
+        )
+        .block_on()
+        .unwrap()
+    };
+    assert_eq!(parse(&conflict, &materialized), conflict);
+
+    // Resolve the second conflict. The sides of the remaining conflict are
+    // preserved, and the conflict is materialized in the same way.
+    let edited = materialized.replace(
+        indoc! {"
+            <<<<<<< Side #1 (Conflict 2 of 2)
+            line 5 left
+            ||||||| Base
+            line 5
+            =======
+            line 5 right
+            >>>>>>> Side #2 (Conflict 2 of 2 ends)
+        "},
+        "line 5 resolved\n",
+    );
+    assert_ne!(edited, materialized);
+    let new_conflict = parse(&conflict, &edited);
+    let read = |id: &Option<FileId>| {
+        let mut content = String::new();
+        store
+            .read_file(path, id.as_ref().unwrap())
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        content
+    };
+    insta::assert_debug_snapshot!(new_conflict.map(read), @r#"
+    Conflicted(
+        [
+            "line 1\nfn foo() {\n    left\n}\nline 4\nline 5 resolved\n",
+            "line 1\nfn foo() {\n}\nline 4\nline 5 resolved\n",
+            "line 1\nfn foo() {\n    right\n}\nline 4\nline 5 resolved\n",
+        ],
+    )
+    "#);
+    let rematerialized =
+        materialize_conflict_string(store, path, &new_conflict, ConflictMarkerStyle::Zdiff3);
+    assert_eq!(
+        rematerialized,
+        edited.replace("Conflict 1 of 2", "Conflict 1 of 1")
+    );
+    assert_eq!(parse(&new_conflict, &rematerialized), new_conflict);
+}
+
 #[test]
 fn test_materialize_conflict_no_newlines_at_eof() {
     let test_repo = TestRepo::init();
@@ -2452,6 +2572,9 @@ fn materialize_conflict_string(
     let contents = extract_as_single_hunk(conflict, store, path)
         .block_on()
         .unwrap();
-    String::from_utf8(materialize_merge_result_to_bytes(&contents, conflict_marker_style).into())
-        .unwrap()
+    String::from_utf8(
+        materialize_merge_result_to_bytes(&contents, conflict_marker_style, store.diff_algorithm())
+            .into(),
+    )
+    .unwrap()
 }
@@ -0,0 +1,196 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use futures::executor::block_on_stream;
+use jj_lib::backend::CopyRecord;
+use jj_lib::commit::Commit;
+use jj_lib::config::ConfigLayer;
+use jj_lib::config::ConfigSource;
+use jj_lib::copies::CopyDetectionOptions;
+use jj_lib::repo::Repo as _;
+use jj_lib::repo_path::RepoPath;
+use jj_lib::repo_path::RepoPathBuf;
+use jj_lib::settings::UserSettings;
+use testutils::create_tree;
+use testutils::TestRepo;
+
+fn settings_with_config(text: &str) -> UserSettings {
+    let mut config = testutils::base_user_config();
+    config.add_layer(ConfigLayer::parse(ConfigSource::User, text).unwrap());
+    UserSettings::from_config(config).unwrap()
+}
+
+fn init_content_repo() -> TestRepo {
+    TestRepo::init_with_settings(&settings_with_config("copy-tracking.method = 'content'"))
+}
+
+fn to_paths<'a>(files: &[(&'a str, &'a str)]) -> Vec<(&'a RepoPath, &'a str)> {
+    files
+        .iter()
+        .map(|&(path, content)| (RepoPath::from_internal_string(path), content))
+        .collect()
+}
+
+/// Creates a commit with the `before` files and a child commit with the
+/// `after` files.
+fn write_commits(
+    test_repo: &TestRepo,
+    before: &[(&str, &str)],
+    after: &[(&str, &str)],
+) -> (Commit, Commit) {
+    let repo = &test_repo.repo;
+    let mut tx = repo.start_transaction();
+    let before_tree = create_tree(repo, &to_paths(before));
+    let after_tree = create_tree(repo, &to_paths(after));
+    let parent = tx
+        .repo_mut()
+        .new_commit(
+            vec![repo.store().root_commit_id().clone()],
+            before_tree.id(),
+        )
+        .write()
+        .unwrap();
+    let child = tx
+        .repo_mut()
+        .new_commit(vec![parent.id().clone()], after_tree.id())
+        .write()
+        .unwrap();
+    tx.commit("test").unwrap();
+    (parent, child)
+}
+
+fn get_copy_records(
+    test_repo: &TestRepo,
+    paths: Option<&[RepoPathBuf]>,
+    before: &[(&str, &str)],
+    after: &[(&str, &str)],
+) -> Vec<(String, String)> {
+    let (parent, child) = write_commits(test_repo, before, after);
+    let stream = test_repo
+        .repo
+        .store()
+        .get_copy_records(paths, parent.id(), child.id())
+        .unwrap();
+    block_on_stream(stream)
+        .map(|record| {
+            let CopyRecord { source, target, .. } = record.unwrap();
+            (
+                source.as_internal_file_string().to_owned(),
+                target.as_internal_file_string().to_owned(),
+            )
+        })
+        .collect()
+}
+
+fn pair(source: &str, target: &str) -> (String, String) {
+    (source.to_owned(), target.to_owned())
+}
+
+#[test]
+fn test_copy_detection_exact() {
+    let test_repo = init_content_repo();
+    let records = get_copy_records(
+        &test_repo,
+        None,
+        &[("a", "content\n"), ("b", "modified\n"), ("empty", "")],
+        &[
+            ("b", "modified2\n"),
+            ("renamed", "content\n"),
+            ("copied", "modified\n"),
+            ("empty2", ""),
+        ],
+    );
+    // Records are sorted by target, and empty files aren't paired
+    assert_eq!(records, vec![pair("b", "copied"), pair("a", "renamed")]);
+}
+
+#[test]
+fn test_copy_detection_similar() {
+    let test_repo = init_content_repo();
+    let records = get_copy_records(
+        &test_repo,
+        None,
+        &[
+            ("a", "1\n2\n3\n4\n5\n6\n"),
+            ("b", "1\n2\n3\n4\n5\n6\n"),
+            ("c", "x\ny\nz\n"),
+        ],
+        &[
+            ("b", "1\n2\n3\n4\n5\n6\n7\n"),
+            ("a2", "1\n2\n3\n4\n5\nsix\n"),
+            ("c2", "x\nY\nZ\n"),
+        ],
+    );
+    // Deleted files are preferred over modified files with the same score. "c2"
+    // is only 33% similar to "c".
+    assert_eq!(records, vec![pair("a", "a2")]);
+}
+
+#[test]
+fn test_copy_detection_paths() {
+    let test_repo = init_content_repo();
+    let records = get_copy_records(
+        &test_repo,
+        Some(&[RepoPathBuf::from_internal_string("b2")]),
+        &[("a", "a\n"), ("b", "b\n")],
+        &[("a2", "a\n"), ("b2", "b\n")],
+    );
+    assert_eq!(records, vec![pair("b", "b2")]);
+}
+
+#[test]
+fn test_copy_detection_config() {
+    let settings = settings_with_config(indoc::indoc! {"
+        [copy-tracking]
+        method = 'content'
+        similarity-threshold = 30
+        detect-copies = false
+    "});
+    let test_repo = TestRepo::init_with_settings(&settings);
+    let records = get_copy_records(
+        &test_repo,
+        None,
+        &[("a", "x\ny\nz\n"), ("b", "1\n2\n")],
+        &[("a2", "x\nY\nZ\n"), ("b", "1\n"), ("b2", "1\n2\n")],
+    );
+    assert_eq!(records, vec![pair("a", "a2")]);
+
+    // Inexact matching can be disabled by the limit
+    let settings = settings_with_config(indoc::indoc! {"
+        [copy-tracking]
+        method = 'content'
+        max-files = 0
+    "});
+    let test_repo = TestRepo::init_with_settings(&settings);
+    let records = get_copy_records(
+        &test_repo,
+        None,
+        &[("a", "1\n2\n3\n"), ("b", "b\n")],
+        &[("a2", "1\n2\n3\n4\n"), ("b2", "b\n")],
+    );
+    assert_eq!(records, vec![pair("b", "b2")]);
+
+    // Backend copy records are used by default, and the test backend doesn't
+    // detect copies
+    let test_repo = TestRepo::init();
+    let records = get_copy_records(&test_repo, None, &[("a", "a\n")], &[("b", "a\n")]);
+    assert_eq!(records, vec![]);
+}
+
+#[test]
+fn test_copy_detection_invalid_config() {
+    let settings = settings_with_config("copy-tracking.similarity-threshold = 101");
+    let err = CopyDetectionOptions::from_settings(&settings).unwrap_err();
+    insta::assert_snapshot!(err, @"Invalid type or value for copy-tracking.similarity-threshold");
+}
@@ -21,9 +21,12 @@ use std::time::Duration;
 use std::time::SystemTime;
 
 use futures::executor::block_on_stream;
+use jj_lib::backend::Backend as _;
+use jj_lib::backend::ChangeId;
 use jj_lib::backend::CommitId;
 use jj_lib::backend::CopyRecord;
 use jj_lib::commit::Commit;
+use jj_lib::git_backend::CommitMetadataImportStats;
 use jj_lib::git_backend::GitBackend;
 use jj_lib::git_backend::JJ_TREES_COMMIT_HEADER;
 use jj_lib::object_id::ObjectId as _;
@@ -34,6 +37,7 @@ use jj_lib::repo_path::RepoPathBuf;
 use jj_lib::store::Store;
 use jj_lib::transaction::Transaction;
 use maplit::hashset;
+use pollster::FutureExt as _;
 use testutils::commit_with_tree;
 use testutils::create_random_commit;
 use testutils::create_single_tree;
@@ -62,7 +66,7 @@ fn collect_no_gc_refs(git_repo_path: &Path) -> HashSet<CommitId> {
 }
 
 fn get_copy_records(
-    store: &Store,
+    store: &Arc<Store>,
     paths: Option<&[RepoPathBuf]>,
     a: &Commit,
     b: &Commit,
@@ -333,3 +337,55 @@ fn test_jj_trees_header_with_one_tree() {
     )
     "#);
 }
+
+#[test]
+fn test_import_commit_metadata() {
+    let test_repo = TestRepo::init_with_backend(TestRepoBackend::Git);
+    let repo = test_repo.repo;
+    let git_backend = get_git_backend(&repo);
+    let git_repo = git_backend.git_repo();
+
+    let mut tx = repo.start_transaction();
+    let jj_commit = create_random_commit(tx.repo_mut()).write().unwrap();
+    tx.commit("test").unwrap();
+
+    // A commit written by Git gets synthesized metadata when imported
+    let git_commit_id = gix::ObjectId::from_bytes_or_panic(jj_commit.id().as_bytes());
+    let mut git_commit: gix::objs::Commit = git_repo
+        .find_commit(git_commit_id)
+        .unwrap()
+        .decode()
+        .unwrap()
+        .into();
+    git_commit.message = "written by git\n".into();
+    let git_commit_id = git_repo.write_object(&git_commit).unwrap();
+    let git_commit_id = CommitId::from_bytes(git_commit_id.as_bytes());
+    git_backend.import_head_commits([&git_commit_id]).unwrap();
+    let read_change_id = |id: &CommitId| git_backend.read_commit(id).block_on().unwrap().change_id;
+    let synthesized_change_id = read_change_id(&git_commit_id);
+
+    let change_id = ChangeId::from_hex("a5333333333333333333333333333333");
+    let mut git_commit_metadata = jj_commit.store_commit().clone();
+    git_commit_metadata.change_id = change_id.clone();
+    let mut jj_commit_metadata = jj_commit.store_commit().clone();
+    jj_commit_metadata.change_id = change_id.clone();
+    let stats = git_backend
+        .import_commit_metadata([
+            (&git_commit_id, &git_commit_metadata),
+            (jj_commit.id(), &jj_commit_metadata),
+        ])
+        .unwrap();
+
+    // The synthesized metadata is replaced, but the existing metadata is kept
+    assert_eq!(stats.changed_ids, vec![git_commit_id.clone()]);
+    assert_eq!(stats.conflicting_ids, vec![jj_commit.id().clone()]);
+    assert_ne!(synthesized_change_id, change_id);
+    assert_eq!(read_change_id(&git_commit_id), change_id);
+    assert_eq!(read_change_id(jj_commit.id()), *jj_commit.change_id());
+
+    // Importing the same metadata again is a no-op
+    let stats = git_backend
+        .import_commit_metadata([(&git_commit_id, &git_commit_metadata)])
+        .unwrap();
+    assert_eq!(stats, CommitMetadataImportStats::default());
+}
@@ -39,11 +39,13 @@ use jj_lib::ref_name::RemoteRefSymbol;
 use jj_lib::repo::MutableRepo;
 use jj_lib::repo::ReadonlyRepo;
 use jj_lib::repo::Repo as _;
+use jj_lib::repo_path::RepoPath;
 use jj_lib::revset::ResolvedExpression;
 use jj_lib::revset::GENERATION_RANGE_FULL;
 use maplit::hashset;
 use testutils::commit_transactions;
 use testutils::create_random_commit;
+use testutils::create_tree;
 use testutils::test_backend::TestBackend;
 use testutils::write_random_commit;
 use testutils::CommitGraphBuilder;
@@ -644,7 +646,9 @@ fn test_reindex_corrupt_segment_files() {
         // u32: number of local change ids
         // u32: number of overflow parent entries
         // u32: number of overflow change id positions
-        fs::write(entry.path(), b"\0".repeat(24)).unwrap();
+        // u32: number of changed-path filters
+        // u32: size of changed-path filter data
+        fs::write(entry.path(), b"\0".repeat(32)).unwrap();
     }
 
     let repo = test_env.load_repo_at_head(&settings, test_repo.repo_path());
@@ -732,6 +736,129 @@ fn test_reindex_missing_commit() {
     assert_matches!(err, DefaultIndexStoreError::IndexCommits { op_id, .. } if op_id == *bad_op_id);
 }
 
+#[test]
+fn test_index_changed_path_filters() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let file1_path = RepoPath::from_internal_string("dir/file1");
+    let file2_path = RepoPath::from_internal_string("file2");
+    let tree1 = create_tree(repo, &[(file1_path, "a")]);
+    let tree2 = create_tree(repo, &[(file1_path, "a"), (file2_path, "b")]);
+    let mut tx = repo.start_transaction();
+    let commit1 = tx
+        .repo_mut()
+        .new_commit(vec![repo.store().root_commit_id().clone()], tree1.id())
+        .write()
+        .unwrap();
+    let commit2 = tx
+        .repo_mut()
+        .new_commit(vec![commit1.id().clone()], tree2.id())
+        .write()
+        .unwrap();
+    let repo = tx.commit("test").unwrap();
+
+    // Not recorded by default
+    let index = as_readonly_composite(&repo);
+    let entry1 = index.entry_by_id(commit1.id()).unwrap();
+    assert!(entry1.changed_path_filter().is_none());
+    assert_eq!(index.stats().num_changed_path_filters, 0);
+
+    let index_store =
+        DefaultIndexStore::load(&test_repo.repo_path().join("index")).with_changed_paths(true);
+    index_store.reinit().unwrap();
+    let index = index_store
+        .build_index_at_operation(repo.operation(), repo.store())
+        .unwrap();
+    let index = index.as_composite();
+    assert_eq!(index.stats().num_changed_path_filters, 3);
+
+    let root_entry = index.entry_by_id(repo.store().root_commit_id()).unwrap();
+    let filter = root_entry.changed_path_filter().unwrap();
+    assert!(!filter.might_contain(RepoPath::root()));
+
+    let entry1 = index.entry_by_id(commit1.id()).unwrap();
+    let filter = entry1.changed_path_filter().unwrap();
+    assert!(filter.might_contain(RepoPath::root()));
+    assert!(filter.might_contain(RepoPath::from_internal_string("dir")));
+    assert!(filter.might_contain(file1_path));
+    assert!(!filter.might_contain(file2_path));
+
+    let entry2 = index.entry_by_id(commit2.id()).unwrap();
+    let filter = entry2.changed_path_filter().unwrap();
+    assert!(!filter.might_contain(RepoPath::from_internal_string("dir")));
+    assert!(!filter.might_contain(file1_path));
+    assert!(filter.might_contain(file2_path));
+}
+
+#[test]
+fn test_index_visible_commits() {
+    let settings = testutils::user_settings();
+    let test_repo = TestRepo::init();
+    let test_env = &test_repo.env;
+    let repo = &test_repo.repo;
+    let root_commit_id = repo.store().root_commit_id();
+
+    let stats = as_readonly_composite(repo).stats();
+    assert_eq!(stats.num_visible_heads, 1);
+    assert_eq!(stats.num_visible_commits, 1);
+    assert_eq!(stats.num_divergent_changes, 0);
+
+    // Commit C is divergent with B
+    let mut tx = repo.start_transaction();
+    let commit_a = write_random_commit(tx.repo_mut());
+    let commit_b = child_commit(tx.repo_mut(), &commit_a).write().unwrap();
+    let commit_c = create_random_commit(tx.repo_mut())
+        .set_change_id(commit_b.change_id().clone())
+        .write()
+        .unwrap();
+    let repo = tx.commit("test").unwrap();
+    let stats = as_readonly_composite(&repo).stats();
+    assert_eq!(stats.num_visible_heads, 2);
+    assert_eq!(stats.num_visible_commits, 4);
+    assert_eq!(stats.num_divergent_changes, 1);
+    assert_eq!(
+        repo.resolve_change_id(commit_b.change_id())
+            .map(HashSet::from_iter),
+        Some(hashset! {commit_b.id().clone(), commit_c.id().clone()})
+    );
+    assert!(repo.is_divergent_change(commit_b.change_id()));
+    assert!(!repo.is_divergent_change(commit_a.change_id()));
+
+    // Hiding C doesn't add commits, but the visibility change is recorded
+    let mut tx = repo.start_transaction();
+    tx.repo_mut().remove_head(commit_c.id());
+    let repo = tx.commit("test").unwrap();
+    assert_eq!(commits_by_level(&repo), vec![4, 0]);
+    let stats = as_readonly_composite(&repo).stats();
+    assert_eq!(stats.num_visible_heads, 1);
+    assert_eq!(stats.num_visible_commits, 3);
+    assert_eq!(stats.num_divergent_changes, 0);
+    assert_eq!(
+        repo.resolve_change_id(commit_b.change_id()),
+        Some(vec![commit_b.id().clone()])
+    );
+    assert!(!repo.is_divergent_change(commit_b.change_id()));
+
+    // Visibility should be preserved when segments are squashed
+    let repo = create_n_commits(&repo, 10);
+    assert_eq!(commits_by_level(&repo), vec![14]);
+    let repo = test_env.load_repo_at_head(&settings, test_repo.repo_path());
+    let stats = as_readonly_composite(&repo).stats();
+    assert_eq!(stats.num_visible_heads, 11);
+    assert_eq!(stats.num_visible_commits, 13);
+    assert_eq!(stats.num_divergent_changes, 0);
+    assert_eq!(
+        repo.resolve_change_id(commit_b.change_id()),
+        Some(vec![commit_b.id().clone()])
+    );
+    assert!(!repo.is_divergent_change(commit_b.change_id()));
+    assert_eq!(
+        repo.resolve_change_id(repo.store().root_change_id()),
+        Some(vec![root_commit_id.clone()])
+    );
+}
+
 /// Test that .jj/repo/index/type is created when the repo is created.
 #[test]
 fn test_index_store_type() {
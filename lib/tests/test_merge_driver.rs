@@ -0,0 +1,278 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::Read as _;
+
+use jj_lib::backend::TreeValue;
+use jj_lib::config::ConfigLayer;
+use jj_lib::config::ConfigSource;
+use jj_lib::merge::Merge;
+use jj_lib::merge_driver::MergeDrivers;
+use jj_lib::merged_tree::resolve_file_values;
+use jj_lib::merged_tree::MergedTree;
+use jj_lib::repo::Repo as _;
+use jj_lib::repo_path::RepoPath;
+use jj_lib::rewrite::resolve_with_merge_drivers;
+use jj_lib::settings::UserSettings;
+use pollster::FutureExt as _;
+use testutils::create_tree;
+use testutils::TestRepo;
+
+fn settings_with_config(text: &str) -> UserSettings {
+    let mut config = testutils::base_user_config();
+    config.add_layer(ConfigLayer::parse(ConfigSource::User, text).unwrap());
+    UserSettings::from_config(config).unwrap()
+}
+
+fn read_file(tree: &MergedTree, path: &str) -> Option<String> {
+    let path = RepoPath::from_internal_string(path);
+    let value = tree.path_value(path).unwrap();
+    let TreeValue::File { id, .. } = value.into_resolved().ok()?? else {
+        return None;
+    };
+    let mut content = String::new();
+    tree.store()
+        .read_file(path, &id)
+        .unwrap()
+        .read_to_string(&mut content)
+        .unwrap();
+    Some(content)
+}
+
+/// Merges `left` and `right` changes of the files, all of which have the same
+/// base contents.
+fn merge_files(
+    test_repo: &TestRepo,
+    gitattributes: &str,
+    files: &[(&str, &str, &str, &str)],
+) -> MergedTree {
+    let gitattributes_path = RepoPath::from_internal_string(".gitattributes");
+    let make_tree = |side: usize| {
+        let mut contents = vec![(gitattributes_path, gitattributes)];
+        for (path, base, left, right) in files {
+            let content = [base, left, right][side];
+            contents.push((RepoPath::from_internal_string(path), content));
+        }
+        create_tree(&test_repo.repo, &contents)
+    };
+    let base_tree = make_tree(0);
+    let left_tree = make_tree(1);
+    let right_tree = make_tree(2);
+    resolve_with_merge_drivers(&left_tree.merge_no_resolve(&base_tree, &right_tree)).unwrap()
+}
+
+#[test]
+fn test_merge_driver_from_gitattributes() {
+    let test_repo = TestRepo::init();
+    let gitattributes = indoc::indoc! {"
+        CHANGELOG.md merge=union
+        *.lock merge=ours
+        *.bin -merge
+        unknown.txt merge=unknown
+    "};
+    let merged_tree = merge_files(
+        &test_repo,
+        gitattributes,
+        &[
+            ("CHANGELOG.md", "a\n", "a\nleft\n", "a\nright\n"),
+            ("dir/Cargo.lock", "a\n", "left\n", "right\n"),
+            ("data.bin", "a\nb\nc\n", "left\nb\nc\n", "a\nb\nright\n"),
+            ("unknown.txt", "a\nb\nc\n", "left\nb\nc\n", "a\nb\nright\n"),
+            ("other.txt", "a\n", "left\n", "right\n"),
+        ],
+    );
+    assert_eq!(
+        read_file(&merged_tree, "CHANGELOG.md").as_deref(),
+        Some("a\nleft\nright\n")
+    );
+    assert_eq!(
+        read_file(&merged_tree, "dir/Cargo.lock").as_deref(),
+        Some("left\n")
+    );
+    // Not merged even if the changes don't conflict
+    assert_eq!(read_file(&merged_tree, "data.bin"), None);
+    // Undefined drivers fall back to the default merge
+    assert_eq!(
+        read_file(&merged_tree, "unknown.txt").as_deref(),
+        Some("left\nb\nright\n")
+    );
+    assert_eq!(read_file(&merged_tree, "other.txt"), None);
+}
+
+#[test]
+fn test_merge_driver_from_nested_gitattributes() {
+    let test_repo = TestRepo::init();
+    let gitattributes_path = RepoPath::from_internal_string(".gitattributes");
+    let nested_gitattributes_path = RepoPath::from_internal_string("sub/.gitattributes");
+    let file_path = RepoPath::from_internal_string("sub/file.txt");
+    let make_tree = |content| {
+        create_tree(
+            &test_repo.repo,
+            &[
+                (gitattributes_path, "*.txt merge=union\n"),
+                (nested_gitattributes_path, "file.txt merge=ours\n"),
+                (file_path, content),
+            ],
+        )
+    };
+    let base_tree = make_tree("a\n");
+    let left_tree = make_tree("left\n");
+    let right_tree = make_tree("right\n");
+    let merged_tree =
+        resolve_with_merge_drivers(&left_tree.merge_no_resolve(&base_tree, &right_tree)).unwrap();
+    assert_eq!(
+        read_file(&merged_tree, "sub/file.txt").as_deref(),
+        Some("left\n")
+    );
+}
+
+#[test]
+fn test_merge_driver_patterns_from_config() {
+    // Config patterns take precedence over .gitattributes
+    let settings = settings_with_config(indoc::indoc! {"
+        [merge-drivers.ours]
+        patterns = ['CHANGELOG.md', '/generated/*']
+    "});
+    let test_repo = TestRepo::init_with_settings(&settings);
+    let merged_tree = merge_files(
+        &test_repo,
+        "CHANGELOG.md merge=union\n",
+        &[
+            ("CHANGELOG.md", "a\n", "a\nleft\n", "a\nright\n"),
+            ("generated/file", "a\n", "left\n", "right\n"),
+            ("sub/generated/file", "a\n", "left\n", "right\n"),
+        ],
+    );
+    assert_eq!(
+        read_file(&merged_tree, "CHANGELOG.md").as_deref(),
+        Some("a\nleft\n")
+    );
+    assert_eq!(
+        read_file(&merged_tree, "generated/file").as_deref(),
+        Some("left\n")
+    );
+    assert_eq!(read_file(&merged_tree, "sub/generated/file"), None);
+}
+
+#[test]
+fn test_merge_driver_only_used_when_requested() {
+    // Merge drivers only apply when rebasing or creating merge commits, not
+    // when looking up values or resolving trees in general
+    let settings = settings_with_config(indoc::indoc! {"
+        [merge-drivers.ours]
+        patterns = ['file']
+    "});
+    let test_repo = TestRepo::init_with_settings(&settings);
+    let store = test_repo.repo.store();
+    let path = RepoPath::from_internal_string("file");
+    let base_tree = create_tree(&test_repo.repo, &[(path, "a\n")]);
+    let left_tree = create_tree(&test_repo.repo, &[(path, "left\n")]);
+    let right_tree = create_tree(&test_repo.repo, &[(path, "right\n")]);
+    let unresolved_tree = MergedTree::new(Merge::from_vec(vec![
+        left_tree.as_merge().first().clone(),
+        base_tree.as_merge().first().clone(),
+        right_tree.as_merge().first().clone(),
+    ]));
+    let value = unresolved_tree.path_value(path).unwrap();
+    let resolved_value = resolve_file_values(store, path, value.clone())
+        .block_on()
+        .unwrap();
+    assert_eq!(resolved_value, value);
+    assert!(unresolved_tree.resolve().unwrap().has_conflict());
+    assert_eq!(
+        read_file(
+            &resolve_with_merge_drivers(&unresolved_tree).unwrap(),
+            "file"
+        )
+        .as_deref(),
+        Some("left\n")
+    );
+}
+
+#[test]
+fn test_merge_driver_builtin_cannot_be_redefined() {
+    let settings = settings_with_config(indoc::indoc! {"
+        [merge-drivers.union]
+        command = ['true']
+    "});
+    let err = MergeDrivers::from_settings(&settings).unwrap_err();
+    insta::assert_snapshot!(err, @"Invalid type or value for merge-drivers.union.command");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_merge_driver_external() {
+    let settings = settings_with_config(indoc::indoc! {r#"
+        [merge-drivers.concat]
+        command = ["sh", "-c", "cat $base $right >> $output && echo $path >> $output"]
+        [merge-drivers.fail]
+        command = ["false"]
+    "#});
+    let test_repo = TestRepo::init_with_settings(&settings);
+    let merged_tree = merge_files(
+        &test_repo,
+        "*.txt merge=concat\n*.fail merge=fail\n",
+        &[
+            ("dir/file.txt", "base\n", "left\n", "right\n"),
+            ("file.fail", "base\n", "left\n", "right\n"),
+        ],
+    );
+    assert_eq!(
+        read_file(&merged_tree, "dir/file.txt").as_deref(),
+        Some("left\nbase\nright\ndir/file.txt\n")
+    );
+    assert_eq!(read_file(&merged_tree, "file.fail"), None);
+}
+
+#[test]
+fn test_merge_driver_structured() {
+    let test_repo = TestRepo::init();
+    let merged_tree = merge_files(
+        &test_repo,
+        "*.json merge=structured\n*.txt merge=structured\n",
+        &[
+            // Adjacent lines conflict, so the file is merged key by key
+            (
+                "package.json",
+                "{\n  \"a\": 1,\n  \"b\": 2\n}\n",
+                "{\n  \"a\": 0,\n  \"b\": 2\n}\n",
+                "{\n  \"a\": 1,\n  \"b\": 3\n}\n",
+            ),
+            // The line-based merge is preferred, which preserves the formatting
+            (
+                "config.json",
+                "{\n    \"a\": 1,\n    \"b\": 2,\n    \"c\": 3\n}\n",
+                "{\n    \"a\": 0,\n    \"b\": 2,\n    \"c\": 3\n}\n",
+                "{\n    \"a\": 1,\n    \"b\": 2,\n    \"c\": 4\n}\n",
+            ),
+            // Conflicts are left if the file can't be parsed
+            ("invalid.json", "{\na\n", "{\nleft\n", "{\nright\n"),
+            // Unsupported formats are merged line by line
+            ("file.txt", "a\nb\nc\n", "left\nb\nc\n", "a\nb\nright\n"),
+        ],
+    );
+    assert_eq!(
+        read_file(&merged_tree, "package.json").as_deref(),
+        Some("{\n  \"a\": 0,\n  \"b\": 3\n}\n")
+    );
+    assert_eq!(
+        read_file(&merged_tree, "config.json").as_deref(),
+        Some("{\n    \"a\": 0,\n    \"b\": 2,\n    \"c\": 4\n}\n")
+    );
+    assert_eq!(read_file(&merged_tree, "invalid.json"), None);
+    assert_eq!(
+        read_file(&merged_tree, "file.txt").as_deref(),
+        Some("left\nb\nright\n")
+    );
+}
@@ -22,6 +22,7 @@ use jj_lib::backend::TreeValue;
 use jj_lib::copies::CopiesTreeDiffEntryPath;
 use jj_lib::copies::CopyOperation;
 use jj_lib::copies::CopyRecords;
+use jj_lib::diff::DiffAlgorithm;
 use jj_lib::files::MergeResult;
 use jj_lib::matchers::EverythingMatcher;
 use jj_lib::matchers::FilesMatcher;
@@ -1532,7 +1533,7 @@ fn test_merge_simplify_file_conflict() {
         ],
     );
     assert!(matches!(
-        jj_lib::files::merge(&text_merge.flatten()),
+        jj_lib::files::merge(&text_merge.flatten(), DiffAlgorithm::default()),
         MergeResult::Conflict(_)
     ));
 }
@@ -0,0 +1,232 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use assert_matches::assert_matches;
+use itertools::Itertools as _;
+use jj_lib::config::ConfigLayer;
+use jj_lib::config::ConfigSource;
+use jj_lib::op_bundle::export_operations;
+use jj_lib::op_bundle::export_operations_with_options;
+use jj_lib::op_bundle::import_operations;
+use jj_lib::op_bundle::OpBundleError;
+use jj_lib::op_bundle::OpBundleExportOptions;
+use jj_lib::op_store::RefTarget;
+use jj_lib::repo::Repo as _;
+use jj_lib::repo_path::RepoPath;
+use jj_lib::settings::UserSettings;
+use jj_lib::signing::SignBehavior;
+use jj_lib::signing::Signer;
+use jj_lib::test_signing_backend::TestSigningBackend;
+use test_case::test_case;
+use testutils::create_random_commit;
+use testutils::create_tree;
+use testutils::TestRepo;
+use testutils::TestRepoBackend;
+use testutils::TestWorkspace;
+
+#[test_case(TestRepoBackend::Simple ; "simple backend")]
+#[test_case(TestRepoBackend::Git ; "git backend")]
+fn test_export_import_operations(backend: TestRepoBackend) {
+    let source_repo = TestRepo::init_with_backend(backend);
+    let dest_repo = TestRepo::init_with_backend(backend);
+    let file_path = RepoPath::from_internal_string("dir/file");
+
+    let repo = &source_repo.repo;
+    let mut tx = repo.start_transaction();
+    let tree = create_tree(repo, &[(file_path, "contents")]);
+    let commit1 = tx
+        .repo_mut()
+        .new_commit(vec![repo.store().root_commit_id().clone()], tree.id())
+        .write()
+        .unwrap();
+    let commit2 = create_random_commit(tx.repo_mut())
+        .set_parents(vec![commit1.id().clone()])
+        .write()
+        .unwrap();
+    tx.repo_mut()
+        .set_local_bookmark_target("main".as_ref(), RefTarget::normal(commit2.id().clone()));
+    let repo = tx.commit("add commits").unwrap();
+
+    let mut data = vec![];
+    let stats = export_operations(repo.loader(), &[repo.operation().clone()], &mut data).unwrap();
+    assert_eq!(stats.head_ids, vec![repo.op_id().clone()]);
+    // Excludes the root operation
+    assert_eq!(stats.num_operations, 1);
+    assert_eq!(stats.commit_ids.len(), 2);
+
+    let mut tx = dest_repo.repo.start_transaction();
+    let local_commit = create_random_commit(tx.repo_mut()).write().unwrap();
+    let local_repo = tx.commit("add local commit").unwrap();
+
+    let dest_loader = local_repo.loader();
+    let stats = import_operations(dest_loader, &mut data.as_slice()).unwrap();
+    assert_eq!(stats.head_ids, vec![repo.op_id().clone()]);
+    assert_eq!(stats.num_operations, 1);
+    assert_eq!(stats.commit_ids.len(), 2);
+
+    // The imported operation isn't visible until merged
+    assert_eq!(
+        dest_loader.load_at_head().unwrap().op_id(),
+        local_repo.op_id()
+    );
+    let mut tx = local_repo.start_transaction();
+    tx.merge_operation(dest_loader.load_operation(repo.op_id()).unwrap())
+        .unwrap();
+    let merged_repo = tx.commit("merge imported operation").unwrap();
+    let merged_op = merged_repo.operation();
+    assert_eq!(merged_op.parent_ids().len(), 2);
+    assert!(merged_op.parent_ids().contains(repo.op_id()));
+    assert!(merged_op.parent_ids().contains(local_repo.op_id()));
+    assert!(merged_repo.view().heads().contains(commit2.id()));
+    assert!(merged_repo.view().heads().contains(local_commit.id()));
+    assert_eq!(
+        merged_repo.view().get_local_bookmark("main".as_ref()),
+        &RefTarget::normal(commit2.id().clone())
+    );
+    let imported_commit = merged_repo.store().get_commit(commit1.id()).unwrap();
+    assert_eq!(imported_commit.store_commit(), commit1.store_commit());
+    assert_eq!(
+        imported_commit
+            .tree()
+            .unwrap()
+            .path_value(file_path)
+            .unwrap(),
+        tree.path_value(file_path).unwrap()
+    );
+
+    // Importing again doesn't write new commits
+    let stats = import_operations(dest_loader, &mut data.as_slice()).unwrap();
+    assert_eq!(stats.commit_ids.len(), 0);
+}
+
+#[test_case(TestRepoBackend::Simple ; "simple backend")]
+#[test_case(TestRepoBackend::Git ; "git backend")]
+fn test_export_import_signed_commit(backend: TestRepoBackend) {
+    let mut config = testutils::base_user_config();
+    config.add_layer(ConfigLayer::parse(ConfigSource::User, "signing.key = 'impeccable'").unwrap());
+    let settings = UserSettings::from_config(config).unwrap();
+    let signer = Signer::new(Some(Box::new(TestSigningBackend)), vec![]);
+    let source_workspace = TestWorkspace::init_with_backend_and_signer(backend, signer, &settings);
+    let dest_repo = TestRepo::init_with_backend(backend);
+
+    let repo = &source_workspace.repo;
+    let mut tx = repo.start_transaction();
+    let commit = create_random_commit(tx.repo_mut())
+        .set_sign_behavior(SignBehavior::Own)
+        .write()
+        .unwrap();
+    assert!(commit.is_signed());
+    let repo = tx.commit("add signed commit").unwrap();
+
+    let mut data = vec![];
+    export_operations(repo.loader(), &[repo.operation().clone()], &mut data).unwrap();
+    let dest_loader = dest_repo.repo.loader();
+    import_operations(dest_loader, &mut data.as_slice()).unwrap();
+
+    let imported_commit = dest_loader.store().get_commit(commit.id()).unwrap();
+    assert!(imported_commit.is_signed());
+    assert_eq!(imported_commit.store_commit(), commit.store_commit());
+}
+
+#[test]
+fn test_export_operations_with_options() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let commit0 = create_random_commit(tx.repo_mut()).write().unwrap();
+    let commit1 = create_random_commit(tx.repo_mut())
+        .set_parents(vec![commit0.id().clone()])
+        .write()
+        .unwrap();
+    let repo1 = tx.commit("add commit1").unwrap();
+    let mut tx = repo1.start_transaction();
+    let commit2 = create_random_commit(tx.repo_mut())
+        .set_parents(vec![commit1.id().clone()])
+        .write()
+        .unwrap();
+    let repo2 = tx.commit("add commit2").unwrap();
+    // commit0 isn't a head of the known view, but is an ancestor of one
+    let mut tx = repo2.start_transaction();
+    let commit3 = create_random_commit(tx.repo_mut())
+        .set_parents(vec![commit0.id().clone()])
+        .write()
+        .unwrap();
+    let repo3 = tx.commit("add commit3").unwrap();
+
+    // Operations and commits known to the destination are left out
+    let options = OpBundleExportOptions {
+        known_ops: vec![repo1.operation().clone()],
+        exclude_tree_objects: false,
+    };
+    let mut data = vec![];
+    let stats = export_operations_with_options(
+        repo3.loader(),
+        &[repo3.operation().clone()],
+        &options,
+        &mut data,
+    )
+    .unwrap();
+    assert_eq!(stats.num_operations, 2);
+    assert_eq!(
+        stats.commit_ids.iter().sorted().collect_vec(),
+        [commit2.id(), commit3.id()]
+            .into_iter()
+            .sorted()
+            .collect_vec()
+    );
+
+    // Bundles without tree objects are smaller
+    let options = OpBundleExportOptions {
+        exclude_tree_objects: true,
+        ..options
+    };
+    let mut data_without_trees = vec![];
+    export_operations_with_options(
+        repo3.loader(),
+        &[repo3.operation().clone()],
+        &options,
+        &mut data_without_trees,
+    )
+    .unwrap();
+    assert!(data_without_trees.len() < data.len());
+}
+
+#[test]
+fn test_import_invalid_bundle() {
+    let test_repo = TestRepo::init_with_backend(TestRepoBackend::Simple);
+    let loader = test_repo.repo.loader();
+    assert_matches!(
+        import_operations(loader, &mut b"not a bundle".as_slice()),
+        Err(OpBundleError::UnsupportedFormat)
+    );
+    assert_matches!(
+        import_operations(loader, &mut b"".as_slice()),
+        Err(OpBundleError::UnsupportedFormat)
+    );
+
+    // Commit ids from the Git backend can't be imported to the simple backend
+    let git_repo = TestRepo::init_with_backend(TestRepoBackend::Git);
+    let mut data = vec![];
+    export_operations(
+        git_repo.repo.loader(),
+        &[git_repo.repo.operation().clone()],
+        &mut data,
+    )
+    .unwrap();
+    assert_matches!(
+        import_operations(loader, &mut data.as_slice()),
+        Err(OpBundleError::IncompatibleBackend)
+    );
+}
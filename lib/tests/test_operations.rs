@@ -23,6 +23,8 @@ use jj_lib::backend::CommitId;
 use jj_lib::config::ConfigLayer;
 use jj_lib::config::ConfigSource;
 use jj_lib::object_id::ObjectId as _;
+use jj_lib::op_store::OperationId;
+use jj_lib::op_store::ViewId;
 use jj_lib::op_walk;
 use jj_lib::op_walk::OpsetEvaluationError;
 use jj_lib::op_walk::OpsetResolutionError;
@@ -416,6 +418,81 @@ fn test_reparent_range_branchy() {
     assert_eq!(new_op_f.parent_ids(), slice::from_ref(repo_d.op_id()));
 }
 
+#[test]
+fn test_walk_ancestors_range() {
+    let test_repo = TestRepo::init();
+    let repo_0 = test_repo.repo;
+
+    // Set up branchy operation graph:
+    // G
+    // |\
+    // | F
+    // E |
+    // D |
+    // C |
+    // |/
+    // B
+    // A
+    // 0 (initial)
+    let random_tx = |repo: &Arc<ReadonlyRepo>| {
+        let mut tx = repo.start_transaction();
+        write_random_commit(tx.repo_mut());
+        tx
+    };
+    let repo_a = random_tx(&repo_0).commit("op A").unwrap();
+    let repo_b = random_tx(&repo_a).commit("op B").unwrap();
+    let repo_c = random_tx(&repo_b).commit("op C").unwrap();
+    let repo_d = random_tx(&repo_c).commit("op D").unwrap();
+    let tx_e = random_tx(&repo_d);
+    let tx_f = random_tx(&repo_b);
+    let repo_g = testutils::commit_transactions(vec![tx_e, tx_f]);
+    let op_g = repo_g.operation();
+
+    let walk_range = |head_ops: &[Operation], root_ops: &[Operation]| -> Vec<Operation> {
+        op_walk::walk_ancestors_range(head_ops, root_ops)
+            .try_collect()
+            .unwrap()
+    };
+    let walk_ancestors = |head_ops: &[Operation]| -> Vec<Operation> {
+        op_walk::walk_ancestors(head_ops).try_collect().unwrap()
+    };
+
+    // Without roots, all ancestors are emitted
+    let ops = walk_range(slice::from_ref(op_g), &[]);
+    assert_eq!(&ops[0], op_g);
+    assert_eq!(
+        ops.iter().sorted().collect_vec(),
+        walk_ancestors(slice::from_ref(op_g))
+            .iter()
+            .sorted()
+            .collect_vec()
+    );
+
+    // B..G = C|D|E|F|G
+    let ops = walk_range(slice::from_ref(op_g), slice::from_ref(repo_b.operation()));
+    assert_eq!(ops.len(), 5);
+    assert_eq!(&ops[0], op_g);
+    assert!(!ops.contains(repo_b.operation()));
+    assert!(ops.contains(repo_c.operation()));
+    assert!(ops.contains(repo_d.operation()));
+
+    // D..G = E|F|G
+    let ops = walk_range(slice::from_ref(op_g), slice::from_ref(repo_d.operation()));
+    assert_eq!(ops.len(), 3);
+    assert_eq!(&ops[0], op_g);
+    assert!(!ops.contains(repo_c.operation()));
+
+    // Empty ranges
+    assert_eq!(
+        walk_range(slice::from_ref(op_g), slice::from_ref(op_g)),
+        vec![]
+    );
+    assert_eq!(
+        walk_range(slice::from_ref(repo_c.operation()), slice::from_ref(op_g)),
+        vec![]
+    );
+}
+
 fn stable_op_id_settings() -> UserSettings {
     let mut config = testutils::base_user_config();
     config.add_layer(
@@ -637,30 +714,57 @@ fn test_gc() {
     let repo_f = random_tx(&repo_e).commit("op F").unwrap();
 
     // Sanity check for the original state
-    let mut expected_op_entries = list_dir(&op_dir);
-    let mut expected_view_entries = list_dir(&view_dir);
+    let all_op_entries = list_dir(&op_dir);
+    let all_view_entries = list_dir(&view_dir);
+    let mut expected_op_entries = all_op_entries.clone();
+    let mut expected_view_entries = all_view_entries.clone();
     assert_eq!(expected_op_entries.len(), 6);
     assert_eq!(expected_view_entries.len(), 5);
 
+    // Objects may be moved to pack files, so test readability instead of
+    // listing files.
+    let readable_op_entries = || {
+        all_op_entries
+            .iter()
+            .filter(|name| {
+                let id = OperationId::try_from_hex(name).unwrap();
+                op_store.read_operation(&id).is_ok()
+            })
+            .cloned()
+            .collect_vec()
+    };
+    let readable_view_entries = || {
+        all_view_entries
+            .iter()
+            .filter(|name| {
+                let id = ViewId::try_from_hex(name).unwrap();
+                op_store.read_view(&id).is_ok()
+            })
+            .cloned()
+            .collect_vec()
+    };
+
     // No heads, but all kept by file modification time
     op_store.gc(&[], SystemTime::UNIX_EPOCH).unwrap();
     assert_eq!(list_dir(&op_dir), expected_op_entries);
     assert_eq!(list_dir(&view_dir), expected_view_entries);
 
-    // All reachable from heads
+    // All reachable from heads, and packed
     let now = SystemTime::now();
     let head_ids = [repo_d.op_id().clone(), repo_f.op_id().clone()];
     op_store.gc(&head_ids, now).unwrap();
-    assert_eq!(list_dir(&op_dir), expected_op_entries);
-    assert_eq!(list_dir(&view_dir), expected_view_entries);
+    assert!(list_dir(&op_dir).is_empty());
+    assert!(list_dir(&view_dir).is_empty());
+    assert_eq!(readable_op_entries(), expected_op_entries);
+    assert_eq!(readable_view_entries(), expected_view_entries);
 
     // E|F are no longer reachable, but E's view is still reachable
     op_store.gc(slice::from_ref(repo_d.op_id()), now).unwrap();
     expected_op_entries
         .retain(|name| *name != repo_e.op_id().hex() && *name != repo_f.op_id().hex());
     expected_view_entries.retain(|name| *name != repo_f.operation().view_id().hex());
-    assert_eq!(list_dir(&op_dir), expected_op_entries);
-    assert_eq!(list_dir(&view_dir), expected_view_entries);
+    assert_eq!(readable_op_entries(), expected_op_entries);
+    assert_eq!(readable_view_entries(), expected_view_entries);
 
     // B|C|D are no longer reachable
     op_store.gc(slice::from_ref(repo_a.op_id()), now).unwrap();
@@ -674,8 +778,8 @@ fn test_gc() {
             && *name != repo_c.operation().view_id().hex()
             && *name != repo_d.operation().view_id().hex()
     });
-    assert_eq!(list_dir(&op_dir), expected_op_entries);
-    assert_eq!(list_dir(&view_dir), expected_view_entries);
+    assert_eq!(readable_op_entries(), expected_op_entries);
+    assert_eq!(readable_view_entries(), expected_view_entries);
 
     // Sanity check for the last state
     assert_eq!(expected_op_entries.len(), 1);
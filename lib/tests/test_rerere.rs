@@ -0,0 +1,202 @@
+// Copyright 2025 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::Read as _;
+
+use jj_lib::backend::TreeValue;
+use jj_lib::commit::Commit;
+use jj_lib::config::ConfigLayer;
+use jj_lib::config::ConfigSource;
+use jj_lib::matchers::EverythingMatcher;
+use jj_lib::repo::MutableRepo;
+use jj_lib::repo::Repo as _;
+use jj_lib::repo_path::RepoPath;
+use jj_lib::rerere;
+use jj_lib::rewrite::rebase_commit;
+use jj_lib::settings::UserSettings;
+use testutils::create_tree;
+use testutils::TestRepo;
+
+fn settings_with_rerere(enabled: bool) -> UserSettings {
+    let mut config = testutils::base_user_config();
+    config.add_layer(
+        ConfigLayer::parse(ConfigSource::User, &format!("rerere.enabled = {enabled}")).unwrap(),
+    );
+    UserSettings::from_config(config).unwrap()
+}
+
+fn write_commit(mut_repo: &mut MutableRepo, parent: &Commit, contents: &str) -> Commit {
+    let path = RepoPath::from_internal_string("file");
+    let tree = create_tree(mut_repo.base_repo(), &[(path, contents)]);
+    mut_repo
+        .new_commit(vec![parent.id().clone()], tree.id())
+        .write()
+        .unwrap()
+}
+
+fn read_file(commit: &Commit) -> Option<String> {
+    let path = RepoPath::from_internal_string("file");
+    let value = commit.tree().unwrap().path_value(path).unwrap();
+    let TreeValue::File { id, .. } = value.into_resolved().ok()?? else {
+        return None;
+    };
+    let mut content = String::new();
+    commit
+        .store()
+        .read_file(path, &id)
+        .unwrap()
+        .read_to_string(&mut content)
+        .unwrap();
+    Some(content)
+}
+
+#[test]
+fn test_rerere_record_and_replay() {
+    let settings = settings_with_rerere(true);
+    let test_repo = TestRepo::init_with_settings(&settings);
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let root = repo.store().root_commit();
+    let base = write_commit(tx.repo_mut(), &root, "a\nb\nc\nd\ne\n");
+    let left = write_commit(tx.repo_mut(), &base, "a\nleft1\nc\nleft2\ne\n");
+    let right = write_commit(tx.repo_mut(), &base, "a\nright1\nc\nright2\ne\n");
+    let rebased = rebase_commit(tx.repo_mut(), right.clone(), vec![left.id().clone()]).unwrap();
+    assert!(rebased.has_conflict().unwrap());
+
+    // Resolving the conflict records the resolutions of both hunks
+    let resolved_tree = create_tree(
+        repo,
+        &[(
+            RepoPath::from_internal_string("file"),
+            "a\nresolved1\nc\nresolved2\ne\n",
+        )],
+    );
+    tx.repo_mut()
+        .rewrite_commit(&rebased)
+        .set_tree_id(resolved_tree.id())
+        .write()
+        .unwrap();
+
+    // The same conflict is resolved when another commit is rebased
+    let other = write_commit(tx.repo_mut(), &base, "a\nright1\nc\nright2\ne\n");
+    let rebased_other = rebase_commit(tx.repo_mut(), other, vec![left.id().clone()]).unwrap();
+    assert!(!rebased_other.has_conflict().unwrap());
+    assert_eq!(
+        read_file(&rebased_other).as_deref(),
+        Some("a\nresolved1\nc\nresolved2\ne\n")
+    );
+
+    // The order of the sides doesn't matter
+    let rebased_left =
+        rebase_commit(tx.repo_mut(), left.clone(), vec![right.id().clone()]).unwrap();
+    assert_eq!(
+        read_file(&rebased_left).as_deref(),
+        Some("a\nresolved1\nc\nresolved2\ne\n")
+    );
+
+    // After forgetting the resolutions, the conflict is left alone
+    let resolution_store = repo.loader().resolution_store().unwrap();
+    let conflicted_tree = rebased.tree().unwrap();
+    assert_eq!(
+        rerere::forget_resolutions(resolution_store, &conflicted_tree, &EverythingMatcher).unwrap(),
+        2
+    );
+    let rebased_left = rebase_commit(tx.repo_mut(), left, vec![right.id().clone()]).unwrap();
+    assert!(rebased_left.has_conflict().unwrap());
+}
+
+#[test]
+fn test_rerere_partial_resolution() {
+    let settings = settings_with_rerere(true);
+    let test_repo = TestRepo::init_with_settings(&settings);
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let root = repo.store().root_commit();
+    let base = write_commit(tx.repo_mut(), &root, "a\nb\nc\n");
+    let left = write_commit(tx.repo_mut(), &base, "a\nleft\nc\n");
+    let right = write_commit(tx.repo_mut(), &base, "a\nright\nc\n");
+    let rebased = rebase_commit(tx.repo_mut(), right.clone(), vec![left.id().clone()]).unwrap();
+
+    // Only one hunk is recorded, so a conflict with another hunk isn't resolved
+    let resolved_tree = create_tree(
+        repo,
+        &[(RepoPath::from_internal_string("file"), "a\nresolved\nc\n")],
+    );
+    tx.repo_mut()
+        .rewrite_commit(&rebased)
+        .set_tree_id(resolved_tree.id())
+        .write()
+        .unwrap();
+    let left2 = write_commit(tx.repo_mut(), &base, "a\nleft\nc\nleft\n");
+    let right2 = write_commit(tx.repo_mut(), &base, "a\nright\nc\nright\n");
+    let rebased2 = rebase_commit(tx.repo_mut(), right2, vec![left2.id().clone()]).unwrap();
+    assert!(rebased2.has_conflict().unwrap());
+}
+
+#[test]
+fn test_rerere_record_failure() {
+    let settings = settings_with_rerere(true);
+    let test_repo = TestRepo::init_with_settings(&settings);
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let root = repo.store().root_commit();
+    let base = write_commit(tx.repo_mut(), &root, "a\nb\nc\n");
+    let left = write_commit(tx.repo_mut(), &base, "a\nleft\nc\n");
+    let right = write_commit(tx.repo_mut(), &base, "a\nright\nc\n");
+    let rebased = rebase_commit(tx.repo_mut(), right, vec![left.id().clone()]).unwrap();
+
+    // The resolutions can't be stored if the directory is occupied by a file,
+    // but the commit should still be written
+    std::fs::write(test_repo.repo_path().join("rerere"), "").unwrap();
+    let resolved_tree = create_tree(
+        repo,
+        &[(RepoPath::from_internal_string("file"), "a\nresolved\nc\n")],
+    );
+    let resolved = tx
+        .repo_mut()
+        .rewrite_commit(&rebased)
+        .set_tree_id(resolved_tree.id())
+        .write()
+        .unwrap();
+    assert_eq!(read_file(&resolved).as_deref(), Some("a\nresolved\nc\n"));
+}
+
+#[test]
+fn test_rerere_disabled() {
+    let settings = settings_with_rerere(false);
+    let test_repo = TestRepo::init_with_settings(&settings);
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction();
+    let root = repo.store().root_commit();
+    let base = write_commit(tx.repo_mut(), &root, "a\nb\nc\n");
+    let left = write_commit(tx.repo_mut(), &base, "a\nleft\nc\n");
+    let right = write_commit(tx.repo_mut(), &base, "a\nright\nc\n");
+    let rebased = rebase_commit(tx.repo_mut(), right.clone(), vec![left.id().clone()]).unwrap();
+    let resolved_tree = create_tree(
+        repo,
+        &[(RepoPath::from_internal_string("file"), "a\nresolved\nc\n")],
+    );
+    tx.repo_mut()
+        .rewrite_commit(&rebased)
+        .set_tree_id(resolved_tree.id())
+        .write()
+        .unwrap();
+
+    let rebased_left = rebase_commit(tx.repo_mut(), left, vec![right.id().clone()]).unwrap();
+    assert!(rebased_left.has_conflict().unwrap());
+}